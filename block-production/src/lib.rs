@@ -92,19 +92,12 @@ impl BlockProducer {
             inherents,
         );
 
-        // Store the extended transactions into the history tree and calculate the history root.
-        let mut txn = blockchain.write_transaction();
-
+        // Calculate the history root that adding the extended transactions would result in.
         let history_root = blockchain
             .history_store
-            .add_to_history(&mut txn, policy::epoch_at(block_number), &ext_txs)
+            .get_root_with(policy::epoch_at(block_number), &ext_txs, None)
             .expect("Failed to compute history root during block production.");
 
-        // Not strictly necessary to drop the lock here, but sign as well as compress might be somewhat expensive
-        // and there is no need to hold the lock after this point.
-        // Abort txn so that blockchain is no longer borrowed.
-        txn.abort();
-
         // Create the micro block body.
         let body = MicroBody {
             fork_proofs,
@@ -209,16 +202,12 @@ impl BlockProducer {
             inherents,
         );
 
-        // Store the extended transactions into the history tree and calculate the history root.
-        let mut txn = blockchain.write_transaction();
-
+        // Calculate the history root that adding the extended transactions would result in.
         header.history_root = blockchain
             .history_store
-            .add_to_history(&mut txn, policy::epoch_at(block_number), &ext_txs)
+            .get_root_with(policy::epoch_at(block_number), &ext_txs, None)
             .expect("Failed to compute history root during block production.");
 
-        txn.abort();
-
         // Calculate the disabled set for the current validator set.
         // Note: We are fetching the previous disabled set here because we have already updated the
         // state. So the staking contract has already moved the disabled set for this batch into the
@@ -1,4 +1,4 @@
-use nimiq_block::{Block, BlockType, MacroBlock};
+use nimiq_block::{Block, BlockType, MacroBlock, ViewChange, ViewChangeProof};
 use nimiq_database::Transaction;
 use nimiq_hash::Blake2bHash;
 use nimiq_primitives::networks::NetworkId;
@@ -119,6 +119,11 @@ pub trait AbstractBlockchain {
         view_number: u32,
         txn_option: Option<&Transaction>,
     ) -> Option<(Validator, u16)>;
+
+    /// Verifies a view change proof against the current validators.
+    fn verify_view_change_proof(&self, view_change: &ViewChange, proof: &ViewChangeProof) -> bool {
+        proof.verify(view_change, &self.current_validators().unwrap())
+    }
 }
 
 impl AbstractBlockchain for Blockchain {
@@ -207,4 +212,8 @@ impl AbstractBlockchain for Blockchain {
         self.get_proposer_at(block_number, view_number, vrf_entropy, txn_option)
             .map(|slot| (slot.validator, slot.number))
     }
+
+    fn verify_view_change_proof(&self, view_change: &ViewChange, proof: &ViewChangeProof) -> bool {
+        Blockchain::verify_view_change_proof(self, view_change, proof)
+    }
 }
@@ -1,10 +1,15 @@
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
 use nimiq_account::Accounts;
-use nimiq_block::{Block, MicroBlock, ViewChanges};
+use nimiq_block::{Block, BlockError, MicroBlock, ViewChanges};
 use nimiq_database::WriteTransaction;
 use nimiq_primitives::policy;
 use nimiq_vrf::VrfEntropy;
 
 use crate::blockchain_state::BlockchainState;
+#[cfg(feature = "metrics")]
+use crate::chain_metrics::BlockProcessingStage;
 use crate::history_store::ExtendedTransaction;
 use crate::{Blockchain, PushError};
 
@@ -28,6 +33,9 @@ impl Blockchain {
                 // Initialize a vector to store the inherents
                 let inherents = self.create_macro_block_inherents(state, &macro_block.header);
 
+                #[cfg(feature = "metrics")]
+                let commit_start = Instant::now();
+
                 // Commit block to AccountsTree and create the receipts.
                 let receipts = accounts.commit(
                     txn,
@@ -42,6 +50,12 @@ impl Blockchain {
                     return Err(PushError::AccountsError(e));
                 }
 
+                #[cfg(feature = "metrics")]
+                self.metrics.note_stage_duration(
+                    BlockProcessingStage::CommitAccounts,
+                    commit_start.elapsed(),
+                );
+
                 // Macro blocks are final and receipts for the previous batch are no longer necessary
                 // as rebranching across this block is not possible.
                 self.chain_store.clear_receipts(txn);
@@ -55,16 +69,33 @@ impl Blockchain {
                     inherents,
                 );
 
-                self.history_store.add_to_history(
-                    txn,
-                    policy::epoch_at(macro_block.header.block_number),
-                    &ext_txs,
+                #[cfg(feature = "metrics")]
+                let history_start = Instant::now();
+
+                self.add_to_history(txn, macro_block.header.block_number, &ext_txs)?;
+
+                self.prune_history(txn, macro_block.header.block_number);
+
+                #[cfg(feature = "metrics")]
+                self.metrics.note_stage_duration(
+                    BlockProcessingStage::HistoryUpdate,
+                    history_start.elapsed(),
                 );
             }
             Block::Micro(ref micro_block) => {
                 // Get the body of the block.
                 let body = micro_block.body.as_ref().unwrap();
 
+                // Never apply transactions outside of their validity window, even if the body
+                // hasn't been verified before.
+                if body
+                    .transactions
+                    .iter()
+                    .any(|tx| !tx.is_valid_at(micro_block.header.block_number))
+                {
+                    return Err(PushError::InvalidBlock(BlockError::ExpiredTransaction));
+                }
+
                 // Get the view changes.
                 let view_changes = ViewChanges::new(
                     micro_block.header.block_number,
@@ -77,6 +108,9 @@ impl Blockchain {
                 let inherents =
                     self.create_slash_inherents(&body.fork_proofs, &view_changes, Some(txn));
 
+                #[cfg(feature = "metrics")]
+                let commit_start = Instant::now();
+
                 // Commit block to AccountsTree and create the receipts.
                 let receipts = accounts.commit(
                     txn,
@@ -91,6 +125,12 @@ impl Blockchain {
                     return Err(PushError::AccountsError(e));
                 }
 
+                #[cfg(feature = "metrics")]
+                self.metrics.note_stage_duration(
+                    BlockProcessingStage::CommitAccounts,
+                    commit_start.elapsed(),
+                );
+
                 // Store receipts.
                 let receipts = receipts.unwrap();
                 self.chain_store
@@ -105,10 +145,15 @@ impl Blockchain {
                     inherents,
                 );
 
-                self.history_store.add_to_history(
-                    txn,
-                    policy::epoch_at(micro_block.header.block_number),
-                    &ext_txs,
+                #[cfg(feature = "metrics")]
+                let history_start = Instant::now();
+
+                self.add_to_history(txn, micro_block.header.block_number, &ext_txs)?;
+
+                #[cfg(feature = "metrics")]
+                self.metrics.note_stage_duration(
+                    BlockProcessingStage::HistoryUpdate,
+                    history_start.elapsed(),
                 );
             }
         }
@@ -116,6 +161,38 @@ impl Blockchain {
         Ok(())
     }
 
+    /// Adds the extended transactions of the block at the given block number to the history store.
+    /// The history store is updated within the same transaction as the accounts, so if it fails,
+    /// the caller aborts the transaction and the whole block commit is rolled back.
+    pub(crate) fn add_to_history(
+        &self,
+        txn: &mut WriteTransaction,
+        block_number: u32,
+        ext_txs: &[ExtendedTransaction],
+    ) -> Result<(), PushError> {
+        let epoch_number = policy::epoch_at(block_number);
+        if self
+            .history_store
+            .add_to_history(txn, epoch_number, ext_txs)
+            .is_none()
+        {
+            error!(
+                "Failed to add the history of block #{} to the history tree of epoch {}",
+                block_number, epoch_number
+            );
+            return Err(PushError::HistoryError);
+        }
+        Ok(())
+    }
+
+    /// Removes the history of all epochs that are no longer retained once the block at the given
+    /// block number is committed, if the history is pruned. Election blocks are final, so the
+    /// pruned history is never needed to revert blocks.
+    pub(crate) fn prune_history(&self, txn: &mut WriteTransaction, block_number: u32) {
+        self.history_store
+            .prune(txn, self.history_mode, block_number);
+    }
+
     /// Reverts the accounts given a block. This only applies to micro blocks, since macro blocks
     /// are final and can't be reverted.
     pub(crate) fn revert_accounts(
@@ -131,6 +208,13 @@ impl Blockchain {
             accounts.get_root(Some(txn)),
             "Failed to revert - inconsistent state"
         );
+        // The state root is maintained incrementally. Cross-check it against a full recompute of
+        // the accounts tree in debug builds.
+        debug_assert_eq!(
+            micro_block.header.state_root,
+            accounts.tree.compute_root_hash(txn),
+            "Failed to revert - incrementally maintained state root is inconsistent"
+        );
 
         debug!(
             "Reverting block #{}.{}",
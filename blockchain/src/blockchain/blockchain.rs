@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use parking_lot::Mutex;
+
 use nimiq_account::{Account, Accounts};
 use nimiq_block::Block;
 use nimiq_database::{Environment, ReadTransaction, WriteTransaction};
@@ -10,14 +12,17 @@ use nimiq_primitives::networks::NetworkId;
 use nimiq_primitives::policy;
 use nimiq_primitives::slots::Validators;
 use nimiq_utils::observer::Notifier;
-use nimiq_utils::time::OffsetTime;
+use nimiq_utils::time::Clock;
 
+use crate::blockchain::slots::ProposerCache;
+use crate::blockchain::verify::ViewChangeProofCache;
 use crate::blockchain_state::BlockchainState;
 use crate::chain_info::ChainInfo;
 #[cfg(feature = "metrics")]
 use crate::chain_metrics::BlockchainMetrics;
 use crate::chain_store::ChainStore;
-use crate::history_store::HistoryStore;
+use crate::checkpoint::Checkpoint;
+use crate::history_store::{HistoryMode, HistoryStore};
 use crate::reward::genesis_parameters;
 use crate::{BlockchainError, BlockchainEvent, ForkEvent};
 use nimiq_trie::key_nibbles::KeyNibbles;
@@ -29,8 +34,8 @@ pub struct Blockchain {
     env: Environment,
     // The network ID. It determines if this is the mainnet or one of the testnets.
     pub network_id: NetworkId,
-    // The OffsetTime struct. It allows us to query the current time.
-    pub time: Arc<OffsetTime>, // shared with network
+    // The clock. It allows us to query the current time.
+    pub time: Arc<dyn Clock>, // shared with network
     // The notifier processes events relative to the blockchain.
     pub notifier: Notifier<BlockchainEvent>,
     // The fork notifier processes fork events.
@@ -43,6 +48,14 @@ pub struct Blockchain {
     pub state: BlockchainState,
     // A reference to a "function" to test whether a given transaction is known and valid.
     pub tx_verification_cache: Arc<dyn TransactionVerificationCache>,
+    // The maximum number of micro blocks that may be reverted when rebranching to a fork.
+    pub max_reorg_depth: u32,
+    // Determines whether the full history is retained or only the history of recent epochs.
+    pub history_mode: HistoryMode,
+    // Cache of the proposer slots for the current batch.
+    pub(crate) proposer_cache: Mutex<ProposerCache>,
+    // Cache of the view change proofs that were already verified in the current batch.
+    pub(crate) view_change_proof_cache: Mutex<ViewChangeProofCache>,
     // The metrics for the blockchain. Needed for analysis.
     #[cfg(feature = "metrics")]
     pub(crate) metrics: BlockchainMetrics,
@@ -54,11 +67,18 @@ pub struct Blockchain {
 
 /// Implements methods to start a Blockchain.
 impl Blockchain {
+    /// The default maximum reorg depth. Rebranching can't go past the last macro block anyway,
+    /// so by default this doesn't restrict rebranching any further.
+    pub const DEFAULT_MAX_REORG_DEPTH: u32 = policy::BATCH_LENGTH;
+
+    /// The default number of recent blocks whose bodies are kept in memory.
+    pub const DEFAULT_BODY_CACHE_SIZE: u32 = ChainStore::DEFAULT_BODY_CACHE_SIZE;
+
     /// Creates a new blockchain from a given environment and network ID.
     pub fn new(
         env: Environment,
         network_id: NetworkId,
-        time: Arc<OffsetTime>,
+        time: Arc<dyn Clock>,
     ) -> Result<Self, BlockchainError> {
         let network_info = NetworkInfo::from_network_id(network_id);
         let genesis_block = network_info.genesis_block::<Block>();
@@ -69,7 +89,7 @@ impl Blockchain {
     /// Creates a new blockchain with the given genesis block.
     pub fn with_genesis(
         env: Environment,
-        time: Arc<OffsetTime>,
+        time: Arc<dyn Clock>,
         network_id: NetworkId,
         genesis_block: Block,
         genesis_accounts: Vec<(KeyNibbles, Account)>,
@@ -78,39 +98,98 @@ impl Blockchain {
         let history_store = HistoryStore::new(env.clone());
 
         Ok(match chain_store.get_head(None) {
-            Some(head_hash) => Blockchain::load(
+            Some(head_hash) => {
+                let genesis_hash = genesis_block.hash();
+                Blockchain::load(
+                    env,
+                    chain_store,
+                    history_store,
+                    time,
+                    network_id,
+                    genesis_block,
+                    genesis_hash,
+                    head_hash,
+                )?
+            }
+            None => Blockchain::init(
                 env,
                 chain_store,
                 history_store,
                 time,
                 network_id,
                 genesis_block,
-                head_hash,
+                genesis_accounts,
             )?,
-            None => Blockchain::init(
+        })
+    }
+
+    /// Creates a new blockchain that starts at the given checkpoint instead of the genesis block.
+    /// The checkpoint must be trusted, see `Checkpoint`. The checkpoint is only used if the
+    /// database is empty. Otherwise the stored chain is loaded as is, which must have been started
+    /// either from the genesis block or from the same checkpoint.
+    pub fn with_trusted_checkpoint(
+        env: Environment,
+        network_id: NetworkId,
+        time: Arc<dyn Clock>,
+        checkpoint: Checkpoint,
+    ) -> Result<Self, BlockchainError> {
+        let network_info = NetworkInfo::from_network_id(network_id);
+        let genesis_block = network_info.genesis_block::<Block>();
+
+        let chain_store = ChainStore::new(env.clone());
+        let history_store = HistoryStore::new(env.clone());
+
+        Ok(match chain_store.get_head(None) {
+            Some(head_hash) => {
+                let genesis_hash = genesis_block.hash();
+                let started_from_genesis = chain_store
+                    .get_chain_info(&genesis_hash, false, None)
+                    .map_or(false, |info| info.on_main_chain);
+                let root_hash = if started_from_genesis {
+                    log::info!("The database already contains a chain, ignoring the checkpoint");
+                    genesis_hash
+                } else {
+                    checkpoint.election_block.hash()
+                };
+
+                Blockchain::load(
+                    env,
+                    chain_store,
+                    history_store,
+                    time,
+                    network_id,
+                    genesis_block,
+                    root_hash,
+                    head_hash,
+                )?
+            }
+            None => Blockchain::init_from_checkpoint(
                 env,
                 chain_store,
                 history_store,
                 time,
                 network_id,
                 genesis_block,
-                genesis_accounts,
+                checkpoint,
             )?,
         })
     }
 
-    /// Loads a blockchain from given inputs.
+    /// Loads a blockchain from given inputs. The stored chain must start at the block with the
+    /// given `root_hash`, which is either the genesis block or a checkpoint.
+    #[allow(clippy::too_many_arguments)]
     fn load(
         env: Environment,
         chain_store: ChainStore,
         history_store: HistoryStore,
-        time: Arc<OffsetTime>,
+        time: Arc<dyn Clock>,
         network_id: NetworkId,
         genesis_block: Block,
+        root_hash: Blake2bHash,
         head_hash: Blake2bHash,
     ) -> Result<Self, BlockchainError> {
-        // Check that the correct genesis block is stored.
-        let genesis_info = chain_store.get_chain_info(&genesis_block.hash(), false, None);
+        // Check that the correct genesis block (or checkpoint) is stored.
+        let genesis_info = chain_store.get_chain_info(&root_hash, false, None);
         if !genesis_info
             .as_ref()
             .map(|i| i.on_main_chain)
@@ -208,6 +287,10 @@ impl Blockchain {
                 previous_slots: last_slots,
             },
             tx_verification_cache: Arc::new(DEFAULT_TX_VERIFICATION_CACHE),
+            max_reorg_depth: Self::DEFAULT_MAX_REORG_DEPTH,
+            history_mode: HistoryMode::default(),
+            proposer_cache: Mutex::default(),
+            view_change_proof_cache: Mutex::default(),
             #[cfg(feature = "metrics")]
             metrics: BlockchainMetrics::default(),
             genesis_supply,
@@ -220,7 +303,7 @@ impl Blockchain {
         env: Environment,
         chain_store: ChainStore,
         history_store: HistoryStore,
-        time: Arc<OffsetTime>,
+        time: Arc<dyn Clock>,
         network_id: NetworkId,
         genesis_block: Block,
         genesis_accounts: Vec<(KeyNibbles, Account)>,
@@ -264,6 +347,96 @@ impl Blockchain {
                 previous_slots: Some(Validators::default()),
             },
             tx_verification_cache: Arc::new(DEFAULT_TX_VERIFICATION_CACHE),
+            max_reorg_depth: Self::DEFAULT_MAX_REORG_DEPTH,
+            history_mode: HistoryMode::default(),
+            proposer_cache: Mutex::default(),
+            view_change_proof_cache: Mutex::default(),
+            #[cfg(feature = "metrics")]
+            metrics: BlockchainMetrics::default(),
+            genesis_supply,
+            genesis_timestamp,
+        })
+    }
+
+    /// Initializes a blockchain from a checkpoint.
+    fn init_from_checkpoint(
+        env: Environment,
+        chain_store: ChainStore,
+        history_store: HistoryStore,
+        time: Arc<dyn Clock>,
+        network_id: NetworkId,
+        genesis_block: Block,
+        checkpoint: Checkpoint,
+    ) -> Result<Self, BlockchainError> {
+        checkpoint.verify()?;
+
+        let election_block = checkpoint.election_block;
+        let previous_election_block = checkpoint.previous_election_block;
+        let head_hash = election_block.hash();
+
+        let current_slots = election_block.get_validators();
+        let previous_slots = previous_election_block.get_validators();
+        // The rewards still depend on the actual genesis block.
+        let (genesis_supply, genesis_timestamp) =
+            genesis_parameters(&genesis_block.unwrap_macro().header);
+
+        let mut main_chain = ChainInfo::new(Block::Macro(election_block.clone()), true);
+        main_chain.cum_tx_fees = checkpoint.cum_tx_fees;
+
+        // Initialize accounts and check them against the state root of the checkpoint.
+        let accounts = Accounts::new(env.clone());
+        let mut txn = WriteTransaction::new(&env);
+        accounts.init(&mut txn, checkpoint.accounts);
+
+        let accounts_hash = accounts.get_root(Some(&txn));
+        if accounts_hash != election_block.header.state_root {
+            log::error!(
+                "Checkpoint state root: {:?}, Account state root: {:?}",
+                election_block.header.state_root,
+                accounts_hash
+            );
+            txn.abort();
+            return Err(BlockchainError::InvalidCheckpoint(
+                "accounts don't match the state root of the election block",
+            ));
+        }
+
+        // Store the checkpoint blocks.
+        let previous_election_info = ChainInfo::new(Block::Macro(previous_election_block), true);
+        chain_store.put_chain_info(
+            &mut txn,
+            &previous_election_info.head.hash(),
+            &previous_election_info,
+            true,
+        );
+        chain_store.put_chain_info(&mut txn, &head_hash, &main_chain, true);
+        chain_store.set_head(&mut txn, &head_hash);
+        txn.commit();
+
+        Ok(Blockchain {
+            env,
+            network_id,
+            time,
+            notifier: Notifier::new(),
+            fork_notifier: Notifier::new(),
+            chain_store,
+            history_store,
+            state: BlockchainState {
+                accounts,
+                macro_info: main_chain.clone(),
+                main_chain,
+                head_hash: head_hash.clone(),
+                macro_head_hash: head_hash.clone(),
+                election_head: election_block,
+                election_head_hash: head_hash,
+                current_slots,
+                previous_slots,
+            },
+            tx_verification_cache: Arc::new(DEFAULT_TX_VERIFICATION_CACHE),
+            max_reorg_depth: Self::DEFAULT_MAX_REORG_DEPTH,
+            history_mode: HistoryMode::default(),
+            proposer_cache: Mutex::default(),
+            view_change_proof_cache: Mutex::default(),
             #[cfg(feature = "metrics")]
             metrics: BlockchainMetrics::default(),
             genesis_supply,
@@ -278,6 +451,12 @@ impl Blockchain {
     pub fn write_transaction(&self) -> WriteTransaction {
         WriteTransaction::new(&self.env)
     }
+
+    /// Returns whether the database files can't be written to anymore. In this state, no blocks
+    /// are pushed, but the blockchain keeps serving reads.
+    pub fn is_read_only_filesystem(&self) -> bool {
+        self.env.is_read_only_filesystem()
+    }
 }
 
 pub trait TransactionVerificationCache: Send + Sync {
@@ -1,7 +1,7 @@
 use parking_lot::{RwLockUpgradableReadGuard, RwLockWriteGuard};
 
 use nimiq_block::{Block, BlockError, TendermintProof};
-use nimiq_database::WriteTransaction;
+use nimiq_database::{DatabaseError, WriteTransaction};
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_primitives::coin::Coin;
 use nimiq_primitives::policy;
@@ -38,6 +38,12 @@ impl Blockchain {
             "You can't push micro blocks with history sync!"
         );
 
+        // Don't process any blocks once the database files can't be written to anymore.
+        if this.is_read_only_filesystem() {
+            debug!("Not pushing block {} - database is read-only", block);
+            return Err(PushError::DatabaseError(DatabaseError::ReadOnlyFilesystem));
+        }
+
         // Create a new database read transaction.
         let read_txn = this.read_transaction();
 
@@ -313,14 +319,16 @@ impl Blockchain {
         this.chain_store.clear_receipts(&mut txn);
 
         // Store the new extended transactions into the History tree.
-        this.history_store.add_to_history(
-            &mut txn,
-            policy::epoch_at(block.block_number()),
-            &ext_txs[first_new_ext_tx..],
-        );
+        if let Err(e) =
+            this.add_to_history(&mut txn, block.block_number(), &ext_txs[first_new_ext_tx..])
+        {
+            txn.abort();
+            return Err(e);
+        }
+        this.prune_history(&mut txn, block.block_number());
 
         // Give up database transactions and push lock before creating notifications.
-        txn.commit();
+        txn.try_commit()?;
 
         // Update the blockchain state.
         let mut this = RwLockUpgradableReadGuard::upgrade(this);
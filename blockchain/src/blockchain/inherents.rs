@@ -1,6 +1,6 @@
-use beserial::Serialize;
+use beserial::{Deserialize, Serialize};
 use nimiq_account::{Inherent, InherentType, StakingContract};
-use nimiq_block::{ForkProof, MacroHeader, ViewChanges};
+use nimiq_block::{Block, ForkProof, MacroHeader, ViewChanges};
 use nimiq_database as db;
 use nimiq_keys::Address;
 use nimiq_primitives::coin::Coin;
@@ -10,10 +10,27 @@ use nimiq_vrf::{AliasMethod, VrfUseCase};
 
 use crate::blockchain_state::BlockchainState;
 use crate::reward::block_reward_for_batch;
-use crate::Blockchain;
+use crate::{AbstractBlockchain, Blockchain};
 use nimiq_primitives::account::AccountType;
 use nimiq_trie::key_nibbles::KeyNibbles;
 
+/// The reason why a slot was slashed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlashReason {
+    /// The slot owner produced two different blocks for the same slot.
+    Fork,
+    /// The slot owner didn't produce its block in time, so that a view change happened.
+    ViewChange,
+}
+
+/// A slash applied by a micro block, decoded from the corresponding slash inherent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Slash {
+    /// The slashed slot, its owner and the block at which the slashable action occurred.
+    pub slot: SlashedSlot,
+    pub reason: SlashReason,
+}
+
 /// Implements methods that create inherents.
 impl Blockchain {
     pub fn create_macro_block_inherents(
@@ -58,6 +75,40 @@ impl Blockchain {
         inherents
     }
 
+    /// Returns the slashes that the micro block at the given block number applied, in the order of
+    /// its slash inherents. Returns `None` if there is no micro block with a body at this block
+    /// number in the main chain.
+    pub fn get_slashes(&self, block_number: u32) -> Option<Vec<Slash>> {
+        let read_txn = self.read_transaction();
+        let num_fork_proofs = match self.get_block_at(block_number, true, Some(&read_txn))? {
+            Block::Micro(micro_block) => micro_block.body?.fork_proofs.len(),
+            Block::Macro(_) => return None,
+        };
+
+        // The slash inherents of the fork proofs precede the ones of the view changes, see
+        // `create_slash_inherents`.
+        let slashes = self
+            .history_store
+            .get_block_transactions(block_number, Some(&read_txn))
+            .iter()
+            .filter(|ext_tx| ext_tx.is_inherent())
+            .map(|ext_tx| ext_tx.unwrap_inherent())
+            .filter(|inherent| inherent.ty == InherentType::Slash)
+            .enumerate()
+            .map(|(i, inherent)| Slash {
+                slot: SlashedSlot::deserialize_from_vec(&inherent.data)
+                    .expect("Corrupted store: Invalid slash inherent"),
+                reason: if i < num_fork_proofs {
+                    SlashReason::Fork
+                } else {
+                    SlashReason::ViewChange
+                },
+            })
+            .collect();
+
+        Some(slashes)
+    }
+
     /// It creates a slash inherent from a fork proof. It expects a *verified* fork proof!
     pub fn inherent_from_fork_proof(
         &self,
@@ -3,6 +3,7 @@ pub mod accounts;
 pub mod blockchain;
 pub mod history_sync;
 pub mod inherents;
+pub mod parallel_verify;
 pub mod push;
 pub mod slots;
 pub mod verify;
@@ -0,0 +1,262 @@
+use rayon::prelude::*;
+
+use nimiq_block::{Block, ViewChange, ViewChangeProof};
+use nimiq_hash::Blake2bHash;
+use nimiq_keys::PublicKey as SchnorrPublicKey;
+use nimiq_primitives::networks::NetworkId;
+use nimiq_primitives::slots::Validators;
+use nimiq_vrf::VrfSeed;
+
+use crate::blockchain::verify::ViewChangeProofCache;
+use crate::{AbstractBlockchain, Blockchain};
+
+/// A block that went through the parallel signature verification stage. It is pushed into the
+/// chain with `Blockchain::push_verified`.
+#[derive(Clone, Debug)]
+pub struct VerifiedBlock {
+    pub(crate) block: Block,
+    pub(crate) signatures_verified: bool,
+    // The macro block number the verification was prepared at, the cache key of the view change
+    // proof and whether it was valid, if the proof was verified in the parallel stage.
+    pub(crate) view_change_proof_verification: Option<(u32, Blake2bHash, bool)>,
+}
+
+impl VerifiedBlock {
+    /// Returns whether the signatures of this block were verified ahead of the push. If they
+    /// weren't, the block is fully verified when it is pushed.
+    pub fn signatures_verified(&self) -> bool {
+        self.signatures_verified
+    }
+
+    pub fn block(&self) -> &Block {
+        &self.block
+    }
+
+    pub fn into_block(self) -> Block {
+        self.block
+    }
+}
+
+/// A sequence of blocks prepared for the parallel signature verification by
+/// `Blockchain::prepare_signature_verification`. It holds everything the verification needs, so
+/// `verify` runs without access to the blockchain and the blockchain lock can be released first.
+pub struct SignatureVerification {
+    network_id: NetworkId,
+    validators: Option<Validators>,
+    macro_block_number: u32,
+    jobs: Vec<SignatureVerificationJob>,
+}
+
+struct SignatureVerificationJob {
+    block: Block,
+    // The seed of the previous block and the signing key of the proposer, if it is known.
+    proposer: Option<(VrfSeed, SchnorrPublicKey)>,
+    // The view change of the block and the cache key of its proof, if the block has one.
+    view_change_proof: Option<(ViewChange, Blake2bHash)>,
+    // Whether the view change proof was already found in the cache.
+    view_change_proof_cached: bool,
+}
+
+impl SignatureVerification {
+    /// Verifies the VRF seeds, the justification signatures, the view change proofs and the
+    /// transaction signatures of the blocks on the rayon worker pool. The blocks are returned in
+    /// the same order.
+    ///
+    /// Blocks whose proposer wasn't known when the verification was prepared, as well as blocks
+    /// that fail any of the checks, are returned unverified, so that pushing them performs the
+    /// full verification and reports the actual error.
+    pub fn verify(self) -> Vec<VerifiedBlock> {
+        let network_id = self.network_id;
+        let validators = self.validators.as_ref();
+        let macro_block_number = self.macro_block_number;
+
+        self.jobs
+            .into_par_iter()
+            .map(|job| {
+                let mut verification = None;
+                let signatures_verified = job
+                    .proposer
+                    .as_ref()
+                    .map(|(prev_seed, signing_key)| {
+                        Blockchain::verify_block_signatures(
+                            &job.block,
+                            prev_seed,
+                            signing_key,
+                            network_id,
+                        ) && Self::verify_view_change_proof(&job, validators, &mut verification)
+                    })
+                    .unwrap_or(false);
+
+                VerifiedBlock {
+                    block: job.block,
+                    signatures_verified,
+                    view_change_proof_verification: verification
+                        .map(|(key, valid)| (macro_block_number, key, valid)),
+                }
+            })
+            .collect()
+    }
+
+    /// Verifies the view change proof of a micro block, if it has one and it isn't cached. The
+    /// result of a fresh verification is returned through `verification`, it is added to the
+    /// cache when the block is pushed.
+    fn verify_view_change_proof(
+        job: &SignatureVerificationJob,
+        validators: Option<&Validators>,
+        verification: &mut Option<(Blake2bHash, bool)>,
+    ) -> bool {
+        let (view_change, key) = match &job.view_change_proof {
+            Some(view_change_proof) => view_change_proof,
+            None => return true,
+        };
+        if job.view_change_proof_cached {
+            return true;
+        }
+
+        let proof = match Self::view_change_proof_of(&job.block) {
+            Some(proof) => proof,
+            None => return false,
+        };
+        let valid = match validators {
+            Some(validators) => proof.verify(view_change, validators),
+            None => return false,
+        };
+        *verification = Some((key.clone(), valid));
+        valid
+    }
+
+    fn view_change_proof_of(block: &Block) -> Option<&ViewChangeProof> {
+        match block {
+            Block::Micro(micro_block) => micro_block
+                .justification
+                .as_ref()
+                .and_then(|justification| justification.view_change_proof.as_ref()),
+            Block::Macro(_) => None,
+        }
+    }
+}
+
+/// Implements the verification stage of the block pipeline used when pushing many blocks at once
+/// (e.g. when catching up during sync). Verifying signatures is CPU-bound and independent of the
+/// accounts state, so it is done for all blocks on the rayon worker pool, while the blocks are
+/// still committed one after the other and strictly in order.
+impl Blockchain {
+    /// Looks up what is needed to verify the signatures of a sequence of blocks. Only this step
+    /// needs access to the blockchain, the signatures are verified with
+    /// `SignatureVerification::verify` after the blockchain lock was released.
+    ///
+    /// Only micro blocks whose proposer can already be determined are verified, i.e. those of the
+    /// current batch. Macro blocks and micro blocks of later batches are left for the push.
+    pub fn prepare_signature_verification(&self, blocks: Vec<Block>) -> SignatureVerification {
+        let macro_block_number = self.state.macro_info.head.block_number();
+        let mut cache = self.view_change_proof_cache.lock();
+        cache.invalidate(macro_block_number);
+
+        let read_txn = self.read_transaction();
+        let mut prev_block: Option<(Blake2bHash, VrfSeed)> = None;
+        let jobs = blocks
+            .into_iter()
+            .map(|block| {
+                let prev_seed = match &prev_block {
+                    Some((prev_hash, prev_seed)) if prev_hash == block.parent_hash() => {
+                        Some(prev_seed.clone())
+                    }
+                    _ => self
+                        .get_chain_info(block.parent_hash(), false, Some(&read_txn))
+                        .map(|prev_info| prev_info.head.seed().clone()),
+                };
+                prev_block = Some((block.hash(), block.seed().clone()));
+
+                let proposer = match (&block, prev_seed) {
+                    (Block::Micro(_), Some(prev_seed)) => self
+                        .get_proposer_at(
+                            block.block_number(),
+                            block.view_number(),
+                            prev_seed.entropy(),
+                            Some(&read_txn),
+                        )
+                        .map(|slot| (prev_seed, slot.validator.signing_key)),
+                    _ => None,
+                };
+
+                let view_change_proof = proposer.as_ref().and_then(|(prev_seed, _)| {
+                    let proof = SignatureVerification::view_change_proof_of(&block)?;
+                    let view_change = ViewChange {
+                        block_number: block.block_number(),
+                        new_view_number: block.view_number(),
+                        vrf_entropy: prev_seed.entropy(),
+                    };
+                    let key = ViewChangeProofCache::key(&view_change, proof);
+                    Some((view_change, key))
+                });
+                let view_change_proof_cached = view_change_proof
+                    .as_ref()
+                    .map(|(_, key)| cache.contains(key))
+                    .unwrap_or(false);
+
+                SignatureVerificationJob {
+                    block,
+                    proposer,
+                    view_change_proof,
+                    view_change_proof_cached,
+                }
+            })
+            .collect();
+        read_txn.close();
+
+        SignatureVerification {
+            network_id: self.network_id,
+            validators: self.current_validators(),
+            macro_block_number,
+            jobs,
+        }
+    }
+
+    /// Adds the result of a view change proof verification of the parallel stage to the cache.
+    /// Results of an earlier epoch are dropped, since the cache was cleared in the meantime.
+    pub(crate) fn note_view_change_proof_verification(
+        &self,
+        macro_block_number: u32,
+        key: Blake2bHash,
+        valid: bool,
+    ) {
+        let mut cache = self.view_change_proof_cache.lock();
+        cache.invalidate(self.state.macro_info.head.block_number());
+        if macro_block_number == self.state.macro_info.head.block_number() {
+            cache.note_verification(key, valid);
+        }
+    }
+
+    /// Performs exactly the checks that are skipped when pushing a block with the trusted flag.
+    fn verify_block_signatures(
+        block: &Block,
+        prev_seed: &VrfSeed,
+        signing_key: &SchnorrPublicKey,
+        network_id: NetworkId,
+    ) -> bool {
+        let micro_block = match block {
+            Block::Micro(micro_block) => micro_block,
+            Block::Macro(_) => return false,
+        };
+
+        if block.seed().verify(prev_seed, signing_key).is_err() {
+            return false;
+        }
+
+        let justification = match &micro_block.justification {
+            Some(justification) => justification,
+            None => return false,
+        };
+        if !signing_key.verify(&justification.signature, block.hash().as_slice()) {
+            return false;
+        }
+
+        match &micro_block.body {
+            Some(body) => body
+                .transactions
+                .iter()
+                .all(|tx| tx.verify(network_id).is_ok()),
+            None => false,
+        }
+    }
+}
@@ -1,15 +1,20 @@
 use std::ops::Deref;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
 
 use parking_lot::{RwLockUpgradableReadGuard, RwLockWriteGuard};
 
 use nimiq_block::{Block, ForkProof};
-use nimiq_database::WriteTransaction;
+use nimiq_database::{DatabaseError, WriteTransaction};
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_primitives::policy;
 use nimiq_vrf::VrfEntropy;
 
+use crate::blockchain::parallel_verify::VerifiedBlock;
 use crate::blockchain_state::BlockchainState;
 use crate::chain_info::ChainInfo;
+#[cfg(feature = "metrics")]
+use crate::chain_metrics::BlockProcessingStage;
 use crate::chain_store::MAX_EPOCHS_STORED;
 use crate::{
     AbstractBlockchain, Blockchain, BlockchainEvent, ChainOrdering, ForkEvent, PushError,
@@ -27,6 +32,25 @@ impl Blockchain {
         this: RwLockUpgradableReadGuard<Self>,
         block: Block,
         trusted: bool,
+    ) -> Result<PushResult, PushError> {
+        let lock = RwLockUpgradableReadGuard::rwlock(&this);
+        match Self::try_push(this, block.clone(), trusted) {
+            // The database grows its memory map before the next write transaction, so retry once.
+            Err(PushError::DatabaseError(DatabaseError::MapFull)) => {
+                debug!(
+                    "Database ran out of space while pushing {}, retrying",
+                    block
+                );
+                Self::try_push(lock.upgradable_read(), block, trusted)
+            }
+            result => result,
+        }
+    }
+
+    fn try_push(
+        this: RwLockUpgradableReadGuard<Self>,
+        block: Block,
+        trusted: bool,
     ) -> Result<PushResult, PushError> {
         // Ignore all blocks that precede (or are at the same height) as the most recent accepted
         // macro block.
@@ -39,6 +63,12 @@ impl Blockchain {
             return Ok(PushResult::Ignored);
         }
 
+        // Don't process any blocks once the database files can't be written to anymore.
+        if this.is_read_only_filesystem() {
+            debug!("Not pushing block {} - database is read-only", block);
+            return Err(PushError::DatabaseError(DatabaseError::ReadOnlyFilesystem));
+        }
+
         // TODO: We might want to pass this as argument to this method.
         let read_txn = this.read_transaction();
 
@@ -80,6 +110,9 @@ impl Blockchain {
                 PushError::Orphan
             })?;
 
+        #[cfg(feature = "metrics")]
+        let verification_start = Instant::now();
+
         // Check the header.
         if let Err(e) = Blockchain::verify_block_header(
             this.deref(),
@@ -112,6 +145,12 @@ impl Blockchain {
             return Err(e);
         }
 
+        #[cfg(feature = "metrics")]
+        this.metrics.note_stage_duration(
+            BlockProcessingStage::Verification,
+            verification_start.elapsed(),
+        );
+
         // Detect forks.
         if let Block::Micro(micro_block) = &block {
             // Check if there are two blocks in the same slot and with the same height. Since we already
@@ -191,7 +230,7 @@ impl Blockchain {
         let mut txn = this.write_transaction();
         this.chain_store
             .put_chain_info(&mut txn, &chain_info.head.hash(), &chain_info, true);
-        txn.commit();
+        txn.try_commit()?;
 
         Ok(result)
     }
@@ -224,6 +263,20 @@ impl Blockchain {
         Self::do_push(this, block, true)
     }
 
+    /// Pushes a block that went through `SignatureVerification::verify`. The signature checks are
+    /// only skipped if they already succeeded in the parallel stage, all other checks are
+    /// performed as usual.
+    pub fn push_verified(
+        this: RwLockUpgradableReadGuard<Self>,
+        block: VerifiedBlock,
+    ) -> Result<PushResult, PushError> {
+        if let Some((macro_block_number, key, valid)) = block.view_change_proof_verification {
+            this.note_view_change_proof_verification(macro_block_number, key, valid);
+        }
+
+        Self::do_push(this, block.block, block.signatures_verified)
+    }
+
     /// Extends the current main chain.
     fn extend(
         this: RwLockUpgradableReadGuard<Blockchain>,
@@ -264,7 +317,7 @@ impl Blockchain {
             );
         }
 
-        txn.commit();
+        txn.try_commit()?;
 
         // Upgrade the lock as late as possible.
         let mut this = RwLockUpgradableReadGuard::upgrade(this);
@@ -359,6 +412,16 @@ impl Blockchain {
             return Err(PushError::InvalidFork);
         }
 
+        // Check that we don't need to revert more micro blocks than allowed.
+        let reorg_depth = this.block_number() - ancestor.1.head.block_number();
+        if reorg_depth > this.max_reorg_depth {
+            warn!(
+                "Rejecting block {} - rebranching would revert {} blocks (max {})",
+                target_block, reorg_depth, this.max_reorg_depth
+            );
+            return Err(PushError::ReorgTooDeep);
+        }
+
         let mut write_txn = this.write_transaction();
 
         current = (this.state.head_hash.clone(), this.state.main_chain.clone());
@@ -386,6 +449,10 @@ impl Blockchain {
                         prev_info.head.next_view_number(),
                     )?;
 
+                    if let Some(e) = write_txn.error() {
+                        return Err(PushError::DatabaseError(e));
+                    }
+
                     assert_eq!(
                         prev_info.head.state_root(),
                         &this.state.accounts.get_root(Some(&write_txn)),
@@ -413,6 +480,11 @@ impl Blockchain {
                 prev_view_number,
                 &mut write_txn,
             ) {
+                // The fork blocks aren't invalid if we just failed to store them.
+                if let PushError::DatabaseError(_) = e {
+                    return Err(e);
+                }
+
                 warn!(
                     "Rejecting block {} - failed to apply fork block {} while rebranching: {:?}",
                     target_block, fork_block.1.head, e
@@ -428,7 +500,7 @@ impl Blockchain {
                         fork_block.1.head.block_number(),
                     )
                 }
-                write_txn.commit();
+                write_txn.try_commit()?;
 
                 return Err(PushError::InvalidFork);
             }
@@ -476,7 +548,7 @@ impl Blockchain {
         let new_head_hash = &fork_chain[0].0;
         let new_head_info = &fork_chain[0].1;
         this.chain_store.set_head(&mut write_txn, new_head_hash);
-        write_txn.commit();
+        write_txn.try_commit()?;
 
         // Upgrade the lock as late as possible.
         let mut this = RwLockUpgradableReadGuard::upgrade(this);
@@ -563,7 +635,16 @@ impl Blockchain {
         }
 
         // Commit block to AccountsTree.
-        if let Err(e) = self.commit_accounts(state, block, prev_entropy, first_view_number, txn) {
+        let result = self.commit_accounts(state, block, prev_entropy, first_view_number, txn);
+
+        // If a database write failed, the accounts tree is incomplete. This must not be mistaken
+        // for an invalid block.
+        if let Some(e) = txn.error() {
+            warn!("Failed to commit block {} - database error: {}", block, e);
+            return Err(PushError::DatabaseError(e));
+        }
+
+        if let Err(e) = result {
             warn!("Rejecting block {} - commit failed: {:?}", block, e);
             #[cfg(feature = "metrics")]
             self.metrics.note_invalid_block();
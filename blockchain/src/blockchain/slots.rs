@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use nimiq_account::StakingContract;
 use nimiq_collections::BitSet;
 use nimiq_database::Transaction;
@@ -7,12 +9,23 @@ use nimiq_vrf::{Rng, VrfEntropy, VrfSeed, VrfUseCase};
 
 use crate::{AbstractBlockchain, Blockchain};
 
+#[derive(Clone, Debug)]
 pub struct Slot {
     pub number: u16,
     pub band: u16,
     pub validator: Validator,
 }
 
+/// Memoizes the proposer slots computed by `get_proposer_at`. The entries are only valid as long
+/// as the macro head doesn't change, so the cache is cleared once a new macro block is finalized.
+#[derive(Default)]
+pub(crate) struct ProposerCache {
+    // The block number of the macro head for which the entries were computed.
+    macro_block_number: u32,
+    // The proposer slots indexed by block number, view number and VRF entropy.
+    entries: HashMap<(u32, u32, VrfEntropy), Slot>,
+}
+
 /// Implements methods to handle slots and validators.
 impl Blockchain {
     /// Gets the active validators for a given epoch.
@@ -46,12 +59,46 @@ impl Blockchain {
         )
     }
 
+    /// Gets the proposer slot at the given block number and view number. The result is cached
+    /// until the next macro block is finalized.
     pub fn get_proposer_at(
         &self,
         block_number: u32,
         view_number: u32,
         vrf_entropy: VrfEntropy,
         txn: Option<&Transaction>,
+    ) -> Option<Slot> {
+        let macro_block_number = self.state.macro_info.head.block_number();
+        let key = (block_number, view_number, vrf_entropy);
+
+        {
+            let mut cache = self.proposer_cache.lock();
+
+            // Invalidate the cache if a new macro block was finalized.
+            if cache.macro_block_number != macro_block_number {
+                cache.entries.clear();
+                cache.macro_block_number = macro_block_number;
+            }
+
+            if let Some(slot) = cache.entries.get(&key) {
+                return Some(slot.clone());
+            }
+        }
+
+        let slot = self.compute_proposer_at(block_number, view_number, key.2.clone(), txn)?;
+
+        self.proposer_cache.lock().entries.insert(key, slot.clone());
+
+        Some(slot)
+    }
+
+    /// Computes the proposer slot at the given block number and view number, bypassing the cache.
+    pub fn compute_proposer_at(
+        &self,
+        block_number: u32,
+        view_number: u32,
+        vrf_entropy: VrfEntropy,
+        txn: Option<&Transaction>,
     ) -> Option<Slot> {
         // Fetch the latest macro block that precedes the block at the given block_number.
         // We use the disabled_slots set from that macro block for the slot selection.
@@ -1,12 +1,13 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
 
 use beserial::Serialize;
 use nimiq_block::{
-    Block, BlockBody, BlockError, BlockHeader, BlockType, ForkProof, MacroBlock, MacroBody,
-    TendermintProof, ViewChange,
+    Block, BlockBody, BlockError, BlockHeader, BlockType, ForkProof, ForkProofError, MacroBlock,
+    MacroBody, TendermintProof, ViewChange, ViewChangeProof,
 };
 use nimiq_database::Transaction as DBtx;
-use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_hash::{Blake2bHash, Blake2bHasher, Hash, Hasher};
 use nimiq_keys::PublicKey as SchnorrPublicKey;
 use nimiq_primitives::policy;
 
@@ -15,6 +16,51 @@ use nimiq_transaction::Transaction;
 use crate::blockchain_state::BlockchainState;
 use crate::{AbstractBlockchain, Blockchain, PushError};
 
+/// Remembers the view change proofs that were already verified, so that a block whose proof was
+/// checked in the parallel verification stage doesn't have it checked again when it is pushed.
+/// The proofs are verified against the current validators, so the cache is cleared once a new
+/// macro block is finalized.
+#[derive(Default)]
+pub(crate) struct ViewChangeProofCache {
+    // The block number of the macro head for which the entries were verified.
+    macro_block_number: u32,
+    // The hashes of the verified view changes together with their proofs.
+    verified: HashSet<Blake2bHash>,
+    // The number of proofs that actually had to be verified.
+    num_verifications: usize,
+}
+
+impl ViewChangeProofCache {
+    /// Computes the cache key of a view change proof. It covers the view change as well, since
+    /// the same proof must not be accepted for a different view change.
+    pub(crate) fn key(view_change: &ViewChange, proof: &ViewChangeProof) -> Blake2bHash {
+        let mut data = view_change.serialize_to_vec();
+        proof.serialize(&mut data).unwrap();
+        Blake2bHasher::default().digest(&data)
+    }
+
+    /// Clears the cache if a new macro block was finalized since the entries were added.
+    pub(crate) fn invalidate(&mut self, macro_block_number: u32) {
+        if self.macro_block_number != macro_block_number {
+            self.verified.clear();
+            self.macro_block_number = macro_block_number;
+        }
+    }
+
+    /// Returns whether the proof with the given key was already verified.
+    pub(crate) fn contains(&self, key: &Blake2bHash) -> bool {
+        self.verified.contains(key)
+    }
+
+    /// Records the result of verifying a proof. Only valid proofs are remembered.
+    pub(crate) fn note_verification(&mut self, key: Blake2bHash, valid: bool) {
+        self.num_verifications += 1;
+        if valid {
+            self.verified.insert(key);
+        }
+    }
+}
+
 /// Implements methods to verify the validity of blocks.
 impl Blockchain {
     /// Verifies the header of a block. This function is used when we are pushing a normal block
@@ -193,12 +239,10 @@ impl Blockchain {
                         vrf_entropy: prev_info.head.seed().entropy(),
                     };
 
-                    if !justification
-                        .view_change_proof
-                        .as_ref()
-                        .unwrap()
-                        .verify(&view_change, &blockchain.current_validators().unwrap())
-                    {
+                    if !blockchain.verify_view_change_proof(
+                        &view_change,
+                        justification.view_change_proof.as_ref().unwrap(),
+                    ) {
                         warn!("Rejecting block {} - bad view change proof", block);
                         return Err(PushError::InvalidBlock(BlockError::InvalidViewChangeProof));
                     }
@@ -224,6 +268,64 @@ impl Blockchain {
         Ok(())
     }
 
+    /// Verifies a view change proof against the current validators. A proof that was already
+    /// verified is not verified again until the next macro block is finalized.
+    pub fn verify_view_change_proof(
+        &self,
+        view_change: &ViewChange,
+        proof: &ViewChangeProof,
+    ) -> bool {
+        let key = ViewChangeProofCache::key(view_change, proof);
+        {
+            let mut cache = self.view_change_proof_cache.lock();
+            cache.invalidate(self.state.macro_info.head.block_number());
+            if cache.contains(&key) {
+                return true;
+            }
+        }
+
+        let valid = proof.verify(view_change, &self.current_validators().unwrap());
+
+        self.view_change_proof_cache
+            .lock()
+            .note_verification(key, valid);
+
+        valid
+    }
+
+    /// Returns the number of view change proofs that had to be verified, i.e. that weren't
+    /// found in the cache.
+    pub fn num_view_change_proof_verifications(&self) -> usize {
+        self.view_change_proof_cache.lock().num_verifications
+    }
+
+    /// Verifies a fork proof as if it was included in a block at the given block number, i.e. that
+    /// the fork is within the reporting window and that both headers were signed by the intended
+    /// slot owner.
+    pub fn verify_fork_proof(
+        &self,
+        proof: &ForkProof,
+        block_number: u32,
+        txn_opt: Option<&DBtx>,
+    ) -> Result<(), ForkProofError> {
+        // Check that the proof is within the reporting window.
+        if !proof.is_valid_at(block_number) {
+            return Err(ForkProofError::OutsideReportingWindow);
+        }
+
+        // Get intended slot owner for that block.
+        let slot = self
+            .get_proposer_at(
+                proof.header1.block_number,
+                proof.header1.view_number,
+                proof.prev_vrf_seed.entropy(),
+                txn_opt,
+            )
+            .ok_or(ForkProofError::UnknownSlotOwner)?;
+
+        proof.verify(&slot.validator.signing_key)
+    }
+
     /// Verifies the body of a block.
     /// This only performs checks that can be made BEFORE the state is updated with the block. All
     /// checks that require the updated state (ex: if an account has enough funds) are made on the
@@ -287,32 +389,12 @@ impl Blockchain {
                         }
                     }
 
-                    // Check that the proof is within the reporting window.
-                    if !proof.is_valid_at(header.block_number()) {
+                    if let Err(e) = self.verify_fork_proof(proof, header.block_number(), txn_opt) {
+                        warn!("Rejecting block {} - bad fork proof: {}", header, e);
                         return Err(PushError::InvalidBlock(BlockError::InvalidForkProof));
                     }
 
-                    // Get intended slot owner for that block.
-                    if let Some(slot) = self.get_proposer_at(
-                        proof.header1.block_number,
-                        proof.header1.view_number,
-                        proof.prev_vrf_seed.entropy(),
-                        txn_opt,
-                    ) {
-                        // Verify fork proof.
-                        if let Err(e) = proof.verify(&slot.validator.signing_key) {
-                            warn!("Rejecting block {} - bad fork proof: {:?}", header, e);
-                            return Err(PushError::InvalidBlock(BlockError::InvalidForkProof));
-                        }
-
-                        previous_proof = Some(proof);
-                    } else {
-                        warn!(
-                            "Rejecting block {} - bad fork proof: Couldn't calculate slot owner",
-                            header
-                        );
-                        return Err(PushError::InvalidBlock(BlockError::InvalidForkProof));
-                    }
+                    previous_proof = Some(proof);
                 }
 
                 // Verify transactions.
@@ -336,6 +418,18 @@ impl Blockchain {
                         }
                     }
 
+                    // Check the size of the transaction.
+                    let tx_size = tx.serialized_size();
+                    if tx_size > policy::MAX_SIZE_TRANSACTION {
+                        warn!(
+                            "Rejecting block {} - transaction size exceeds maximum size ({} > {})",
+                            header,
+                            tx_size,
+                            policy::MAX_SIZE_TRANSACTION
+                        );
+                        return Err(PushError::TransactionTooLarge);
+                    }
+
                     // Check that the transaction is within its validity window.
                     if !tx.is_valid_at(header.block_number()) {
                         return Err(PushError::InvalidBlock(BlockError::ExpiredTransaction));
@@ -1,4 +1,4 @@
-use nimiq_account::{Account, StakingContract};
+use nimiq_account::{Account, Inherent, InherentType, StakingContract};
 use nimiq_block::Block;
 use nimiq_database::Transaction;
 use nimiq_hash::Blake2bHash;
@@ -6,11 +6,14 @@ use nimiq_keys::Address;
 use nimiq_primitives::policy;
 use nimiq_utils::observer::{Listener, ListenerHandle};
 
-use crate::blockchain_state::BlockchainState;
+use crate::blockchain_state::{BlockchainState, HeadSnapshot};
 #[cfg(feature = "metrics")]
 use crate::chain_metrics::BlockchainMetrics;
-use crate::{AbstractBlockchain, Blockchain, BlockchainEvent, Direction};
+use crate::{
+    AbstractBlockchain, Blockchain, BlockchainError, BlockchainEvent, ChainStatus, Direction,
+};
 use nimiq_trie::key_nibbles::KeyNibbles;
+use nimiq_trie::trie_proof::TrieProof;
 
 /// Implements several wrapper functions.
 impl Blockchain {
@@ -19,6 +22,65 @@ impl Blockchain {
         &self.state
     }
 
+    /// Returns a snapshot of the head of the main chain. Use this instead of reading the block
+    /// number and hash separately when they need to be consistent.
+    pub fn head_snapshot(&self) -> HeadSnapshot {
+        let head = &self.state.main_chain.head;
+        HeadSnapshot {
+            block_number: head.block_number(),
+            hash: self.state.head_hash.clone(),
+            timestamp: head.timestamp(),
+            view_number: head.view_number(),
+        }
+    }
+
+    /// Makes sure that the body of the given block is present, loading it from the chain store if
+    /// necessary. Fails if the body isn't stored either, e.g. because the block was pruned or only
+    /// its header was synced. The block is left unchanged in that case.
+    pub fn load_block_body(
+        &self,
+        block: &mut Block,
+        txn_option: Option<&Transaction>,
+    ) -> Result<(), BlockchainError> {
+        if block.has_body() {
+            return Ok(());
+        }
+
+        let hash = block.hash();
+        match self.chain_store.get_block(&hash, true, txn_option) {
+            Some(full_block) if full_block.has_body() => {
+                *block = full_block;
+                Ok(())
+            }
+            _ => Err(BlockchainError::BlockBodyNotAvailable(hash)),
+        }
+    }
+
+    /// Checks that the history at the given block number hasn't been pruned. History queries for
+    /// pruned epochs would otherwise silently return no transactions.
+    pub fn check_history_available(&self, block_number: u32) -> Result<(), BlockchainError> {
+        if self
+            .history_mode
+            .is_history_available(self.block_number(), block_number)
+        {
+            Ok(())
+        } else {
+            Err(BlockchainError::HistoryPruned(policy::epoch_at(
+                block_number,
+            )))
+        }
+    }
+
+    /// Returns whether the block with the given hash is on the main chain or on a fork. Blocks that
+    /// were pruned from the chain store are reported as unknown.
+    pub fn get_chain_status(&self, hash: &Blake2bHash) -> ChainStatus {
+        match self.chain_store.get_chain_info(hash, false, None) {
+            Some(chain_info) if chain_info.on_main_chain => ChainStatus::Main,
+            Some(_) => ChainStatus::Fork,
+            None => ChainStatus::Unknown,
+        }
+    }
+
     /// Fetches a given number of blocks, starting at a specific block (by its hash).
     pub fn get_blocks(
         &self,
@@ -52,6 +114,30 @@ impl Blockchain {
         )
     }
 
+    /// Returns the reward inherents of the macro block at the given block number, i.e. the rewards
+    /// for the batch preceding it, including the burned reward. Returns `None` if there is no
+    /// macro block at this block number in the main chain.
+    pub fn get_reward_inherents(&self, block_number: u32) -> Option<Vec<Inherent>> {
+        if !policy::is_macro_block_at(block_number) {
+            return None;
+        }
+
+        let read_txn = self.read_transaction();
+        self.chain_store
+            .get_block_at(block_number, false, Some(&read_txn))?;
+
+        let inherents = self
+            .history_store
+            .get_block_transactions(block_number, Some(&read_txn))
+            .into_iter()
+            .filter(|ext_tx| ext_tx.is_inherent())
+            .map(|ext_tx| ext_tx.unwrap_inherent().clone())
+            .filter(|inherent| inherent.ty == InherentType::Reward)
+            .collect();
+
+        Some(inherents)
+    }
+
     /// Returns the current staking contract.
     pub fn get_staking_contract(&self) -> StakingContract {
         let staking_contract_address = StakingContract::get_key_staking_contract();
@@ -78,14 +164,24 @@ impl Blockchain {
     }
 
     pub fn get_account(&self, address: &Address) -> Option<Account> {
+        self.state.accounts.get(&Self::account_key(address), None)
+    }
+
+    /// Produces a Merkle proof of the inclusion (or exclusion, if it doesn't exist) of the account
+    /// at the given address against the state root of the current head.
+    pub fn get_account_proof(&self, address: &Address) -> TrieProof<Account> {
+        self.state
+            .accounts
+            .get_proof(&Self::account_key(address), None)
+    }
+
+    fn account_key(address: &Address) -> KeyNibbles {
         // TODO: Find a better place for this differentiation, it should be in a more general location.
-        let key = if *address == policy::STAKING_CONTRACT_ADDRESS {
+        if *address == policy::STAKING_CONTRACT_ADDRESS {
             StakingContract::get_key_staking_contract()
         } else {
             KeyNibbles::from(address)
-        };
-
-        self.state.accounts.get(&key, None)
+        }
     }
 
     /// Checks if we have seen some transaction with this hash inside the a validity window.
@@ -27,3 +27,17 @@ pub struct BlockchainState {
     // The validator slots for the previous epoch.
     pub previous_slots: Option<Validators>,
 }
+
+/// The head of the main chain as seen at a single point in time. All fields are taken under the
+/// same lock, so they always belong to the same block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeadSnapshot {
+    // The block number of the head.
+    pub block_number: u32,
+    // The hash of the head.
+    pub hash: Blake2bHash,
+    // The timestamp of the head.
+    pub timestamp: u64,
+    // The view number of the head.
+    pub view_number: u32,
+}
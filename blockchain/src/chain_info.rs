@@ -11,6 +11,17 @@ use nimiq_hash::Blake2bHash;
 use nimiq_primitives::coin::Coin;
 use nimiq_primitives::policy;
 
+/// Whether a block is part of the main chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainStatus {
+    /// The block is on the main chain.
+    Main,
+    /// The block is known, but on a fork of the main chain.
+    Fork,
+    /// The block is unknown, or it has been pruned from the chain store.
+    Unknown,
+}
+
 /// Struct that, for each block, keeps information relative to the chain the block is on.
 #[derive(Clone, Debug)]
 pub struct ChainInfo {
@@ -1,7 +1,92 @@
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
 
 use crate::{PushError, PushResult};
 
+/// The stages of pushing a block for which the processing time is recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockProcessingStage {
+    /// Verifying the header, justification and body of the block.
+    Verification,
+    /// Committing the block to the accounts tree.
+    CommitAccounts,
+    /// Adding the transactions and inherents of the block to the history tree.
+    HistoryUpdate,
+}
+
+impl BlockProcessingStage {
+    pub const ALL: [BlockProcessingStage; 3] = [
+        BlockProcessingStage::Verification,
+        BlockProcessingStage::CommitAccounts,
+        BlockProcessingStage::HistoryUpdate,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A histogram over the most recent durations recorded for a stage.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    /// The number of durations in the window.
+    pub count: usize,
+    /// The sum of all durations in the window.
+    pub sum: Duration,
+    /// The cumulative number of durations that are less or equal to each bound in
+    /// `StageTimings::BUCKETS`. Durations above the largest bound are only included in `count`.
+    pub buckets: Vec<(Duration, usize)>,
+}
+
+impl StageTimings {
+    /// The upper bounds of the histogram buckets.
+    pub const BUCKETS: [Duration; 6] = [
+        Duration::from_micros(100),
+        Duration::from_millis(1),
+        Duration::from_millis(10),
+        Duration::from_millis(100),
+        Duration::from_secs(1),
+        Duration::from_secs(10),
+    ];
+}
+
+/// Keeps the most recent `WINDOW_SIZE` durations of a stage. The histogram is only computed when
+/// it is read, so recording a duration is cheap.
+#[derive(Default)]
+struct RollingTimings {
+    durations: VecDeque<Duration>,
+}
+
+impl RollingTimings {
+    const WINDOW_SIZE: usize = 1024;
+
+    fn record(&mut self, duration: Duration) {
+        if self.durations.len() == Self::WINDOW_SIZE {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(duration);
+    }
+
+    fn snapshot(&self) -> StageTimings {
+        let buckets = StageTimings::BUCKETS
+            .iter()
+            .map(|bound| {
+                let count = self.durations.iter().filter(|d| *d <= bound).count();
+                (*bound, count)
+            })
+            .collect();
+
+        StageTimings {
+            count: self.durations.len(),
+            sum: self.durations.iter().sum(),
+            buckets,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct BlockchainMetrics {
     block_invalid_count: AtomicUsize,
@@ -11,6 +96,7 @@ pub struct BlockchainMetrics {
     block_rebranched_count: AtomicUsize,
     block_forked_count: AtomicUsize,
     block_ignored_count: AtomicUsize,
+    stage_timings: [Mutex<RollingTimings>; 3],
 }
 
 impl BlockchainMetrics {
@@ -96,4 +182,58 @@ impl BlockchainMetrics {
     pub fn block_forked_count(&self) -> usize {
         self.block_forked_count.load(Ordering::Acquire)
     }
+
+    #[inline]
+    pub fn note_stage_duration(&self, stage: BlockProcessingStage, duration: Duration) {
+        self.stage_timings[stage.index()].lock().record(duration);
+    }
+
+    /// Returns a histogram of the most recent processing times of the given stage.
+    pub fn stage_timings(&self, stage: BlockProcessingStage) -> StageTimings {
+        self.stage_timings[stage.index()].lock().snapshot()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_timings_are_a_rolling_histogram() {
+        let metrics = BlockchainMetrics::default();
+        let stage = BlockProcessingStage::CommitAccounts;
+        assert_eq!(metrics.stage_timings(stage).count, 0);
+
+        metrics.note_stage_duration(stage, Duration::from_micros(50));
+        metrics.note_stage_duration(stage, Duration::from_millis(5));
+        metrics.note_stage_duration(stage, Duration::from_secs(20));
+
+        let timings = metrics.stage_timings(stage);
+        assert_eq!(timings.count, 3);
+        assert_eq!(
+            timings.sum,
+            Duration::from_micros(50) + Duration::from_millis(5) + Duration::from_secs(20)
+        );
+        let counts: Vec<usize> = timings.buckets.iter().map(|(_, count)| *count).collect();
+        assert_eq!(counts, vec![1, 1, 2, 2, 2, 2]);
+
+        // Other stages are tracked separately.
+        assert_eq!(
+            metrics.stage_timings(BlockProcessingStage::Verification),
+            StageTimings {
+                count: 0,
+                sum: Duration::ZERO,
+                buckets: StageTimings::BUCKETS.iter().map(|b| (*b, 0)).collect(),
+            }
+        );
+
+        // Only the most recent durations are kept.
+        for _ in 0..RollingTimings::WINDOW_SIZE {
+            metrics.note_stage_duration(stage, Duration::from_millis(50));
+        }
+        let timings = metrics.stage_timings(stage);
+        assert_eq!(timings.count, RollingTimings::WINDOW_SIZE);
+        assert_eq!(timings.buckets[2].1, 0);
+        assert_eq!(timings.buckets[3].1, RollingTimings::WINDOW_SIZE);
+    }
 }
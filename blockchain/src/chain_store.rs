@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+
 use nimiq_account::Receipts;
 use nimiq_block::Block;
 use nimiq_database::cursor::{ReadCursor, WriteCursor};
@@ -14,6 +18,62 @@ use crate::Direction;
 /// Epochs older than this number will be pruned. A minimum of 1 is recommended.
 pub const MAX_EPOCHS_STORED: u32 = 1;
 
+/// Keeps the bodies of the most recent blocks in memory. Bodies of blocks that are more than
+/// `max_blocks` blocks behind the most recent cached block are dropped, they are loaded from the
+/// database again when requested.
+#[derive(Debug, Default)]
+struct BodyCache {
+    max_blocks: u32,
+    // The block bodies indexed by their block hashes.
+    blocks: HashMap<Blake2bHash, Block>,
+    // The highest block number of all blocks that were cached.
+    newest_block_number: u32,
+}
+
+impl BodyCache {
+    fn get(&self, hash: &Blake2bHash) -> Option<Block> {
+        self.blocks.get(hash).cloned()
+    }
+
+    /// Returns the lowest block number that is retained.
+    fn first_retained_block_number(&self) -> u32 {
+        (self.newest_block_number + 1).saturating_sub(self.max_blocks)
+    }
+
+    fn insert(&mut self, hash: Blake2bHash, block: &Block) {
+        let block_number = block.block_number();
+        if self.max_blocks == 0 || block_number < self.first_retained_block_number() {
+            return;
+        }
+
+        self.blocks.insert(hash, block.clone());
+        if block_number > self.newest_block_number {
+            self.newest_block_number = block_number;
+            self.prune();
+        }
+    }
+
+    fn remove(&mut self, hash: &Blake2bHash) {
+        self.blocks.remove(hash);
+    }
+
+    fn set_max_blocks(&mut self, max_blocks: u32) {
+        self.max_blocks = max_blocks;
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        if self.max_blocks == 0 {
+            self.blocks.clear();
+            return;
+        }
+
+        let first_retained_block_number = self.first_retained_block_number();
+        self.blocks
+            .retain(|_, block| block.block_number() >= first_retained_block_number);
+    }
+}
+
 #[derive(Debug)]
 pub struct ChainStore {
     env: Environment,
@@ -25,6 +85,8 @@ pub struct ChainStore {
     height_idx: Database,
     // A database of the transaction receipts for a block, by their corresponding block hashes.
     receipt_db: Database,
+    // The bodies of recent blocks, to avoid loading them from the database repeatedly.
+    body_cache: Mutex<BodyCache>,
 }
 
 impl ChainStore {
@@ -35,6 +97,9 @@ impl ChainStore {
 
     const HEAD_KEY: &'static str = "head";
 
+    /// The default number of recent blocks whose bodies are kept in memory.
+    pub const DEFAULT_BODY_CACHE_SIZE: u32 = policy::BATCH_LENGTH;
+
     pub fn new(env: Environment) -> Self {
         let chain_db = env.open_database(Self::CHAIN_DB_NAME.to_string());
         let block_db = env.open_database(Self::BLOCK_DB_NAME.to_string());
@@ -50,7 +115,40 @@ impl ChainStore {
             block_db,
             height_idx,
             receipt_db,
+            body_cache: Mutex::new(BodyCache {
+                max_blocks: Self::DEFAULT_BODY_CACHE_SIZE,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Sets the number of recent blocks whose bodies are kept in memory. Zero disables the cache.
+    pub fn set_body_cache_size(&self, max_blocks: u32) {
+        self.body_cache.lock().set_max_blocks(max_blocks);
+    }
+
+    /// Returns whether the body of the given block is currently held in memory.
+    pub fn is_body_cached(&self, hash: &Blake2bHash) -> bool {
+        self.body_cache.lock().blocks.contains_key(hash)
+    }
+
+    /// Loads a block including its body, from memory if possible. Bodies are only cached when they
+    /// are read outside of a transaction, so that uncommitted blocks never end up in the cache.
+    fn get_block_with_body(
+        &self,
+        hash: &Blake2bHash,
+        txn: &Transaction,
+        cacheable: bool,
+    ) -> Option<Block> {
+        if let Some(block) = self.body_cache.lock().get(hash) {
+            return Some(block);
+        }
+
+        let block: Block = txn.get(&self.block_db, hash)?;
+        if cacheable {
+            self.body_cache.lock().insert(hash.clone(), &block);
         }
+        Some(block)
     }
 
     pub fn get_head(&self, txn_option: Option<&Transaction>) -> Option<Blake2bHash> {
@@ -85,7 +183,7 @@ impl ChainStore {
         };
 
         if include_body {
-            if let Some(block) = txn.get(&self.block_db, hash) {
+            if let Some(block) = self.get_block_with_body(hash, txn, txn_option.is_none()) {
                 chain_info.head = block;
             } else {
                 warn!("Block body requested but not present");
@@ -133,7 +231,7 @@ impl ChainStore {
         };
 
         if include_body {
-            if let Some(block) = txn.get(&self.block_db, &block_hash) {
+            if let Some(block) = self.get_block_with_body(&block_hash, txn, txn_option.is_none()) {
                 chain_info.head = block;
             } else {
                 warn!("Block body requested but not present");
@@ -165,6 +263,7 @@ impl ChainStore {
     }
 
     pub fn remove_chain_info(&self, txn: &mut WriteTransaction, hash: &Blake2bHash, height: u32) {
+        self.body_cache.lock().remove(hash);
         txn.remove(&self.chain_db, hash);
         txn.remove(&self.block_db, hash);
         txn.remove_item(&self.height_idx, &height, hash);
@@ -186,7 +285,7 @@ impl ChainStore {
         };
 
         if include_body {
-            txn.get(&self.block_db, hash)
+            self.get_block_with_body(hash, txn, txn_option.is_none())
         } else {
             txn.get(&self.chain_db, hash)
                 .map(|chain_info: ChainInfo| chain_info.head)
@@ -463,6 +562,7 @@ impl ChainStore {
         for height in policy::first_block_of(epoch_number)..policy::election_block_of(epoch_number)
         {
             if let Some(hash) = txn.get::<u32, Blake2bHash>(&self.height_idx, &height) {
+                self.body_cache.lock().remove(&hash);
                 txn.remove(&self.chain_db, &hash);
                 txn.remove(&self.block_db, &hash);
                 txn.remove_item(&self.height_idx, &height, &hash);
@@ -0,0 +1,141 @@
+use std::convert::TryFrom;
+
+use beserial::{Deserialize, ReadBytesExt, Serialize, SerializingError, WriteBytesExt};
+use nimiq_account::Account;
+use nimiq_block::{Block, MacroBlock, TendermintProof};
+use nimiq_primitives::coin::Coin;
+use nimiq_trie::key_nibbles::KeyNibbles;
+
+use crate::{AbstractBlockchain, Blockchain, BlockchainError};
+
+/// A snapshot of the chain at an election block. A node can start from a checkpoint instead of
+/// the genesis block and sync forward from there, skipping the history before it.
+///
+/// A checkpoint has to be trusted: Only its internal consistency is verified, not that the
+/// election block actually is part of the chain starting at the genesis block.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// The election block the chain starts from.
+    pub election_block: MacroBlock,
+    /// The election block of the previous epoch. Its validators are needed to verify the
+    /// checkpoint and to distribute the rewards of the last batch of that epoch.
+    pub previous_election_block: MacroBlock,
+    /// The transaction fees collected in the batch that ends with the election block.
+    pub cum_tx_fees: Coin,
+    /// All accounts at the election block.
+    pub accounts: Vec<(KeyNibbles, Account)>,
+}
+
+impl Checkpoint {
+    /// Verifies that the checkpoint consists of two consecutive election blocks and that the
+    /// checkpoint block was signed by the validators of the previous epoch. The accounts are
+    /// verified against the state root when the blockchain is initialized.
+    pub(crate) fn verify(&self) -> Result<(), BlockchainError> {
+        if !self.election_block.is_election_block()
+            || !self.previous_election_block.is_election_block()
+        {
+            return Err(BlockchainError::InvalidCheckpoint(
+                "checkpoint blocks must be election blocks",
+            ));
+        }
+
+        if self.election_block.header.parent_election_hash != self.previous_election_block.hash() {
+            return Err(BlockchainError::InvalidCheckpoint(
+                "previous election block doesn't precede the checkpoint",
+            ));
+        }
+
+        let previous_validators = self.previous_election_block.get_validators().ok_or(
+            BlockchainError::InvalidCheckpoint("previous election block is missing its validators"),
+        )?;
+        if self.election_block.get_validators().is_none() {
+            return Err(BlockchainError::InvalidCheckpoint(
+                "election block is missing its validators",
+            ));
+        }
+
+        if !TendermintProof::verify(&self.election_block, &previous_validators) {
+            return Err(BlockchainError::InvalidCheckpoint(
+                "invalid justification of the election block",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Serialize for Checkpoint {
+    fn serialize<W: WriteBytesExt>(&self, writer: &mut W) -> Result<usize, SerializingError> {
+        let mut size = 0;
+        size += self.election_block.serialize(writer)?;
+        size += self.previous_election_block.serialize(writer)?;
+        size += self.cum_tx_fees.serialize(writer)?;
+        let count = u32::try_from(self.accounts.len()).map_err(|_| SerializingError::Overflow)?;
+        size += count.serialize(writer)?;
+        for (key, account) in self.accounts.iter() {
+            size += key.serialize(writer)?;
+            size += account.serialize(writer)?;
+        }
+        Ok(size)
+    }
+
+    fn serialized_size(&self) -> usize {
+        let mut size = 4; // count as u32
+        size += self.election_block.serialized_size();
+        size += self.previous_election_block.serialized_size();
+        size += self.cum_tx_fees.serialized_size();
+        for (key, account) in self.accounts.iter() {
+            size += key.serialized_size();
+            size += account.serialized_size();
+        }
+        size
+    }
+}
+
+impl Deserialize for Checkpoint {
+    fn deserialize<R: ReadBytesExt>(reader: &mut R) -> Result<Self, SerializingError> {
+        let election_block = Deserialize::deserialize(reader)?;
+        let previous_election_block = Deserialize::deserialize(reader)?;
+        let cum_tx_fees = Deserialize::deserialize(reader)?;
+        let count: u32 = Deserialize::deserialize(reader)?;
+        let mut accounts = Vec::new();
+        for _ in 0..count {
+            accounts.push((
+                Deserialize::deserialize(reader)?,
+                Deserialize::deserialize(reader)?,
+            ));
+        }
+        Ok(Self {
+            election_block,
+            previous_election_block,
+            cum_tx_fees,
+            accounts,
+        })
+    }
+}
+
+impl Blockchain {
+    /// Creates a checkpoint at the current head. Returns `None` if the head isn't an election
+    /// block or is the genesis block.
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        if self.state.head_hash != self.state.election_head_hash
+            || self.state.election_head.header.block_number == 0
+        {
+            return None;
+        }
+
+        let election_block = self.state.election_head.clone();
+        let previous_election_block =
+            match self.get_block(&election_block.header.parent_election_hash, true, None)? {
+                Block::Macro(block) => block,
+                Block::Micro(_) => return None,
+            };
+
+        Some(Checkpoint {
+            election_block,
+            previous_election_block,
+            cum_tx_fees: self.state.main_chain.cum_tx_fees,
+            accounts: self.state.accounts.get_all(None),
+        })
+    }
+}
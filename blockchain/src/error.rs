@@ -2,6 +2,7 @@ use thiserror::Error;
 
 use nimiq_account::AccountError;
 use nimiq_block::{Block, BlockError, ForkProof};
+use nimiq_database::DatabaseError;
 use nimiq_hash::Blake2bHash;
 use nimiq_primitives::networks::NetworkId;
 
@@ -29,6 +30,12 @@ pub enum BlockchainError {
     InconsistentState,
     #[error("No network for: {:?}", _0)]
     NoNetwork(NetworkId),
+    #[error("Invalid checkpoint: {0}")]
+    InvalidCheckpoint(&'static str),
+    #[error("Body of block {0} is not available")]
+    BlockBodyNotAvailable(Blake2bHash),
+    #[error("History of epoch {0} has been pruned")]
+    HistoryPruned(u32),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,12 +61,20 @@ pub enum PushError {
     InvalidPredecessor,
     #[error("Duplicate transaction")]
     DuplicateTransaction,
+    #[error("Transaction too large")]
+    TransactionTooLarge,
     #[error("Account error: {0}")]
     AccountsError(#[from] AccountError),
     #[error("Invalid fork")]
     InvalidFork,
+    #[error("Reorg too deep")]
+    ReorgTooDeep,
     #[error("Blockchain error: {0}")]
     BlockchainError(#[from] BlockchainError),
+    #[error("Failed to update the history store")]
+    HistoryError,
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] DatabaseError),
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
@@ -0,0 +1,139 @@
+use std::ops::Range;
+
+use nimiq_primitives::policy;
+
+/// Determines how much of the history a node keeps in its history store.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HistoryMode {
+    /// Retain the full history.
+    Archive,
+    /// Retain only the history of the current epoch and of the given number of completed epochs
+    /// before it. The history of older epochs is removed once a new election block is committed.
+    Pruned { retained_epochs: u32 },
+}
+
+impl Default for HistoryMode {
+    fn default() -> Self {
+        HistoryMode::Archive
+    }
+}
+
+impl HistoryMode {
+    /// The minimum number of completed epochs a pruned node retains. The history is needed to
+    /// detect replayed transactions, so it must always cover the transaction validity window.
+    pub const MIN_RETAINED_EPOCHS: u32 =
+        (policy::TRANSACTION_VALIDITY_WINDOW + policy::EPOCH_LENGTH - 1) / policy::EPOCH_LENGTH;
+
+    /// Returns the first epoch whose history is retained when the chain is at the given block
+    /// number. The history of all epochs before it has been pruned.
+    pub fn first_retained_epoch(&self, head_block_number: u32) -> u32 {
+        match self {
+            HistoryMode::Archive => 0,
+            HistoryMode::Pruned { retained_epochs } => {
+                let retained_epochs = (*retained_epochs).max(Self::MIN_RETAINED_EPOCHS);
+                let completed_epochs =
+                    policy::epoch_at(policy::last_election_block(head_block_number));
+
+                if completed_epochs > retained_epochs {
+                    completed_epochs - retained_epochs + 1
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// Returns the epochs whose history has to be removed when committing the given block. Only
+    /// election blocks cause history to be pruned. All epochs before the first retained one are
+    /// returned, so that a node switching from archive to pruned mode removes its whole surplus
+    /// history at once. Removing the history of an epoch that was already pruned is a no-op.
+    pub fn epochs_to_prune(&self, block_number: u32) -> Range<u32> {
+        if !policy::is_election_block_at(block_number) {
+            return 0..0;
+        }
+
+        match self.first_retained_epoch(block_number) {
+            0 | 1 => 0..0,
+            first_retained_epoch => 1..first_retained_epoch,
+        }
+    }
+
+    /// Returns whether the history at the given block number is still available when the chain is
+    /// at the given head block number.
+    pub fn is_history_available(&self, head_block_number: u32, block_number: u32) -> bool {
+        policy::epoch_at(block_number) >= self.first_retained_epoch(head_block_number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_nodes_retain_everything() {
+        let mode = HistoryMode::Archive;
+        let head = 1000 * policy::EPOCH_LENGTH;
+
+        assert_eq!(mode.first_retained_epoch(head), 0);
+        assert!(mode.epochs_to_prune(head).is_empty());
+        assert!(mode.is_history_available(head, 1));
+    }
+
+    #[test]
+    fn pruning_starts_after_the_retained_epochs() {
+        let retained_epochs = HistoryMode::MIN_RETAINED_EPOCHS + 2;
+        let mode = HistoryMode::Pruned { retained_epochs };
+
+        // Nothing is pruned while there are at most `retained_epochs` completed epochs.
+        let last_unpruned = retained_epochs * policy::EPOCH_LENGTH;
+        assert_eq!(mode.first_retained_epoch(last_unpruned), 0);
+        assert!(mode.epochs_to_prune(last_unpruned).is_empty());
+        assert!(mode.is_history_available(last_unpruned + 1, 1));
+
+        // The election block completing the next epoch prunes the first one.
+        let first_pruning = last_unpruned + policy::EPOCH_LENGTH;
+        assert_eq!(mode.first_retained_epoch(first_pruning - 1), 0);
+        assert!(mode.epochs_to_prune(first_pruning - 1).is_empty());
+        assert_eq!(mode.first_retained_epoch(first_pruning), 2);
+        assert_eq!(mode.epochs_to_prune(first_pruning), 1..2);
+
+        assert!(!mode.is_history_available(first_pruning, 1));
+        assert!(!mode.is_history_available(first_pruning, policy::EPOCH_LENGTH));
+        assert!(mode.is_history_available(first_pruning, policy::EPOCH_LENGTH + 1));
+        assert!(mode.is_history_available(first_pruning, first_pruning + 1));
+
+        // Every following election block prunes one more epoch.
+        let next_pruning = first_pruning + policy::EPOCH_LENGTH;
+        assert_eq!(mode.epochs_to_prune(next_pruning), 1..3);
+        assert!(mode
+            .epochs_to_prune(next_pruning - policy::BATCH_LENGTH)
+            .is_empty());
+    }
+
+    #[test]
+    fn switching_to_pruned_prunes_all_old_epochs() {
+        // A node that ran as an archive node for many epochs prunes everything before the retained
+        // epochs at the first election block after switching, not just a single epoch.
+        let head = 1000 * policy::EPOCH_LENGTH;
+        assert!(HistoryMode::Archive.epochs_to_prune(head).is_empty());
+
+        let mode = HistoryMode::Pruned { retained_epochs: 0 };
+        let first_retained_epoch = 1000 - HistoryMode::MIN_RETAINED_EPOCHS + 1;
+        assert_eq!(mode.first_retained_epoch(head), first_retained_epoch);
+        assert_eq!(mode.epochs_to_prune(head), 1..first_retained_epoch);
+        assert!(mode.epochs_to_prune(head + 1).is_empty());
+    }
+
+    #[test]
+    fn pruned_nodes_cover_the_validity_window() {
+        let mode = HistoryMode::Pruned { retained_epochs: 0 };
+        assert_eq!(
+            mode.first_retained_epoch(HistoryMode::MIN_RETAINED_EPOCHS * policy::EPOCH_LENGTH),
+            0
+        );
+
+        for head in [1000 * policy::EPOCH_LENGTH, 1000 * policy::EPOCH_LENGTH + 1] {
+            assert!(mode.is_history_available(head, head - policy::TRANSACTION_VALIDITY_WINDOW));
+        }
+    }
+}
@@ -13,12 +13,12 @@ use nimiq_mmr::mmr::partial::PartialMerkleMountainRange;
 use nimiq_mmr::mmr::position::leaf_number_to_index;
 use nimiq_mmr::mmr::proof::RangeProof;
 use nimiq_mmr::mmr::MerkleMountainRange;
-use nimiq_mmr::store::memory::MemoryStore;
+use nimiq_mmr::store::memory::{MemoryStore, MemoryTransaction};
 use nimiq_primitives::policy;
 
 use crate::history_store::mmr_store::MMRStore;
 use crate::history_store::ordered_hash::OrderedHash;
-use crate::history_store::{ExtendedTransaction, HistoryTreeChunk, HistoryTreeProof};
+use crate::history_store::{ExtendedTransaction, HistoryMode, HistoryTreeChunk, HistoryTreeProof};
 use crate::ExtTxData;
 
 /// A struct that contains databases to store history trees (which are Merkle Mountain Ranges
@@ -218,6 +218,24 @@ impl HistoryStore {
         Some(())
     }
 
+    /// Removes the history of all epochs that are no longer retained in the given history mode
+    /// once the block at the given block number is committed.
+    pub fn prune(&self, txn: &mut WriteTransaction, history_mode: HistoryMode, block_number: u32) {
+        let epochs = history_mode.epochs_to_prune(block_number);
+        if epochs.is_empty() {
+            return;
+        }
+
+        debug!(
+            "Pruning history of epochs {} to {}",
+            epochs.start,
+            epochs.end - 1
+        );
+        for epoch_number in epochs {
+            self.remove_history(txn, epoch_number);
+        }
+    }
+
     /// Gets the history tree root for a given epoch.
     pub fn get_history_tree_root(
         &self,
@@ -244,6 +262,37 @@ impl HistoryStore {
         tree.get_root().ok()
     }
 
+    /// Calculates the history tree root that appending a list of extended transactions to the
+    /// history tree of the given epoch would result in, without modifying the history tree. This
+    /// is used to compute the history root of a block before committing it.
+    pub fn get_root_with(
+        &self,
+        epoch_number: u32,
+        ext_txs: &[ExtendedTransaction],
+        txn_option: Option<&Transaction>,
+    ) -> Option<Blake2bHash> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        // Get the history tree. The new nodes are only kept in memory on top of the stored tree.
+        let mut store = MMRStore::with_read_transaction(&self.hist_tree_db, txn, epoch_number);
+        let mut tree = MerkleMountainRange::new(MemoryTransaction::new(&mut store));
+
+        // Append the extended transactions to the history tree.
+        for tx in ext_txs {
+            tree.push(tx).ok()?;
+        }
+
+        // Return the history root.
+        tree.get_root().ok()
+    }
+
     /// Calculates the history tree root from a vector of extended transactions. It doesn't use the
     /// database, it is just used to check the correctness of the history root when syncing.
     pub fn root_from_ext_txs(ext_txs: &[ExtendedTransaction]) -> Option<Blake2bHash> {
@@ -429,6 +478,67 @@ impl HistoryStore {
         tx_hashes
     }
 
+    /// Returns the extended transactions (and reward inherents) corresponding to the given address.
+    /// It fetches the transactions from most recent to least recent, skipping the first `offset`
+    /// ones and returning up to `limit` of them. This allows to paginate the history of an address.
+    pub fn get_transactions_by_address(
+        &self,
+        address: &Address,
+        offset: u32,
+        limit: u16,
+        txn_option: Option<&Transaction>,
+    ) -> Vec<ExtendedTransaction> {
+        if limit == 0 {
+            return vec![];
+        }
+
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        let mut ext_txs = vec![];
+
+        // Seek to the first transaction hash at the given address. If there's none, stop here.
+        let mut cursor = txn.cursor(&self.address_db);
+
+        if cursor.seek_key::<Address, OrderedHash>(address).is_none() {
+            return ext_txs;
+        }
+
+        // Then go to the last transaction hash at the given address and walk backwards, skipping
+        // the first `offset` transaction hashes.
+        let mut entry = cursor.last_duplicate::<OrderedHash>();
+
+        for _ in 0..offset {
+            if entry.is_none() {
+                break;
+            }
+            entry = cursor
+                .prev_duplicate::<Address, OrderedHash>()
+                .map(|(_, v)| v);
+        }
+
+        while let Some(ordered_hash) = entry {
+            if ext_txs.len() >= limit as usize {
+                break;
+            }
+
+            ext_txs.append(&mut self.get_ext_tx_by_hash(&ordered_hash.hash, Some(txn)));
+
+            entry = cursor
+                .prev_duplicate::<Address, OrderedHash>()
+                .map(|(_, v)| v);
+        }
+
+        ext_txs.truncate(limit as usize);
+        ext_txs
+    }
+
     /// Returns a proof for transactions with the given hashes. The proof also includes the extended
     /// transactions.
     pub fn prove(
@@ -453,6 +563,32 @@ impl HistoryStore {
         self.prove_with_position(epoch_number, positions, txn_option)
     }
 
+    /// Returns an inclusion proof for the transaction with the given hash against the history root
+    /// of the epoch it is part of, i.e. the history root of that epoch's election block. Returns
+    /// None if the transaction is not known.
+    pub fn prove_transaction(
+        &self,
+        tx_hash: &Blake2bHash,
+        txn_option: Option<&Transaction>,
+    ) -> Option<HistoryTreeProof> {
+        let read_txn: ReadTransaction;
+        let txn = match txn_option {
+            Some(txn) => txn,
+            None => {
+                read_txn = ReadTransaction::new(&self.env);
+                &read_txn
+            }
+        };
+
+        // Get the epoch of the transaction from its block number.
+        let block_number = self
+            .get_ext_tx_by_hash(tx_hash, Some(txn))
+            .first()?
+            .block_number;
+
+        self.prove(policy::epoch_at(block_number), vec![tx_hash], Some(txn))
+    }
+
     /// Returns a proof for all the extended transactions at the given positions (leaf indexes). The
     /// proof also includes the extended transactions.
     fn prove_with_position(
@@ -935,6 +1071,7 @@ impl HistoryStore {
 
 #[cfg(test)]
 mod tests {
+    use beserial::{Deserialize, Serialize};
     use nimiq_account::{Inherent, InherentType};
     use nimiq_database::volatile::VolatileEnvironment;
     use nimiq_primitives::coin::Coin;
@@ -973,6 +1110,47 @@ mod tests {
         assert_eq!(history_store.length_at(9, Some(&txn)), 4);
     }
 
+    #[test]
+    fn prune_removes_all_epochs_before_the_retained_ones() {
+        // Initialize History Store.
+        let env = VolatileEnvironment::new(10).unwrap();
+        let history_store = HistoryStore::new(env.clone());
+
+        // Fill the history of a few epochs as an archive node would have.
+        let mode = HistoryMode::Pruned { retained_epochs: 0 };
+        let election_block = 1000 * policy::EPOCH_LENGTH;
+        let first_retained_epoch = mode.first_retained_epoch(election_block);
+        let epochs = [1, 2, 3, first_retained_epoch - 1, first_retained_epoch];
+
+        let mut txn = WriteTransaction::new(&env);
+        for epoch_number in epochs {
+            let block_number = (epoch_number - 1) * policy::EPOCH_LENGTH + 1;
+            let ext_tx = create_transaction(block_number, epoch_number as u64);
+            history_store.add_to_history(&mut txn, epoch_number, &[ext_tx]);
+        }
+
+        // Switching to pruned mode removes the history of all old epochs at once.
+        history_store.prune(&mut txn, HistoryMode::Archive, election_block);
+        for epoch_number in epochs {
+            assert_eq!(
+                history_store.get_num_extended_transactions(epoch_number, Some(&txn)),
+                1
+            );
+        }
+
+        history_store.prune(&mut txn, mode, election_block);
+        for epoch_number in &epochs[..4] {
+            assert_eq!(
+                history_store.get_num_extended_transactions(*epoch_number, Some(&txn)),
+                0
+            );
+        }
+        assert_eq!(
+            history_store.get_num_extended_transactions(first_retained_epoch, Some(&txn)),
+            1
+        );
+    }
+
     #[test]
     fn get_root_from_ext_txs_works() {
         // Initialize History Store.
@@ -999,6 +1177,42 @@ mod tests {
         assert_eq!(real_root_1, calc_root_1);
     }
 
+    #[test]
+    fn get_root_with_works() {
+        // Initialize History Store.
+        let env = VolatileEnvironment::new(10).unwrap();
+        let history_store = HistoryStore::new(env.clone());
+
+        // Create extended transactions.
+        let ext_txs = gen_ext_txs();
+
+        // Add the first extended transactions to History Store.
+        let mut txn = WriteTransaction::new(&env);
+        history_store.add_to_history(&mut txn, 1, &ext_txs[..3]);
+        txn.commit();
+
+        // Predict the roots without modifying the history tree.
+        let old_root = history_store.get_history_tree_root(1, None);
+        let predicted_root = history_store.get_root_with(1, &ext_txs[3..], None);
+        assert_eq!(history_store.get_history_tree_root(1, None), old_root);
+        assert_eq!(history_store.length_at(2, None), 3);
+
+        let predicted_empty_root = history_store.get_root_with(2, &ext_txs, None);
+        assert_eq!(
+            predicted_empty_root,
+            HistoryStore::root_from_ext_txs(&ext_txs)
+        );
+
+        // The prediction matches the root after actually adding the extended transactions.
+        let mut txn = WriteTransaction::new(&env);
+        let real_root = history_store.add_to_history(&mut txn, 1, &ext_txs[3..]);
+        txn.commit();
+
+        assert!(predicted_root.is_some());
+        assert_eq!(real_root, predicted_root);
+        assert_eq!(history_store.get_history_tree_root(1, None), predicted_root);
+    }
+
     #[test]
     fn get_ext_tx_by_hash_works() {
         // Initialize History Store.
@@ -1322,6 +1536,122 @@ mod tests {
         assert_eq!(query_4.len(), 0);
     }
 
+    #[test]
+    fn get_transactions_by_address_works() {
+        // Initialize History Store.
+        let env = VolatileEnvironment::new(10).unwrap();
+        let history_store = HistoryStore::new(env.clone());
+
+        // Create extended transactions.
+        let ext_txs = gen_ext_txs();
+
+        // Add extended transactions to History Store.
+        let mut txn = WriteTransaction::new(&env);
+        history_store.add_to_history(&mut txn, 0, &ext_txs[..3]);
+        history_store.add_to_history(&mut txn, 1, &ext_txs[3..]);
+
+        let sender =
+            Address::from_user_friendly_address("NQ09 VF5Y 1PKV MRM4 5LE1 55KV P6R2 GXYJ XYQF")
+                .unwrap();
+
+        // Verify method works.
+        let query_1 = history_store.get_transactions_by_address(&sender, 0, 99, Some(&txn));
+
+        assert_eq!(query_1.len(), 5);
+        assert_eq!(query_1[0].tx_hash(), ext_txs[6].tx_hash());
+        assert_eq!(query_1[1].tx_hash(), ext_txs[5].tx_hash());
+        assert_eq!(query_1[2].tx_hash(), ext_txs[3].tx_hash());
+        assert_eq!(query_1[3].tx_hash(), ext_txs[1].tx_hash());
+        assert_eq!(query_1[4].tx_hash(), ext_txs[0].tx_hash());
+
+        // Paginate through the transactions of the sender.
+        let query_2 = history_store.get_transactions_by_address(&sender, 0, 2, Some(&txn));
+
+        assert_eq!(query_2.len(), 2);
+        assert_eq!(query_2[0].tx_hash(), ext_txs[6].tx_hash());
+        assert_eq!(query_2[1].tx_hash(), ext_txs[5].tx_hash());
+
+        let query_3 = history_store.get_transactions_by_address(&sender, 2, 2, Some(&txn));
+
+        assert_eq!(query_3.len(), 2);
+        assert_eq!(query_3[0].tx_hash(), ext_txs[3].tx_hash());
+        assert_eq!(query_3[1].tx_hash(), ext_txs[1].tx_hash());
+
+        let query_4 = history_store.get_transactions_by_address(&sender, 4, 2, Some(&txn));
+
+        assert_eq!(query_4.len(), 1);
+        assert_eq!(query_4[0].tx_hash(), ext_txs[0].tx_hash());
+
+        let query_5 = history_store.get_transactions_by_address(&sender, 5, 2, Some(&txn));
+
+        assert_eq!(query_5.len(), 0);
+
+        // Reward inherents are included for their recipient.
+        let query_6 = history_store.get_transactions_by_address(
+            &Address::from_user_friendly_address("NQ04 B79B R4FF 4NGU A9H0 2PT9 9ART 5A88 J73T")
+                .unwrap(),
+            1,
+            99,
+            Some(&txn),
+        );
+
+        assert_eq!(query_6.len(), 2);
+        assert_eq!(query_6[0].tx_hash(), ext_txs[4].tx_hash());
+        assert_eq!(query_6[1].tx_hash(), ext_txs[2].tx_hash());
+    }
+
+    #[test]
+    fn prove_transaction_works() {
+        // Initialize History Store.
+        let env = VolatileEnvironment::new(10).unwrap();
+        let history_store = HistoryStore::new(env.clone());
+
+        // Create extended transactions for the first two epochs.
+        let ext_txs_0 = vec![create_transaction(1, 0), create_transaction(2, 1)];
+        let ext_txs_1 = vec![
+            create_transaction(policy::EPOCH_LENGTH + 1, 2),
+            create_inherent(policy::EPOCH_LENGTH + 1, 3),
+            create_transaction(policy::EPOCH_LENGTH + 2, 4),
+        ];
+
+        // Add extended transactions to History Store.
+        let mut txn = WriteTransaction::new(&env);
+        history_store.add_to_history(&mut txn, 0, &ext_txs_0);
+        history_store.add_to_history(&mut txn, 1, &ext_txs_1);
+
+        let root_0 = history_store.get_history_tree_root(0, Some(&txn)).unwrap();
+        let root_1 = history_store.get_history_tree_root(1, Some(&txn)).unwrap();
+
+        // Verify method works.
+        let proof = history_store
+            .prove_transaction(&ext_txs_1[2].tx_hash(), Some(&txn))
+            .unwrap();
+
+        assert_eq!(proof.positions, vec![2]);
+        assert_eq!(proof.history.len(), 1);
+        assert_eq!(proof.history[0].tx_hash(), ext_txs_1[2].tx_hash());
+
+        // The proof can be verified independently after transmitting it, but only against the
+        // history root of its epoch.
+        let proof = HistoryTreeProof::deserialize_from_vec(&proof.serialize_to_vec()).unwrap();
+
+        assert!(proof.verify(root_1).unwrap());
+        assert!(!proof.verify(root_0).unwrap_or(false));
+
+        let proof = history_store
+            .prove_transaction(&ext_txs_0[1].tx_hash(), Some(&txn))
+            .unwrap();
+
+        assert!(proof.verify(root_0).unwrap());
+
+        // Unknown transactions can't be proven.
+        let unknown_tx = create_transaction(3, 5);
+
+        assert!(history_store
+            .prove_transaction(&unknown_tx.tx_hash(), Some(&txn))
+            .is_none());
+    }
+
     #[test]
     fn prove_works() {
         // Initialize History Store.
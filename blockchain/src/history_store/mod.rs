@@ -1,9 +1,11 @@
 pub use extended_transaction::*;
+pub use history_mode::HistoryMode;
 pub use history_store::HistoryStore;
 pub use history_tree_chunk::{HistoryTreeChunk, CHUNK_SIZE};
 pub use history_tree_proof::HistoryTreeProof;
 
 mod extended_transaction;
+mod history_mode;
 mod history_store;
 mod history_tree_chunk;
 mod history_tree_proof;
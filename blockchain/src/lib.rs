@@ -5,10 +5,15 @@ extern crate log;
 
 pub use abstract_blockchain::AbstractBlockchain;
 pub use blockchain::blockchain::{Blockchain, TransactionVerificationCache};
-pub use chain_info::ChainInfo;
+pub use blockchain::inherents::{Slash, SlashReason};
+pub use blockchain::parallel_verify::{SignatureVerification, VerifiedBlock};
+pub use blockchain_state::HeadSnapshot;
+pub use chain_info::{ChainInfo, ChainStatus};
 pub use chain_ordering::ChainOrdering;
+pub use checkpoint::Checkpoint;
 pub use error::*;
 pub use history_store::*;
+pub use participation::{ParticipationTracker, ValidatorParticipation};
 
 pub(crate) mod abstract_blockchain;
 pub(crate) mod blockchain;
@@ -18,6 +23,8 @@ pub(crate) mod chain_info;
 pub mod chain_metrics;
 pub(crate) mod chain_ordering;
 pub(crate) mod chain_store;
+pub(crate) mod checkpoint;
 pub(crate) mod error;
 pub(crate) mod history_store;
+pub(crate) mod participation;
 pub mod reward;
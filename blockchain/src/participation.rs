@@ -0,0 +1,126 @@
+use nimiq_block::Block;
+use nimiq_collections::BitSet;
+use nimiq_database::Transaction;
+use nimiq_keys::Address;
+use nimiq_primitives::policy;
+use nimiq_primitives::slots::Validators;
+
+use crate::{AbstractBlockchain, Blockchain};
+
+/// How often a validator signed the aggregations of an epoch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorParticipation {
+    pub validator_address: Address,
+    pub num_slots: u16,
+    /// The number of aggregations that the validator signed.
+    pub num_signed: u32,
+    /// The number of aggregations in the epoch.
+    pub num_aggregations: u32,
+}
+
+impl ValidatorParticipation {
+    /// Returns the share of aggregations that the validator signed. A validator that had no
+    /// aggregation to sign yet has a participation rate of 1.
+    pub fn rate(&self) -> f64 {
+        if self.num_aggregations == 0 {
+            return 1.0;
+        }
+        self.num_signed as f64 / self.num_aggregations as f64
+    }
+}
+
+/// Accumulates which validators signed the aggregations (view change proofs and macro block
+/// justifications) of an epoch.
+#[derive(Clone, Debug)]
+pub struct ParticipationTracker {
+    validators: Validators,
+    num_signed: Vec<u32>,
+    num_aggregations: u32,
+}
+
+impl ParticipationTracker {
+    pub fn new(validators: Validators) -> Self {
+        let num_signed = vec![0; validators.num_validators()];
+        Self {
+            validators,
+            num_signed,
+            num_aggregations: 0,
+        }
+    }
+
+    /// Records an aggregation given the slots that signed it. A validator signs with all of its
+    /// slots at once, so it counts as a signer if any of its slots is set.
+    pub fn record(&mut self, signers: &BitSet) {
+        self.num_aggregations += 1;
+
+        for (validator, num_signed) in self.validators.iter().zip(self.num_signed.iter_mut()) {
+            let (first_slot, end_slot) = validator.slot_range;
+            if (first_slot..end_slot).any(|slot| signers.contains(slot as usize)) {
+                *num_signed += 1;
+            }
+        }
+    }
+
+    pub fn num_aggregations(&self) -> u32 {
+        self.num_aggregations
+    }
+
+    /// Returns the participation of every validator, ordered by slot band.
+    pub fn summary(&self) -> Vec<ValidatorParticipation> {
+        self.validators
+            .iter()
+            .zip(self.num_signed.iter())
+            .map(|(validator, &num_signed)| ValidatorParticipation {
+                validator_address: validator.address.clone(),
+                num_slots: validator.num_slots(),
+                num_signed,
+                num_aggregations: self.num_aggregations,
+            })
+            .collect()
+    }
+}
+
+impl Blockchain {
+    /// Returns how often each validator of the given epoch signed the view change proofs and
+    /// macro block justifications of that epoch on the main chain. For the current epoch, the
+    /// blocks up to the head are taken into account.
+    ///
+    /// Returns `None` for the genesis epoch and for epochs whose blocks were already pruned.
+    pub fn get_validator_participation(
+        &self,
+        epoch: u32,
+        txn_option: Option<&Transaction>,
+    ) -> Option<Vec<ValidatorParticipation>> {
+        if epoch == 0 {
+            return None;
+        }
+
+        // The validators of an epoch are elected in the election block of the previous epoch.
+        let validators = self
+            .get_block_at(policy::election_block_of(epoch - 1), true, txn_option)?
+            .unwrap_macro()
+            .get_validators()?;
+        let mut tracker = ParticipationTracker::new(validators);
+
+        let last_block = policy::election_block_of(epoch).min(self.block_number());
+        for block_number in policy::first_block_of(epoch)..=last_block {
+            match self.get_block_at(block_number, false, txn_option)? {
+                Block::Micro(block) => {
+                    if let Some(proof) = block
+                        .justification
+                        .and_then(|justification| justification.view_change_proof)
+                    {
+                        tracker.record(&proof.sig.signers);
+                    }
+                }
+                Block::Macro(block) => {
+                    if let Some(proof) = block.justification {
+                        tracker.record(&proof.sig.signers);
+                    }
+                }
+            }
+        }
+
+        Some(tracker.summary())
+    }
+}
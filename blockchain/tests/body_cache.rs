@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use nimiq_block::Block;
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, PushResult};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_hash::Blake2bHash;
+use nimiq_test_utils::blockchain::{signing_key, voting_key};
+use nimiq_utils::time::OffsetTime;
+
+fn produce_micro_blocks(
+    producer: &BlockProducer,
+    blockchain: &Arc<RwLock<Blockchain>>,
+    count: usize,
+) -> Vec<Blake2bHash> {
+    let mut hashes = vec![];
+    for _ in 0..count {
+        let blockchain = blockchain.upgradable_read();
+        let block = producer.next_micro_block(
+            &blockchain,
+            blockchain.time.now() + blockchain.block_number() as u64 * 1000,
+            0,
+            None,
+            vec![],
+            vec![],
+            vec![0x42],
+        );
+        hashes.push(block.hash());
+        assert_eq!(
+            Blockchain::push(blockchain, Block::Micro(block)),
+            Ok(PushResult::Extended)
+        );
+    }
+    hashes
+}
+
+fn has_body(block: Option<Block>) -> bool {
+    block.unwrap().unwrap_micro().body.is_some()
+}
+
+#[test]
+fn bodies_beyond_the_retention_window_are_reloaded() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    blockchain.read().chain_store.set_body_cache_size(2);
+
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    let hashes = produce_micro_blocks(&producer, &blockchain, 5);
+
+    let blockchain = blockchain.read();
+    for hash in &hashes {
+        assert!(has_body(blockchain.chain_store.get_block(hash, true, None)));
+    }
+
+    // Only the bodies of the two most recent blocks are retained.
+    for (i, hash) in hashes.iter().enumerate() {
+        assert_eq!(blockchain.chain_store.is_body_cached(hash), i >= 3);
+    }
+
+    // Dropped bodies are loaded from the database again, without being cached.
+    assert!(has_body(
+        blockchain.chain_store.get_block(&hashes[0], true, None)
+    ));
+    assert!(has_body(blockchain.chain_store.get_block_at(1, true, None)));
+    assert!(!blockchain.chain_store.is_body_cached(&hashes[0]));
+
+    // Once the window covers them, they are cached again.
+    blockchain.chain_store.set_body_cache_size(5);
+    assert!(has_body(
+        blockchain.chain_store.get_block(&hashes[0], true, None)
+    ));
+    assert!(blockchain.chain_store.is_body_cached(&hashes[0]));
+
+    // Disabling the cache drops all bodies.
+    blockchain.chain_store.set_body_cache_size(0);
+    assert!(hashes
+        .iter()
+        .all(|hash| !blockchain.chain_store.is_body_cached(hash)));
+    assert!(has_body(
+        blockchain.chain_store.get_block(&hashes[4], true, None)
+    ));
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{
+    AbstractBlockchain, Blockchain, BlockchainError, Checkpoint, Direction, PushResult,
+};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_primitives::policy::BATCHES_PER_EPOCH;
+use nimiq_test_utils::blockchain::{
+    fill_micro_blocks, produce_macro_blocks, signing_key, voting_key,
+};
+use nimiq_utils::time::OffsetTime;
+
+fn new_blockchain() -> Arc<RwLock<Blockchain>> {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ))
+}
+
+#[test]
+fn it_can_sync_from_a_checkpoint() {
+    let blockchain = new_blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    // Produce two epochs and take a checkpoint at the second election block.
+    produce_macro_blocks(2 * BATCHES_PER_EPOCH as usize, &producer, &blockchain);
+    let checkpoint = blockchain.read().checkpoint().unwrap();
+    let checkpoint_hash = checkpoint.election_block.hash();
+    assert_eq!(checkpoint_hash, blockchain.read().election_head_hash());
+
+    // Continue the chain past the next election block.
+    produce_macro_blocks(BATCHES_PER_EPOCH as usize + 1, &producer, &blockchain);
+    fill_micro_blocks(&producer, &blockchain);
+    assert!(blockchain.read().checkpoint().is_none());
+
+    let blocks = {
+        let blockchain = blockchain.read();
+        let num_blocks = blockchain.block_number() - checkpoint.election_block.header.block_number;
+        blockchain.get_blocks(&checkpoint_hash, num_blocks, true, Direction::Forward)
+    };
+
+    // The checkpoint survives serialization.
+    let checkpoint = Checkpoint::deserialize_from_vec(&checkpoint.serialize_to_vec()).unwrap();
+
+    // Start a new node from the checkpoint and sync forward with full verification.
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let synced = Arc::new(RwLock::new(
+        Blockchain::with_trusted_checkpoint(
+            env.clone(),
+            NetworkId::UnitAlbatross,
+            time.clone(),
+            checkpoint.clone(),
+        )
+        .unwrap(),
+    ));
+    assert_eq!(synced.read().head_hash(), checkpoint_hash);
+    assert_eq!(synced.read().election_head_hash(), checkpoint_hash);
+
+    for block in blocks {
+        assert_eq!(
+            Blockchain::push(synced.upgradable_read(), block),
+            Ok(PushResult::Extended)
+        );
+    }
+
+    assert_eq!(synced.read().head_hash(), blockchain.read().head_hash());
+    assert_eq!(
+        synced.read().state().accounts.get_root(None),
+        blockchain.read().state().accounts.get_root(None)
+    );
+
+    // The chain can be loaded again from the database.
+    let reloaded =
+        Blockchain::with_trusted_checkpoint(env, NetworkId::UnitAlbatross, time, checkpoint)
+            .unwrap();
+    assert_eq!(reloaded.head_hash(), blockchain.read().head_hash());
+}
+
+#[test]
+fn it_ignores_the_checkpoint_for_an_existing_chain() {
+    let blockchain = new_blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(2 * BATCHES_PER_EPOCH as usize, &producer, &blockchain);
+    let checkpoint = blockchain.read().checkpoint().unwrap();
+
+    // A chain started from the genesis block that hasn't reached the checkpoint yet.
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let existing = Arc::new(RwLock::new(
+        Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time.clone()).unwrap(),
+    ));
+    produce_macro_blocks(1, &producer, &existing);
+    let head_hash = existing.read().head_hash();
+    drop(existing);
+
+    let loaded =
+        Blockchain::with_trusted_checkpoint(env, NetworkId::UnitAlbatross, time, checkpoint)
+            .unwrap();
+    assert_eq!(loaded.head_hash(), head_hash);
+}
+
+#[test]
+fn it_rejects_inconsistent_checkpoints() {
+    let blockchain = new_blockchain();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(2 * BATCHES_PER_EPOCH as usize, &producer, &blockchain);
+    let checkpoint = blockchain.read().checkpoint().unwrap();
+
+    let from_checkpoint = |checkpoint: Checkpoint| {
+        Blockchain::with_trusted_checkpoint(
+            VolatileEnvironment::new(10).unwrap(),
+            NetworkId::UnitAlbatross,
+            Arc::new(OffsetTime::new()),
+            checkpoint,
+        )
+    };
+
+    // Accounts that don't match the state root.
+    let mut invalid_checkpoint = checkpoint.clone();
+    invalid_checkpoint.accounts.pop();
+    assert!(matches!(
+        from_checkpoint(invalid_checkpoint),
+        Err(BlockchainError::InvalidCheckpoint(_))
+    ));
+
+    // A previous election block that doesn't precede the checkpoint.
+    let mut invalid_checkpoint = checkpoint.clone();
+    invalid_checkpoint.previous_election_block = blockchain
+        .read()
+        .get_block_at(0, true, None)
+        .unwrap()
+        .unwrap_macro();
+    assert!(matches!(
+        from_checkpoint(invalid_checkpoint),
+        Err(BlockchainError::InvalidCheckpoint(_))
+    ));
+
+    assert!(from_checkpoint(checkpoint).is_ok());
+}
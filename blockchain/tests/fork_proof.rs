@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use nimiq_block::{Block, ForkProof, ForkProofError};
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, PushResult, SlashReason};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
+use nimiq_utils::time::OffsetTime;
+
+/// Produces a micro block and returns a fork proof for a conflicting header at the same slot.
+fn produce_fork(producer: &BlockProducer, blockchain: &Arc<RwLock<Blockchain>>) -> ForkProof {
+    let bc = blockchain.upgradable_read();
+    let prev_vrf_seed = bc.head().seed().clone();
+    let block = producer.next_micro_block(&bc, bc.time.now(), 0, None, vec![], vec![], vec![]);
+    assert_eq!(
+        Blockchain::push(bc, Block::Micro(block.clone())),
+        Ok(PushResult::Extended)
+    );
+
+    let header1 = block.header;
+    let mut header2 = header1.clone();
+    header2.timestamp += 1;
+    ForkProof {
+        justification1: block.justification.unwrap().signature,
+        justification2: signing_key().sign(header2.hash::<Blake2bHash>().as_slice()),
+        header1,
+        header2,
+        prev_vrf_seed,
+    }
+}
+
+#[test]
+fn it_verifies_fork_proofs() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    let fork_proof = produce_fork(&producer, &blockchain);
+    let next_block_number = blockchain.read().block_number() + 1;
+    assert_eq!(
+        blockchain
+            .read()
+            .verify_fork_proof(&fork_proof, next_block_number, None),
+        Ok(())
+    );
+
+    let mut same_header = fork_proof.clone();
+    same_header.header2 = same_header.header1.clone();
+    same_header.justification2 = same_header.justification1.clone();
+    assert_eq!(
+        blockchain
+            .read()
+            .verify_fork_proof(&same_header, next_block_number, None),
+        Err(ForkProofError::SameHeader)
+    );
+
+    let mut bad_justification = fork_proof.clone();
+    bad_justification.justification2 = bad_justification.justification1.clone();
+    assert_eq!(
+        blockchain
+            .read()
+            .verify_fork_proof(&bad_justification, next_block_number, None),
+        Err(ForkProofError::InvalidJustification)
+    );
+
+    // Fork proofs can only be reported during the batch of the fork and the one after it.
+    produce_macro_blocks(2, &producer, &blockchain);
+    let next_block_number = blockchain.read().block_number() + 1;
+    assert_eq!(
+        blockchain
+            .read()
+            .verify_fork_proof(&fork_proof, next_block_number, None),
+        Err(ForkProofError::OutsideReportingWindow)
+    );
+}
+
+#[test]
+fn it_reports_slashes_of_fork_proofs() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    let fork_proof = produce_fork(&producer, &blockchain);
+    let fork_block_number = fork_proof.header1.block_number;
+    assert_eq!(
+        blockchain.read().get_slashes(fork_block_number),
+        Some(vec![])
+    );
+
+    // Include the fork proof in the next block.
+    let block = {
+        let bc = blockchain.read();
+        producer.next_micro_block(
+            &bc,
+            bc.time.now() + 1000,
+            0,
+            None,
+            vec![fork_proof],
+            vec![],
+            vec![],
+        )
+    };
+    let block_number = block.header.block_number;
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), Block::Micro(block)),
+        Ok(PushResult::Extended)
+    );
+
+    let blockchain = blockchain.read();
+    let (validator, slot_number) = blockchain
+        .get_slot_owner_at(fork_block_number, 0, None)
+        .unwrap();
+    let slashes = blockchain.get_slashes(block_number).unwrap();
+    assert_eq!(slashes.len(), 1);
+    assert_eq!(slashes[0].reason, SlashReason::Fork);
+    assert_eq!(slashes[0].slot.slot, slot_number);
+    assert_eq!(slashes[0].slot.validator_address, validator.address);
+    assert_eq!(slashes[0].slot.event_block, fork_block_number);
+
+    // There is no micro block after the head.
+    assert_eq!(blockchain.get_slashes(block_number + 1), None);
+}
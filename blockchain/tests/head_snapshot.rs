@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use parking_lot::RwLock;
+
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
+use nimiq_utils::time::OffsetTime;
+
+#[test]
+fn head_snapshots_are_consistent_while_the_head_changes() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let producer = {
+        let blockchain = Arc::clone(&blockchain);
+        let done = Arc::clone(&done);
+        thread::spawn(move || {
+            let producer = BlockProducer::new(signing_key(), voting_key());
+            produce_macro_blocks(2, &producer, &blockchain);
+            done.store(true, Ordering::SeqCst);
+        })
+    };
+
+    let mut num_snapshots = 0;
+    let mut last_block_number = 0;
+    loop {
+        let finished = done.load(Ordering::SeqCst);
+
+        let snapshot = blockchain.read().head_snapshot();
+        assert!(snapshot.block_number >= last_block_number);
+        last_block_number = snapshot.block_number;
+
+        // All fields of the snapshot belong to the same block.
+        let block = blockchain
+            .read()
+            .get_block_at(snapshot.block_number, false, None)
+            .unwrap();
+        assert_eq!(snapshot.hash, block.hash());
+        assert_eq!(snapshot.timestamp, block.timestamp());
+        assert_eq!(snapshot.view_number, block.view_number());
+        num_snapshots += 1;
+
+        if finished {
+            break;
+        }
+    }
+    producer.join().unwrap();
+    assert!(num_snapshots > 0);
+
+    let blockchain = blockchain.read();
+    let snapshot = blockchain.head_snapshot();
+    assert_eq!(snapshot.block_number, blockchain.block_number());
+    assert_eq!(snapshot.hash, blockchain.head_hash());
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, HistoryMode, PushResult};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_primitives::policy;
+use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
+use nimiq_utils::time::OffsetTime;
+
+fn blockchain(history_mode: HistoryMode) -> Arc<RwLock<Blockchain>> {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let mut blockchain = Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap();
+    blockchain.history_mode = history_mode;
+    Arc::new(RwLock::new(blockchain))
+}
+
+#[test]
+fn pruned_nodes_keep_the_history_until_the_boundary() {
+    let archive = blockchain(HistoryMode::Archive);
+    let pruned = blockchain(HistoryMode::Pruned { retained_epochs: 1 });
+
+    // Produce two epochs on the archive node and push them to the pruned node.
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(2 * policy::BATCHES_PER_EPOCH as usize, &producer, &archive);
+
+    let head_block_number = archive.read().block_number();
+    assert_eq!(head_block_number, 2 * policy::EPOCH_LENGTH);
+
+    for block_number in 1..=head_block_number {
+        let block = archive
+            .read()
+            .chain_store
+            .get_block_at(block_number, true, None)
+            .unwrap();
+        assert_eq!(
+            Blockchain::push(pruned.upgradable_read(), block),
+            Ok(PushResult::Extended)
+        );
+    }
+
+    let archive = archive.read();
+    let pruned = pruned.read();
+    assert_eq!(pruned.head_hash(), archive.head_hash());
+
+    // The pruning boundary is past the head, so both nodes have the same history.
+    assert_eq!(
+        pruned.history_mode.first_retained_epoch(head_block_number),
+        0
+    );
+    for epoch_number in 1..=2 {
+        assert_eq!(
+            pruned
+                .history_store
+                .get_history_tree_root(epoch_number, None),
+            archive
+                .history_store
+                .get_history_tree_root(epoch_number, None),
+        );
+        assert_eq!(
+            pruned
+                .history_store
+                .get_num_extended_transactions(epoch_number, None),
+            archive
+                .history_store
+                .get_num_extended_transactions(epoch_number, None),
+        );
+    }
+
+    for block_number in [1, policy::EPOCH_LENGTH, head_block_number] {
+        assert_eq!(pruned.check_history_available(block_number), Ok(()));
+        assert_eq!(archive.check_history_available(block_number), Ok(()));
+    }
+
+    // Once enough epochs are completed, the first epoch is the first one to be pruned.
+    let first_pruning = (HistoryMode::MIN_RETAINED_EPOCHS + 1) * policy::EPOCH_LENGTH;
+    assert_eq!(pruned.history_mode.epochs_to_prune(first_pruning), 1..2);
+    assert!(archive
+        .history_mode
+        .epochs_to_prune(first_pruning)
+        .is_empty());
+}
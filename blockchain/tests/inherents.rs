@@ -1,6 +1,9 @@
+use parking_lot::RwLock;
+
 use beserial::Serialize;
 use nimiq_account::{Inherent, InherentType};
 use nimiq_block::MacroHeader;
+use nimiq_block_production::BlockProducer;
 use nimiq_blockchain::Blockchain;
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_hash::{Blake2bHasher, Hasher};
@@ -9,6 +12,7 @@ use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
 use nimiq_primitives::policy;
 use nimiq_primitives::slots::SlashedSlot;
+use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
 use nimiq_utils::time::OffsetTime;
 use nimiq_vrf::VrfSeed;
 use std::sync::Arc;
@@ -121,3 +125,36 @@ fn it_can_create_batch_finalization_inherents() {
     }
     assert!(got_reward && got_slash && got_finalize_batch);
 }
+
+#[test]
+fn it_can_get_reward_inherents_of_macro_blocks() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(2, &producer, &blockchain);
+
+    let blockchain = blockchain.read();
+
+    // The first batch is finalized by definition, so there are no rewards for it.
+    let inherents = blockchain
+        .get_reward_inherents(policy::macro_block_of(1))
+        .unwrap();
+    assert!(inherents.is_empty());
+
+    let inherents = blockchain
+        .get_reward_inherents(policy::macro_block_of(2))
+        .unwrap();
+    assert!(!inherents.is_empty());
+    assert!(inherents
+        .iter()
+        .all(|inherent| inherent.ty == InherentType::Reward));
+
+    // Micro blocks and macro blocks that don't exist yet have no reward inherents.
+    assert!(blockchain.get_reward_inherents(1).is_none());
+    assert!(blockchain
+        .get_reward_inherents(policy::macro_block_of(3))
+        .is_none());
+}
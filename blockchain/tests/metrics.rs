@@ -0,0 +1,37 @@
+#![cfg(feature = "metrics")]
+
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::chain_metrics::BlockProcessingStage;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
+use nimiq_utils::time::OffsetTime;
+
+#[test]
+fn pushing_blocks_records_stage_timings() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+
+    for stage in BlockProcessingStage::ALL {
+        assert_eq!(blockchain.read().metrics().stage_timings(stage).count, 0);
+    }
+
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(1, &producer, &blockchain);
+
+    let blockchain = blockchain.read();
+    let num_blocks = blockchain.block_number() as usize;
+    for stage in BlockProcessingStage::ALL {
+        let timings = blockchain.metrics().stage_timings(stage);
+        assert_eq!(timings.count, num_blocks, "{:?}", stage);
+        assert!(!timings.sum.is_zero(), "{:?}", stage);
+    }
+}
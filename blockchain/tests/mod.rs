@@ -1,17 +1,23 @@
 use parking_lot::RwLock;
 use std::sync::Arc;
 
-use beserial::Deserialize;
-use nimiq_block::Block;
+use beserial::{Deserialize, Serialize};
+use nimiq_block::{Block, BlockError};
 use nimiq_block_production::{test_utils::TemporaryBlockProducer, BlockProducer};
-use nimiq_blockchain::{AbstractBlockchain, Blockchain};
-use nimiq_blockchain::{ForkEvent, PushResult};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, BlockchainError, ChainStatus};
+use nimiq_blockchain::{BlockchainEvent, ForkEvent, PushError, PushResult};
 use nimiq_bls::{KeyPair, SecretKey};
 use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_database::{DatabaseError, WriteTransaction};
 use nimiq_genesis::NetworkId;
-use nimiq_keys::{KeyPair as SchnorrKeyPair, PrivateKey as SchnorrPrivateKey};
+use nimiq_keys::{Address, KeyPair as SchnorrKeyPair, PrivateKey as SchnorrPrivateKey};
+use nimiq_primitives::account::AccountType;
+use nimiq_primitives::coin::Coin;
 use nimiq_primitives::policy;
-use nimiq_test_utils::blockchain::{sign_view_change, SIGNING_KEY, VOTING_KEY};
+use nimiq_test_utils::blockchain::{
+    sign_view_change, signing_key, voting_key, SIGNING_KEY, VOTING_KEY,
+};
+use nimiq_transaction::{SignatureProof, Transaction};
 use nimiq_utils::time::OffsetTime;
 
 #[test]
@@ -268,6 +274,78 @@ fn it_can_rebranch_forks() {
     assert_eq!(temp_producer2.push(fork1d), Ok(PushResult::Ignored));
 }
 
+#[test]
+fn it_cant_rebranch_beyond_max_reorg_depth() {
+    // Build forks using two producers.
+    let temp_producer1 = TemporaryBlockProducer::new();
+    let temp_producer2 = TemporaryBlockProducer::new();
+
+    // Producer 1 may only revert a single micro block.
+    temp_producer1.blockchain.write().max_reorg_depth = 1;
+
+    // Case 1: shallow rebranch
+    // [0] - [0] - [0]
+    //          \- [1]
+    let block = temp_producer1.next_block(0, vec![]);
+    temp_producer2.push(block).unwrap();
+
+    let inferior = temp_producer1.next_block(0, vec![]);
+    let fork = temp_producer2.next_block(1, vec![]);
+
+    assert_eq!(temp_producer2.push(inferior), Ok(PushResult::Ignored));
+
+    // Check that producer 1 rebranches, reverting one block.
+    assert_eq!(temp_producer1.push(fork), Ok(PushResult::Rebranched));
+
+    // Case 2: deep rebranch
+    // ... - [1] - [1] - [1]
+    //          \- [1] - [2]
+    let block = temp_producer1.next_block(1, vec![]);
+    temp_producer2.push(block).unwrap();
+
+    temp_producer1.next_block(1, vec![0x48]);
+    let fork2a = temp_producer2.next_block(1, vec![]);
+
+    let fork1b = temp_producer1.next_block(1, vec![]);
+    let fork2b = temp_producer2.next_block(2, vec![]);
+
+    assert_eq!(temp_producer1.push(fork2a), Ok(PushResult::Forked));
+
+    // Check that producer 1 refuses to revert two blocks.
+    assert_eq!(temp_producer1.push(fork2b), Err(PushError::ReorgTooDeep));
+    assert_eq!(temp_producer1.blockchain.read().head_hash(), fork1b.hash());
+}
+
+#[test]
+fn it_caches_proposer_slots() {
+    let temp_producer = TemporaryBlockProducer::new();
+
+    for _ in 0..3 {
+        temp_producer.next_block(0, vec![]);
+    }
+
+    let blockchain = temp_producer.blockchain.read();
+    let head = blockchain.head();
+    let vrf_entropy = head.seed().entropy();
+    let block_number = head.block_number() + 1;
+
+    for view_number in 0..5 {
+        // The first lookup fills the cache, the second one is served from it.
+        for _ in 0..2 {
+            let cached = blockchain
+                .get_proposer_at(block_number, view_number, vrf_entropy.clone(), None)
+                .unwrap();
+            let uncached = blockchain
+                .compute_proposer_at(block_number, view_number, vrf_entropy.clone(), None)
+                .unwrap();
+
+            assert_eq!(cached.number, uncached.number);
+            assert_eq!(cached.band, uncached.band);
+            assert_eq!(cached.validator, uncached.validator);
+        }
+    }
+}
+
 #[test]
 fn it_cant_rebranch_across_epochs() {
     // Build forks using two producers.
@@ -397,3 +475,412 @@ fn create_fork_proof() {
     // Verify that the fork proof was generated
     assert!(*event1_rc1.read().unwrap());
 }
+
+#[test]
+fn it_does_not_commit_transactions_outside_validity_window() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap();
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    let mut block = producer.next_micro_block(
+        &blockchain,
+        blockchain.time.now(),
+        0,
+        None,
+        vec![],
+        vec![],
+        vec![],
+    );
+
+    // A transaction that only becomes valid at block 100.
+    let tx = Transaction::new_basic(
+        Address::default(),
+        Address::default(),
+        Coin::from_u64_unchecked(1),
+        Coin::ZERO,
+        100,
+        NetworkId::UnitAlbatross,
+    );
+    block.body.as_mut().unwrap().transactions = vec![tx];
+
+    let mut txn = WriteTransaction::new(&env);
+    assert_eq!(
+        blockchain.commit_accounts(
+            blockchain.state(),
+            &Block::Micro(block),
+            blockchain.head().seed().entropy(),
+            0,
+            &mut txn,
+        ),
+        Err(PushError::InvalidBlock(BlockError::ExpiredTransaction))
+    );
+    txn.abort();
+}
+
+#[test]
+fn it_loads_missing_block_bodies() {
+    let temp_producer = TemporaryBlockProducer::new();
+    let block = temp_producer.next_block(0, vec![0x42]);
+    let blockchain = temp_producer.blockchain.read();
+
+    // A block without body is completed from the chain store.
+    let mut header_only = blockchain.get_block(&block.hash(), false, None).unwrap();
+    assert!(!header_only.has_body());
+    assert_eq!(blockchain.load_block_body(&mut header_only, None), Ok(()));
+    assert_eq!(header_only, block);
+
+    // Blocks that already have a body are left as they are.
+    let mut full_block = block.clone();
+    assert_eq!(blockchain.load_block_body(&mut full_block, None), Ok(()));
+    assert_eq!(full_block, block);
+
+    // The body of an unknown block can't be loaded.
+    drop(blockchain);
+    let mut unknown = temp_producer.next_block_no_push(0, vec![]).unwrap_micro();
+    unknown.body = None;
+    let mut unknown = Block::Micro(unknown);
+    assert_eq!(
+        temp_producer
+            .blockchain
+            .read()
+            .load_block_body(&mut unknown, None),
+        Err(BlockchainError::BlockBodyNotAvailable(unknown.hash()))
+    );
+    assert!(!unknown.has_body());
+}
+
+#[test]
+fn it_reports_the_chain_status_of_blocks() {
+    let temp_producer1 = TemporaryBlockProducer::new();
+    let temp_producer2 = TemporaryBlockProducer::new();
+
+    let block = temp_producer1.next_block(0, vec![]);
+    temp_producer2.push(block.clone()).unwrap();
+
+    // [0] - [0]
+    //    \- [1]
+    let inferior = temp_producer1.next_block(0, vec![]);
+    let fork = temp_producer2.next_block(1, vec![]);
+
+    let blockchain = &temp_producer1.blockchain;
+    assert_eq!(
+        blockchain.read().get_chain_status(&block.hash()),
+        ChainStatus::Main
+    );
+    assert_eq!(
+        blockchain.read().get_chain_status(&inferior.hash()),
+        ChainStatus::Main
+    );
+    assert_eq!(
+        blockchain.read().get_chain_status(&fork.hash()),
+        ChainStatus::Unknown
+    );
+
+    // After the rebranch, the previous head is on a fork.
+    assert_eq!(
+        temp_producer1.push(fork.clone()),
+        Ok(PushResult::Rebranched)
+    );
+    assert_eq!(
+        blockchain.read().get_chain_status(&block.hash()),
+        ChainStatus::Main
+    );
+    assert_eq!(
+        blockchain.read().get_chain_status(&inferior.hash()),
+        ChainStatus::Fork
+    );
+    assert_eq!(
+        blockchain.read().get_chain_status(&fork.hash()),
+        ChainStatus::Main
+    );
+}
+
+#[test]
+fn it_notifies_about_reverted_transactions_on_rebranch() {
+    let temp_producer1 = TemporaryBlockProducer::new();
+    let temp_producer2 = TemporaryBlockProducer::new();
+
+    let events = Arc::new(std::sync::RwLock::new(vec![]));
+    let events_rc = Arc::clone(&events);
+    temp_producer1
+        .blockchain
+        .write()
+        .notifier
+        .register(move |e: &BlockchainEvent| events_rc.write().unwrap().push(e.clone()));
+
+    // A transaction from the account funded in the genesis block.
+    let key_pair = SchnorrKeyPair::from(
+        SchnorrPrivateKey::deserialize_from_vec(
+            &hex::decode("6c9320ac201caf1f8eaa5b05f5d67a9e77826f3f6be266a0ecccc20416dc6587")
+                .unwrap(),
+        )
+        .unwrap(),
+    );
+    let mut tx = Transaction::new_basic(
+        Address::from(&key_pair.public),
+        Address::default(),
+        Coin::from_u64_unchecked(1),
+        Coin::ZERO,
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    tx.proof = SignatureProof::from(key_pair.public, key_pair.sign(&tx.serialize_content()))
+        .serialize_to_vec();
+
+    // [0] - [0] (with the transaction)
+    //    \- [1]
+    let inferior = {
+        let blockchain = temp_producer1.blockchain.read();
+        Block::Micro(temp_producer1.producer.next_micro_block(
+            &blockchain,
+            blockchain.time.now() + 1000,
+            0,
+            None,
+            vec![],
+            vec![tx.clone()],
+            vec![],
+        ))
+    };
+    assert_eq!(
+        temp_producer1.push(inferior.clone()),
+        Ok(PushResult::Extended)
+    );
+    let fork = temp_producer2.next_block(1, vec![]);
+
+    events.write().unwrap().clear();
+    assert_eq!(
+        temp_producer1.push(fork.clone()),
+        Ok(PushResult::Rebranched)
+    );
+
+    let events = events.read().unwrap();
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        BlockchainEvent::Rebranched(reverted, adopted) => {
+            assert_eq!(reverted, &vec![(inferior.hash(), inferior.clone())]);
+            assert_eq!(adopted, &vec![(fork.hash(), fork.clone())]);
+
+            // The reverted transaction can be re-admitted to the mempool.
+            let reverted_txs: Vec<_> = reverted
+                .iter()
+                .flat_map(|(_, block)| block.transactions().cloned().unwrap_or_default())
+                .collect();
+            assert_eq!(reverted_txs, vec![tx]);
+        }
+        event => panic!("Unexpected event: {:?}", event),
+    }
+}
+
+#[test]
+fn it_rolls_back_the_block_commit_if_the_history_store_fails() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    // Transactions from the account funded in the genesis block.
+    let key_pair = SchnorrKeyPair::from(
+        SchnorrPrivateKey::deserialize_from_vec(
+            &hex::decode("6c9320ac201caf1f8eaa5b05f5d67a9e77826f3f6be266a0ecccc20416dc6587")
+                .unwrap(),
+        )
+        .unwrap(),
+    );
+    let sender = Address::from(&key_pair.public);
+    let transaction = |value: u64| {
+        let mut tx = Transaction::new_basic(
+            sender.clone(),
+            Address::default(),
+            Coin::from_u64_unchecked(value),
+            Coin::ZERO,
+            1,
+            NetworkId::UnitAlbatross,
+        );
+        tx.proof = SignatureProof::from(key_pair.public, key_pair.sign(&tx.serialize_content()))
+            .serialize_to_vec();
+        tx
+    };
+    let next_block = |tx: Transaction| {
+        let blockchain = blockchain.read();
+        Block::Micro(producer.next_micro_block(
+            &blockchain,
+            blockchain.time.now() + (blockchain.block_number() as u64 + 1) * 1000,
+            0,
+            None,
+            vec![],
+            vec![tx],
+            vec![],
+        ))
+    };
+
+    let block1 = next_block(transaction(1));
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), block1.clone()),
+        Ok(PushResult::Extended)
+    );
+    let block2 = next_block(transaction(2));
+
+    let state_root = blockchain.read().state.accounts.get_root(None);
+    let balance = blockchain.read().get_account(&sender).unwrap().balance();
+    let num_ext_txs = blockchain
+        .read()
+        .history_store
+        .get_num_extended_transactions(1, None);
+    assert_eq!(num_ext_txs, 1);
+
+    // Simulate a failure of the history store by removing the first node of the history tree of
+    // epoch 1. Appending the transaction of the second block needs it as a sibling.
+    {
+        let hist_tree_db = env.open_database("HistoryTrees".to_string());
+        let mut key = 1u32.to_be_bytes().to_vec();
+        key.extend_from_slice(&0usize.to_be_bytes());
+
+        let mut txn = WriteTransaction::new(&env);
+        txn.remove(&hist_tree_db, &key);
+        txn.commit();
+    }
+
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), block2),
+        Err(PushError::HistoryError)
+    );
+
+    // The whole block commit was rolled back.
+    let blockchain = blockchain.read();
+    assert_eq!(blockchain.head_hash(), block1.hash());
+    assert_eq!(blockchain.state.accounts.get_root(None), state_root);
+    assert_eq!(blockchain.get_account(&sender).unwrap().balance(), balance);
+    assert_eq!(
+        blockchain
+            .history_store
+            .get_num_extended_transactions(1, None),
+        num_ext_txs
+    );
+    assert!(blockchain.chain_store.get_receipts(2, None).is_none());
+}
+
+#[test]
+fn it_rejects_blocks_with_transactions_exceeding_max_size() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    // Transactions from the account funded in the genesis block, padded to the given size.
+    let key_pair = SchnorrKeyPair::from(
+        SchnorrPrivateKey::deserialize_from_vec(
+            &hex::decode("6c9320ac201caf1f8eaa5b05f5d67a9e77826f3f6be266a0ecccc20416dc6587")
+                .unwrap(),
+        )
+        .unwrap(),
+    );
+    let sign = |data: Vec<u8>| {
+        let mut tx = Transaction::new_extended(
+            Address::from(&key_pair.public),
+            AccountType::Basic,
+            Address::default(),
+            AccountType::Basic,
+            Coin::from_u64_unchecked(1),
+            Coin::ZERO,
+            data,
+            1,
+            NetworkId::UnitAlbatross,
+        );
+        tx.proof = SignatureProof::from(key_pair.public, key_pair.sign(&tx.serialize_content()))
+            .serialize_to_vec();
+        tx
+    };
+    let transaction = |size: usize| {
+        let base_size = sign(vec![0]).serialized_size();
+        let tx = sign(vec![0; size - base_size + 1]);
+        assert_eq!(tx.serialized_size(), size);
+        tx
+    };
+    let next_block = |tx: Transaction| {
+        let blockchain = blockchain.read();
+        Block::Micro(producer.next_micro_block(
+            &blockchain,
+            blockchain.time.now() + (blockchain.block_number() as u64 + 1) * 1000,
+            0,
+            None,
+            vec![],
+            vec![tx],
+            vec![],
+        ))
+    };
+
+    let block = next_block(transaction(policy::MAX_SIZE_TRANSACTION));
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), block.clone()),
+        Ok(PushResult::Extended)
+    );
+
+    let too_large_block = next_block(transaction(policy::MAX_SIZE_TRANSACTION + 1));
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), too_large_block),
+        Err(PushError::TransactionTooLarge)
+    );
+    assert_eq!(blockchain.read().head_hash(), block.hash());
+}
+
+#[test]
+fn it_serves_reads_after_the_database_becomes_read_only() {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let producer = BlockProducer::new(signing_key(), voting_key());
+
+    let next_block = || {
+        let blockchain = blockchain.read();
+        Block::Micro(producer.next_micro_block(
+            &blockchain,
+            blockchain.time.now() + (blockchain.block_number() as u64 + 1) * 1000,
+            0,
+            None,
+            vec![],
+            vec![],
+            vec![],
+        ))
+    };
+
+    let block1 = next_block();
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), block1.clone()),
+        Ok(PushResult::Extended)
+    );
+    let block2 = next_block();
+    let state_root = blockchain.read().state.accounts.get_root(None);
+    assert!(!blockchain.read().is_read_only_filesystem());
+
+    // Simulate the file system refusing writes, e.g. because the disk quota was exceeded.
+    env.simulate_write_failure();
+
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), block2.clone()),
+        Err(PushError::DatabaseError(DatabaseError::ReadOnlyFilesystem))
+    );
+    assert!(blockchain.read().is_read_only_filesystem());
+
+    // Block processing stays paused.
+    assert_eq!(
+        Blockchain::push(blockchain.upgradable_read(), block2.clone()),
+        Err(PushError::DatabaseError(DatabaseError::ReadOnlyFilesystem))
+    );
+
+    // Reads are still served from the last committed state.
+    let blockchain = blockchain.read();
+    assert_eq!(blockchain.head_hash(), block1.hash());
+    assert_eq!(blockchain.state.accounts.get_root(None), state_root);
+    assert_eq!(
+        blockchain.get_block(&block1.hash(), true, None),
+        Some(block1)
+    );
+    assert!(blockchain.get_block(&block2.hash(), false, None).is_none());
+}
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use nimiq_block::{Block, ViewChange};
+use nimiq_block_production::{test_utils::TemporaryBlockProducer, BlockProducer};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, Direction, PushError, PushResult};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_primitives::policy::{self, BATCH_LENGTH};
+use nimiq_test_utils::blockchain::{
+    fill_micro_blocks, produce_macro_blocks, signing_key, voting_key,
+};
+use nimiq_utils::time::OffsetTime;
+
+fn new_blockchain() -> Arc<RwLock<Blockchain>> {
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+    Arc::new(RwLock::new(
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+    ))
+}
+
+/// Produces two full batches and the micro blocks of a third one and returns them in order.
+fn produce_blocks() -> Vec<Block> {
+    let blockchain = new_blockchain();
+    let genesis_hash = blockchain.read().head_hash();
+
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(2, &producer, &blockchain);
+    fill_micro_blocks(&producer, &blockchain);
+
+    let blockchain = blockchain.read();
+    let blocks = blockchain.get_blocks(
+        &genesis_hash,
+        blockchain.block_number(),
+        true,
+        Direction::Forward,
+    );
+    assert_eq!(blocks.len(), blockchain.block_number() as usize);
+    blocks
+}
+
+#[test]
+fn parallel_verification_produces_same_head_as_serial_push() {
+    let blocks = produce_blocks();
+
+    let serial = new_blockchain();
+    for block in blocks.iter().cloned() {
+        assert_eq!(
+            Blockchain::push(serial.upgradable_read(), block),
+            Ok(PushResult::Extended)
+        );
+    }
+
+    let parallel = new_blockchain();
+    let mut num_verified = 0;
+    for batch in blocks.chunks(BATCH_LENGTH as usize) {
+        let verification = parallel
+            .read()
+            .prepare_signature_verification(batch.to_vec());
+        let verified_blocks = verification.verify();
+
+        // The blocks are returned in order.
+        for (verified_block, block) in verified_blocks.iter().zip(batch) {
+            assert_eq!(verified_block.block(), block);
+        }
+
+        for verified_block in verified_blocks {
+            if verified_block.signatures_verified() {
+                num_verified += 1;
+            }
+            assert_eq!(
+                Blockchain::push_verified(parallel.upgradable_read(), verified_block),
+                Ok(PushResult::Extended)
+            );
+        }
+    }
+
+    // All micro blocks were verified in the parallel stage.
+    assert_eq!(num_verified, blocks.len() - 2);
+    assert_eq!(serial.read().head_hash(), parallel.read().head_hash());
+    assert_eq!(
+        serial.read().state().accounts.get_root(None),
+        parallel.read().state().accounts.get_root(None)
+    );
+}
+
+#[test]
+fn blocks_of_later_batches_are_verified_on_push() {
+    let blocks = produce_blocks();
+
+    let blockchain = new_blockchain();
+    let verification = blockchain
+        .read()
+        .prepare_signature_verification(blocks.clone());
+    let verified_blocks = verification.verify();
+
+    // Only the micro blocks of the first batch can be verified upfront.
+    let first_batch = BATCH_LENGTH as usize - 1;
+    for (i, verified_block) in verified_blocks.iter().enumerate() {
+        assert_eq!(verified_block.signatures_verified(), i < first_batch);
+    }
+
+    for verified_block in verified_blocks {
+        assert_eq!(
+            Blockchain::push_verified(blockchain.upgradable_read(), verified_block),
+            Ok(PushResult::Extended)
+        );
+    }
+    assert_eq!(blockchain.read().head_hash(), blocks.last().unwrap().hash());
+}
+
+#[test]
+fn blocks_with_invalid_signatures_are_rejected() {
+    let mut blocks = produce_blocks();
+    blocks.truncate(2);
+
+    // Replace the justification of the second block with the one of the first.
+    let signature = blocks[0]
+        .clone()
+        .unwrap_micro()
+        .justification
+        .unwrap()
+        .signature;
+    if let Block::Micro(ref mut micro_block) = blocks[1] {
+        micro_block.justification.as_mut().unwrap().signature = signature;
+    }
+
+    let blockchain = new_blockchain();
+    let verification = blockchain.read().prepare_signature_verification(blocks);
+    let mut verified_blocks = verification.verify().into_iter();
+
+    let valid_block = verified_blocks.next().unwrap();
+    assert!(valid_block.signatures_verified());
+    assert_eq!(
+        Blockchain::push_verified(blockchain.upgradable_read(), valid_block),
+        Ok(PushResult::Extended)
+    );
+
+    let invalid_block = verified_blocks.next().unwrap();
+    assert!(!invalid_block.signatures_verified());
+    assert!(matches!(
+        Blockchain::push_verified(blockchain.upgradable_read(), invalid_block),
+        Err(PushError::InvalidBlock(_))
+    ));
+}
+
+#[test]
+fn view_change_proofs_are_verified_once() {
+    // Produce a micro block followed by one with a view change.
+    let source = TemporaryBlockProducer::new();
+    let view_change = ViewChange {
+        block_number: 2,
+        new_view_number: 1,
+        vrf_entropy: source.next_block(0, vec![]).seed().entropy(),
+    };
+    let block = source.next_block(1, vec![]);
+    let view_change_proof = block
+        .clone()
+        .unwrap_micro()
+        .justification
+        .unwrap()
+        .view_change_proof
+        .unwrap();
+    let blocks = {
+        let blockchain = source.blockchain.read();
+        let genesis_hash = blockchain.get_block_at(0, false, None).unwrap().hash();
+        blockchain.get_blocks(&genesis_hash, 2, true, Direction::Forward)
+    };
+
+    // The proof is verified in the parallel stage and not again when the block is pushed. The
+    // result is only added to the cache on push, since the blockchain isn't locked while the
+    // signatures are verified.
+    let synced = TemporaryBlockProducer::new();
+    let blockchain = &synced.blockchain;
+    let verification = blockchain.read().prepare_signature_verification(blocks);
+    let verified_blocks = verification.verify();
+    assert!(verified_blocks
+        .iter()
+        .all(|block| block.signatures_verified()));
+    assert_eq!(blockchain.read().num_view_change_proof_verifications(), 0);
+
+    for verified_block in verified_blocks {
+        assert_eq!(
+            Blockchain::push_verified(blockchain.upgradable_read(), verified_block),
+            Ok(PushResult::Extended)
+        );
+    }
+    assert_eq!(blockchain.read().head_hash(), block.hash());
+    assert!(blockchain
+        .read()
+        .verify_view_change_proof(&view_change, &view_change_proof));
+    assert_eq!(blockchain.read().num_view_change_proof_verifications(), 1);
+
+    // The cache is cleared once the next macro block is finalized.
+    while !policy::is_macro_block_at(blockchain.read().block_number()) {
+        synced.next_block(1, vec![]);
+    }
+    assert!(blockchain
+        .read()
+        .verify_view_change_proof(&view_change, &view_change_proof));
+    assert_eq!(blockchain.read().num_view_change_proof_verifications(), 2);
+}
+
+#[test]
+fn signatures_are_verified_without_the_blockchain_lock() {
+    let blocks = produce_blocks();
+
+    let blockchain = new_blockchain();
+    let verification = blockchain.read().prepare_signature_verification(blocks);
+
+    // The verification doesn't need the blockchain, so a writer may hold it in the meantime.
+    let _write_guard = blockchain.write();
+    let verified_blocks = verification.verify();
+    assert!(verified_blocks
+        .iter()
+        .any(|block| block.signatures_verified()));
+}
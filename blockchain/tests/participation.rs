@@ -0,0 +1,83 @@
+use nimiq_block_production::test_utils::TemporaryBlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, ParticipationTracker};
+use nimiq_bls::KeyPair as BlsKeyPair;
+use nimiq_collections::BitSet;
+use nimiq_keys::{Address, SecureGenerate};
+use nimiq_primitives::policy;
+use nimiq_test_utils::validator::seeded_rng;
+use nimiq_test_utils::validator_network::equal_slot_validators;
+
+#[test]
+fn it_accumulates_participation_across_aggregations() {
+    let mut rng = seeded_rng(0);
+    let voting_keys: Vec<BlsKeyPair> = (0..3).map(|_| BlsKeyPair::generate(&mut rng)).collect();
+    let validators = equal_slot_validators(&voting_keys);
+
+    let signers = |validator_ids: &[usize]| {
+        let mut signers = BitSet::new();
+        for (id, validator) in validators.iter().enumerate() {
+            if validator_ids.contains(&id) {
+                let (first_slot, end_slot) = validator.slot_range;
+                for slot in first_slot..end_slot {
+                    signers.insert(slot as usize);
+                }
+            }
+        }
+        signers
+    };
+
+    let mut tracker = ParticipationTracker::new(validators.clone());
+    tracker.record(&signers(&[0, 1, 2]));
+    tracker.record(&signers(&[0, 1]));
+
+    // A single slot is enough for a validator to count as a signer.
+    let mut partial_signers = signers(&[0]);
+    let last_validator = validators.iter().last().unwrap();
+    partial_signers.insert(last_validator.slot_range.0 as usize);
+    tracker.record(&partial_signers);
+
+    assert_eq!(tracker.num_aggregations(), 3);
+    let summary = tracker.summary();
+    assert_eq!(summary.len(), 3);
+    let num_signed: Vec<u32> = summary.iter().map(|p| p.num_signed).collect();
+    assert_eq!(num_signed, vec![3, 2, 2]);
+    assert_eq!(summary[0].validator_address, Address::from([0u8; 20]));
+    assert_eq!(summary[0].rate(), 1.0);
+    assert_eq!(summary[1].rate(), 2.0 / 3.0);
+    assert_eq!(
+        summary.iter().map(|p| p.num_slots).sum::<u16>(),
+        policy::SLOTS
+    );
+}
+
+#[test]
+fn it_reports_the_participation_of_an_epoch() {
+    let producer = TemporaryBlockProducer::new();
+
+    // A view change at the first block, followed by the rest of the epoch.
+    producer.next_block(1, vec![]);
+    while producer.blockchain.read().block_number() < policy::election_block_of(1) {
+        producer.next_block(0, vec![]);
+    }
+
+    let blockchain = producer.blockchain.read();
+    assert_eq!(blockchain.get_validator_participation(0, None), None);
+
+    // The single validator signed the view change and all macro blocks of the epoch.
+    let participation = blockchain.get_validator_participation(1, None).unwrap();
+    assert_eq!(participation.len(), 1);
+    assert_eq!(
+        participation[0].num_aggregations,
+        policy::BATCHES_PER_EPOCH as u32 + 1
+    );
+    assert_eq!(
+        participation[0].num_signed,
+        participation[0].num_aggregations
+    );
+    assert_eq!(participation[0].num_slots, policy::SLOTS);
+
+    // Nothing was aggregated in the new epoch yet.
+    let participation = blockchain.get_validator_participation(2, None).unwrap();
+    assert_eq!(participation[0].num_aggregations, 0);
+    assert_eq!(participation[0].rate(), 1.0);
+}
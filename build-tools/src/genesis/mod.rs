@@ -25,6 +25,10 @@ mod config;
 pub enum GenesisBuilderError {
     #[error("No VRF seed to generate genesis block")]
     NoVrfSeed,
+    #[error("No timestamp to generate genesis block")]
+    NoTimestamp,
+    #[error("No validators in genesis block")]
+    NoValidators,
     #[error("Invalid timestamp: {0}")]
     InvalidTimestamp(OffsetDateTime),
     #[error("Serialization failed")]
@@ -151,6 +155,26 @@ impl GenesisBuilder {
         Ok(self)
     }
 
+    /// Generates the genesis block of a custom network from the given config file. In contrast to
+    /// the genesis blocks compiled into the client, the timestamp and the VRF seed must be set
+    /// explicitly, because every node of the network has to generate the same genesis block.
+    pub fn generate_custom<P: AsRef<Path>>(path: P) -> Result<GenesisInfo, GenesisBuilderError> {
+        let mut builder = Self::new();
+        builder.with_config_file(path)?;
+
+        if builder.timestamp.is_none() {
+            return Err(GenesisBuilderError::NoTimestamp);
+        }
+        if builder.vrf_seed.is_none() {
+            return Err(GenesisBuilderError::NoVrfSeed);
+        }
+        if builder.validators.is_empty() {
+            return Err(GenesisBuilderError::NoValidators);
+        }
+
+        builder.generate()
+    }
+
     pub fn generate(&self) -> Result<GenesisInfo, GenesisBuilderError> {
         // Initialize the environment.
         let env = VolatileEnvironment::new(10)?;
@@ -88,6 +88,10 @@ async fn main_inner() -> Result<(), Error> {
     tokio::spawn(consensus);
     let consensus = client.consensus_proxy();
 
+    // Keep the mempool to persist its pending transactions on shutdown. This has to be done
+    // before the validator is moved out of the client.
+    let mempool = client.mempool();
+
     // Start validator
     if let Some(validator) = client.validator() {
         log::info!("Spawning validator");
@@ -104,22 +108,34 @@ async fn main_inner() -> Result<(), Error> {
         show_statistics = false;
     }
 
-    // Run periodically
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    // Run periodically until the client is shut down
     let mut interval = tokio::time::interval(Duration::from_secs(statistics_interval));
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = &mut shutdown => {
+                log::info!("Shutting down");
+                if let Some(mempool) = &mempool {
+                    mempool.persist();
+                }
+                return Ok(());
+            }
+        }
 
         if show_statistics {
             match client.network().network_info().await {
                 Ok(network_info) => {
-                    let head = client.blockchain_head().clone();
+                    let head = client.blockchain_head_snapshot();
 
                     log::info!(
                         "Consensus established: {:?} - Head: #{}.{}- {}, Peers: {}",
                         consensus.is_established(),
-                        head.block_number(),
-                        head.view_number(),
-                        head.hash(),
+                        head.block_number,
+                        head.view_number,
+                        head.hash,
                         network_info.num_peers()
                     );
                 }
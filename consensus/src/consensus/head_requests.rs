@@ -1,4 +1,5 @@
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 use std::pin::Pin;
 use std::sync::{Arc, Weak};
@@ -24,6 +25,8 @@ pub struct HeadRequests<TPeer: Peer + 'static> {
     head_blocks:
         FuturesUnordered<BoxFuture<'static, (Result<Option<Block>, RequestError>, TPeer::Id)>>,
     requested_hashes: HashSet<Blake2bHash>,
+    peer_scores_request: Option<BoxFuture<'static, HashMap<TPeer::Id, f64>>>,
+    peer_scores: HashMap<TPeer::Id, f64>,
     blockchain: Arc<RwLock<Blockchain>>,
     num_known_blocks: usize,
     num_unknown_blocks: usize,
@@ -34,11 +37,33 @@ pub struct HeadRequestsResult<TPeer: Peer + 'static> {
     pub num_known_blocks: usize,
     pub num_unknown_blocks: usize,
     pub unknown_blocks: Vec<(Block, TPeer::Id)>,
+    /// The scores of the peers that misbehaved recently. Peers that are not included have a clean
+    /// score of 0.
+    pub peer_scores: HashMap<TPeer::Id, f64>,
+}
+
+/// Selects the peer to sync from among the candidates, given as pairs of peer id and the peer's
+/// score. The peer with the best (i.e. highest) score is chosen. If several peers have the same
+/// score, the one with the lowest peer id is chosen, so that the choice doesn't depend on the order
+/// of the candidates.
+pub fn select_sync_peer<TPeerId: Ord>(
+    candidates: impl IntoIterator<Item = (TPeerId, f64)>,
+) -> Option<TPeerId> {
+    candidates
+        .into_iter()
+        .max_by(|(peer_a, score_a), (peer_b, score_b)| {
+            score_a
+                .partial_cmp(score_b)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| peer_b.cmp(peer_a))
+        })
+        .map(|(peer_id, _)| peer_id)
 }
 
 impl<TPeer: Peer + 'static> HeadRequests<TPeer> {
     pub fn new(
         peers: Vec<Weak<ConsensusAgent<TPeer>>>,
+        peer_scores: BoxFuture<'static, HashMap<TPeer::Id, f64>>,
         blockchain: Arc<RwLock<Blockchain>>,
     ) -> Self {
         let peers: Vec<_> = peers
@@ -60,6 +85,8 @@ impl<TPeer: Peer + 'static> HeadRequests<TPeer> {
             head_hashes,
             head_blocks: Default::default(),
             requested_hashes: Default::default(),
+            peer_scores_request: Some(peer_scores),
+            peer_scores: Default::default(),
             blockchain,
             num_known_blocks: 0,
             num_unknown_blocks: 0,
@@ -68,7 +95,9 @@ impl<TPeer: Peer + 'static> HeadRequests<TPeer> {
     }
 
     pub fn is_finished(&self) -> bool {
-        self.head_hashes.is_empty() && self.head_blocks.is_empty()
+        self.head_hashes.is_empty()
+            && self.head_blocks.is_empty()
+            && self.peer_scores_request.is_none()
     }
 }
 
@@ -119,15 +148,46 @@ impl<TPeer: Peer + 'static> Future for HeadRequests<TPeer> {
             }
         }
 
-        // We're done if both queues are empty.
+        // And the peer scores.
+        if let Some(ref mut peer_scores_request) = self.peer_scores_request {
+            if let Poll::Ready(peer_scores) = peer_scores_request.poll_unpin(cx) {
+                self.peer_scores = peer_scores;
+                self.peer_scores_request = None;
+            }
+        }
+
+        // We're done if both queues are empty and we got the peer scores.
         if self.is_finished() {
             return Poll::Ready(HeadRequestsResult {
                 num_known_blocks: self.num_known_blocks,
                 num_unknown_blocks: self.num_unknown_blocks,
                 unknown_blocks: mem::take(&mut self.unknown_blocks),
+                peer_scores: mem::take(&mut self.peer_scores),
             });
         }
 
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_selects_the_best_score_then_the_lowest_peer_id() {
+        assert_eq!(select_sync_peer(Vec::<(u32, f64)>::new()), None);
+
+        let candidates = vec![(1, -20.0), (7, -5.0), (5, 0.0), (3, 0.0), (9, 0.0)];
+        assert_eq!(select_sync_peer(candidates.clone()), Some(3));
+
+        // The choice doesn't depend on the order of the candidates.
+        let mut reversed = candidates;
+        reversed.reverse();
+        assert_eq!(select_sync_peer(reversed), Some(3));
+
+        // A misbehaving peer isn't chosen, even if its peer id is lower.
+        assert_eq!(select_sync_peer(vec![(2, -10.0), (4, 0.0)]), Some(4));
+        assert_eq!(select_sync_peer(vec![(2, -10.0), (4, -10.5)]), Some(2));
+    }
+}
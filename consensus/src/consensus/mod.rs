@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -6,7 +7,9 @@ use std::time::{Duration, Instant};
 use futures::task::{Context, Poll};
 use futures::{Future, FutureExt, StreamExt};
 use parking_lot::RwLock;
-use tokio::sync::broadcast::{channel as broadcast, Sender as BroadcastSender};
+use tokio::sync::broadcast::{
+    channel as broadcast, Receiver as BroadcastReceiver, Sender as BroadcastSender,
+};
 use tokio::time::Sleep;
 use tokio_stream::wrappers::BroadcastStream;
 
@@ -14,9 +17,10 @@ use nimiq_blockchain::{AbstractBlockchain, Blockchain};
 use nimiq_database::Environment;
 use nimiq_mempool::mempool::TransactionTopic;
 use nimiq_network_interface::network::Network;
+use nimiq_network_interface::peer::Peer;
 use nimiq_transaction::Transaction;
 
-use crate::consensus::head_requests::{HeadRequests, HeadRequestsResult};
+use crate::consensus::head_requests::{select_sync_peer, HeadRequests, HeadRequestsResult};
 use crate::sync::block_queue::{BlockQueue, BlockQueueConfig, BlockQueueEvent};
 use crate::sync::request_component::{BlockRequestComponent, HistorySyncStream};
 
@@ -27,6 +31,7 @@ pub struct ConsensusProxy<N: Network> {
     pub blockchain: Arc<RwLock<Blockchain>>,
     pub network: Arc<N>,
     established_flag: Arc<AtomicBool>,
+    events: BroadcastSender<ConsensusEventOf<N>>,
 }
 
 impl<N: Network> Clone for ConsensusProxy<N> {
@@ -35,6 +40,7 @@ impl<N: Network> Clone for ConsensusProxy<N> {
             blockchain: Arc::clone(&self.blockchain),
             network: Arc::clone(&self.network),
             established_flag: Arc::clone(&self.established_flag),
+            events: self.events.clone(),
         }
     }
 }
@@ -47,12 +53,83 @@ impl<N: Network> ConsensusProxy<N> {
     pub fn is_established(&self) -> bool {
         self.established_flag.load(Ordering::Acquire)
     }
+
+    /// Returns a receiver for the events emitted by the consensus. See `Consensus::subscribe`.
+    pub fn subscribe(&self) -> BroadcastReceiver<ConsensusEventOf<N>> {
+        self.events.subscribe()
+    }
 }
 
+/// Events emitted by the consensus.
+///
+/// `PeerSynced` is emitted once for each peer that finished syncing with us and from which we now
+/// follow the chain. The `PeerSynced` events of all peers that count towards establishing consensus
+/// are always emitted before the corresponding `Established` event. Peers that finish syncing
+/// afterwards still emit `PeerSynced`, and a peer that is put back into sync mode emits it again
+/// once it is synced.
 #[derive(Clone)]
-pub enum ConsensusEvent {
+pub enum ConsensusEvent<TPeerId> {
     Established,
     Lost,
+    SyncProgress(SyncProgress),
+    PeerSynced(TPeerId),
+}
+
+/// The type of the events emitted by a consensus running on network `N`.
+pub type ConsensusEventOf<N> = ConsensusEvent<<<N as Network>::PeerType as Peer>::Id>;
+
+/// An estimate of how far along we are in catching up to the tip of the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyncProgress {
+    /// The block number of our current head.
+    pub current_height: u32,
+    /// The highest block number that we know of, either announced by our peers or buffered in the
+    /// block queue.
+    pub target_height: u32,
+}
+
+/// Limits how often `ConsensusEvent::SyncProgress` is emitted while syncing, so that a fast sync
+/// doesn't flood the consensus event channel.
+#[derive(Default)]
+struct SyncProgressThrottle {
+    /// The time and head height at which progress was last emitted.
+    last_emitted: Option<(Instant, u32)>,
+}
+
+impl SyncProgressThrottle {
+    /// The minimum time between two progress events.
+    const MIN_INTERVAL: Duration = Duration::from_secs(1);
+    /// The number of blocks after which progress is emitted, even if `MIN_INTERVAL` didn't pass.
+    const MIN_BLOCK_DELTA: u32 = 1000;
+
+    /// Checks whether progress at `current_height` should be emitted at time `now`. Progress is
+    /// always emitted if `force` is set.
+    fn should_emit(&mut self, now: Instant, current_height: u32, force: bool) -> bool {
+        let emit = force
+            || match self.last_emitted {
+                None => true,
+                Some((time, height)) => {
+                    now.saturating_duration_since(time) >= Self::MIN_INTERVAL
+                        || current_height.saturating_sub(height) >= Self::MIN_BLOCK_DELTA
+                }
+            };
+
+        if emit {
+            self.last_emitted = Some((now, current_height));
+        }
+        emit
+    }
+}
+
+impl SyncProgress {
+    /// Returns the fraction of the chain that we already have, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        if self.target_height == 0 || self.current_height >= self.target_height {
+            1.0
+        } else {
+            self.current_height as f64 / self.target_height as f64
+        }
+    }
 }
 
 pub struct Consensus<N: Network> {
@@ -66,11 +143,19 @@ pub struct Consensus<N: Network> {
     /// FIXME Remove this
     next_execution_timer: Option<Pin<Box<Sleep>>>,
 
-    events: BroadcastSender<ConsensusEvent>,
+    events: BroadcastSender<ConsensusEventOf<N>>,
     established_flag: Arc<AtomicBool>,
     head_requests: Option<HeadRequests<N::PeerType>>,
     head_requests_time: Option<Instant>,
 
+    /// The highest block number of the head blocks that our peers reported to us.
+    best_peer_height: u32,
+
+    sync_progress_throttle: SyncProgressThrottle,
+
+    /// The peers that finished syncing and from which we follow the chain.
+    synced_peers: HashSet<<N::PeerType as Peer>::Id>,
+
     min_peers: usize,
 }
 
@@ -153,13 +238,27 @@ impl<N: Network> Consensus<N> {
             established_flag,
             head_requests: None,
             head_requests_time: None,
+            best_peer_height: 0,
+            sync_progress_throttle: SyncProgressThrottle::default(),
+            synced_peers: HashSet::new(),
 
             min_peers,
         }
     }
 
-    pub fn subscribe_events(&self) -> BroadcastStream<ConsensusEvent> {
-        BroadcastStream::new(self.events.subscribe())
+    /// Returns a receiver for the events emitted by the consensus. Every receiver gets its own
+    /// copy of each event emitted after it subscribed, so any number of consumers can await
+    /// events independently.
+    ///
+    /// The consensus never waits for its consumers. A receiver that falls too far behind loses
+    /// the oldest events and gets a `RecvError::Lagged` before receiving the newer ones.
+    pub fn subscribe(&self) -> BroadcastReceiver<ConsensusEventOf<N>> {
+        self.events.subscribe()
+    }
+
+    /// Returns the events emitted by the consensus as a stream. See `subscribe`.
+    pub fn subscribe_events(&self) -> BroadcastStream<ConsensusEventOf<N>> {
+        BroadcastStream::new(self.subscribe())
     }
 
     pub fn is_established(&self) -> bool {
@@ -175,6 +274,35 @@ impl<N: Network> Consensus<N> {
             blockchain: Arc::clone(&self.blockchain),
             network: Arc::clone(&self.network),
             established_flag: Arc::clone(&self.established_flag),
+            events: self.events.clone(),
+        }
+    }
+
+    /// Returns an estimate of the sync progress, computed from our head and the best head that we
+    /// know of from our peers.
+    pub fn sync_progress(&self) -> SyncProgress {
+        let current_height = self.blockchain.read().block_number();
+        let buffered_height = self.block_queue.max_buffered_block_number().unwrap_or(0);
+
+        SyncProgress {
+            current_height,
+            target_height: current_height
+                .max(buffered_height)
+                .max(self.best_peer_height),
+        }
+    }
+
+    /// Emits the current sync progress, unless progress was emitted very recently. Progress is
+    /// always emitted if `force` is set.
+    fn emit_sync_progress(&mut self, force: bool) {
+        let progress = self.sync_progress();
+        if self
+            .sync_progress_throttle
+            .should_emit(Instant::now(), progress.current_height, force)
+        {
+            self.events
+                .send(ConsensusEvent::SyncProgress(progress))
+                .ok();
         }
     }
 
@@ -204,7 +332,7 @@ impl<N: Network> Consensus<N> {
     fn check_established(
         &mut self,
         finished_head_request: Option<HeadRequestsResult<N::PeerType>>,
-    ) -> Option<ConsensusEvent> {
+    ) -> Option<ConsensusEventOf<N>> {
         // We can only lose established state right now if we drop below our minimum peer threshold.
         if self.is_established() {
             if self.num_agents() < self.min_peers {
@@ -248,6 +376,28 @@ impl<N: Network> Consensus<N> {
         None
     }
 
+    /// Checks which peers finished syncing since the last call and emits a `PeerSynced` event
+    /// for each of them. Peers that disconnected or were put back into sync mode are forgotten,
+    /// so that they emit a new event once they are synced again.
+    fn check_synced_peers(&mut self) {
+        let peers: HashSet<_> = self
+            .block_queue
+            .peers()
+            .into_iter()
+            .filter_map(|agent| agent.upgrade())
+            .map(|agent| agent.peer.id())
+            .collect();
+
+        for peer_id in peers.difference(&self.synced_peers) {
+            debug!("Peer {:?} finished syncing", peer_id);
+            self.events
+                .send(ConsensusEvent::PeerSynced(peer_id.clone()))
+                .ok();
+        }
+
+        self.synced_peers = peers;
+    }
+
     /// Requests heads from connected peers in a predefined interval.
     fn request_heads(&mut self) {
         // If there's no ongoing head request and we have at least one peer, check whether we should
@@ -260,8 +410,10 @@ impl<N: Network> Consensus<N> {
                 .unwrap_or(true);
             if should_start_request {
                 debug!("Initiating head requests");
+                let network = Arc::clone(&self.network);
                 self.head_requests = Some(HeadRequests::new(
                     self.block_queue.peers(),
+                    async move { network.get_peer_scores().await }.boxed(),
                     Arc::clone(&self.blockchain),
                 ));
                 self.head_requests_time = Some(Instant::now());
@@ -298,6 +450,8 @@ impl<N: Network> Future for Consensus<N> {
                         if remaining_in_buffer == 0 {
                             self.head_requests_time = None;
                         }
+
+                        self.emit_sync_progress(remaining_in_buffer == 0);
                     }
                 }
                 BlockQueueEvent::ReceivedMissingBlocks(_, _) => {
@@ -313,6 +467,10 @@ impl<N: Network> Future for Consensus<N> {
             }
         }
 
+        // Notify about peers that finished syncing. This happens before checking the established
+        // state, so that the `PeerSynced` events always precede the `Established` event.
+        self.check_synced_peers();
+
         // Check consensus established state on changes.
         if let Some(event) = self.check_established(None) {
             self.events.send(event).ok(); // Ignore result.
@@ -324,7 +482,30 @@ impl<N: Network> Future for Consensus<N> {
                 // Reset head requests.
                 self.head_requests = None;
 
-                // Push unknown blocks to the block queue, trying to sync.
+                // Remember the best head that our peers reported.
+                if let Some(best_peer_height) = result
+                    .unknown_blocks
+                    .iter()
+                    .map(|(block, _)| block.block_number())
+                    .max()
+                {
+                    self.best_peer_height = self.best_peer_height.max(best_peer_height);
+                }
+
+                if !self.is_established() {
+                    self.emit_sync_progress(true);
+                }
+
+                // Push unknown blocks to the block queue, trying to sync. The blocks of the selected
+                // sync peer are pushed first, so that missing blocks are requested from that peer.
+                let sync_peer =
+                    select_sync_peer(result.unknown_blocks.iter().map(|(_, peer_id)| {
+                        let score = result.peer_scores.get(peer_id).copied().unwrap_or(0.0);
+                        (peer_id.clone(), score)
+                    }));
+                result
+                    .unknown_blocks
+                    .sort_by_key(|(_, peer_id)| Some(peer_id) != sync_peer.as_ref());
                 for (block, peer) in result.unknown_blocks.drain(..) {
                     self.block_queue.push_block(block, peer);
                 }
@@ -352,3 +533,30 @@ impl<N: Network> Future for Consensus<N> {
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_progress_is_throttled() {
+        let mut throttle = SyncProgressThrottle::default();
+        let start = Instant::now();
+
+        // The first progress is always emitted.
+        assert!(throttle.should_emit(start, 10, false));
+
+        // Further progress is suppressed until enough time passed or enough blocks were pushed.
+        for height in 11..(10 + SyncProgressThrottle::MIN_BLOCK_DELTA) {
+            assert!(!throttle.should_emit(start, height, false));
+        }
+        assert!(throttle.should_emit(start, 10 + SyncProgressThrottle::MIN_BLOCK_DELTA, false));
+
+        let later = start + SyncProgressThrottle::MIN_INTERVAL;
+        assert!(!throttle.should_emit(later - Duration::from_millis(1), 1011, false));
+        assert!(throttle.should_emit(later, 1011, false));
+
+        // Forced progress is emitted regardless.
+        assert!(throttle.should_emit(later, 1011, true));
+    }
+}
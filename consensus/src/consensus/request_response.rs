@@ -10,8 +10,8 @@ use nimiq_network_interface::prelude::{Message, Network, Peer, ResponseMessage};
 
 use crate::messages::handlers::Handle;
 use crate::messages::{
-    RequestBatchSet, RequestBlock, RequestBlockHashes, RequestHead, RequestHistoryChunk,
-    RequestMissingBlocks,
+    RequestAccountsTreeChunk, RequestBatchSet, RequestBlock, RequestBlockHashes, RequestHead,
+    RequestHistoryChunk, RequestMissingBlocks,
 };
 use crate::Consensus;
 
@@ -36,6 +36,9 @@ impl<N: Network> Consensus<N> {
 
         let stream = network.receive_from_all::<RequestHead>();
         tokio::spawn(Self::request_handler(stream, blockchain));
+
+        let stream = network.receive_from_all::<RequestAccountsTreeChunk>();
+        tokio::spawn(Self::request_handler(stream, blockchain));
     }
 
     fn request_handler<Req: Handle<Res> + ResponseMessage, Res: Message>(
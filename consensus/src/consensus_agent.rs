@@ -1,14 +1,18 @@
 use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use parking_lot::RwLock;
 
+use nimiq_account::Account;
 use nimiq_block::Block;
 use nimiq_hash::Blake2bHash;
-use nimiq_network_interface::peer::Peer;
+use nimiq_network_interface::peer::{CloseReason, Peer};
 use nimiq_network_interface::request_response::{RequestError, RequestResponse};
 use nimiq_subscription::Subscription;
+use nimiq_trie::key_nibbles::KeyNibbles;
+use nimiq_trie::trie_proof::TrieProof;
 
 use crate::messages::*;
 
@@ -35,6 +39,10 @@ pub struct ConsensusAgent<P: Peer> {
     block_requests: RequestResponse<P, RequestBlock, ResponseBlock>,
     missing_block_requests: RequestResponse<P, RequestMissingBlocks, ResponseBlocks>,
     head_requests: RequestResponse<P, RequestHead, HeadResponse>,
+    accounts_tree_chunk_requests: RequestResponse<P, RequestAccountsTreeChunk, AccountsTreeChunk>,
+
+    /// The number of requests to this peer that timed out in a row.
+    consecutive_timeouts: AtomicUsize,
 }
 
 impl<P: Peer> Debug for ConsensusAgent<P> {
@@ -44,15 +52,24 @@ impl<P: Peer> Debug for ConsensusAgent<P> {
 }
 
 impl<P: Peer> ConsensusAgent<P> {
+    /// The default time to wait for the response to a request before it is considered failed.
+    pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// The number of consecutive request timeouts after which the peer is disconnected.
+    const MAX_CONSECUTIVE_TIMEOUTS: usize = 3;
+
     pub fn new(peer: Arc<P>) -> Self {
-        // TODO: Timeout
-        let timeout = Duration::from_secs(10);
+        Self::with_request_timeout(peer, Self::DEFAULT_REQUEST_TIMEOUT)
+    }
+
+    pub fn with_request_timeout(peer: Arc<P>, timeout: Duration) -> Self {
         let block_hashes_requests = RequestResponse::new(Arc::clone(&peer), timeout);
         let epoch_requests = RequestResponse::new(Arc::clone(&peer), timeout);
         let history_chunk_requests = RequestResponse::new(Arc::clone(&peer), timeout);
         let block_requests = RequestResponse::new(Arc::clone(&peer), timeout);
         let missing_block_requests = RequestResponse::new(Arc::clone(&peer), timeout);
         let head_requests = RequestResponse::new(Arc::clone(&peer), timeout);
+        let accounts_tree_chunk_requests = RequestResponse::new(Arc::clone(&peer), timeout);
 
         ConsensusAgent {
             peer,
@@ -66,9 +83,36 @@ impl<P: Peer> ConsensusAgent<P> {
             block_requests,
             missing_block_requests,
             head_requests,
+            accounts_tree_chunk_requests,
+            consecutive_timeouts: AtomicUsize::new(0),
         }
     }
 
+    /// Keeps track of requests that timed out. A peer that accepts requests but repeatedly
+    /// doesn't respond to them is penalized by closing the connection to it. The failed request
+    /// itself is retried with another peer by the caller.
+    fn track_timeout<T>(&self, result: Result<T, RequestError>) -> Result<T, RequestError> {
+        match result {
+            Err(RequestError::Timeout) => {
+                let timeouts = self.consecutive_timeouts.fetch_add(1, Ordering::AcqRel) + 1;
+                if timeouts >= Self::MAX_CONSECUTIVE_TIMEOUTS {
+                    log::warn!(
+                        "Closing connection to peer {:?}: {} consecutive requests timed out",
+                        self.peer.id(),
+                        timeouts
+                    );
+                    self.consecutive_timeouts.store(0, Ordering::Release);
+                    self.peer.close(CloseReason::Other);
+                } else {
+                    log::debug!("Request to peer {:?} timed out", self.peer.id());
+                }
+            }
+            Ok(_) => self.consecutive_timeouts.store(0, Ordering::Release),
+            Err(_) => {}
+        }
+        result
+    }
+
     pub async fn request_block(&self, hash: Blake2bHash) -> Result<Option<Block>, RequestError> {
         let result = self
             .block_requests
@@ -77,6 +121,7 @@ impl<P: Peer> ConsensusAgent<P> {
                 request_identifier: 0, // will automatically be set at a later point
             })
             .await;
+        let result = self.track_timeout(result);
 
         result.map(|response_block| response_block.block)
     }
@@ -89,6 +134,7 @@ impl<P: Peer> ConsensusAgent<P> {
                 request_identifier: 0, // will automatically be set at a later point
             })
             .await;
+        let result = self.track_timeout(result);
 
         // TODO verify that hash of returned epoch matches the one we requested
 
@@ -110,6 +156,7 @@ impl<P: Peer> ConsensusAgent<P> {
                 request_identifier: 0, // will automatically be set at a later point
             })
             .await;
+        let result = self.track_timeout(result);
 
         result
     }
@@ -129,6 +176,7 @@ impl<P: Peer> ConsensusAgent<P> {
                 request_identifier: 0, // will automatically be set at a later point
             })
             .await;
+        let result = self.track_timeout(result);
 
         // TODO filter empty chunks here?
 
@@ -148,6 +196,7 @@ impl<P: Peer> ConsensusAgent<P> {
                 request_identifier: 0, // will automatically be set at a later point
             })
             .await;
+        let result = self.track_timeout(result);
 
         result.map(|response_blocks| response_blocks.blocks)
     }
@@ -159,7 +208,28 @@ impl<P: Peer> ConsensusAgent<P> {
                 request_identifier: 0, // will automatically be set at a later point
             })
             .await;
+        let result = self.track_timeout(result);
 
         result.map(|response_blocks| response_blocks.hash)
     }
+
+    pub async fn request_accounts_tree_chunk(
+        &self,
+        block_hash: Blake2bHash,
+        start_key: KeyNibbles,
+        max_accounts: u16,
+    ) -> Result<Option<TrieProof<Account>>, RequestError> {
+        let result = self
+            .accounts_tree_chunk_requests
+            .request(RequestAccountsTreeChunk {
+                block_hash,
+                start_key,
+                max_accounts,
+                request_identifier: 0, // will automatically be set at a later point
+            })
+            .await;
+        let result = self.track_timeout(result);
+
+        result.map(|response| response.chunk)
+    }
 }
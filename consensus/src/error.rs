@@ -1,6 +1,8 @@
 use thiserror::Error;
 
 use nimiq_blockchain::BlockchainError;
+use nimiq_hash::Blake2bHash;
+use nimiq_network_interface::request_response::RequestError;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -16,5 +18,20 @@ pub enum SyncError {
     NoValidSyncTarget,
 }
 
+#[derive(Debug, Error)]
+pub enum AccountsTreeSyncError {
+    #[error("Request failed: {0}")]
+    Request(#[from] RequestError),
+    #[error("Peer can't serve the accounts tree at the requested block")]
+    Unavailable,
+    #[error("Invalid accounts tree chunk")]
+    InvalidChunk,
+    #[error("Accounts tree root mismatch: expected {expected}, got {actual}")]
+    RootMismatch {
+        expected: Blake2bHash,
+        actual: Blake2bHash,
+    },
+}
+
 #[derive(Debug, Error)]
 pub enum BlockQueueError {}
@@ -18,7 +18,7 @@ extern crate pin_project;
 #[macro_use]
 extern crate nimiq_macros;
 
-pub use consensus::{Consensus, ConsensusEvent, ConsensusProxy};
+pub use consensus::{Consensus, ConsensusEvent, ConsensusEventOf, ConsensusProxy, SyncProgress};
 pub use error::Error;
 
 pub mod consensus;
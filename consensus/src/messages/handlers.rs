@@ -111,13 +111,23 @@ impl Handle<BatchSetInfo> for RequestBatchSet {
 
 impl Handle<HistoryChunk> for RequestHistoryChunk {
     fn handle(&self, blockchain: &Arc<RwLock<Blockchain>>) -> HistoryChunk {
-        let chunk = blockchain.read().history_store.prove_chunk(
-            self.epoch_number,
-            self.block_number,
-            CHUNK_SIZE,
-            self.chunk_index as usize,
-            None,
-        );
+        let blockchain = blockchain.read();
+
+        // A pruned node can't serve the history of old epochs.
+        let chunk = if blockchain
+            .check_history_available(self.block_number)
+            .is_ok()
+        {
+            blockchain.history_store.prove_chunk(
+                self.epoch_number,
+                self.block_number,
+                CHUNK_SIZE,
+                self.chunk_index as usize,
+                None,
+            )
+        } else {
+            None
+        };
         HistoryChunk {
             chunk,
             request_identifier: self.get_request_identifier(),
@@ -222,3 +232,37 @@ impl Handle<HeadResponse> for RequestHead {
         }
     }
 }
+
+impl Handle<AccountsTreeChunk> for RequestAccountsTreeChunk {
+    fn handle(&self, blockchain: &Arc<RwLock<Blockchain>>) -> AccountsTreeChunk {
+        let blockchain = blockchain.read();
+
+        // We don't retain old states, so we can only serve the accounts tree at the requested
+        // block as long as our current state matches it. The requester has to retry at a newer
+        // macro block otherwise.
+        let chunk = match blockchain.get_block(&self.block_hash, false, None) {
+            Some(Block::Macro(block)) => {
+                let tree = &blockchain.state().accounts.tree;
+                let txn = blockchain.read_transaction();
+                if tree.root_hash(&txn) == block.header.state_root {
+                    let max_accounts = self
+                        .max_accounts
+                        .clamp(2, RequestAccountsTreeChunk::MAX_ACCOUNTS);
+                    tree.get_chunk_proof(&txn, &self.start_key, max_accounts as usize)
+                } else {
+                    debug!(
+                        "AccountsTreeChunk [{}] - state moved past the requested block",
+                        self.request_identifier
+                    );
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        AccountsTreeChunk {
+            chunk,
+            request_identifier: self.get_request_identifier(),
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use std::fmt::{Debug, Formatter};
 
 use beserial::{Deserialize, Serialize};
+use nimiq_account::Account;
 use nimiq_block::{Block, MacroBlock};
 use nimiq_blockchain::HistoryTreeChunk;
 use nimiq_hash::Blake2bHash;
 use nimiq_network_interface::message::*;
+use nimiq_trie::key_nibbles::KeyNibbles;
+use nimiq_trie::trie_proof::TrieProof;
 
 use crate::request_response;
 
@@ -288,3 +291,37 @@ request_response!(HeadResponse);
 impl Message for HeadResponse {
     const TYPE_ID: u64 = 211;
 }
+
+/// Requests a chunk of the accounts tree at the given macro block. The chunk contains the accounts
+/// starting at `start_key` (inclusive), in key order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestAccountsTreeChunk {
+    pub block_hash: Blake2bHash,
+    pub start_key: KeyNibbles,
+    pub max_accounts: u16,
+    pub request_identifier: u32,
+}
+request_response!(RequestAccountsTreeChunk);
+
+impl RequestAccountsTreeChunk {
+    /// The maximum number of accounts that are served in a single chunk.
+    pub const MAX_ACCOUNTS: u16 = 1000;
+}
+
+impl Message for RequestAccountsTreeChunk {
+    const TYPE_ID: u64 = 212;
+}
+
+/// This message contains a chunk of the accounts tree, proven against the state root of the
+/// requested macro block. The chunk is `None` if the peer can't serve the accounts tree at that
+/// block, e.g. because its state has already moved past it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountsTreeChunk {
+    pub chunk: Option<TrieProof<Account>>,
+    pub request_identifier: u32,
+}
+request_response!(AccountsTreeChunk);
+
+impl Message for AccountsTreeChunk {
+    const TYPE_ID: u64 = 213;
+}
@@ -0,0 +1,178 @@
+use nimiq_account::{Account, Accounts};
+use nimiq_block::MacroBlock;
+use nimiq_database::WriteTransaction;
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_network_interface::peer::Peer;
+use nimiq_trie::key_nibbles::KeyNibbles;
+use nimiq_trie::trie_proof::TrieProof;
+
+use crate::consensus_agent::ConsensusAgent;
+use crate::error::AccountsTreeSyncError;
+use crate::messages::RequestAccountsTreeChunk;
+
+/// Assembles the accounts tree at a macro block from chunks received from other peers.
+///
+/// Each chunk is verified against the state root of the macro block as it arrives, such that
+/// invalid chunks are rejected early. Since a chunk proof only proves the inclusion of the
+/// accounts it contains, the completeness of the tree is verified once all chunks have been
+/// received, by comparing the root of the assembled tree to the state root.
+///
+/// Peers don't retain old states, so they can only serve the accounts tree at a macro block while
+/// their accounts state still matches it, i.e. until they apply the next block that changes it.
+/// Use `request_with_retry` to move on to a newer macro block if that happens during the sync.
+pub struct AccountsTreeAssembler {
+    block_hash: Blake2bHash,
+    state_root: Blake2bHash,
+    max_accounts: u16,
+    accounts: Vec<(KeyNibbles, Account)>,
+    complete: bool,
+}
+
+impl AccountsTreeAssembler {
+    pub fn new(block: &MacroBlock, max_accounts: u16) -> Self {
+        AccountsTreeAssembler {
+            block_hash: block.hash(),
+            state_root: block.header.state_root.clone(),
+            // Chunks include their start key, which we already have from the previous chunk. So
+            // we need to request at least two accounts per chunk to make progress.
+            max_accounts: max_accounts.clamp(2, RequestAccountsTreeChunk::MAX_ACCOUNTS),
+            accounts: vec![],
+            complete: false,
+        }
+    }
+
+    /// Whether all chunks have been received.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// The key the next chunk has to start at.
+    pub fn next_start_key(&self) -> KeyNibbles {
+        self.accounts
+            .last()
+            .map(|(key, _)| key.clone())
+            .unwrap_or_else(KeyNibbles::root)
+    }
+
+    /// Verifies a chunk that was requested starting at `next_start_key` and adds its accounts.
+    pub fn push_chunk(&mut self, chunk: TrieProof<Account>) -> Result<(), AccountsTreeSyncError> {
+        if self.complete || !chunk.verify(&self.state_root) {
+            return Err(AccountsTreeSyncError::InvalidChunk);
+        }
+
+        let leaf_nodes = chunk.leaf_nodes();
+        if leaf_nodes.len() > self.max_accounts as usize {
+            return Err(AccountsTreeSyncError::InvalidChunk);
+        }
+
+        let start_key = self.next_start_key();
+        let mut num_new_accounts = 0;
+        for node in &leaf_nodes {
+            // The accounts must be in key order and must not precede the start key.
+            if node.key() < &start_key
+                || matches!(self.accounts.last(), Some((last_key, _)) if node.key() < last_key)
+            {
+                return Err(AccountsTreeSyncError::InvalidChunk);
+            }
+
+            // The chunk includes the start key, which we already have.
+            if matches!(self.accounts.last(), Some((last_key, _)) if node.key() == last_key) {
+                continue;
+            }
+
+            let account = node
+                .value()
+                .map_err(|_| AccountsTreeSyncError::InvalidChunk)?;
+            self.accounts.push((node.key().clone(), account));
+            num_new_accounts += 1;
+        }
+
+        if leaf_nodes.len() < self.max_accounts as usize {
+            self.complete = true;
+        } else if num_new_accounts == 0 {
+            // A full chunk must make progress.
+            return Err(AccountsTreeSyncError::InvalidChunk);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the assembled accounts into the given, empty accounts tree and verifies that its
+    /// root matches the state root of the macro block. The caller has to abort the transaction if
+    /// this fails.
+    pub fn finish(
+        self,
+        accounts: &Accounts,
+        txn: &mut WriteTransaction,
+    ) -> Result<(), AccountsTreeSyncError> {
+        if !self.complete {
+            return Err(AccountsTreeSyncError::InvalidChunk);
+        }
+
+        accounts.init(txn, self.accounts);
+
+        let root = accounts.get_root(Some(txn));
+        if root != self.state_root {
+            return Err(AccountsTreeSyncError::RootMismatch {
+                expected: self.state_root,
+                actual: root,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Requests all chunks of the accounts tree at the given macro block from a peer. Fails with
+    /// `Unavailable` if the peer's state doesn't match the macro block (anymore).
+    pub async fn request<P: Peer>(
+        agent: &ConsensusAgent<P>,
+        block: &MacroBlock,
+        max_accounts: u16,
+    ) -> Result<Self, AccountsTreeSyncError> {
+        let mut assembler = Self::new(block, max_accounts);
+
+        while !assembler.is_complete() {
+            let chunk = agent
+                .request_accounts_tree_chunk(
+                    assembler.block_hash.clone(),
+                    assembler.next_start_key(),
+                    assembler.max_accounts,
+                )
+                .await?
+                .ok_or(AccountsTreeSyncError::Unavailable)?;
+            assembler.push_chunk(chunk)?;
+        }
+
+        Ok(assembler)
+    }
+
+    /// Like `request`, but if the peer can't serve the accounts tree at a macro block, the sync
+    /// starts over at the newer macro block returned by `next_block`. Gives up with `Unavailable`
+    /// once `next_block` returns `None`. Returns the macro block the accounts tree was synced at.
+    pub async fn request_with_retry<P: Peer, F>(
+        agent: &ConsensusAgent<P>,
+        mut block: MacroBlock,
+        max_accounts: u16,
+        mut next_block: F,
+    ) -> Result<(MacroBlock, Self), AccountsTreeSyncError>
+    where
+        F: FnMut(&MacroBlock) -> Option<MacroBlock>,
+    {
+        loop {
+            match Self::request(agent, &block, max_accounts).await {
+                Ok(assembler) => return Ok((block, assembler)),
+                Err(AccountsTreeSyncError::Unavailable) => {
+                    let next = next_block(&block)
+                        .filter(|next| next.header.block_number > block.header.block_number)
+                        .ok_or(AccountsTreeSyncError::Unavailable)?;
+                    debug!(
+                        "Accounts tree at macro block #{} unavailable, retrying at #{}",
+                        block.header.block_number, next.header.block_number
+                    );
+                    block = next;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use nimiq_block::Block;
+use nimiq_hash::Blake2bHash;
+
+/// Statistics about the lookups in and evictions from a [`BlockCache`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockCacheStats {
+    /// The number of lookups that found the block in the cache.
+    pub hits: u64,
+    /// The number of lookups that didn't find the block in the cache.
+    pub misses: u64,
+    /// The number of blocks that were evicted because the cache was full.
+    pub evictions: u64,
+}
+
+/// A bounded cache for blocks that were downloaded but not yet pushed to the chain. When the cache
+/// is full, the blocks with the highest block numbers are evicted, as they are the furthest from
+/// the head and will be needed last. Evicted blocks are remembered, so they can be requested again
+/// once their parent is known.
+pub struct BlockCache {
+    max_blocks: usize,
+    /// The cached blocks by block number and hash. There can be multiple blocks at a height if
+    /// there are forks.
+    blocks: BTreeMap<u32, HashMap<Blake2bHash, Block>>,
+    /// The number of cached blocks.
+    num_blocks: usize,
+    /// The block number and parent hash of evicted blocks that haven't been requested again yet,
+    /// by hash.
+    evicted: HashMap<Blake2bHash, (u32, Blake2bHash)>,
+    /// The hashes of evicted blocks, oldest first. Bounds the number of remembered evictions.
+    evicted_order: VecDeque<Blake2bHash>,
+    stats: BlockCacheStats,
+}
+
+impl BlockCache {
+    pub fn new(max_blocks: usize) -> Self {
+        Self {
+            max_blocks: max_blocks.max(1),
+            blocks: BTreeMap::new(),
+            num_blocks: 0,
+            evicted: HashMap::new(),
+            evicted_order: VecDeque::new(),
+            stats: BlockCacheStats::default(),
+        }
+    }
+
+    /// Returns the number of cached blocks.
+    pub fn len(&self) -> usize {
+        self.num_blocks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_blocks == 0
+    }
+
+    /// Returns the maximum number of cached blocks.
+    pub fn max_blocks(&self) -> usize {
+        self.max_blocks
+    }
+
+    pub fn stats(&self) -> BlockCacheStats {
+        self.stats
+    }
+
+    /// Returns the highest block number of the cached blocks.
+    pub fn max_block_number(&self) -> Option<u32> {
+        self.blocks.keys().next_back().copied()
+    }
+
+    /// Inserts a block, evicting the blocks with the highest block numbers if the cache is full.
+    /// Returns `true` if the block was already cached, or if it was evicted right away because it
+    /// is the furthest from the head.
+    pub fn insert(&mut self, block: Block) -> bool {
+        let block_number = block.block_number();
+        let block_hash = block.hash();
+
+        let blocks = self.blocks.entry(block_number).or_default();
+        if blocks.contains_key(&block_hash) {
+            return true;
+        }
+        blocks.insert(block_hash.clone(), block);
+
+        self.evicted.remove(&block_hash);
+        self.num_blocks += 1;
+
+        let mut kept = true;
+        while self.num_blocks > self.max_blocks {
+            match self.evict_highest() {
+                Some(evicted_hash) => kept &= evicted_hash != block_hash,
+                None => break,
+            }
+        }
+        !kept
+    }
+
+    /// Checks whether the block with the given number and hash is cached and updates the stats.
+    pub fn contains(&mut self, block_number: u32, block_hash: &Blake2bHash) -> bool {
+        let contains = self
+            .blocks
+            .get(&block_number)
+            .map_or(false, |blocks| blocks.contains_key(block_hash));
+
+        if contains {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        contains
+    }
+
+    /// Returns an iterator over the cached blocks by block number.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, Vec<&Block>)> {
+        self.blocks
+            .iter()
+            .map(|(block_number, blocks)| (*block_number, blocks.values().collect()))
+    }
+
+    /// Removes and returns all blocks matching the predicate, in order of their block numbers.
+    pub fn remove_matching<F>(&mut self, mut predicate: F) -> Vec<Block>
+    where
+        F: FnMut(&Blake2bHash, &Block) -> bool,
+    {
+        let mut removed = vec![];
+        self.blocks.drain_filter(|_, blocks| {
+            removed.extend(
+                blocks
+                    .drain_filter(|hash, block| predicate(hash, block))
+                    .map(|(_, block)| block),
+            );
+            blocks.is_empty()
+        });
+
+        self.num_blocks -= removed.len();
+        removed
+    }
+
+    /// Returns the hash and block number of evicted blocks whose parent is known, as determined by
+    /// `is_known`, and that aren't known themselves. Those blocks are needed now and have to be
+    /// requested again. They are forgotten, so that they are only requested once.
+    pub fn take_missing_evicted<F>(&mut self, is_known: F) -> Vec<(Blake2bHash, u32)>
+    where
+        F: Fn(&Blake2bHash) -> bool,
+    {
+        if self.evicted.is_empty() {
+            return vec![];
+        }
+
+        let mut missing = vec![];
+        self.evicted.retain(|hash, (block_number, parent_hash)| {
+            if is_known(hash) {
+                false
+            } else if is_known(parent_hash) {
+                missing.push((hash.clone(), *block_number));
+                false
+            } else {
+                true
+            }
+        });
+
+        let evicted = &self.evicted;
+        self.evicted_order.retain(|hash| evicted.contains_key(hash));
+
+        missing.sort_by_key(|(_, block_number)| *block_number);
+        missing
+    }
+
+    /// Evicts a block with the highest block number and returns its hash.
+    fn evict_highest(&mut self) -> Option<Blake2bHash> {
+        let mut entry = self.blocks.last_entry()?;
+        let block_number = *entry.key();
+        let hash = entry.get().keys().next()?.clone();
+        let block = entry.get_mut().remove(&hash)?;
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        self.num_blocks -= 1;
+
+        log::debug!(
+            "Evicting block #{} from full block cache (max {}): {}",
+            block_number,
+            self.max_blocks,
+            hash
+        );
+        self.stats.evictions += 1;
+
+        self.evicted
+            .insert(hash.clone(), (block_number, block.parent_hash().clone()));
+        self.evicted_order.push_back(hash.clone());
+        while self.evicted_order.len() > self.max_blocks {
+            if let Some(hash) = self.evicted_order.pop_front() {
+                self.evicted.remove(&hash);
+            }
+        }
+
+        Some(hash)
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    collections::{BTreeSet, HashSet, VecDeque},
     pin::Pin,
     sync::{Arc, Weak},
     task::{Context, Poll, Waker},
@@ -23,6 +23,7 @@ use nimiq_network_interface::{
 use nimiq_primitives::policy;
 
 use crate::consensus_agent::ConsensusAgent;
+use crate::sync::block_cache::{BlockCache, BlockCacheStats};
 use crate::sync::request_component::RequestComponentEvent;
 
 use super::request_component::RequestComponent;
@@ -50,7 +51,8 @@ pub enum BlockQueueEvent {
 
 #[derive(Clone, Debug)]
 pub struct BlockQueueConfig {
-    /// Buffer size limit
+    /// The maximum number of buffered blocks. If the buffer is full, the blocks furthest from the
+    /// head are evicted and requested again when they are needed.
     pub buffer_max: usize,
 
     /// How many blocks ahead we will buffer.
@@ -76,13 +78,8 @@ struct Inner<N: Network> {
     /// Reference to the network
     network: Arc<N>,
 
-    /// Buffered blocks - `block_height -> [Block]`. There can be multiple blocks at a height if there are forks.
-    ///
-    /// # TODO
-    ///
-    ///  - The inner `Vec` should really be a `SmallVec<[Block; 1]>` or similar.
-    ///
-    buffer: BTreeMap<u32, HashMap<Blake2bHash, Block>>,
+    /// Buffered blocks. There can be multiple blocks at a height if there are forks.
+    buffer: BlockCache,
 
     /// Vector of pending `blockchain.push()` operations.
     push_ops: VecDeque<BoxFuture<'static, PushOpResult>>,
@@ -142,13 +139,6 @@ impl<N: Network> Inner<N> {
             if let Some(peer) = self.network.get_peer(peer_id) {
                 request_component.put_peer_into_sync_mode(peer);
             }
-        } else if self.buffer.len() >= self.config.buffer_max {
-            log::warn!(
-                "Discarding block #{}.{}, buffer full (max {})",
-                block_number,
-                view_number,
-                self.buffer.len(),
-            )
         } else if block_number <= macro_height {
             // Block is from a previous batch/epoch, discard it.
             log::warn!(
@@ -162,13 +152,9 @@ impl<N: Network> Inner<N> {
             let block_hash = block.hash();
             let parent_hash = block.parent_hash().clone();
 
-            // Insert block into buffer. If we already know the block, we're done.
-            let block_known = self
-                .buffer
-                .entry(block_number)
-                .or_default()
-                .insert(block_hash.clone(), block)
-                .is_some();
+            // Insert block into buffer. If we already know the block or it was evicted right away,
+            // we're done.
+            let block_known = self.buffer.insert(block);
             log::trace!(
                 "Buffering block #{}.{}, known={}",
                 block_number,
@@ -180,10 +166,7 @@ impl<N: Network> Inner<N> {
             }
 
             // If the parent of this block is already in the buffer, we're done.
-            let parent_buffered = self
-                .buffer
-                .get(&(block_number - 1))
-                .map_or(false, |blocks| blocks.contains_key(&parent_hash));
+            let parent_buffered = self.buffer.contains(block_number - 1, &parent_hash);
             log::trace!(
                 "Parent of block #{}.{} buffered={}",
                 block_number,
@@ -216,25 +199,55 @@ impl<N: Network> Inner<N> {
                 macro_height
             );
 
-            // Get block locators.
-            let block_locators = blockchain
-                .chain_store
-                .get_blocks(
-                    &head_hash,
-                    // FIXME We don't want to send the full batch as locators here.
-                    block_number - macro_height + 2,
-                    false,
-                    Direction::Backward,
-                    None,
-                )
-                .into_iter()
-                .map(|block| block.hash())
-                .collect::<Vec<Blake2bHash>>();
-
+            let block_locators = Self::block_locators(&blockchain, block_number);
             request_component.request_missing_blocks(parent_hash, block_locators);
         }
     }
 
+    /// Returns the block locators to request the missing blocks before the given block number.
+    fn block_locators(blockchain: &Blockchain, block_number: u32) -> Vec<Blake2bHash> {
+        let head_hash = blockchain.head_hash();
+        let macro_height = policy::last_macro_block(blockchain.block_number());
+
+        blockchain
+            .chain_store
+            .get_blocks(
+                &head_hash,
+                // FIXME We don't want to send the full batch as locators here.
+                block_number.saturating_sub(macro_height) + 2,
+                false,
+                Direction::Backward,
+                None,
+            )
+            .into_iter()
+            .map(|block| block.hash())
+            .collect()
+    }
+
+    /// Requests the blocks that were evicted from the buffer again, once their parent is known.
+    fn request_evicted_blocks<TReq: RequestComponent<N::PeerType>>(
+        &mut self,
+        mut request_component: Pin<&mut TReq>,
+    ) {
+        let blockchain = self.blockchain.read();
+        let pending_blocks = &self.pending_blocks;
+        let missing_blocks = self.buffer.take_missing_evicted(|hash| {
+            pending_blocks.contains(hash) || blockchain.contains(hash, true)
+        });
+
+        for (hash, block_number) in missing_blocks {
+            log::debug!(
+                "Requesting evicted block #{} again: target_hash = {}",
+                block_number,
+                hash
+            );
+            let block_locators = Self::block_locators(&blockchain, block_number);
+            request_component
+                .as_mut()
+                .request_missing_blocks(hash, block_locators);
+        }
+    }
+
     fn on_missing_blocks_received(&mut self, blocks: Vec<Block>) {
         if blocks.is_empty() {
             log::debug!("Received empty missing blocks response");
@@ -246,6 +259,16 @@ impl<N: Network> Inner<N> {
 
         let blockchain = Arc::clone(&self.blockchain);
         let future = async move {
+            // Verify the signatures of all blocks in parallel before pushing them one by one.
+            // The blockchain lock is only held to prepare the verification.
+            let blockchain1 = Arc::clone(&blockchain);
+            let blocks = spawn_blocking(move || {
+                let verification = blockchain1.read().prepare_signature_verification(blocks);
+                verification.verify()
+            })
+            .await
+            .expect("Signature verification should not panic");
+
             let mut block_iter = blocks.into_iter();
 
             // Hashes of adopted blocks
@@ -261,19 +284,25 @@ impl<N: Network> Inner<N> {
             // Try to push blocks, until we encounter an invalid block.
             #[allow(clippy::while_let_on_iterator)]
             while let Some(block) = block_iter.next() {
-                let block_hash = block.hash();
+                let block_hash = block.block().hash();
 
                 log::debug!(
                     "Pushing block #{} from missing blocks response",
-                    block.block_number()
+                    block.block().block_number()
                 );
 
                 let blockchain1 = Arc::clone(&blockchain);
-                push_result =
-                    spawn_blocking(move || Blockchain::push(blockchain1.upgradable_read(), block))
-                        .await
-                        .expect("blockchain.push() should not panic");
+                push_result = spawn_blocking(move || {
+                    Blockchain::push_verified(blockchain1.upgradable_read(), block)
+                })
+                .await
+                .expect("blockchain.push() should not panic");
                 match &push_result {
+                    Err(PushError::DatabaseError(e)) => {
+                        // The blocks aren't invalid, we just can't store them.
+                        log::warn!("Failed to push missing block: {}", e);
+                        return PushOpResult::Missing(push_result, adopted_blocks, invalid_blocks);
+                    }
                     Err(e) => {
                         log::warn!("Failed to push missing block: {}", e);
                         invalid_blocks.insert(block_hash);
@@ -288,7 +317,7 @@ impl<N: Network> Inner<N> {
 
             // If there are remaining blocks in the iterator, those are invalid.
             for block in block_iter {
-                invalid_blocks.insert(block.hash());
+                invalid_blocks.insert(block.block().hash());
             }
 
             PushOpResult::Missing(push_result, adopted_blocks, invalid_blocks)
@@ -325,6 +354,8 @@ impl<N: Network> Inner<N> {
                     }
                     PushResult::Forked | PushResult::Ignored => MsgAcceptance::Ignore,
                 },
+                // Don't punish the sender if the block can't be stored locally.
+                Err(PushError::DatabaseError(_)) => MsgAcceptance::Ignore,
                 Err(_) => {
                     // TODO Ban peer
                     MsgAcceptance::Reject
@@ -351,20 +382,12 @@ impl<N: Network> Inner<N> {
     }
 
     fn push_buffered(&mut self) {
-        let mut blocks_to_push = vec![];
-        {
+        // Push all blocks with a known parent to the chain.
+        let blocks_to_push = {
             let blockchain = self.blockchain.read();
-            self.buffer.drain_filter(|_, blocks| {
-                // Push all blocks with a known parent to the chain.
-                let blocks_with_known_parent = blocks
-                    .drain_filter(|_, block| blockchain.contains(block.parent_hash(), true))
-                    .map(|(_, block)| block);
-                blocks_to_push.extend(blocks_with_known_parent);
-
-                // Remove buffer entry if there are no blocks left.
-                blocks.is_empty()
-            });
-        }
+            self.buffer
+                .remove_matching(|_, block| blockchain.contains(block.parent_hash(), true))
+        };
 
         for block in blocks_to_push {
             self.push_block(block, None, PushOpResult::Buffered);
@@ -376,19 +399,16 @@ impl<N: Network> Inner<N> {
             return;
         }
 
-        // Iterate over all offsets, remove element if no blocks remain at that offset.
-        self.buffer.drain_filter(|_block_number, blocks| {
-            // Iterate over all blocks at an offset, remove block, if parent is invalid
-            blocks.drain_filter(|hash, block| {
-                if invalid_blocks.contains(block.parent_hash()) {
-                    log::trace!("Removing block because parent is invalid: {}", hash);
-                    invalid_blocks.insert(hash.clone());
-                    true
-                } else {
-                    false
-                }
-            });
-            blocks.is_empty()
+        // Remove all blocks whose parent is invalid. Blocks are visited in order of their block
+        // numbers, so descendants of invalid blocks are removed as well.
+        self.buffer.remove_matching(|hash, block| {
+            if invalid_blocks.contains(block.parent_hash()) {
+                log::trace!("Removing block because parent is invalid: {}", hash);
+                invalid_blocks.insert(hash.clone());
+                true
+            } else {
+                false
+            }
         });
     }
 }
@@ -501,10 +521,10 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> BlockQueue<N, TReq> {
             request_component,
             block_stream,
             inner: Inner {
+                buffer: BlockCache::new(config.buffer_max),
                 config,
                 blockchain,
                 network,
-                buffer: BTreeMap::new(),
                 push_ops: VecDeque::new(),
                 pending_blocks: BTreeSet::new(),
                 waker: None,
@@ -515,10 +535,22 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> BlockQueue<N, TReq> {
 
     /// Returns an iterator over the buffered blocks
     pub fn buffered_blocks(&self) -> impl Iterator<Item = (u32, Vec<&Block>)> {
-        self.inner
-            .buffer
-            .iter()
-            .map(|(block_number, blocks)| (*block_number, blocks.values().collect()))
+        self.inner.buffer.iter()
+    }
+
+    /// Returns the highest block number of the buffered blocks.
+    pub fn max_buffered_block_number(&self) -> Option<u32> {
+        self.inner.buffer.max_block_number()
+    }
+
+    /// Returns the number of buffered blocks.
+    pub fn num_buffered_blocks(&self) -> usize {
+        self.inner.buffer.len()
+    }
+
+    /// Returns the hit, miss and eviction statistics of the block buffer.
+    pub fn buffer_stats(&self) -> BlockCacheStats {
+        self.inner.buffer.stats()
     }
 
     pub fn num_peers(&self) -> usize {
@@ -584,6 +616,10 @@ impl<N: Network, TReq: RequestComponent<N::PeerType>> Stream for BlockQueue<N, T
             }
         }
 
+        // Request blocks that were evicted from the buffer but are needed now.
+        this.inner
+            .request_evicted_blocks(this.request_component.as_mut());
+
         // Then, read all the responses we got for our missing blocks requests.
         loop {
             match this.request_component.as_mut().poll_next(cx) {
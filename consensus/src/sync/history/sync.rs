@@ -1,6 +1,7 @@
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::task::Waker;
+use std::time::Duration;
 
 use futures::future::BoxFuture;
 use futures::stream::FuturesUnordered;
@@ -59,6 +60,7 @@ pub struct HistorySync<TNetwork: Network> {
     pub(crate) active_cluster: Option<SyncCluster<TNetwork::PeerType>>,
     pub(crate) job_queue: VecDeque<Job<TNetwork::PeerType>>,
     pub(crate) waker: Option<Waker>,
+    pub(crate) request_timeout: Duration,
 }
 
 pub enum HistorySyncReturn<TPeer: Peer> {
@@ -73,6 +75,20 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
     pub fn new(
         blockchain: Arc<RwLock<Blockchain>>,
         network_event_rx: BroadcastStream<NetworkEvent<TNetwork::PeerType>>,
+    ) -> Self {
+        Self::with_request_timeout(
+            blockchain,
+            network_event_rx,
+            ConsensusAgent::<TNetwork::PeerType>::DEFAULT_REQUEST_TIMEOUT,
+        )
+    }
+
+    /// Creates a history sync whose consensus agents give up on requests that weren't answered
+    /// within `request_timeout`.
+    pub fn with_request_timeout(
+        blockchain: Arc<RwLock<Blockchain>>,
+        network_event_rx: BroadcastStream<NetworkEvent<TNetwork::PeerType>>,
+        request_timeout: Duration,
     ) -> Self {
         Self {
             blockchain,
@@ -84,6 +100,7 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
             active_cluster: None,
             job_queue: VecDeque::new(),
             waker: None,
+            request_timeout,
         }
     }
 
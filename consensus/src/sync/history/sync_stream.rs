@@ -32,9 +32,13 @@ impl<TNetwork: Network> HistorySync<TNetwork> {
                 }
                 Ok(NetworkEvent::PeerJoined(peer)) => {
                     // Create a ConsensusAgent for the peer that joined and request epoch_ids from it.
-                    let agent = Arc::new(ConsensusAgent::new(peer));
+                    let agent = Arc::new(ConsensusAgent::with_request_timeout(
+                        peer,
+                        self.request_timeout,
+                    ));
                     self.add_agent(agent);
                 }
+                Ok(NetworkEvent::Reconnecting(_)) => {}
                 Err(_) => return Poll::Ready(None),
             }
         }
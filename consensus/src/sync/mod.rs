@@ -1,3 +1,5 @@
+pub mod accounts;
+pub mod block_cache;
 pub mod block_queue;
 pub mod history;
 pub mod request_component;
@@ -0,0 +1,161 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::task::{Context, Poll};
+use futures::{Stream, StreamExt};
+use parking_lot::RwLock;
+
+use nimiq_account::Accounts;
+use nimiq_block_production::BlockProducer;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_consensus::consensus::Consensus;
+use nimiq_consensus::consensus_agent::ConsensusAgent;
+use nimiq_consensus::error::AccountsTreeSyncError;
+use nimiq_consensus::sync::accounts::AccountsTreeAssembler;
+use nimiq_consensus::sync::history::HistorySyncReturn;
+use nimiq_consensus::sync::request_component::HistorySyncStream;
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_database::WriteTransaction;
+use nimiq_genesis::NetworkId;
+use nimiq_network_interface::network::Network;
+use nimiq_network_mock::{MockHub, MockNetwork, MockPeer};
+use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
+use nimiq_utils::time::OffsetTime;
+
+pub struct MockHistorySyncStream<TNetwork: Network> {
+    _network: Arc<TNetwork>,
+}
+
+impl<TNetwork: Network> HistorySyncStream<TNetwork::PeerType> for MockHistorySyncStream<TNetwork> {
+    fn add_agent(&self, _agent: Arc<ConsensusAgent<TNetwork::PeerType>>) {}
+}
+
+impl<TNetwork: Network> Stream for MockHistorySyncStream<TNetwork> {
+    type Item = HistorySyncReturn<TNetwork::PeerType>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Pending
+    }
+}
+
+/// Sets up a consensus serving the accounts tree of the given blockchain, and a consensus agent
+/// for another network to request it from.
+async fn serve_accounts_tree(
+    hub: &mut MockHub,
+    env: VolatileEnvironment,
+    blockchain: &Arc<RwLock<Blockchain>>,
+) -> (Consensus<MockNetwork>, ConsensusAgent<MockPeer>) {
+    let net1 = Arc::new(hub.new_network());
+    let consensus = Consensus::from_network(
+        env,
+        Arc::clone(blockchain),
+        Arc::clone(&net1),
+        Box::pin(MockHistorySyncStream {
+            _network: Arc::clone(&net1),
+        }),
+    )
+    .await;
+
+    let net2 = Arc::new(hub.new_network());
+    let mut stream = net2.subscribe_events();
+    net1.dial_mock(&net2);
+    let _ = stream.next().await.unwrap();
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let agent = ConsensusAgent::new(Arc::clone(&net2.get_peers()[0]));
+    (consensus, agent)
+}
+
+#[tokio::test]
+async fn accounts_tree_can_be_synced_in_chunks() {
+    let mut hub = MockHub::default();
+
+    // Setup the peer serving the accounts tree.
+    let env1 = VolatileEnvironment::new(10).unwrap();
+    let time = Arc::new(OffsetTime::new());
+    let blockchain1 = Arc::new(RwLock::new(
+        Blockchain::new(env1.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(1, &producer, &blockchain1);
+
+    let (consensus1, agent) = serve_accounts_tree(&mut hub, env1, &blockchain1).await;
+
+    let macro_head = consensus1.blockchain.read().macro_head();
+    let expected_accounts = blockchain1.read().state().accounts.get_all(None);
+    // Make sure the tree spans several chunks.
+    assert!(expected_accounts.len() > 2);
+
+    let assembler = AccountsTreeAssembler::request(&agent, &macro_head, 2)
+        .await
+        .expect("Should sync the accounts tree");
+    assert!(assembler.is_complete());
+
+    let env2 = VolatileEnvironment::new(10).unwrap();
+    let accounts = Accounts::new(env2.clone());
+    let mut txn = WriteTransaction::new(&env2);
+    assembler
+        .finish(&accounts, &mut txn)
+        .expect("Should match the state root");
+    txn.commit();
+
+    assert_eq!(accounts.get_root(None), macro_head.header.state_root);
+    assert_eq!(accounts.get_all(None), expected_accounts);
+}
+
+#[tokio::test]
+async fn accounts_tree_sync_moves_on_to_newer_macro_block() {
+    let mut hub = MockHub::default();
+
+    let env1 = VolatileEnvironment::new(10).unwrap();
+    let time = Arc::new(OffsetTime::new());
+    let blockchain1 = Arc::new(RwLock::new(
+        Blockchain::new(env1.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(1, &producer, &blockchain1);
+    let old_macro_head = blockchain1.read().macro_head();
+    produce_macro_blocks(1, &producer, &blockchain1);
+    let macro_head = blockchain1.read().macro_head();
+    assert_ne!(
+        old_macro_head.header.state_root,
+        macro_head.header.state_root
+    );
+
+    let (_consensus1, agent) = serve_accounts_tree(&mut hub, env1, &blockchain1).await;
+
+    // The state of the peer moved past the old macro block, so it can't serve it anymore.
+    assert!(matches!(
+        AccountsTreeAssembler::request(&agent, &old_macro_head, 2).await,
+        Err(AccountsTreeSyncError::Unavailable)
+    ));
+
+    // Without a newer macro block, the sync gives up.
+    assert!(matches!(
+        AccountsTreeAssembler::request_with_retry(&agent, old_macro_head.clone(), 2, |_| None)
+            .await,
+        Err(AccountsTreeSyncError::Unavailable)
+    ));
+
+    // Otherwise it starts over at the newer macro block.
+    let (block, assembler) =
+        AccountsTreeAssembler::request_with_retry(&agent, old_macro_head, 2, |_| {
+            Some(blockchain1.read().macro_head())
+        })
+        .await
+        .expect("Should sync the accounts tree at the newer macro block");
+    assert_eq!(block, macro_head);
+
+    let env2 = VolatileEnvironment::new(10).unwrap();
+    let accounts = Accounts::new(env2.clone());
+    let mut txn = WriteTransaction::new(&env2);
+    assembler
+        .finish(&accounts, &mut txn)
+        .expect("Should match the state root");
+    txn.commit();
+
+    assert_eq!(accounts.get_root(None), macro_head.header.state_root);
+}
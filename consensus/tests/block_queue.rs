@@ -597,3 +597,116 @@ async fn put_peer_back_into_sync_mode() {
 
     assert!(block_queue.request_component.peer_put_into_sync);
 }
+
+#[tokio::test]
+async fn evicted_blocks_are_requested_again() {
+    let env1 = VolatileEnvironment::new(10).unwrap();
+    let time1 = Arc::new(OffsetTime::new());
+    let env2 = VolatileEnvironment::new(10).unwrap();
+    let time2 = Arc::new(OffsetTime::new());
+    let blockchain1 = Arc::new(RwLock::new(
+        Blockchain::new(env1, NetworkId::UnitAlbatross, time1).unwrap(),
+    ));
+    let blockchain2 = Arc::new(RwLock::new(
+        Blockchain::new(env2, NetworkId::UnitAlbatross, time2).unwrap(),
+    ));
+    let mut hub = MockHub::new();
+    let network = Arc::new(hub.new_network());
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    let (request_component, mut mock_ptarc_rx, mock_ptarc_tx) =
+        MockRequestComponent::<MockPeer>::new();
+    let (mut tx, rx) = mpsc::channel(32);
+
+    let mut block_queue = BlockQueue::with_block_stream(
+        BlockQueueConfig {
+            buffer_max: 3,
+            window_max: 10,
+        },
+        Arc::clone(&blockchain1),
+        network,
+        request_component,
+        rx.boxed(),
+    );
+
+    let mut blocks = Vec::new();
+    for n in 0..6 {
+        let bc = blockchain2.upgradable_read();
+        let block = Block::Micro(producer.next_micro_block(
+            &bc,
+            bc.time.now() + n * 1000,
+            0,
+            None,
+            vec![],
+            vec![],
+            vec![0x42],
+        ));
+        Blockchain::push(bc, block.clone()).unwrap();
+        blocks.push(block);
+    }
+
+    let mock_id = MockId::new(hub.new_address().into());
+
+    // Send all blocks except the first one out of order, so they are buffered.
+    for i in [1, 5, 2, 3, 4] {
+        tx.send((blocks[i].clone(), mock_id.clone())).await.unwrap();
+        let _ = block_queue.poll_next_unpin(&mut Context::from_waker(noop_waker_ref()));
+
+        // The buffer never exceeds its bound.
+        assert!(block_queue.num_buffered_blocks() <= 3);
+    }
+    assert_eq!(blockchain1.read().block_number(), 0);
+
+    // Blocks #6 and #5 were evicted, as they are the furthest from the head.
+    let buffered: Vec<u32> = block_queue
+        .buffered_blocks()
+        .map(|(block_number, _)| block_number)
+        .collect();
+    assert_eq!(buffered, vec![2, 3, 4]);
+
+    let stats = block_queue.buffer_stats();
+    assert_eq!(stats.evictions, 2);
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.misses, 2);
+
+    // The parents of blocks #2 and #6 were requested because they were missing.
+    let mut targets = vec![];
+    while let Ok(Some((target_block_hash, _locators))) = mock_ptarc_rx.try_next() {
+        targets.push(target_block_hash);
+    }
+    assert_eq!(targets, vec![blocks[0].hash(), blocks[4].hash()]);
+
+    // Answer the request for the first block, after which the buffered blocks are pushed. Each
+    // evicted block is requested again once its parent is known.
+    mock_ptarc_tx.unbounded_send(blocks[..1].to_vec()).unwrap();
+    for n in 4..6 {
+        while blockchain1.read().block_number() < n {
+            block_queue.next().await;
+        }
+        let _ = block_queue.poll_next_unpin(&mut Context::from_waker(noop_waker_ref()));
+
+        let mut targets = vec![];
+        while let Ok(Some((target_block_hash, _locators))) = mock_ptarc_rx.try_next() {
+            targets.push(target_block_hash);
+        }
+        assert_eq!(targets, vec![blocks[n as usize].hash()]);
+
+        mock_ptarc_tx
+            .unbounded_send(vec![blocks[n as usize].clone()])
+            .unwrap();
+    }
+
+    while blockchain1.read().block_number() < 6 {
+        block_queue.next().await;
+    }
+
+    for (i, block) in blocks.iter().enumerate() {
+        assert_eq!(
+            &blockchain1
+                .read()
+                .get_block_at(i as u32 + 1, true, None)
+                .unwrap(),
+            block
+        );
+    }
+    assert!(block_queue.buffered_blocks().next().is_none());
+}
@@ -0,0 +1,84 @@
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::task::{Context, Poll};
+use futures::Stream;
+use parking_lot::RwLock;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+use nimiq_blockchain::Blockchain;
+use nimiq_consensus::consensus::Consensus;
+use nimiq_consensus::consensus_agent::ConsensusAgent;
+use nimiq_consensus::sync::history::HistorySyncReturn;
+use nimiq_consensus::sync::request_component::HistorySyncStream;
+use nimiq_consensus::ConsensusEvent;
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
+use nimiq_network_mock::{MockHub, MockNetwork, MockPeer};
+use nimiq_utils::time::OffsetTime;
+
+/// A history sync that never yields any peers.
+struct PendingHistorySyncStream;
+
+impl HistorySyncStream<MockPeer> for PendingHistorySyncStream {
+    fn add_agent(&self, _agent: Arc<ConsensusAgent<MockPeer>>) {}
+}
+
+impl Stream for PendingHistorySyncStream {
+    type Item = HistorySyncReturn<MockPeer>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Pending
+    }
+}
+
+async fn new_consensus(hub: &mut MockHub) -> Consensus<MockNetwork> {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let time = Arc::new(OffsetTime::new());
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    Consensus::from_network(
+        env,
+        blockchain,
+        Arc::new(hub.new_network()),
+        Box::pin(PendingHistorySyncStream),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn events_are_delivered_to_all_subscribers() {
+    let mut hub = MockHub::default();
+    let mut consensus = new_consensus(&mut hub).await;
+
+    let mut rx1 = consensus.subscribe();
+    let mut rx2 = consensus.subscribe();
+    let mut rx3 = consensus.proxy().subscribe();
+
+    consensus.force_established();
+
+    for rx in [&mut rx1, &mut rx2, &mut rx3] {
+        assert!(matches!(rx.recv().await, Ok(ConsensusEvent::Established)));
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Empty)));
+    }
+}
+
+#[tokio::test]
+async fn slow_subscribers_lag_behind() {
+    let mut hub = MockHub::default();
+    let mut consensus = new_consensus(&mut hub).await;
+
+    let mut slow = consensus.subscribe();
+    let mut fast = consensus.subscribe();
+
+    // Emitting events never waits for the subscribers, even if they don't receive anything.
+    for _ in 0..1000 {
+        consensus.force_established();
+        assert!(matches!(fast.recv().await, Ok(ConsensusEvent::Established)));
+    }
+
+    // The slow subscriber missed the oldest events, but still receives the most recent ones.
+    assert!(matches!(slow.recv().await, Err(RecvError::Lagged(_))));
+    assert!(matches!(slow.recv().await, Ok(ConsensusEvent::Established)));
+}
@@ -0,0 +1,198 @@
+use nimiq_consensus::messages::{
+    BatchSetInfo, BlockHashType, BlockHashes, HeadResponse, HistoryChunk, RequestBatchSet,
+    RequestBlock, RequestBlockHashes, RequestBlockHashesFilter, RequestHead, RequestHistoryChunk,
+    RequestMissingBlocks, ResponseBlock, ResponseBlocks,
+};
+use nimiq_hash::Blake2bHash;
+use nimiq_network_interface::message::Message;
+
+// Golden vectors for the wire format of the consensus messages, including the message header
+// (magic, type, length and checksum). Any change to the encoding of a message makes peers
+// running other implementations unable to understand it, so these vectors must only ever be
+// updated together with a deliberate protocol change.
+
+fn hash(byte: u8) -> Blake2bHash {
+    Blake2bHash::from([byte; 32])
+}
+
+/// Asserts that `message` serializes to `expected` and that `expected` deserializes to a message
+/// that serializes to the same bytes again.
+fn assert_wire_format<M: Message>(message: M, expected: &str) {
+    let mut serialized = Vec::with_capacity(message.serialized_message_size());
+    message.serialize_message(&mut serialized).unwrap();
+    assert_eq!(serialized.len(), message.serialized_message_size());
+    assert_eq!(hex::encode(&serialized), expected);
+
+    let bytes = hex::decode(expected).unwrap();
+    let deserialized = M::deserialize_message(&mut &bytes[..]).unwrap();
+    let mut reserialized = Vec::new();
+    deserialized.serialize_message(&mut reserialized).unwrap();
+    assert_eq!(reserialized, bytes);
+}
+
+#[test]
+fn request_block_hashes_wire_format() {
+    let message = RequestBlockHashes {
+        locators: vec![hash(0x11), hash(0x22)],
+        max_blocks: 1000,
+        filter: RequestBlockHashesFilter::ElectionOnly,
+        request_identifier: 7,
+    };
+    assert_wire_format(
+        message,
+        concat!(
+            "42042042804800000057bc867350000211111111111111111111111111111111",
+            "1111111111111111111111111111111122222222222222222222222222222222",
+            "2222222222222222222222222222222203e80200000007",
+        ),
+    );
+}
+
+#[test]
+fn block_hashes_wire_format() {
+    let message = BlockHashes {
+        hashes: Some(vec![
+            (BlockHashType::Micro, hash(0x11)),
+            (BlockHashType::Election, hash(0x22)),
+        ]),
+        request_identifier: 7,
+    };
+    assert_wire_format(
+        message,
+        concat!(
+            "420420428049000000577b4a3fbd010002011111111111111111111111111111",
+            "1111111111111111111111111111111111110322222222222222222222222222",
+            "2222222222222222222222222222222222222200000007",
+        ),
+    );
+
+    let message = BlockHashes {
+        hashes: None,
+        request_identifier: 7,
+    };
+    assert_wire_format(message, "420420428049000000139cf442910000000007");
+}
+
+#[test]
+fn request_batch_set_wire_format() {
+    let message = RequestBatchSet {
+        hash: hash(0x11),
+        request_identifier: 1,
+    };
+    assert_wire_format(
+        message,
+        concat!(
+            "42042042804a000000327f737635111111111111111111111111111111111111",
+            "111111111111111111111111111100000001",
+        ),
+    );
+}
+
+#[test]
+fn batch_set_info_wire_format() {
+    let message = BatchSetInfo {
+        block: None,
+        history_len: 42,
+        request_identifier: 2,
+    };
+    assert_wire_format(message, "42042042804b0000001715e895a4000000002a00000002");
+}
+
+#[test]
+fn request_history_chunk_wire_format() {
+    let message = RequestHistoryChunk {
+        epoch_number: 3,
+        block_number: 1234,
+        chunk_index: 5,
+        request_identifier: 3,
+    };
+    assert_wire_format(
+        message,
+        concat!(
+            "42042042804c0000002221a96f7b00000003000004d200000000000000050000",
+            "0003",
+        ),
+    );
+}
+
+#[test]
+fn history_chunk_wire_format() {
+    let message = HistoryChunk {
+        chunk: None,
+        request_identifier: 4,
+    };
+    assert_wire_format(message, "42042042804d000000131c519c300000000004");
+}
+
+#[test]
+fn response_block_wire_format() {
+    let message = ResponseBlock {
+        block: None,
+        request_identifier: 5,
+    };
+    assert_wire_format(message, "42042042804e000000131737897d0000000005");
+}
+
+#[test]
+fn request_block_wire_format() {
+    let message = RequestBlock {
+        hash: hash(0x22),
+        request_identifier: 6,
+    };
+    assert_wire_format(
+        message,
+        concat!(
+            "42042042804f00000032341eb2f5222222222222222222222222222222222222",
+            "222222222222222222222222222200000006",
+        ),
+    );
+}
+
+#[test]
+fn response_blocks_wire_format() {
+    let message = ResponseBlocks {
+        blocks: None,
+        request_identifier: 7,
+    };
+    assert_wire_format(message, "42042042805000000013541081bd0000000007");
+}
+
+#[test]
+fn request_missing_blocks_wire_format() {
+    let message = RequestMissingBlocks {
+        target_hash: hash(0x22),
+        locators: vec![hash(0x11)],
+        request_identifier: 8,
+    };
+    assert_wire_format(
+        message,
+        concat!(
+            "42042042805100000054ab8b2d6f222222222222222222222222222222222222",
+            "2222222222222222222222222222000111111111111111111111111111111111",
+            "1111111111111111111111111111111100000008",
+        ),
+    );
+}
+
+#[test]
+fn request_head_wire_format() {
+    let message = RequestHead {
+        request_identifier: 9,
+    };
+    assert_wire_format(message, "42042042805200000012cf47fd7b00000009");
+}
+
+#[test]
+fn head_response_wire_format() {
+    let message = HeadResponse {
+        hash: hash(0x11),
+        request_identifier: 10,
+    };
+    assert_wire_format(
+        message,
+        concat!(
+            "420420428053000000326e1d7518111111111111111111111111111111111111",
+            "11111111111111111111111111110000000a",
+        ),
+    );
+}
@@ -1,14 +1,119 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
 use std::{sync::Arc, time::Duration};
 
+use futures::task::{Context, Poll};
+use futures::{Stream, StreamExt};
+use parking_lot::{Mutex, RwLock};
+
 use nimiq_block_production::BlockProducer;
-use nimiq_blockchain::AbstractBlockchain;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
 use nimiq_bls::KeyPair as BLSKeyPair;
 use nimiq_build_tools::genesis::GenesisBuilder;
+use nimiq_consensus::consensus::Consensus;
+use nimiq_consensus::consensus_agent::ConsensusAgent;
+use nimiq_consensus::sync::history::HistorySyncReturn;
+use nimiq_consensus::sync::request_component::{
+    BlockRequestComponent, HistorySyncStream, RequestComponent, RequestComponentEvent,
+};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::NetworkId;
 use nimiq_keys::{Address, KeyPair, SecureGenerate};
-use nimiq_network_mock::{MockHub, MockNetwork};
+use nimiq_network_interface::network::Network;
+use nimiq_network_mock::{MockHub, MockNetwork, MockPeer};
 use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
 use nimiq_test_utils::node::Node;
 use nimiq_test_utils::validator::seeded_rng;
+use nimiq_utils::time::OffsetTime;
+
+/// Hands out the given agents as up-to-date peers, in order.
+struct GoodPeersStream {
+    agents: Mutex<VecDeque<Arc<ConsensusAgent<MockPeer>>>>,
+}
+
+impl HistorySyncStream<MockPeer> for GoodPeersStream {
+    fn add_agent(&self, _agent: Arc<ConsensusAgent<MockPeer>>) {}
+}
+
+impl Stream for GoodPeersStream {
+    type Item = HistorySyncReturn<MockPeer>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.agents.lock().pop_front() {
+            Some(agent) => Poll::Ready(Some(HistorySyncReturn::Good(agent))),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[tokio::test]
+async fn unanswered_requests_are_retried_with_another_peer() {
+    let request_timeout = Duration::from_millis(500);
+
+    let mut hub = MockHub::default();
+
+    // Setup a peer that knows the missing blocks.
+    let env = VolatileEnvironment::new(10).unwrap();
+    let time = Arc::new(OffsetTime::new());
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::new(env.clone(), NetworkId::UnitAlbatross, time).unwrap(),
+    ));
+    let genesis_hash = blockchain.read().head_hash();
+
+    let producer = BlockProducer::new(signing_key(), voting_key());
+    produce_macro_blocks(1, &producer, &blockchain);
+    let head_hash = blockchain.read().head_hash();
+
+    let responding_net = Arc::new(hub.new_network());
+    let _consensus = Consensus::from_network(
+        env,
+        Arc::clone(&blockchain),
+        Arc::clone(&responding_net),
+        Box::pin(GoodPeersStream {
+            agents: Default::default(),
+        }),
+    )
+    .await;
+
+    // Setup a peer that accepts requests but never responds to them.
+    let silent_net = Arc::new(hub.new_network());
+
+    // Connect the requesting peer to both of them.
+    let net = Arc::new(hub.new_network());
+    net.dial_mock(&silent_net);
+    net.dial_mock(&responding_net);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // The silent peer is added first, so it receives the request first.
+    let agents = [silent_net.peer_id(), responding_net.peer_id()]
+        .iter()
+        .map(|peer_id| {
+            Arc::new(ConsensusAgent::with_request_timeout(
+                net.get_peer(*peer_id).unwrap(),
+                request_timeout,
+            ))
+        })
+        .collect();
+    let mut request_component = BlockRequestComponent::new(
+        Box::pin(GoodPeersStream {
+            agents: Mutex::new(agents),
+        }),
+        net.subscribe_events(),
+    );
+
+    request_component.request_missing_blocks(head_hash.clone(), vec![genesis_hash]);
+
+    // The request to the silent peer times out and is re-requested from the responding peer.
+    let event = tokio::time::timeout(2 * request_timeout, request_component.next())
+        .await
+        .expect("Request should have been retried with another peer")
+        .unwrap();
+    match event {
+        RequestComponentEvent::ReceivedBlocks(blocks) => {
+            assert_eq!(blocks.last().unwrap().hash(), head_hash);
+        }
+    }
+}
 
 #[ignore]
 #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
@@ -2,6 +2,8 @@
 extern crate log;
 
 use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
 use std::io;
 use std::ops::Deref;
 
@@ -32,6 +34,27 @@ bitflags! {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatabaseError {
+    /// The database files can't be written to anymore, e.g. because the file system was remounted
+    /// read-only or the disk quota was exceeded. Reads are still served.
+    ReadOnlyFilesystem,
+    /// The memory map ran out of space. It is grown before the next write transaction, so the
+    /// write can be retried.
+    MapFull,
+}
+
+impl fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatabaseError::ReadOnlyFilesystem => write!(f, "Database file system is read-only"),
+            DatabaseError::MapFull => write!(f, "Database memory map is full"),
+        }
+    }
+}
+
+impl Error for DatabaseError {}
+
 #[derive(Clone, Debug)]
 pub enum Environment {
     Volatile(volatile::VolatileEnvironment),
@@ -59,6 +82,34 @@ impl Environment {
         }
     }
 
+    /// Returns whether the environment was opened in read-only mode. Write transactions can't be
+    /// started on a read-only environment.
+    pub fn is_read_only(&self) -> bool {
+        match *self {
+            Environment::Volatile(_) => false,
+            Environment::Persistent(ref env) => env.is_read_only(),
+        }
+    }
+
+    /// Returns whether a write to the database files failed because of the file system. Such an
+    /// environment keeps serving reads, but write transactions can't be committed anymore.
+    pub fn is_read_only_filesystem(&self) -> bool {
+        self.as_lmdb().is_read_only_filesystem()
+    }
+
+    /// Makes all following commits fail as if the file system had become read-only.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn simulate_write_failure(&self) {
+        self.as_lmdb().simulate_write_failure()
+    }
+
+    fn as_lmdb(&self) -> &lmdb::LmdbEnvironment {
+        match *self {
+            Environment::Volatile(ref env) => env.as_lmdb(),
+            Environment::Persistent(ref env) => env,
+        }
+    }
+
     pub fn close(self) {}
 
     pub fn drop_database(self) -> io::Result<()> {
@@ -256,7 +307,31 @@ impl<'env> WriteTransaction<'env> {
         }
     }
 
+    /// Returns the error of the first write that failed in this transaction. Once a write failed,
+    /// all further writes are skipped, reads only see the last committed state and the commit
+    /// fails with this error.
+    pub fn error(&self) -> Option<DatabaseError> {
+        match self.0 {
+            Transaction::VolatileWrite(ref txn) => txn.error(),
+            Transaction::PersistentWrite(ref txn) => txn.error(),
+            _ => {
+                unreachable!();
+            }
+        }
+    }
+
+    /// Commits the transaction. Panics if the transaction can't be committed, use `try_commit` to
+    /// handle this case.
     pub fn commit(self) {
+        if let Err(e) = self.try_commit() {
+            panic!("Failed to commit database transaction: {}", e);
+        }
+    }
+
+    /// Commits the transaction. Fails with `DatabaseError::ReadOnlyFilesystem` if the database files
+    /// can't be written to and with `DatabaseError::MapFull` if the transaction ran out of space.
+    /// In both cases none of the changes are persisted.
+    pub fn try_commit(self) -> Result<(), DatabaseError> {
         match self.0 {
             Transaction::VolatileWrite(txn) => txn.commit(),
             Transaction::PersistentWrite(txn) => txn.commit(),
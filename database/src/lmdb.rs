@@ -1,6 +1,10 @@
+use std::cell::Cell;
 use std::fmt;
 use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 // re export the lmdb error
 pub use lmdb_zero::open;
@@ -14,17 +18,69 @@ use super::*;
 #[derive(Debug)]
 pub struct LmdbEnvironment {
     env: Arc<lmdb_zero::Environment>,
+    resize: Arc<ResizeState>,
+    write_state: Arc<WriteState>,
+    read_only: bool,
 }
 
 impl Clone for LmdbEnvironment {
     fn clone(&self) -> Self {
         Self {
             env: Arc::clone(&self.env),
+            resize: Arc::clone(&self.resize),
+            write_state: Arc::clone(&self.write_state),
+            read_only: self.read_only,
         }
     }
 }
 
+/// Keeps track of the open transactions. LMDB only allows to resize the memory map while no
+/// transaction is active in this process.
+#[derive(Debug, Default)]
+struct ResizeState {
+    active_txns: AtomicUsize,
+    resizing: AtomicBool,
+    /// Set when a write transaction ran out of space, so that the memory map is grown before the
+    /// next one.
+    grow_pending: AtomicBool,
+}
+
+/// Keeps track of whether the database files can still be written to. Once a write failed because
+/// of the file system, the environment only serves reads.
+#[derive(Debug, Default)]
+struct WriteState {
+    failed: AtomicBool,
+    #[cfg(any(test, feature = "test-utils"))]
+    simulate_failure: AtomicBool,
+}
+
+/// Marks a transaction as active until it is dropped.
+struct TxnGuard<'env> {
+    resize: &'env ResizeState,
+}
+
+impl<'env> Drop for TxnGuard<'env> {
+    fn drop(&mut self) {
+        self.resize.active_txns.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 impl LmdbEnvironment {
+    /// The memory map is never grown beyond this size (16 TB).
+    pub const MAX_MAP_SIZE: usize = 16 * 1024 * 1024 * 1024 * 1024;
+
+    /// The memory map is grown before a write transaction once this fraction of it is used.
+    const GROW_THRESHOLD: f64 = 0.8;
+
+    /// How long a resize waits for the other transactions of this process to finish. If they
+    /// don't finish in time, the resize is postponed to the next transaction.
+    const RESIZE_WAIT: Duration = Duration::from_millis(100);
+
+    /// Opens the environment at the given path.
+    ///
+    /// If `flags` contains `open::RDONLY`, the environment is opened in read-only mode, e.g. to
+    /// inspect the database of a running node. In that mode, the databases must already exist and
+    /// starting a write transaction panics.
     #[allow(clippy::new_ret_no_self)]
     pub fn new(
         path: &str,
@@ -57,7 +113,10 @@ impl LmdbEnvironment {
         max_readers: Option<u32>,
         flags: open::Flags,
     ) -> Result<Self, LmdbError> {
-        fs::create_dir_all(path).unwrap();
+        let read_only = flags.contains(open::RDONLY);
+        if !read_only {
+            fs::create_dir_all(path).unwrap();
+        }
 
         let mut env = lmdb_zero::EnvBuilder::new()?;
         env.set_maxdbs(max_dbs)?;
@@ -69,14 +128,19 @@ impl LmdbEnvironment {
 
         let info = env.info()?;
         let cur_mapsize = info.mapsize;
-        if cur_mapsize < size {
+        if cur_mapsize < size && !read_only {
             unsafe { env.set_mapsize(size)? };
             let info = env.info()?;
             let cur_mapsize = info.mapsize;
             info!("LMDB memory map size: {}", cur_mapsize);
         }
 
-        let lmdb = LmdbEnvironment { env: Arc::new(env) };
+        let lmdb = LmdbEnvironment {
+            env: Arc::new(env),
+            resize: Default::default(),
+            write_state: Default::default(),
+            read_only,
+        };
         if lmdb.need_resize(0) {
             info!("LMDB memory needs to be resized.");
         }
@@ -86,7 +150,12 @@ impl LmdbEnvironment {
 
     pub(super) fn open_database(&self, name: String, flags: DatabaseFlags) -> LmdbDatabase {
         // This is an implicit transaction, so take the lock first.
-        let mut db_flags = lmdb_zero::db::CREATE;
+        // Databases can't be created in a read-only environment.
+        let mut db_flags = if self.read_only {
+            lmdb_zero::db::Flags::empty()
+        } else {
+            lmdb_zero::db::CREATE
+        };
 
         // Translate flags.
         if flags.contains(DatabaseFlags::DUPLICATE_KEYS) {
@@ -105,15 +174,171 @@ impl LmdbEnvironment {
         }
 
         LmdbDatabase {
-            db: lmdb_zero::Database::open(
-                Arc::clone(&self.env),
-                Some(&name),
-                &lmdb_zero::DatabaseOptions::new(db_flags),
-            )
-            .unwrap(),
+            db: Arc::new(
+                lmdb_zero::Database::open(
+                    Arc::clone(&self.env),
+                    Some(&name),
+                    &lmdb_zero::DatabaseOptions::new(db_flags),
+                )
+                .unwrap(),
+            ),
         }
     }
 
+    /// Registers a new transaction. Blocks while the memory map is being resized.
+    fn begin_txn(&self) -> TxnGuard {
+        loop {
+            while self.resize.resizing.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            self.resize.active_txns.fetch_add(1, Ordering::SeqCst);
+            let guard = TxnGuard {
+                resize: &self.resize,
+            };
+            if !self.resize.resizing.load(Ordering::SeqCst) {
+                return guard;
+            }
+        }
+    }
+
+    /// Registers a new transaction and opens it with `open`. If another process grew the memory
+    /// map beyond our size, our map is adjusted before the transaction is opened again.
+    fn open_txn<T, F>(&self, open: F) -> (TxnGuard, T)
+    where
+        F: Fn() -> Result<T, LmdbError>,
+    {
+        loop {
+            let guard = self.begin_txn();
+            match open() {
+                Ok(txn) => return (guard, txn),
+                Err(LmdbError::Code(lmdb_zero::error::MAP_RESIZED)) => {
+                    drop(guard);
+                    if self.set_map_size(0) {
+                        info!("LMDB memory map adjusted to {}", self.map_size());
+                    }
+                }
+                Err(e) => panic!("Failed to open LMDB transaction: {}", e),
+            }
+        }
+    }
+
+    /// Sets the size of the memory map. A size of 0 adopts the size set by another process.
+    /// This waits up to `RESIZE_WAIT` for the other transactions of this process to finish, the
+    /// caller must not hold a transaction itself. Returns whether the size was set.
+    fn set_map_size(&self, size: usize) -> bool {
+        if self
+            .resize
+            .resizing
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            // Another thread is already resizing the memory map.
+            return false;
+        }
+
+        let deadline = Instant::now() + Self::RESIZE_WAIT;
+        while self.resize.active_txns.load(Ordering::SeqCst) > 0 {
+            if Instant::now() > deadline {
+                debug!("LMDB memory map resize postponed, transactions are still active");
+                self.resize.resizing.store(false, Ordering::SeqCst);
+                return false;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let result = unsafe { self.env.set_mapsize(size) };
+        self.resize.resizing.store(false, Ordering::SeqCst);
+
+        if let Err(e) = result {
+            error!("Failed to resize LMDB memory map: {}", e);
+            return false;
+        }
+        true
+    }
+
+    /// Doubles the size of the memory map, up to `MAX_MAP_SIZE`, if a write transaction ran out of
+    /// space or more than `GROW_THRESHOLD` of it is used. Called before a write transaction, so
+    /// that it doesn't run out of space.
+    fn grow_map_if_needed(&self) {
+        let map_size = self.map_size();
+        let size_used = self.size_used();
+        let grow_pending = self.resize.grow_pending.load(Ordering::SeqCst);
+        if !grow_pending && (size_used as f64) < (map_size as f64) * Self::GROW_THRESHOLD {
+            return;
+        }
+
+        if map_size >= Self::MAX_MAP_SIZE {
+            if grow_pending {
+                error!(
+                    "LMDB memory map can't be grown beyond {}",
+                    Self::MAX_MAP_SIZE
+                );
+            }
+            return;
+        }
+
+        let new_size = map_size.saturating_mul(2).min(Self::MAX_MAP_SIZE);
+        if self.set_map_size(new_size) {
+            self.resize.grow_pending.store(false, Ordering::SeqCst);
+            info!("LMDB memory map grown from {} to {}", map_size, new_size);
+        }
+    }
+
+    /// Returns the number of bytes used in the memory map.
+    fn size_used(&self) -> usize {
+        let info = self.env.info().unwrap();
+        let stat = self.env.stat().unwrap();
+        (stat.psize as usize) * (info.last_pgno + 1)
+    }
+
+    /// Returns whether the environment was opened in read-only mode.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Returns whether writing to the database files failed because of the file system, e.g.
+    /// because it was remounted read-only or the disk quota was exceeded. The environment keeps
+    /// serving reads, but changes can't be committed anymore.
+    pub fn is_read_only_filesystem(&self) -> bool {
+        self.write_state.failed.load(Ordering::SeqCst)
+    }
+
+    /// Makes all following writes and commits fail as if the file system had become read-only.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn simulate_write_failure(&self) {
+        self.write_state
+            .simulate_failure
+            .store(true, Ordering::SeqCst);
+    }
+
+    /// Handles a failed write. Running out of space makes the memory map grow before the next
+    /// write transaction, so the write can be retried. Errors caused by the file system switch the
+    /// environment to serving reads only, all other errors are fatal.
+    fn on_write_error(&self, e: LmdbError) -> DatabaseError {
+        if is_map_full(&e) {
+            self.resize.grow_pending.store(true, Ordering::SeqCst);
+            return DatabaseError::MapFull;
+        }
+        if !is_filesystem_error(&e) {
+            panic!("LMDB write failed: {}", e);
+        }
+        if !self.write_state.failed.swap(true, Ordering::SeqCst) {
+            error!(
+                "Database at {} can't be written to anymore: {}. Block processing is paused and \
+                 only reads are served. Make the database directory writable again (e.g. free up \
+                 disk space) and restart the node.",
+                self.path(),
+                e
+            );
+        }
+        DatabaseError::ReadOnlyFilesystem
+    }
+
+    /// Returns the current size of the memory map.
+    pub fn map_size(&self) -> usize {
+        self.env.info().unwrap().mapsize
+    }
+
     pub(super) fn drop_database(self) -> io::Result<()> {
         fs::remove_dir_all(self.path().as_ref())
     }
@@ -124,9 +349,7 @@ impl LmdbEnvironment {
 
     pub fn need_resize(&self, threshold_size: usize) -> bool {
         let info = self.env.info().unwrap();
-        let stat = self.env.stat().unwrap();
-
-        let size_used = (stat.psize as usize) * (info.last_pgno + 1);
+        let size_used = self.size_used();
 
         if threshold_size > 0 && info.mapsize - size_used < threshold_size {
             info!("DB resize (threshold-based)");
@@ -137,8 +360,8 @@ impl LmdbEnvironment {
             return true;
         }
 
-        // Resize is currently not supported. So don't let the resize happen
-        // if a specific percentage is reached.
+        // The memory map is grown before write transactions, so only report that a resize is
+        // needed once the map is completely used.
         let resize_percent: f64 = 1_f64;
 
         if (size_used as f64) / (info.mapsize as f64) > resize_percent {
@@ -159,19 +382,18 @@ impl LmdbEnvironment {
 
 #[derive(Debug)]
 pub struct LmdbDatabase {
-    db: lmdb_zero::Database<'static>,
+    db: Arc<lmdb_zero::Database<'static>>,
 }
 
 pub struct LmdbReadTransaction<'env> {
     txn: lmdb_zero::ReadTransaction<'env>,
+    _guard: TxnGuard<'env>,
 }
 
 impl<'env> LmdbReadTransaction<'env> {
     pub(super) fn new(env: &'env LmdbEnvironment) -> Self {
-        // This is an implicit transaction, so take the lock first.
-        LmdbReadTransaction {
-            txn: lmdb_zero::ReadTransaction::new(Arc::clone(&env.env)).unwrap(),
-        }
+        let (guard, txn) = env.open_txn(|| lmdb_zero::ReadTransaction::new(Arc::clone(&env.env)));
+        LmdbReadTransaction { txn, _guard: guard }
     }
 
     pub(super) fn get<K, V>(&self, db: &LmdbDatabase, key: &K) -> Option<V>
@@ -188,9 +410,11 @@ impl<'env> LmdbReadTransaction<'env> {
     }
 
     pub(super) fn cursor<'txn, 'db>(&'txn self, db: &'db Database) -> LmdbCursor<'txn, 'db> {
-        let cursor = self.txn.cursor(&db.persistent().unwrap().db).unwrap();
+        let cursor = self.txn.cursor(&*db.persistent().unwrap().db).unwrap();
         LmdbCursor {
-            raw: RawLmdbCursor { cursor },
+            raw: RawLmdbCursor {
+                cursor: Some(cursor),
+            },
             txn: &self.txn,
         }
     }
@@ -202,19 +426,107 @@ impl<'env> fmt::Debug for LmdbReadTransaction<'env> {
     }
 }
 
+fn is_map_full(e: &LmdbError) -> bool {
+    matches!(e, LmdbError::Code(lmdb_zero::error::MAP_FULL))
+}
+
+/// Returns whether the error was caused by the file system refusing writes.
+fn is_filesystem_error(e: &LmdbError) -> bool {
+    match *e {
+        LmdbError::Code(libc::EACCES | libc::EPERM | libc::EROFS | libc::ENOSPC | libc::EIO) => {
+            true
+        }
+        #[cfg(unix)]
+        LmdbError::Code(libc::EDQUOT) => true,
+        _ => false,
+    }
+}
+
+/// Converts the result of a read, treating a missing entry as `None`. LMDB refuses all operations
+/// on a transaction after a write in it failed, those are treated as `None` as well.
+fn read_result<T>(result: Result<T, LmdbError>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(LmdbError::Code(lmdb_zero::error::NOTFOUND | lmdb_zero::error::BAD_TXN)) => None,
+        Err(e) => panic!("LMDB read failed: {}", e),
+    }
+}
+
 pub struct LmdbWriteTransaction<'env> {
-    txn: lmdb_zero::WriteTransaction<'env>,
+    env: &'env LmdbEnvironment,
+    txn: Option<lmdb_zero::WriteTransaction<'env>>,
+    guard: Option<TxnGuard<'env>>,
+    /// The error of the first failed write. All further writes are skipped and the commit fails.
+    error: Cell<Option<DatabaseError>>,
+    /// Whether `txn` can't be used anymore because a write in it failed.
+    txn_failed: Cell<bool>,
 }
 
 impl<'env> LmdbWriteTransaction<'env> {
     pub(super) fn new(env: &'env LmdbEnvironment) -> Self {
-        // Check for enough space before every write transaction.
-        if env.need_resize(0) {
-            log::error!("DB needs resize, resize not supported");
-        }
+        assert!(
+            !env.read_only,
+            "Write transactions are not allowed on a read-only database"
+        );
+
+        env.grow_map_if_needed();
+
+        let (guard, txn) = env.open_txn(|| lmdb_zero::WriteTransaction::new(Arc::clone(&env.env)));
         LmdbWriteTransaction {
-            txn: lmdb_zero::WriteTransaction::new(Arc::clone(&env.env)).unwrap(),
+            env,
+            txn: Some(txn),
+            guard: Some(guard),
+            error: Cell::new(None),
+            txn_failed: Cell::new(false),
+        }
+    }
+
+    fn txn(&self) -> &lmdb_zero::WriteTransaction<'env> {
+        self.txn.as_ref().unwrap()
+    }
+
+    /// Records a failed write. All further writes are skipped and the commit fails.
+    fn fail(&self, e: LmdbError) {
+        self.error.set(Some(self.env.on_write_error(e)));
+        self.txn_failed.set(true);
+    }
+
+    /// Applies a modification, unless a previous one failed.
+    fn write<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut lmdb_zero::WriteAccessor) -> Result<(), LmdbError>,
+    {
+        if self.error.get().is_none() {
+            #[cfg(any(test, feature = "test-utils"))]
+            let result = if self.env.write_state.simulate_failure.load(Ordering::SeqCst) {
+                Err(LmdbError::Code(libc::EROFS))
+            } else {
+                f(&mut self.txn().access())
+            };
+            #[cfg(not(any(test, feature = "test-utils")))]
+            let result = f(&mut self.txn().access());
+
+            if let Err(e) = result {
+                self.fail(e);
+            }
         }
+        self.reset_if_failed();
+    }
+
+    /// LMDB refuses all operations on a transaction after a write in it failed. Replaces such a
+    /// transaction by a fresh one, so that reads keep working. The fresh transaction only sees the
+    /// last committed state and is never committed.
+    fn reset_if_failed(&mut self) {
+        if !self.txn_failed.replace(false) {
+            return;
+        }
+        self.txn = None;
+        self.guard = None;
+        let (guard, txn) = self
+            .env
+            .open_txn(|| lmdb_zero::WriteTransaction::new(Arc::clone(&self.env.env)));
+        self.guard = Some(guard);
+        self.txn = Some(txn);
     }
 
     pub(super) fn get<K, V>(&self, db: &LmdbDatabase, key: &K) -> Option<V>
@@ -222,12 +534,10 @@ impl<'env> LmdbWriteTransaction<'env> {
         K: AsDatabaseBytes + ?Sized,
         V: FromDatabaseValue,
     {
-        let access = self.txn.access();
-        let result: Option<&[u8]> = access
-            .get(&db.db, AsDatabaseBytes::as_database_bytes(key).as_ref())
-            .to_opt()
-            .unwrap();
-        Some(FromDatabaseValue::copy_from_database(result?).unwrap())
+        let access = self.txn().access();
+        let result: &[u8] =
+            read_result(access.get(&db.db, AsDatabaseBytes::as_database_bytes(key).as_ref()))?;
+        Some(FromDatabaseValue::copy_from_database(result).unwrap())
     }
 
     pub(super) fn put_reserve<K, V>(&mut self, db: &LmdbDatabase, key: &K, value: &V)
@@ -237,18 +547,18 @@ impl<'env> LmdbWriteTransaction<'env> {
     {
         let key = AsDatabaseBytes::as_database_bytes(key);
         let value_size = IntoDatabaseValue::database_byte_size(value);
-        unsafe {
-            let mut access = self.txn.access();
-            let bytes: &mut [u8] = access
-                .put_reserve_unsized(
+        self.write(|access| {
+            let bytes: &mut [u8] = unsafe {
+                access.put_reserve_unsized(
                     &db.db,
                     key.as_ref(),
                     value_size,
                     lmdb_zero::put::Flags::empty(),
-                )
-                .unwrap();
+                )?
+            };
             IntoDatabaseValue::copy_into_database(value, bytes);
-        }
+            Ok(())
+        });
     }
 
     pub(super) fn put<K, V>(&mut self, db: &LmdbDatabase, key: &K, value: &V)
@@ -258,26 +568,22 @@ impl<'env> LmdbWriteTransaction<'env> {
     {
         let key = AsDatabaseBytes::as_database_bytes(key);
         let value = AsDatabaseBytes::as_database_bytes(value);
-        let mut access = self.txn.access();
-        access
-            .put(
+        self.write(|access| {
+            access.put(
                 &db.db,
                 key.as_ref(),
                 value.as_ref(),
                 lmdb_zero::put::Flags::empty(),
             )
-            .unwrap();
+        });
     }
 
     pub(super) fn remove<K>(&mut self, db: &LmdbDatabase, key: &K)
     where
         K: AsDatabaseBytes + ?Sized,
     {
-        let mut access = self.txn.access();
-        access
-            .del_key(&db.db, AsDatabaseBytes::as_database_bytes(key).as_ref())
-            .to_opt()
-            .unwrap();
+        let key = AsDatabaseBytes::as_database_bytes(key);
+        self.write(|access| access.del_key(&db.db, key.as_ref()).to_opt().map(|_| ()));
     }
 
     pub(super) fn remove_item<K, V>(&mut self, db: &LmdbDatabase, key: &K, value: &V)
@@ -285,26 +591,39 @@ impl<'env> LmdbWriteTransaction<'env> {
         K: AsDatabaseBytes + ?Sized,
         V: AsDatabaseBytes + ?Sized,
     {
-        let mut access = self.txn.access();
-        access
-            .del_item(
-                &db.db,
-                AsDatabaseBytes::as_database_bytes(key).as_ref(),
-                AsDatabaseBytes::as_database_bytes(value).as_ref(),
-            )
-            .to_opt()
-            .unwrap();
+        let key = AsDatabaseBytes::as_database_bytes(key);
+        let value = AsDatabaseBytes::as_database_bytes(value);
+        self.write(|access| {
+            access
+                .del_item(&db.db, key.as_ref(), value.as_ref())
+                .to_opt()
+                .map(|_| ())
+        });
     }
 
-    pub(super) fn commit(self) {
-        self.txn.commit().unwrap();
+    pub(super) fn error(&self) -> Option<DatabaseError> {
+        self.error.get()
+    }
+
+    pub(super) fn commit(mut self) -> Result<(), DatabaseError> {
+        if let Some(error) = self.error.get() {
+            return Err(error);
+        }
+
+        #[cfg(any(test, feature = "test-utils"))]
+        if self.env.write_state.simulate_failure.load(Ordering::SeqCst) {
+            return Err(self.env.on_write_error(LmdbError::Code(libc::EROFS)));
+        }
+
+        let result = self.txn.take().unwrap().commit();
+        result.map_err(|e| self.env.on_write_error(e))
     }
 
     pub(super) fn cursor<'txn, 'db>(&'txn self, db: &'db Database) -> LmdbCursor<'txn, 'db> {
-        let cursor = self.txn.cursor(&db.persistent().unwrap().db).unwrap();
+        let cursor = read_result(self.txn().cursor(&*db.persistent().unwrap().db));
         LmdbCursor {
             raw: RawLmdbCursor { cursor },
-            txn: &self.txn,
+            txn: self.txn(),
         }
     }
 
@@ -312,10 +631,11 @@ impl<'env> LmdbWriteTransaction<'env> {
         &'txn self,
         db: &'db Database,
     ) -> LmdbWriteCursor<'txn, 'db> {
-        let cursor = self.txn.cursor(&db.persistent().unwrap().db).unwrap();
+        let cursor = read_result(self.txn().cursor(&*db.persistent().unwrap().db));
         LmdbWriteCursor {
             raw: RawLmdbCursor { cursor },
-            txn: &self.txn,
+            txn: self.txn(),
+            write_txn: self,
         }
     }
 }
@@ -327,7 +647,8 @@ impl<'env> fmt::Debug for LmdbWriteTransaction<'env> {
 }
 
 pub struct RawLmdbCursor<'txn, 'db> {
-    cursor: lmdb_zero::Cursor<'txn, 'db>,
+    /// `None` if the transaction failed, in which case all reads return `None`.
+    cursor: Option<lmdb_zero::Cursor<'txn, 'db>>,
 }
 
 impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
@@ -336,7 +657,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.first(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.first(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -348,7 +669,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
     where
         V: FromDatabaseValue,
     {
-        let result: Option<&[u8]> = self.cursor.first_dup(access).to_opt().unwrap();
+        let result: Option<&[u8]> = read_result(self.cursor.as_mut()?.first_dup(access));
         Some(FromDatabaseValue::copy_from_database(result?).unwrap())
     }
 
@@ -357,7 +678,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.last(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.last(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -369,7 +690,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
     where
         V: FromDatabaseValue,
     {
-        let result: Option<&[u8]> = self.cursor.last_dup(access).to_opt().unwrap();
+        let result: Option<&[u8]> = read_result(self.cursor.as_mut()?.last_dup(access));
         Some(FromDatabaseValue::copy_from_database(result?).unwrap())
     }
 
@@ -380,8 +701,10 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
     {
         let key = AsDatabaseBytes::as_database_bytes(key);
         let value = AsDatabaseBytes::as_database_bytes(value);
-        let result = self.cursor.seek_kv(key.as_ref(), value.as_ref());
-        result.is_ok()
+        match self.cursor.as_mut() {
+            Some(cursor) => cursor.seek_kv(key.as_ref(), value.as_ref()).is_ok(),
+            None => false,
+        }
     }
 
     fn seek_key_nearest_value<K, V>(
@@ -396,11 +719,11 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
     {
         let key = AsDatabaseBytes::as_database_bytes(key);
         let value = AsDatabaseBytes::as_database_bytes(value);
-        let result: Option<&[u8]> = self
-            .cursor
-            .seek_k_nearest_v(access, key.as_ref(), value.as_ref())
-            .to_opt()
-            .unwrap();
+        let result: Option<&[u8]> = read_result(self.cursor.as_mut()?.seek_k_nearest_v(
+            access,
+            key.as_ref(),
+            value.as_ref(),
+        ));
         Some(FromDatabaseValue::copy_from_database(result?).unwrap())
     }
 
@@ -409,7 +732,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.get_current(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.get_current(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -422,7 +745,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.next(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.next(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -435,7 +758,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.next_dup(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.next_dup(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -448,7 +771,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.next_nodup(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.next_nodup(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -461,7 +784,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.prev(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.prev(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -474,7 +797,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.prev_dup(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.prev_dup(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -487,7 +810,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         K: FromDatabaseValue,
         V: FromDatabaseValue,
     {
-        let result: Option<(&[u8], &[u8])> = self.cursor.prev_nodup(access).to_opt().unwrap();
+        let result: Option<(&[u8], &[u8])> = read_result(self.cursor.as_mut()?.prev_nodup(access));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -501,7 +824,7 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         V: FromDatabaseValue,
     {
         let key = AsDatabaseBytes::as_database_bytes(key);
-        let result: Option<&[u8]> = self.cursor.seek_k(access, key.as_ref()).to_opt().unwrap();
+        let result: Option<&[u8]> = read_result(self.cursor.as_mut()?.seek_k(access, key.as_ref()));
         Some(FromDatabaseValue::copy_from_database(result?).unwrap())
     }
 
@@ -511,11 +834,8 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         V: FromDatabaseValue,
     {
         let key = AsDatabaseBytes::as_database_bytes(key);
-        let result: Option<(&[u8], &[u8])> = self
-            .cursor
-            .seek_k_both(access, key.as_ref())
-            .to_opt()
-            .unwrap();
+        let result: Option<(&[u8], &[u8])> =
+            read_result(self.cursor.as_mut()?.seek_k_both(access, key.as_ref()));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -529,11 +849,8 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
         V: FromDatabaseValue,
     {
         let key = AsDatabaseBytes::as_database_bytes(key);
-        let result: Option<(&[u8], &[u8])> = self
-            .cursor
-            .seek_range_k(access, key.as_ref())
-            .to_opt()
-            .unwrap();
+        let result: Option<(&[u8], &[u8])> =
+            read_result(self.cursor.as_mut()?.seek_range_k(access, key.as_ref()));
         let (key, value) = result?;
         Some((
             FromDatabaseValue::copy_from_database(key).unwrap(),
@@ -542,7 +859,10 @@ impl<'txn, 'db> RawReadCursor for RawLmdbCursor<'txn, 'db> {
     }
 
     fn count_duplicates(&mut self) -> usize {
-        self.cursor.count().unwrap()
+        self.cursor
+            .as_mut()
+            .and_then(|cursor| read_result(cursor.count()))
+            .unwrap_or(0)
     }
 }
 
@@ -556,17 +876,26 @@ impl_read_cursor_from_raw!(LmdbCursor<'txn, 'db>, raw, txn);
 pub struct LmdbWriteCursor<'txn, 'db> {
     raw: RawLmdbCursor<'txn, 'db>,
     txn: &'txn lmdb_zero::WriteTransaction<'txn>,
+    write_txn: &'txn LmdbWriteTransaction<'txn>,
 }
 
 impl_read_cursor_from_raw!(LmdbWriteCursor<'txn, 'db>, raw, txn);
 
 impl<'txn, 'db> WriteCursorTrait for LmdbWriteCursor<'txn, 'db> {
     fn remove(&mut self) {
+        if self.write_txn.error.get().is_some() {
+            return;
+        }
+        let cursor = match self.raw.cursor.as_mut() {
+            Some(cursor) => cursor,
+            None => return,
+        };
         let mut access = self.txn.access();
-        self.raw
-            .cursor
-            .del(&mut access, lmdb_zero::del::Flags::empty())
-            .unwrap();
+        if let Err(e) = cursor.del(&mut access, lmdb_zero::del::Flags::empty()) {
+            // The transaction can't be used anymore, so stop reading as well.
+            self.raw.cursor = None;
+            self.write_txn.fail(e);
+        }
     }
 }
 
@@ -628,6 +957,207 @@ mod tests {
         env.drop_database().unwrap();
     }
 
+    #[test]
+    fn it_grows_the_map_when_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let lmdb_env = LmdbEnvironment::new_lmdb_environment(
+            dir.path().to_str().unwrap(),
+            0,
+            1,
+            None,
+            open::NOTLS,
+        )
+        .unwrap();
+        let initial_size = lmdb_env.map_size();
+        let env = Environment::Persistent(lmdb_env.clone());
+        let db = env.open_database("test".to_string());
+
+        // Write more data than fits into the initial memory map in a single transaction.
+        let value = vec![0x42u8; 1024 * 1024];
+        let num_values = initial_size / value.len() + 1;
+        let write = || {
+            let mut tx = WriteTransaction::new(&env);
+            for i in 0..num_values as u32 {
+                tx.put(&db, &i, &value);
+            }
+            // Reads keep working after the transaction ran out of space.
+            assert!(tx.get::<u32, Vec<u8>>(&db, &u32::MAX).is_none());
+            tx.try_commit()
+        };
+        assert_eq!(write(), Err(DatabaseError::MapFull));
+
+        // The memory map is grown before the next transaction, so the write can be retried.
+        assert_eq!(write(), Ok(()));
+        assert!(lmdb_env.map_size() > initial_size);
+
+        let tx = ReadTransaction::new(&env);
+        for i in 0..num_values as u32 {
+            assert_eq!(tx.get::<u32, Vec<u8>>(&db, &i), Some(value.clone()));
+        }
+    }
+
+    #[test]
+    fn it_grows_the_map_before_it_is_full() {
+        let dir = tempfile::tempdir().unwrap();
+        let lmdb_env = LmdbEnvironment::new_lmdb_environment(
+            dir.path().to_str().unwrap(),
+            0,
+            1,
+            None,
+            open::NOTLS,
+        )
+        .unwrap();
+        let initial_size = lmdb_env.map_size();
+        let env = Environment::Persistent(lmdb_env.clone());
+        let db = env.open_database("test".to_string());
+
+        // Use up most of the memory map, one value per transaction.
+        let value = vec![0x42u8; 1024 * 1024];
+        let threshold = (initial_size as f64 * LmdbEnvironment::GROW_THRESHOLD) as usize;
+        let mut i = 0u32;
+        while lmdb_env.size_used() < threshold {
+            let mut tx = WriteTransaction::new(&env);
+            tx.put(&db, &i, &value);
+            tx.commit();
+            i += 1;
+        }
+        assert_eq!(lmdb_env.map_size(), initial_size);
+
+        // The next write transaction grows the memory map, even though it isn't full yet.
+        let _tx = WriteTransaction::new(&env);
+        assert!(lmdb_env.map_size() > initial_size);
+    }
+
+    #[test]
+    fn it_serves_reads_after_a_write_failure() {
+        let env = crate::volatile::VolatileEnvironment::new(1).unwrap();
+        let db = env.open_database("test".to_string());
+
+        let mut tx = WriteTransaction::new(&env);
+        tx.put_reserve(&db, "test", "one");
+        tx.commit();
+        assert!(!env.is_read_only_filesystem());
+
+        env.simulate_write_failure();
+        let mut tx = WriteTransaction::new(&env);
+        tx.put_reserve(&db, "test", "two");
+        assert_eq!(tx.error(), Some(DatabaseError::ReadOnlyFilesystem));
+        assert!(env.is_read_only_filesystem());
+
+        // The transaction keeps serving reads from the last committed state.
+        assert_eq!(tx.get::<str, String>(&db, "test"), Some("one".to_string()));
+        {
+            let mut cursor = tx.cursor(&db);
+            assert_eq!(
+                cursor.first::<String, String>(),
+                Some(("test".to_string(), "one".to_string()))
+            );
+        }
+
+        // Further writes are skipped and the commit fails.
+        tx.put_reserve(&db, "other", "three");
+        assert!(tx.get::<str, String>(&db, "other").is_none());
+        assert_eq!(tx.try_commit(), Err(DatabaseError::ReadOnlyFilesystem));
+
+        // The failed transaction didn't change anything and reads are still served.
+        let tx = ReadTransaction::new(&env);
+        assert_eq!(tx.get::<str, String>(&db, "test"), Some("one".to_string()));
+    }
+
+    /// Makes `read_only_writer_process` act as the writer process for
+    /// `read_only_environment_reads_concurrently`.
+    const WRITER_PATH_VAR: &str = "NIMIQ_DATABASE_TEST_WRITER_PATH";
+
+    #[test]
+    #[ignore]
+    fn read_only_writer_process() {
+        let path = match std::env::var(WRITER_PATH_VAR) {
+            Ok(path) => path,
+            Err(_) => return,
+        };
+        let lmdb_env =
+            LmdbEnvironment::new_lmdb_environment(&path, 0, 1, None, open::Flags::empty()).unwrap();
+        let env = Environment::Persistent(lmdb_env.clone());
+        let db = env.open_database("test".to_string());
+
+        let mut tx = WriteTransaction::new(&env);
+        tx.put_reserve(&db, "test", "one");
+        tx.commit();
+
+        // Keep a write transaction open until the reader is done.
+        let mut tx = WriteTransaction::new(&env);
+        tx.put_reserve(&db, "test", "two");
+        fs::write(std::path::Path::new(&path).join("ready"), b"").unwrap();
+        while !std::path::Path::new(&path).join("done").exists() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        tx.commit();
+
+        // Write more data than fits into the memory map, so that it is grown.
+        let value = vec![0x42u8; 1024 * 1024];
+        let num_values = lmdb_env.map_size() / value.len() + 1;
+        loop {
+            let mut tx = WriteTransaction::new(&env);
+            for i in 0..num_values as u32 {
+                tx.put(&db, &i, &value);
+            }
+            match tx.try_commit() {
+                Ok(()) => break,
+                Err(DatabaseError::MapFull) => {}
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+
+    #[test]
+    fn read_only_environment_reads_concurrently() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+
+        let mut writer = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "lmdb::tests::read_only_writer_process",
+                "--ignored",
+            ])
+            .env(WRITER_PATH_VAR, path)
+            .spawn()
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while !dir.path().join("ready").exists() {
+            assert!(Instant::now() < deadline, "Writer process didn't start");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let env = LmdbEnvironment::new(path, 0, 1, open::RDONLY).unwrap();
+        assert!(env.is_read_only());
+        let db = env.open_database("test".to_string());
+
+        // Reads succeed while the other process has a write transaction open.
+        {
+            let tx = ReadTransaction::new(&env);
+            assert_eq!(tx.get::<str, String>(&db, "test"), Some("one".to_string()));
+        }
+
+        // Writes are rejected.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            WriteTransaction::new(&env);
+        }));
+        assert!(result.is_err());
+
+        fs::write(dir.path().join("done"), b"").unwrap();
+        assert!(writer.wait().unwrap().success());
+
+        // The writer grew the memory map, the reader adopts the new size.
+        let tx = ReadTransaction::new(&env);
+        assert_eq!(tx.get::<str, String>(&db, "test"), Some("two".to_string()));
+        assert_eq!(
+            tx.get::<u32, Vec<u8>>(&db, &0),
+            Some(vec![0x42u8; 1024 * 1024])
+        );
+    }
+
     #[test]
     fn isolation_test() {
         let env = LmdbEnvironment::new("./test2", 0, 1, open::NOTLS).unwrap();
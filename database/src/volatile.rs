@@ -108,6 +108,10 @@ impl VolatileEnvironment {
         VolatileDatabase(self.env.open_database(name, flags))
     }
 
+    pub(super) fn as_lmdb(&self) -> &LmdbEnvironment {
+        &self.env
+    }
+
     pub(super) fn drop_database(self) -> io::Result<()> {
         Ok(())
     }
@@ -191,7 +195,11 @@ impl<'env> VolatileWriteTransaction<'env> {
         self.0.remove_item(&db.0, key, value)
     }
 
-    pub(super) fn commit(self) {
+    pub(super) fn error(&self) -> Option<DatabaseError> {
+        self.0.error()
+    }
+
+    pub(super) fn commit(self) -> Result<(), DatabaseError> {
         self.0.commit()
     }
 
@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 use std::pin::Pin;
+use std::sync::Arc;
 
 use futures::channel::mpsc::{unbounded, UnboundedSender};
 use futures::future::BoxFuture;
@@ -7,10 +8,9 @@ use futures::stream::BoxStream;
 use futures::task::{Context, Poll};
 use futures::{ready, select, Future, FutureExt, Sink, Stream, StreamExt};
 use tokio::task::JoinHandle;
-use tokio::time::{interval_at, Instant};
-use tokio_stream::wrappers::IntervalStream;
 
 use beserial::{Deserialize, Serialize};
+use nimiq_utils::time::interval;
 
 use crate::config::Config;
 use crate::contribution::AggregatableContribution;
@@ -62,10 +62,10 @@ struct NextAggregation<
     sender: LevelUpdateSender<P, T>,
 
     /// Interval for starting the next level regardless of previous levels completion
-    start_level_interval: IntervalStream,
+    start_level_interval: BoxStream<'static, ()>,
 
     /// Interval for sending level updates to the corresponding peers regardless of progression
-    periodic_update_interval: IntervalStream,
+    periodic_update_interval: BoxStream<'static, ()>,
 
     /// the level which needs activation next
     next_level_timeout: usize,
@@ -95,15 +95,11 @@ impl<
 
         // Regardless of level completion consecutive levels need to be activated at some point. Activate Levels every time this interval ticks,
         // if the level has not already been activated due to level completion
-        let start_level_interval =
-            IntervalStream::new(interval_at(Instant::now() + config.timeout, config.timeout));
+        let start_level_interval = interval(Arc::clone(&config.clock), config.timeout);
 
         // Every `config.update_interval` send Level updates to corresponding peers no matter the aggregations progression
         // (makes sure other peers can catch up).
-        let periodic_update_interval = IntervalStream::new(interval_at(
-            Instant::now() + config.update_interval,
-            config.update_interval,
-        ));
+        let periodic_update_interval = interval(Arc::clone(&config.clock), config.update_interval);
 
         // Create the NextAggregation struct
         Self {
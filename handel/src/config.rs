@@ -1,5 +1,8 @@
+use std::sync::Arc;
 use std::time::Duration;
 
+use nimiq_utils::time::{Clock, OffsetTime};
+
 #[derive(Clone, Debug)]
 pub struct Config {
     /// Number of peers contacted during an update at each level
@@ -13,6 +16,13 @@ pub struct Config {
 
     /// How many peers are contacted at each level
     pub peer_count: usize,
+
+    /// Time a Tendermint aggregation may go without making progress before it is considered
+    /// stalled
+    pub stall_timeout: Duration,
+
+    /// Clock driving the level timeouts and periodic updates
+    pub clock: Arc<dyn Clock>,
 }
 
 impl Default for Config {
@@ -22,6 +32,8 @@ impl Default for Config {
             update_interval: Duration::from_millis(200),
             timeout: Duration::from_millis(400),
             peer_count: 16,
+            stall_timeout: Duration::from_secs(10),
+            clock: Arc::new(OffsetTime::new()),
         }
     }
 }
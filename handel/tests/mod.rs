@@ -259,6 +259,7 @@ async fn it_can_aggregate() {
         update_interval: Duration::from_millis(500),
         timeout: Duration::from_millis(500),
         peer_count: 1,
+        ..Default::default()
     };
 
     let stopped = Arc::new(RwLock::new(false));
@@ -113,7 +113,7 @@ where
 
 // Blake2b
 
-const BLAKE2B_LENGTH: usize = 32;
+pub(crate) const BLAKE2B_LENGTH: usize = 32;
 create_typed_array!(Blake2bHash, u8, BLAKE2B_LENGTH);
 add_hex_io_fns_typed_arr!(Blake2bHash, BLAKE2B_LENGTH);
 
@@ -1,8 +1,11 @@
-use std::borrow::Cow;
+use std::fmt;
 
-use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de::{Error, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
-use crate::Blake2bHash;
+use crate::{Blake2bHash, BLAKE2B_LENGTH};
 
 impl Serialize for Blake2bHash {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -18,7 +21,41 @@ impl<'de> Deserialize<'de> for Blake2bHash {
     where
         D: Deserializer<'de>,
     {
-        let s: Cow<'de, str> = Deserialize::deserialize(deserializer)?;
-        s.parse().map_err(Error::custom)
+        deserializer.deserialize_str(Blake2bHashVisitor)
+    }
+}
+
+/// Parses hex-encoded hashes and reports non-string input, a wrong length and non-hex characters
+/// with distinct messages, since hashes are commonly entered by hand (e.g. in RPC requests).
+struct Blake2bHashVisitor;
+
+impl<'de> Visitor<'de> for Blake2bHashVisitor {
+    type Value = Blake2bHash;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "a hex-encoded Blake2b hash of {} characters",
+            2 * BLAKE2B_LENGTH
+        )
+    }
+
+    fn visit_str<E: Error>(self, s: &str) -> Result<Self::Value, E> {
+        if s.len() != 2 * BLAKE2B_LENGTH {
+            return Err(E::custom(format!(
+                "invalid Blake2b hash length: expected {} hex characters, got {}",
+                2 * BLAKE2B_LENGTH,
+                s.len()
+            )));
+        }
+
+        if let Some((index, c)) = s.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+            return Err(E::custom(format!(
+                "invalid character {:?} at position {} in Blake2b hash",
+                c, index
+            )));
+        }
+
+        s.parse().map_err(E::custom)
     }
 }
@@ -7,6 +7,8 @@ use nimiq_hash::{
 
 mod hmac;
 mod pbkdf2;
+#[cfg(feature = "serde")]
+mod serde;
 
 #[test]
 fn it_can_compute_sha256() {
@@ -0,0 +1,48 @@
+use serde::de::{value, Deserialize, IntoDeserializer};
+
+use nimiq_hash::Blake2bHash;
+
+fn parse<'de, D: IntoDeserializer<'de, value::Error>>(input: D) -> Result<Blake2bHash, String> {
+    Blake2bHash::deserialize(input.into_deserializer()).map_err(|e| e.to_string())
+}
+
+#[test]
+fn it_deserializes_blake2b_hashes() {
+    let hex = "324dcf027dd4a30a932c441f365a25e86b173defa4b8e58948253471b81b72cf";
+    assert_eq!(parse(hex), Ok(Blake2bHash::from(hex)));
+}
+
+#[test]
+fn it_rejects_non_string_hashes() {
+    let error = parse(42u64).unwrap_err();
+    assert!(error.starts_with("invalid type: integer `42`"), "{}", error);
+    assert!(
+        error.contains("expected a hex-encoded Blake2b hash"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn it_rejects_hashes_of_wrong_length() {
+    assert_eq!(
+        parse("324dcf027dd4a30a"),
+        Err("invalid Blake2b hash length: expected 64 hex characters, got 16".to_string())
+    );
+    assert_eq!(
+        parse("324dcf027dd4a30a932c441f365a25e86b173defa4b8e58948253471b81b72cf00"),
+        Err("invalid Blake2b hash length: expected 64 hex characters, got 66".to_string())
+    );
+}
+
+#[test]
+fn it_rejects_hashes_with_non_hex_characters() {
+    assert_eq!(
+        parse("324dcf027dd4a30a932c441f365a25e86b173defa4b8e58948253471b81b72cg"),
+        Err("invalid character 'g' at position 63 in Blake2b hash".to_string())
+    );
+    assert_eq!(
+        parse("0x4dcf027dd4a30a932c441f365a25e86b173defa4b8e58948253471b81b72cf"),
+        Err("invalid character 'x' at position 1 in Blake2b hash".to_string())
+    );
+}
@@ -2,8 +2,11 @@ use std::sync::Arc;
 
 use parking_lot::RwLock;
 
+use beserial::Deserialize;
+
 use nimiq_block::Block;
-use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, Checkpoint, HeadSnapshot};
+use nimiq_build_tools::genesis::GenesisBuilder;
 use nimiq_consensus::{
     sync::history::HistorySync, Consensus as AbstractConsensus,
     ConsensusProxy as AbstractConsensusProxy,
@@ -18,6 +21,8 @@ use nimiq_network_libp2p::{
 };
 use nimiq_utils::time::OffsetTime;
 #[cfg(feature = "validator")]
+use nimiq_validator::aggregation::AggregationConfig;
+#[cfg(feature = "validator")]
 use nimiq_validator::validator::Validator as AbstractValidator;
 #[cfg(feature = "validator")]
 use nimiq_validator::validator::ValidatorProxy as AbstractValidatorProxy;
@@ -75,6 +80,29 @@ impl ClientInner {
         }
         let network_info = NetworkInfo::from_network_id(config.network_id);
 
+        // Generate the genesis block of a custom network
+        let custom_genesis = config
+            .consensus
+            .genesis_file
+            .as_ref()
+            .map(|genesis_file| {
+                GenesisBuilder::generate_custom(genesis_file).map_err(|e| {
+                    Error::config_error(format!(
+                        "Invalid genesis file {}: {}",
+                        genesis_file.display(),
+                        e
+                    ))
+                })
+            })
+            .transpose()?;
+        let genesis_hash = match custom_genesis {
+            Some(ref genesis) => {
+                log::info!("Using custom genesis block: {}", genesis.hash);
+                genesis.hash.clone()
+            }
+            None => network_info.genesis_hash().clone(),
+        };
+
         // Initialize clock
         let time = Arc::new(OffsetTime::new());
 
@@ -104,12 +132,11 @@ impl ClientInner {
             .collect();
 
         // Setup libp2p network
-        let network_config = NetworkConfig::new(
-            identity_keypair,
-            peer_contact,
-            seeds,
-            network_info.genesis_hash().clone(),
-        );
+        let mut network_config =
+            NetworkConfig::new(identity_keypair, peer_contact, seeds, genesis_hash);
+        network_config.reconnect = config.network.reconnect.clone();
+        network_config.peer_limits = config.network.peer_limits.clone();
+        network_config.tls = config.network.tls.clone();
 
         log::debug!("listen_addresses = {:?}", config.network.listen_addresses);
 
@@ -124,16 +151,74 @@ impl ClientInner {
             config.consensus.sync_mode,
             config.database,
         )?;
-        let blockchain = Arc::new(RwLock::new(
-            Blockchain::new(environment.clone(), config.network_id, time).unwrap(),
-        ));
+        let mut blockchain = match config.consensus.checkpoint_file {
+            Some(ref checkpoint_file) => {
+                if custom_genesis.is_some() {
+                    return Err(Error::config_error(
+                        "Checkpoints can't be used with a custom genesis block",
+                    ));
+                }
+                if !config.consensus.trust_checkpoint {
+                    return Err(Error::config_error(
+                        "A checkpoint is only used if it is explicitly trusted",
+                    ));
+                }
+                let checkpoint = Checkpoint::deserialize_from_vec(&std::fs::read(checkpoint_file)?)
+                    .map_err(|e| {
+                        Error::config_error(format!(
+                            "Invalid checkpoint file {}: {}",
+                            checkpoint_file.display(),
+                            e
+                        ))
+                    })?;
+                Blockchain::with_trusted_checkpoint(
+                    environment.clone(),
+                    config.network_id,
+                    time,
+                    checkpoint,
+                )
+                .map_err(|e| Error::config_error(format!("Can't start from checkpoint: {}", e)))?
+            }
+            None => match custom_genesis {
+                Some(genesis) => Blockchain::with_genesis(
+                    environment.clone(),
+                    time,
+                    config.network_id,
+                    genesis.block,
+                    genesis.accounts,
+                )
+                .map_err(|e| {
+                    Error::config_error(format!("Can't start from custom genesis block: {}", e))
+                })?,
+                None => Blockchain::new(environment.clone(), config.network_id, time).unwrap(),
+            },
+        };
+        blockchain.max_reorg_depth = config.consensus.max_reorg_depth;
+        blockchain.history_mode = config.consensus.history_mode;
+        blockchain
+            .chain_store
+            .set_body_cache_size(config.consensus.body_cache_size);
+
+        let head = blockchain.head_snapshot();
+        log::info!(
+            "Blockchain head: #{}.{} - {}",
+            head.block_number,
+            head.view_number,
+            head.hash
+        );
+
+        let blockchain = Arc::new(RwLock::new(blockchain));
 
         // Open wallet
         #[cfg(feature = "wallet")]
         let wallet_store = Arc::new(WalletStore::new(environment.clone()));
 
         // Initialize consensus
-        let sync = HistorySync::<Network>::new(Arc::clone(&blockchain), network_events);
+        let sync = HistorySync::<Network>::with_request_timeout(
+            Arc::clone(&blockchain),
+            network_events,
+            config.consensus.request_timeout,
+        );
         let consensus = Consensus::with_min_peers(
             environment.clone(),
             blockchain,
@@ -158,7 +243,21 @@ impl ClientInner {
                 // Load fee key (before we give away ownership of the storage config)
                 let fee_key = config.storage.fee_keypair()?;
 
-                let validator_network = Arc::new(ValidatorNetworkImpl::new(Arc::clone(&network)));
+                let clock = Arc::clone(&consensus.blockchain.read().time);
+                let validator_network =
+                    Arc::new(ValidatorNetworkImpl::new(Arc::clone(&network)).with_clock(clock));
+                tokio::spawn(validator_network.monitor_connectivity());
+
+                let mut aggregation_config = AggregationConfig::default();
+                if let Some(update_interval) = validator_config.aggregation_update_interval {
+                    aggregation_config.update_interval = update_interval;
+                }
+                if let Some(timeout) = validator_config.aggregation_timeout {
+                    aggregation_config.timeout = timeout;
+                }
+                if let Some(stall_timeout) = validator_config.aggregation_stall_timeout {
+                    aggregation_config.stall_timeout = stall_timeout;
+                }
 
                 let validator = Validator::new(
                     &consensus,
@@ -168,6 +267,7 @@ impl ClientInner {
                     voting_key,
                     fee_key,
                     config.mempool,
+                    aggregation_config,
                 );
 
                 // Use the validator's mempool as TransactionVerificationCache in the blockchain.
@@ -181,7 +281,7 @@ impl ClientInner {
         };
 
         // Start network.
-        network.listen_on(config.network.listen_addresses).await;
+        network.listen_on(config.network.listen_addresses).await?;
         network.start_connecting().await;
 
         Ok(Client {
@@ -255,6 +355,12 @@ impl Client {
         self.inner.consensus.blockchain.read().head()
     }
 
+    /// Returns a consistent snapshot of the block number, hash, timestamp and view number of the
+    /// blockchain head.
+    pub fn blockchain_head_snapshot(&self) -> HeadSnapshot {
+        self.inner.consensus.blockchain.read().head_snapshot()
+    }
+
     #[cfg(feature = "wallet")]
     pub fn wallet_store(&self) -> Arc<WalletStore> {
         Arc::clone(&self.inner.wallet_store)
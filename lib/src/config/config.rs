@@ -3,12 +3,14 @@ use std::net::IpAddr;
 use std::{
     path::{Path, PathBuf},
     string::ToString,
+    time::Duration,
 };
 
 use derive_builder::Builder;
 use strum_macros::Display;
 
 use beserial::Deserialize;
+use nimiq_blockchain::{Blockchain, HistoryMode};
 #[cfg(feature = "validator")]
 use nimiq_bls::{KeyPair as BlsKeyPair, SecretKey as BlsSecretKey};
 use nimiq_database::{
@@ -18,7 +20,9 @@ use nimiq_database::{
 };
 use nimiq_keys::{Address, KeyPair, PrivateKey};
 use nimiq_mempool::{config::MempoolConfig, filter::MempoolRules};
-use nimiq_network_libp2p::{Keypair as IdentityKeypair, Multiaddr};
+use nimiq_network_libp2p::{
+    Keypair as IdentityKeypair, Multiaddr, PeerLimitsConfig, ReconnectConfig, TlsConfig,
+};
 use nimiq_primitives::networks::NetworkId;
 use nimiq_utils::file_store::FileStore;
 #[cfg(feature = "validator")]
@@ -65,6 +69,32 @@ pub struct ConsensusConfig {
     pub sync_mode: SyncMode,
     #[builder(default = "3")]
     pub min_peers: usize,
+    /// Maximum number of micro blocks that may be reverted when rebranching to a fork.
+    #[builder(default = "Blockchain::DEFAULT_MAX_REORG_DEPTH")]
+    pub max_reorg_depth: u32,
+    /// Time to wait for a peer to respond to a sync request before it is retried with another
+    /// peer.
+    #[builder(default = "Duration::from_secs(10)")]
+    pub request_timeout: Duration,
+    /// Genesis config file of a custom network. If set, the genesis block is generated from this
+    /// file instead of using the one compiled in for the selected network.
+    #[builder(default)]
+    pub genesis_file: Option<PathBuf>,
+    /// File containing a checkpoint to start syncing from instead of the genesis block. It is
+    /// only used to initialize an empty database.
+    #[builder(default)]
+    pub checkpoint_file: Option<PathBuf>,
+    /// A checkpoint can't be verified against the genesis block, so it is only used if it is
+    /// explicitly trusted.
+    #[builder(default)]
+    pub trust_checkpoint: bool,
+    /// Whether the full history is retained or only the history of recent epochs.
+    #[builder(default)]
+    pub history_mode: HistoryMode,
+    /// Number of recent blocks whose bodies are kept in memory. Older bodies are loaded from the
+    /// database when needed.
+    #[builder(default = "Blockchain::DEFAULT_BODY_CACHE_SIZE")]
+    pub body_cache_size: u32,
 }
 
 impl Default for ConsensusConfig {
@@ -72,6 +102,13 @@ impl Default for ConsensusConfig {
         ConsensusConfig {
             sync_mode: SyncMode::default(),
             min_peers: 3,
+            max_reorg_depth: Blockchain::DEFAULT_MAX_REORG_DEPTH,
+            request_timeout: Duration::from_secs(10),
+            genesis_file: None,
+            checkpoint_file: None,
+            trust_checkpoint: false,
+            history_mode: HistoryMode::default(),
+            body_cache_size: Blockchain::DEFAULT_BODY_CACHE_SIZE,
         }
     }
 }
@@ -80,6 +117,11 @@ impl Default for ConsensusConfig {
 #[derive(Debug, Clone, Builder)]
 #[builder(setter(into))]
 pub struct NetworkConfig {
+    /// The addresses to listen on for incoming connections. Connections accepted on any of them
+    /// end up in the same peer pool.
+    ///
+    /// Default: no addresses
+    ///
     #[builder(default)]
     pub listen_addresses: Vec<Multiaddr>,
 
@@ -95,6 +137,28 @@ pub struct NetworkConfig {
 
     #[builder(default)]
     pub seeds: Vec<Seed>,
+
+    /// The exponential backoff used to reconnect to the network after losing all peers.
+    ///
+    /// Default: starts at 1 second and is capped at 60 seconds
+    ///
+    #[builder(default)]
+    pub reconnect: ReconnectConfig,
+
+    /// The maximum number of inbound and outbound connections.
+    ///
+    /// Default: 4000 inbound and 12 outbound connections
+    ///
+    #[builder(default)]
+    pub peer_limits: PeerLimitsConfig,
+
+    /// The certificate and private key used to accept secure websocket connections. Required if
+    /// any of the listen addresses uses `/wss`.
+    ///
+    /// Default: no TLS
+    ///
+    #[builder(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 /// Contains which protocol to use and the configuration needed for that protocol.
@@ -245,8 +309,8 @@ pub struct DatabaseConfig {
     #[builder(default = "1024 * 1024 * 1024 * 1024")]
     size: usize,
 
-    /// Max number of DBs. Recommended: 12
-    #[builder(default = "12")]
+    /// Max number of DBs. Recommended: 13
+    #[builder(default = "13")]
     max_dbs: u32,
 
     /// Max number of threads that can open read transactions.
@@ -256,7 +320,7 @@ pub struct DatabaseConfig {
     #[builder(default = "600")]
     max_readers: u32,
 
-    /// Additional LMDB flags
+    /// Additional LMDB flags. Default: `NOMETASYNC | NOSYNC`
     #[builder(default = "LmdbFlags::NOMETASYNC | LmdbFlags::NOSYNC")]
     flags: LmdbFlags::Flags,
 }
@@ -266,7 +330,7 @@ impl Default for DatabaseConfig {
         Self {
             // 1 TB
             size: 1024 * 1024 * 1024 * 1024,
-            max_dbs: 12,
+            max_dbs: 13,
             max_readers: 600,
             flags: LmdbFlags::NOMETASYNC | LmdbFlags::NOSYNC,
         }
@@ -278,11 +342,19 @@ impl From<Option<config_file::DatabaseSettings>> for DatabaseConfig {
         let default = DatabaseConfig::default();
 
         if let Some(db_settings) = db_settings {
+            let mut flags = default.flags;
+            if let Some(no_sync) = db_settings.no_sync {
+                flags.set(LmdbFlags::NOSYNC, no_sync);
+            }
+            if let Some(no_meta_sync) = db_settings.no_meta_sync {
+                flags.set(LmdbFlags::NOMETASYNC, no_meta_sync);
+            }
+
             Self {
                 size: db_settings.size.unwrap_or(default.size),
                 max_dbs: db_settings.max_dbs.unwrap_or(default.max_dbs),
                 max_readers: db_settings.max_readers.unwrap_or(default.max_readers),
-                flags: default.flags,
+                flags,
             }
         } else {
             default
@@ -357,6 +429,7 @@ impl StorageConfig {
                         ))
                     })?
                     .to_string();
+                Self::create_database_dir(Path::new(&db_path))?;
                 LmdbEnvironment::new_with_max_readers(
                     &db_path,
                     db_config.size,
@@ -369,6 +442,20 @@ impl StorageConfig {
         })
     }
 
+    /// Creates the database directory if necessary. This fails early with a readable error
+    /// instead of panicking when LMDB opens the environment. An existing database is opened even
+    /// if its file system doesn't accept writes anymore (e.g. because the disk is full), so that
+    /// the node can keep serving reads.
+    fn create_database_dir(path: &Path) -> Result<(), Error> {
+        std::fs::create_dir_all(path).map_err(|e| {
+            Error::config_error(format!(
+                "Failed to create database directory: {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
     #[cfg(feature = "validator")]
     pub(crate) fn voting_keypair(&self) -> Result<BlsKeyPair, Error> {
         Ok(match self {
@@ -513,6 +600,23 @@ impl Default for StorageConfig {
 pub struct ValidatorConfig {
     /// The validator address.
     pub validator_address: Address,
+
+    /// The interval at which the validator sends level updates to its peers during Handel
+    /// aggregations (view changes and Tendermint votes).
+    ///
+    /// Default: Handel default (200 ms)
+    pub aggregation_update_interval: Option<Duration>,
+
+    /// The time a Handel aggregation waits before it advances to the next level.
+    ///
+    /// Default: Handel default (400 ms)
+    pub aggregation_timeout: Option<Duration>,
+
+    /// The time a Tendermint vote aggregation may go without making progress before it is given
+    /// up on.
+    ///
+    /// Default: Handel default (10 s)
+    pub aggregation_stall_timeout: Option<Duration>,
 }
 
 /// Credentials for JSON RPC server, metrics server or websocket RPC server
@@ -748,12 +852,21 @@ impl ClientConfigBuilder {
         self.mempool = Some(MempoolConfig {
             filter_rules,
             filter_limit,
+            ..Default::default()
         });
         self
     }
 
     /// Applies settings from a configuration file
     pub fn config_file(&mut self, config_file: &ConfigFile) -> Result<&mut Self, Error> {
+        if let Some(tls) = &config_file.network.tls {
+            if tls.private_key_password.is_some() {
+                return Err(Error::config_error(
+                    "Encrypted TLS private keys are not supported, remove `private_key_password` and use an unencrypted PEM private key",
+                ));
+            }
+        }
+
         // TODO: if the config field of `listen_addresses` is empty, we should at least add `/ip4/127.0.0.1/...`
         self.network(NetworkConfig {
             listen_addresses: config_file
@@ -771,6 +884,42 @@ impl ClientConfigBuilder {
                 .unwrap_or_default(),
 
             seeds: config_file.network.seed_nodes.clone(),
+
+            reconnect: {
+                let mut reconnect = ReconnectConfig::default();
+                if let Some(secs) = config_file.network.reconnect_initial_interval {
+                    reconnect.initial_interval = Duration::from_secs(secs);
+                }
+                if let Some(secs) = config_file.network.reconnect_max_interval {
+                    reconnect.max_interval = Duration::from_secs(secs);
+                }
+                reconnect
+            },
+
+            peer_limits: {
+                let mut peer_limits = PeerLimitsConfig::default();
+                if let Some(max_inbound) = config_file.network.max_inbound {
+                    peer_limits.max_inbound = max_inbound;
+                }
+                if let Some(max_outbound) = config_file.network.max_outbound {
+                    peer_limits.max_outbound = max_outbound;
+                }
+                peer_limits
+            },
+
+            tls: config_file
+                .network
+                .tls
+                .as_ref()
+                .map(|tls| {
+                    TlsConfig::from_pem_files(
+                        &tls.private_key_file,
+                        tls.certificates_file
+                            .as_ref()
+                            .unwrap_or(&tls.private_key_file),
+                    )
+                })
+                .transpose()?,
         });
 
         // Configure consensus
@@ -781,6 +930,32 @@ impl ClientConfigBuilder {
         if let Some(min_peers) = config_file.consensus.min_peers {
             consensus.min_peers = min_peers;
         }
+        if let Some(max_reorg_depth) = config_file.consensus.max_reorg_depth {
+            consensus.max_reorg_depth = max_reorg_depth;
+        }
+        if let Some(body_cache_size) = config_file.consensus.body_cache_size {
+            consensus.body_cache_size = body_cache_size;
+        }
+        if let Some(request_timeout) = config_file.consensus.request_timeout {
+            consensus.request_timeout = Duration::from_secs(request_timeout);
+        }
+        consensus.genesis_file = config_file
+            .consensus
+            .genesis_file
+            .as_ref()
+            .map(PathBuf::from);
+        consensus.checkpoint_file = config_file
+            .consensus
+            .checkpoint_file
+            .as_ref()
+            .map(PathBuf::from);
+        consensus.trust_checkpoint = config_file.consensus.trust_checkpoint;
+        consensus.history_mode = match config_file.consensus.history {
+            config_file::HistorySetting::Archive => HistoryMode::Archive,
+            config_file::HistorySetting::Pruned => HistoryMode::Pruned {
+                retained_epochs: config_file.consensus.retained_epochs.unwrap_or(0),
+            },
+        };
         self.consensus(consensus);
 
         // Configure network
@@ -803,6 +978,15 @@ impl ClientConfigBuilder {
         if let Some(validator_config) = config_file.validator.as_ref() {
             self.validator(ValidatorConfig {
                 validator_address: Address::from_any_str(&validator_config.validator_address)?,
+                aggregation_update_interval: validator_config
+                    .aggregation_update_interval
+                    .map(Duration::from_millis),
+                aggregation_timeout: validator_config
+                    .aggregation_timeout
+                    .map(Duration::from_millis),
+                aggregation_stall_timeout: validator_config
+                    .aggregation_stall_timeout
+                    .map(Duration::from_millis),
             });
 
             if let Some(key_path) = &validator_config.voting_key_file {
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::fs::read_to_string;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use log::LevelFilter;
 use serde_derive::Deserialize;
@@ -10,6 +11,8 @@ use thiserror::Error;
 use nimiq_mempool::{
     config::MempoolConfig,
     filter::{MempoolFilter, MempoolRules},
+    mempool::Mempool,
+    ordering::{ArrivalTimeOrdering, FeePerByteOrdering, TransactionOrdering},
 };
 use nimiq_network_libp2p::Multiaddr;
 use nimiq_peer_address::{address, protocol}; // TODO: probably not needed anymore
@@ -130,6 +133,12 @@ pub struct NetworkSettings {
     #[serde(default)]
     pub user_agent: Option<String>,
 
+    pub reconnect_initial_interval: Option<u64>,
+    pub reconnect_max_interval: Option<u64>,
+
+    pub max_inbound: Option<usize>,
+    pub max_outbound: Option<usize>,
+
     pub tls: Option<TlsSettings>,
     pub instant_inbound: Option<bool>,
 }
@@ -169,8 +178,14 @@ impl From<Protocol> for protocol::Protocol {
 #[derive(Clone, Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct TlsSettings {
-    pub identity_file: String,
-    pub identity_password: String,
+    #[serde(alias = "identity_file")]
+    pub private_key_file: String,
+    /// Defaults to the private key file, which then has to contain the certificates too.
+    pub certificates_file: Option<String>,
+    /// Password of the PKCS#12 identity file used before. Encrypted private keys are not
+    /// supported, so the config is rejected if this is set.
+    #[serde(alias = "identity_password")]
+    pub private_key_password: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -182,6 +197,16 @@ pub struct ConsensusSettings {
     #[serde(default)]
     pub network: Network,
     pub min_peers: Option<usize>,
+    pub max_reorg_depth: Option<u32>,
+    pub request_timeout: Option<u64>,
+    pub genesis_file: Option<String>,
+    pub checkpoint_file: Option<String>,
+    #[serde(default)]
+    pub trust_checkpoint: bool,
+    #[serde(default)]
+    pub history: HistorySetting,
+    pub retained_epochs: Option<u32>,
+    pub body_cache_size: Option<u32>,
 }
 
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
@@ -195,6 +220,19 @@ impl Default for SyncMode {
     }
 }
 
+/// Whether the node retains the full history or only the history of recent epochs.
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistorySetting {
+    Archive,
+    Pruned,
+}
+impl Default for HistorySetting {
+    fn default() -> Self {
+        HistorySetting::Archive
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("Invalid sync mode: {0}")]
 pub struct SyncModeParseError(String);
@@ -387,6 +425,8 @@ pub struct DatabaseSettings {
     pub size: Option<usize>,
     pub max_dbs: Option<u32>,
     pub max_readers: Option<u32>,
+    pub no_sync: Option<bool>,
+    pub no_meta_sync: Option<bool>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -394,6 +434,32 @@ pub struct DatabaseSettings {
 pub struct MempoolSettings {
     pub filter: Option<MempoolFilterSettings>,
     pub blacklist_limit: Option<usize>,
+    pub size_limit: Option<usize>,
+    #[serde(default)]
+    pub persistent: bool,
+    #[serde(default)]
+    pub ordering: MempoolOrdering,
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum MempoolOrdering {
+    FeePerByte,
+    ArrivalTime,
+}
+impl Default for MempoolOrdering {
+    fn default() -> Self {
+        MempoolOrdering::FeePerByte
+    }
+}
+
+impl From<MempoolOrdering> for Arc<dyn TransactionOrdering> {
+    fn from(ordering: MempoolOrdering) -> Self {
+        match ordering {
+            MempoolOrdering::FeePerByte => Arc::new(FeePerByteOrdering),
+            MempoolOrdering::ArrivalTime => Arc::new(ArrivalTimeOrdering),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -441,6 +507,9 @@ impl From<MempoolSettings> for MempoolConfig {
                 .blacklist_limit
                 .unwrap_or(MempoolFilter::DEFAULT_BLACKLIST_SIZE),
             filter_rules: mempool.filter.map(MempoolRules::from).unwrap_or_default(),
+            size_limit: mempool.size_limit.unwrap_or(Mempool::DEFAULT_SIZE_LIMIT),
+            persistent: mempool.persistent,
+            ordering: mempool.ordering.into(),
         }
     }
 }
@@ -475,4 +544,7 @@ pub struct ValidatorSettings {
     pub voting_key: Option<String>,
     pub fee_key_file: Option<String>,
     pub fee_key: Option<String>,
+    pub aggregation_update_interval: Option<u64>,
+    pub aggregation_timeout: Option<u64>,
+    pub aggregation_stall_timeout: Option<u64>,
 }
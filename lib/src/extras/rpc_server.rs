@@ -55,7 +55,10 @@ pub fn initialize_rpc_server(
         dispatcher.add(MempoolDispatcher::new(mempool));
     }
     if let Some(validator_proxy) = client.validator_proxy() {
-        dispatcher.add(ValidatorDispatcher::new(validator_proxy));
+        dispatcher.add(ValidatorDispatcher::new(
+            validator_proxy,
+            client.blockchain(),
+        ));
     }
     dispatcher.add(wallet_dispatcher);
 
@@ -1,9 +1,14 @@
 use std::path::PathBuf;
 
+use nimiq_database::{lmdb::open as LmdbFlags, ReadTransaction, WriteTransaction};
 use nimiq_lib::config::{
-    config::{ClientConfigBuilder, DatabaseConfig, DatabaseConfigBuilder, FileStorageConfig},
+    config::{
+        ClientConfigBuilder, DatabaseConfig, DatabaseConfigBuilder, FileStorageConfig,
+        StorageConfig, SyncMode,
+    },
     config_file::ConfigFile,
 };
+use nimiq_primitives::networks::NetworkId;
 
 #[test]
 fn config_file_no_db_entry() {
@@ -112,3 +117,239 @@ fn config_file_partial_db_entry() {
 
     assert_eq!(config.storage, db_config.into());
 }
+
+#[test]
+fn config_file_db_flags() {
+    let config_file: ConfigFile = toml::from_str(
+        r#"
+    [database]
+    no_sync = false
+    "#,
+    )
+    .unwrap();
+
+    let mut config_builder = ClientConfigBuilder::default();
+    config_builder.config_file(&config_file).unwrap();
+    let config = config_builder.build().unwrap();
+
+    assert_eq!(
+        config.database,
+        DatabaseConfigBuilder::default()
+            .flags(LmdbFlags::NOMETASYNC)
+            .build()
+            .unwrap()
+    );
+
+    let config_file: ConfigFile = toml::from_str(
+        r#"
+    [database]
+    no_sync = false
+    no_meta_sync = false
+    "#,
+    )
+    .unwrap();
+
+    let mut config_builder = ClientConfigBuilder::default();
+    config_builder.config_file(&config_file).unwrap();
+    let config = config_builder.build().unwrap();
+
+    assert_eq!(
+        config.database,
+        DatabaseConfigBuilder::default()
+            .flags(LmdbFlags::Flags::empty())
+            .build()
+            .unwrap()
+    );
+}
+
+#[test]
+fn database_with_custom_map_size_persists_data() {
+    let path = std::env::temp_dir().join("nimiq-custom-map-size");
+    let _ = std::fs::remove_dir_all(&path);
+    let storage: StorageConfig = FileStorageConfig::from_directory(&path).into();
+    let db_config = DatabaseConfigBuilder::default()
+        .size(64 * 1024 * 1024usize)
+        .flags(LmdbFlags::Flags::empty())
+        .build()
+        .unwrap();
+
+    {
+        let env = storage
+            .database(
+                NetworkId::UnitAlbatross,
+                SyncMode::History,
+                db_config.clone(),
+            )
+            .unwrap();
+        let db = env.open_database("test".to_string());
+        let mut txn = WriteTransaction::new(&env);
+        txn.put_reserve(&db, "key", "value");
+        txn.commit();
+    }
+
+    let env = storage
+        .database(NetworkId::UnitAlbatross, SyncMode::History, db_config)
+        .unwrap();
+    let db = env.open_database("test".to_string());
+    let txn = ReadTransaction::new(&env);
+    assert_eq!(
+        txn.get::<str, String>(&db, "key"),
+        Some("value".to_string())
+    );
+    txn.close();
+
+    std::fs::remove_dir_all(&path).unwrap();
+}
+
+#[test]
+fn database_path_must_be_a_directory() {
+    // A regular file can't be used as the database directory.
+    let path = std::env::temp_dir().join("nimiq-not-a-directory");
+    std::fs::write(&path, b"").unwrap();
+    let storage: StorageConfig = FileStorageConfig::from_directory(&path).into();
+
+    assert!(storage
+        .database(
+            NetworkId::UnitAlbatross,
+            SyncMode::History,
+            DatabaseConfig::default()
+        )
+        .is_err());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "validator")]
+#[test]
+fn config_file_validator_aggregation_timing() {
+    use std::time::Duration;
+
+    let config_file: ConfigFile = toml::from_str(
+        r#"
+    [validator]
+    validator_address = "NQ07 0000 0000 0000 0000 0000 0000 0000 0000"
+    "#,
+    )
+    .unwrap();
+
+    let mut config_builder = ClientConfigBuilder::default();
+    config_builder.config_file(&config_file).unwrap();
+    let validator = config_builder.build().unwrap().validator.unwrap();
+
+    assert_eq!(validator.aggregation_update_interval, None);
+    assert_eq!(validator.aggregation_timeout, None);
+    assert_eq!(validator.aggregation_stall_timeout, None);
+
+    let config_file: ConfigFile = toml::from_str(
+        r#"
+    [validator]
+    validator_address = "NQ07 0000 0000 0000 0000 0000 0000 0000 0000"
+    aggregation_update_interval = 100
+    aggregation_timeout = 1500
+    aggregation_stall_timeout = 5000
+    "#,
+    )
+    .unwrap();
+
+    let mut config_builder = ClientConfigBuilder::default();
+    config_builder.config_file(&config_file).unwrap();
+    let validator = config_builder.build().unwrap().validator.unwrap();
+
+    assert_eq!(
+        validator.aggregation_update_interval,
+        Some(Duration::from_millis(100))
+    );
+    assert_eq!(
+        validator.aggregation_timeout,
+        Some(Duration::from_millis(1500))
+    );
+    assert_eq!(
+        validator.aggregation_stall_timeout,
+        Some(Duration::from_millis(5000))
+    );
+}
+
+const TLS_PRIVATE_KEY_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls/node.key");
+const TLS_CERTIFICATES_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls/node.crt");
+
+#[test]
+fn config_file_tls_from_pem_files() {
+    let config_file: ConfigFile = toml::from_str(&format!(
+        r#"
+    [network]
+    listen_addresses = ["/ip4/0.0.0.0/tcp/8443/wss"]
+
+    [network.tls]
+    private_key_file = "{}"
+    certificates_file = "{}"
+    "#,
+        TLS_PRIVATE_KEY_FILE, TLS_CERTIFICATES_FILE
+    ))
+    .unwrap();
+
+    let mut config_builder = ClientConfigBuilder::default();
+    config_builder.config_file(&config_file).unwrap();
+    let tls = config_builder.build().unwrap().network.tls.unwrap();
+
+    assert!(!tls.private_key.is_empty());
+    assert_eq!(tls.certificates.len(), 1);
+}
+
+#[test]
+fn config_file_tls_with_old_key_names() {
+    // The key and the certificate in a single file, configured with the former key names.
+    let path = std::env::temp_dir().join("nimiq-config-tls-identity.pem");
+    let mut identity = std::fs::read_to_string(TLS_PRIVATE_KEY_FILE).unwrap();
+    identity.push_str(&std::fs::read_to_string(TLS_CERTIFICATES_FILE).unwrap());
+    std::fs::write(&path, identity).unwrap();
+
+    let config_file: ConfigFile = toml::from_str(&format!(
+        r#"
+    [network.tls]
+    identity_file = "{}"
+    "#,
+        path.display()
+    ))
+    .unwrap();
+
+    let mut config_builder = ClientConfigBuilder::default();
+    config_builder.config_file(&config_file).unwrap();
+    let tls = config_builder.build().unwrap().network.tls.unwrap();
+
+    assert!(!tls.private_key.is_empty());
+    assert_eq!(tls.certificates.len(), 1);
+}
+
+#[test]
+fn config_file_tls_rejects_private_key_password() {
+    for key in ["private_key_password", "identity_password"] {
+        let config_file: ConfigFile = toml::from_str(&format!(
+            r#"
+    [network.tls]
+    private_key_file = "{}"
+    certificates_file = "{}"
+    {} = "secret"
+    "#,
+            TLS_PRIVATE_KEY_FILE, TLS_CERTIFICATES_FILE, key
+        ))
+        .unwrap();
+
+        let mut config_builder = ClientConfigBuilder::default();
+        assert!(config_builder.config_file(&config_file).is_err());
+    }
+}
+
+#[test]
+fn config_file_tls_rejects_missing_certificate() {
+    let config_file: ConfigFile = toml::from_str(&format!(
+        r#"
+    [network.tls]
+    private_key_file = "{}"
+    "#,
+        TLS_PRIVATE_KEY_FILE
+    ))
+    .unwrap();
+
+    let mut config_builder = ClientConfigBuilder::default();
+    assert!(config_builder.config_file(&config_file).is_err());
+}
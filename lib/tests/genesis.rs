@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_build_tools::genesis::{GenesisBuilder, GenesisBuilderError};
+use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_genesis::{NetworkId, NetworkInfo};
+use nimiq_utils::time::OffsetTime;
+
+const UNIT_GENESIS_FILE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../genesis/src/genesis/unit-albatross.toml"
+);
+
+#[test]
+fn custom_genesis_matches_compiled_genesis() {
+    let genesis = GenesisBuilder::generate_custom(UNIT_GENESIS_FILE).unwrap();
+    let genesis_hash = NetworkInfo::from_network_id(NetworkId::UnitAlbatross)
+        .genesis_hash()
+        .clone();
+    assert_eq!(genesis.hash, genesis_hash);
+
+    let blockchain = Blockchain::with_genesis(
+        VolatileEnvironment::new(10).unwrap(),
+        Arc::new(OffsetTime::new()),
+        NetworkId::UnitAlbatross,
+        genesis.block,
+        genesis.accounts,
+    )
+    .unwrap();
+    assert_eq!(blockchain.head_hash(), genesis_hash);
+}
+
+#[test]
+fn custom_genesis_requires_timestamp() {
+    let path = std::env::temp_dir().join("nimiq-custom-genesis-no-timestamp.toml");
+    let config = std::fs::read_to_string(UNIT_GENESIS_FILE).unwrap();
+    let config: String = config
+        .lines()
+        .filter(|line| !line.starts_with("timestamp"))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    std::fs::write(&path, config).unwrap();
+
+    assert!(matches!(
+        GenesisBuilder::generate_custom(&path),
+        Err(GenesisBuilderError::NoTimestamp)
+    ));
+    std::fs::remove_file(path).unwrap();
+}
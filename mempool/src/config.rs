@@ -1,4 +1,8 @@
+use std::sync::Arc;
+
 use crate::filter::{MempoolFilter, MempoolRules};
+use crate::mempool::Mempool;
+use crate::ordering::{FeePerByteOrdering, TransactionOrdering};
 
 /// Struct defining a Mempool configuration
 #[derive(Debug, Clone)]
@@ -7,6 +11,13 @@ pub struct MempoolConfig {
     pub filter_rules: MempoolRules,
     /// Mempool filter limit or size
     pub filter_limit: usize,
+    /// Maximum number of transactions in the mempool. Once it is reached, the transactions with
+    /// the lowest fee per byte are evicted to make room for transactions paying a higher fee.
+    pub size_limit: usize,
+    /// Whether pending transactions are kept in the database across restarts
+    pub persistent: bool,
+    /// Order in which pending transactions are included in a block
+    pub ordering: Arc<dyn TransactionOrdering>,
 }
 
 impl Default for MempoolConfig {
@@ -14,6 +25,9 @@ impl Default for MempoolConfig {
         MempoolConfig {
             filter_rules: MempoolRules::default(),
             filter_limit: MempoolFilter::DEFAULT_BLACKLIST_SIZE,
+            size_limit: Mempool::DEFAULT_SIZE_LIMIT,
+            persistent: false,
+            ordering: Arc::new(FeePerByteOrdering),
         }
     }
 }
@@ -8,6 +8,8 @@ use futures::{stream::BoxStream, Future, StreamExt};
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
 
 use nimiq_blockchain::Blockchain;
+use nimiq_collections::LimitHashSet;
+use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_network_interface::network::Network;
 use nimiq_network_interface::prelude::MsgAcceptance;
 use nimiq_primitives::networks::NetworkId;
@@ -19,6 +21,9 @@ use crate::verify::verify_tx;
 
 const CONCURRENT_VERIF_TASKS: u32 = 1000;
 
+/// Number of recently received transactions that are remembered to detect duplicates.
+const SEEN_TRANSACTIONS_LIMIT: usize = 25000;
+
 pub(crate) struct MempoolExecutor<N: Network> {
     // Blockchain reference
     blockchain: Arc<RwLock<Blockchain>>,
@@ -40,6 +45,9 @@ pub(crate) struct MempoolExecutor<N: Network> {
 
     // Transaction stream that is used to listen to transactions from the network
     txn_stream: BoxStream<'static, (Transaction, <N as Network>::PubsubId)>,
+
+    // Hashes of recently received transactions, so that each transaction is only relayed once
+    seen_transactions: LimitHashSet<Blake2bHash>,
 }
 
 impl<N: Network> MempoolExecutor<N> {
@@ -58,6 +66,7 @@ impl<N: Network> MempoolExecutor<N> {
             network_id: Arc::new(blockchain.read().network_id),
             verification_tasks: Arc::new(AtomicU32::new(0)),
             txn_stream,
+            seen_transactions: LimitHashSet::new(SEEN_TRANSACTIONS_LIMIT),
         }
     }
 }
@@ -74,6 +83,24 @@ impl<N: Network> Future for MempoolExecutor<N> {
                 continue;
             }
 
+            // Don't relay transactions that we already received, e.g. from another peer.
+            if !self.seen_transactions.insert(tx.hash()) {
+                log::trace!(
+                    "Ignoring already seen transaction {}",
+                    tx.hash::<Blake2bHash>()
+                );
+                let network = Arc::clone(&self.network);
+                tokio::task::spawn(async move {
+                    if let Err(e) = network
+                        .validate_message(pubsub_id, MsgAcceptance::Ignore)
+                        .await
+                    {
+                        log::trace!("failed to validate_message for tx: {:?}", e);
+                    };
+                });
+                continue;
+            }
+
             let blockchain = Arc::clone(&self.blockchain);
             let mempool_state = Arc::clone(&self.state);
             let filter = Arc::clone(&self.filter);
@@ -89,11 +116,12 @@ impl<N: Network> Future for MempoolExecutor<N> {
                 // the verify_tx function immediately
                 let acceptance = {
                     let verify_tx_ret =
-                        verify_tx(&tx, blockchain, network_id, &mempool_state, filter).await;
+                        verify_tx(&tx, None, blockchain, network_id, &mempool_state, filter).await;
 
                     match verify_tx_ret {
                         Ok(mempool_state_lock) => {
-                            RwLockUpgradableReadGuard::upgrade(mempool_state_lock).put(&tx);
+                            RwLockUpgradableReadGuard::upgrade(mempool_state_lock)
+                                .insert(&tx, None);
                             MsgAcceptance::Accept
                         }
                         Err(_) => MsgAcceptance::Ignore,
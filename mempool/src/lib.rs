@@ -17,5 +17,9 @@ pub mod executor;
 pub mod filter;
 /// Main mempool module
 pub mod mempool;
+/// Mempool transaction ordering module
+pub mod ordering;
+/// Mempool persistence module
+pub mod store;
 /// Verify transaction module
 pub mod verify;
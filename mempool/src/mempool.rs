@@ -1,21 +1,25 @@
-use futures::future::{AbortHandle, Abortable};
+use futures::future::{self, AbortHandle, Abortable};
 use futures::lock::Mutex;
 use futures::stream::BoxStream;
 use keyed_priority_queue::KeyedPriorityQueue;
 use parking_lot::{RwLock, RwLockUpgradableReadGuard};
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use beserial::Serialize;
 use nimiq_account::{Account, BasicAccount};
 use nimiq_block::Block;
 use nimiq_blockchain::{AbstractBlockchain, Blockchain, TransactionVerificationCache};
+use nimiq_database::Environment;
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::Address;
 use nimiq_network_interface::network::{Network, Topic};
 use nimiq_primitives::account::AccountType;
 use nimiq_primitives::coin::Coin;
+use nimiq_primitives::policy;
 use nimiq_transaction::account::staking_contract::{
     IncomingStakingTransactionData, OutgoingStakingTransactionProof,
 };
@@ -24,7 +28,9 @@ use nimiq_transaction::Transaction;
 use crate::config::MempoolConfig;
 use crate::executor::MempoolExecutor;
 use crate::filter::{MempoolFilter, MempoolRules};
-use crate::verify::{verify_tx, VerifyErr};
+use crate::ordering::{PendingTransaction, TransactionOrdering};
+use crate::store::MempoolStore;
+use crate::verify::{validate_tx, verify_tx, TransactionVerdict, VerifyErr};
 
 /// Transaction topic for the Mempool to request transactions from the network
 #[derive(Clone, Debug, Default)]
@@ -38,6 +44,15 @@ impl Topic for TransactionTopic {
     const VALIDATE: bool = true;
 }
 
+/// Outcome of adding a transaction to the mempool
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MempoolInsertion {
+    /// The transaction was added as a new pending transaction
+    Inserted,
+    /// The transaction replaced the pending transaction with the given hash
+    Replaced(Blake2bHash),
+}
+
 /// Struct defining the Mempool
 pub struct Mempool {
     /// Blockchain reference
@@ -51,20 +66,60 @@ pub struct Mempool {
 
     /// Mempool executor handle used to stop the executor
     pub(crate) executor_handle: Mutex<Option<AbortHandle>>,
+
+    /// Store used to persist the pending transactions, if persistence is enabled
+    pub(crate) store: Option<Arc<MempoolStore>>,
+
+    /// Whether the persisted transactions have been restored already
+    pub(crate) restored: AtomicBool,
+
+    /// Order in which pending transactions are included in a block
+    pub(crate) ordering: Arc<dyn TransactionOrdering>,
 }
 
 impl Mempool {
+    /// Default maximum number of transactions in the mempool
+    pub const DEFAULT_SIZE_LIMIT: usize = 4000;
+
+    /// Minimum fee per byte increase (in percent) that a transaction needs to pay in order to
+    /// replace a pending transaction.
+    pub const MIN_REPLACEMENT_FEE_BUMP: u64 = 10;
+
+    /// Interval in which the pending transactions are persisted while the executor is running
+    pub const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// Creates a new mempool whose pending transactions are persisted in the given database
+    /// environment if `config.persistent` is set. The transactions are persisted periodically
+    /// while the executor is running, when it is stopped and when `persist` is called. They are
+    /// loaded back with `restore`.
+    pub fn with_env(
+        blockchain: Arc<RwLock<Blockchain>>,
+        config: MempoolConfig,
+        env: Environment,
+    ) -> Self {
+        let persistent = config.persistent;
+        let mut mempool = Self::new(blockchain, config);
+
+        if persistent {
+            mempool.store = Some(Arc::new(MempoolStore::new(env)));
+        }
+
+        mempool
+    }
+
     /// Creates a new mempool
     pub fn new(blockchain: Arc<RwLock<Blockchain>>, config: MempoolConfig) -> Self {
         let state = MempoolState {
             transactions: HashMap::new(),
-            transactions_by_fee: KeyedPriorityQueue::new(),
+            transactions_by_lowest_fee: KeyedPriorityQueue::new(),
             transactions_by_age: KeyedPriorityQueue::new(),
+            insertion_times: HashMap::new(),
             state_by_sender: HashMap::new(),
             outgoing_validators: HashSet::new(),
             outgoing_stakers: HashSet::new(),
             creating_validators: HashSet::new(),
             creating_stakers: HashSet::new(),
+            size_limit: config.size_limit,
         };
 
         let state = Arc::new(RwLock::new(state));
@@ -77,6 +132,9 @@ impl Mempool {
                 config.filter_limit,
             ))),
             executor_handle: Mutex::new(None),
+            store: None,
+            restored: AtomicBool::new(false),
+            ordering: config.ordering,
         }
     }
 
@@ -103,12 +161,8 @@ impl Mempool {
             txn_stream,
         );
 
-        // Start the executor and obtain its handle
-        let (abort_handle, abort_registration) = AbortHandle::new_pair();
-        tokio::spawn(Abortable::new(mempool_executor, abort_registration));
-
-        // Set the executor handle
-        *executor_handle = Some(abort_handle);
+        // Start the executor and set its handle
+        *executor_handle = Some(self.spawn_executor(mempool_executor));
     }
 
     /// Starts the mempool executor with a custom transaction stream
@@ -136,12 +190,49 @@ impl Mempool {
             txn_stream,
         );
 
-        // Start the executor and obtain its handle
+        // Start the executor and set its handle
+        *executor_handle = Some(self.spawn_executor(mempool_executor));
+    }
+
+    /// Spawns the executor, together with a task that periodically persists the pending
+    /// transactions if persistence is enabled. Both are stopped by the returned handle.
+    fn spawn_executor<N: Network>(&self, mempool_executor: MempoolExecutor<N>) -> AbortHandle {
         let (abort_handle, abort_registration) = AbortHandle::new_pair();
-        tokio::spawn(Abortable::new(mempool_executor, abort_registration));
 
-        // Set the executor handle
-        *executor_handle = Some(abort_handle);
+        match &self.store {
+            Some(store) => {
+                let persist =
+                    Self::persist_periodically(Arc::clone(store), Arc::clone(&self.state));
+                tokio::spawn(Abortable::new(
+                    future::join(mempool_executor, persist),
+                    abort_registration,
+                ));
+            }
+            None => {
+                tokio::spawn(Abortable::new(mempool_executor, abort_registration));
+            }
+        }
+
+        abort_handle
+    }
+
+    async fn persist_periodically(store: Arc<MempoolStore>, state: Arc<RwLock<MempoolState>>) {
+        let mut interval = tokio::time::interval(Self::PERSIST_INTERVAL);
+        // The first tick completes immediately, there is nothing new to persist yet.
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            Self::persist_to(&store, &state);
+        }
+    }
+
+    fn persist_to(store: &MempoolStore, state: &RwLock<MempoolState>) {
+        let transactions: Vec<Transaction> = state.read().transactions.values().cloned().collect();
+        log::debug!("Persisting {} mempool transactions", transactions.len());
+        if let Err(e) = store.put_transactions(&transactions) {
+            log::warn!("Failed to persist mempool transactions: {}", e);
+        }
     }
 
     /// Stops the mempool executor
@@ -160,6 +251,8 @@ impl Mempool {
 
         // Stop the executor
         handle.take().expect("Expected an executor handle").abort();
+
+        self.persist();
     }
 
     /// Stops the mempool executor without TX stream
@@ -178,6 +271,8 @@ impl Mempool {
 
         // Stop the executor
         handle.take().expect("Expected an executor handle").abort();
+
+        self.persist();
     }
 
     /// Updates the mempool given a set of reverted and adopted blocks.
@@ -206,27 +301,7 @@ impl Mempool {
         let block_height = blockchain.block_number() + 1;
 
         // First remove the transactions that are no longer valid due to age.
-        loop {
-            // Get the hash of the oldest transaction.
-            let tx_hash = match mempool_state.transactions_by_age.peek() {
-                None => {
-                    break;
-                }
-                Some((tx_hash, _)) => tx_hash.clone(),
-            };
-
-            // Get a reference to the transaction.
-            let tx = mempool_state.get(&tx_hash).unwrap();
-
-            // Check if it is still valid.
-            if tx.is_valid_at(block_height) {
-                // No need to process more transactions, since we arrived to the oldest one that is valid
-                break;
-            } else {
-                // Remove the transaction from the mempool.
-                mempool_state.remove(&tx_hash);
-            }
-        }
+        mempool_state.remove_expired(block_height);
 
         // Now iterate over the transactions in the adopted blocks:
         //  if transaction was known:
@@ -369,9 +444,17 @@ impl Mempool {
         }
     }
 
+    /// Removes all transactions whose validity window has passed at the given block height. This
+    /// is done automatically for the next block height whenever the mempool is updated with new
+    /// blocks.
+    pub fn remove_expired_transactions(&self, block_height: u32) {
+        self.state.write().remove_expired(block_height);
+    }
+
     /// Returns a vector with accepted transactions from the mempool.
     ///
-    /// Returns the highest fee per byte up to max_bytes transactions and removes them from the mempool
+    /// Returns up to max_bytes transactions in the order given by the configured transaction
+    /// ordering (highest fee per byte first by default) and removes them from the mempool
     pub fn get_transactions_for_block(&self, max_bytes: usize) -> Vec<Transaction> {
         let mut tx_vec = vec![];
 
@@ -382,19 +465,13 @@ impl Mempool {
             return tx_vec;
         }
 
+        let tx_hashes = state.ordered_hashes(self.ordering.as_ref());
+
         let mut size = 0_usize;
 
         let mut mempool_state_upgraded = RwLockUpgradableReadGuard::upgrade(state);
 
-        loop {
-            // Get the hash of the highest paying transaction.
-            let tx_hash = match mempool_state_upgraded.transactions_by_fee.peek() {
-                None => {
-                    break;
-                }
-                Some((tx_hash, _)) => tx_hash.clone(),
-            };
-
+        for tx_hash in tx_hashes {
             // Get the transaction.
             let tx = mempool_state_upgraded.get(&tx_hash).unwrap().clone();
 
@@ -422,24 +499,101 @@ impl Mempool {
         tx_vec
     }
 
-    /// Adds a transaction to the Mempool.
-    pub async fn add_transaction(&self, transaction: Transaction) -> Result<(), VerifyErr> {
+    /// Adds a transaction to the Mempool. The transaction never replaces a pending transaction,
+    /// see `replace_transaction` for that.
+    pub async fn add_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<MempoolInsertion, VerifyErr> {
+        self.insert_transaction(transaction, None).await
+    }
+
+    /// Adds a transaction to the Mempool that replaces the pending transaction with the given
+    /// hash. The pending transaction needs to be from the same sender and have the same validity
+    /// start height, and the replacement has to pay at least `MIN_REPLACEMENT_FEE_BUMP` percent
+    /// more fee per byte.
+    ///
+    /// Transactions don't have a nonce, so the replacement only takes effect in this mempool:
+    /// peers that already received the replaced transaction keep it and it can still be included
+    /// in a block.
+    pub async fn replace_transaction(
+        &self,
+        transaction: Transaction,
+        replaced_hash: &Blake2bHash,
+    ) -> Result<MempoolInsertion, VerifyErr> {
+        self.insert_transaction(transaction, Some(replaced_hash))
+            .await
+    }
+
+    async fn insert_transaction(
+        &self,
+        transaction: Transaction,
+        replaced_hash: Option<&Blake2bHash>,
+    ) -> Result<MempoolInsertion, VerifyErr> {
         let blockchain = Arc::clone(&self.blockchain);
         let mempool_state = Arc::clone(&self.state);
         let filter = Arc::clone(&self.filter);
         let network_id = Arc::new(blockchain.read().network_id);
-        let verify_tx_ret =
-            verify_tx(&transaction, blockchain, network_id, &mempool_state, filter).await;
+        let verify_tx_ret = verify_tx(
+            &transaction,
+            replaced_hash,
+            blockchain,
+            network_id,
+            &mempool_state,
+            filter,
+        )
+        .await;
 
         match verify_tx_ret {
-            Ok(mempool_state_lock) => {
-                RwLockUpgradableReadGuard::upgrade(mempool_state_lock).put(&transaction);
-                Ok(())
-            }
+            Ok(mempool_state_lock) => Ok(RwLockUpgradableReadGuard::upgrade(mempool_state_lock)
+                .insert(&transaction, replaced_hash)),
             Err(e) => Err(e),
         }
     }
 
+    /// Validates a transaction against the current state of the blockchain without adding it to
+    /// the Mempool.
+    pub fn validate_transaction(&self, transaction: &Transaction) -> TransactionVerdict {
+        validate_tx(transaction, &self.blockchain.read())
+    }
+
+    /// Writes the pending transactions to the database, replacing the previously persisted ones.
+    /// Does nothing if persistence is disabled. This needs to be called before shutting down.
+    pub fn persist(&self) {
+        if let Some(store) = &self.store {
+            Self::persist_to(store, &self.state);
+        }
+    }
+
+    /// Loads the persisted transactions and adds them back to the mempool. Every transaction is
+    /// verified again, so the ones that are no longer valid against the current accounts state
+    /// are discarded and the persisted transactions are replaced by the restored ones. The
+    /// transactions are only restored on the first call. Returns the number of restored
+    /// transactions.
+    pub async fn restore(&self) -> usize {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return 0,
+        };
+        if self.restored.swap(true, AtomicOrdering::SeqCst) {
+            return 0;
+        }
+        let transactions = store.get_transactions();
+
+        let mut restored = 0;
+        for transaction in transactions {
+            match self.add_transaction(transaction).await {
+                Ok(_) => restored += 1,
+                Err(e) => log::debug!("Discarding persisted mempool transaction: {}", e),
+            }
+        }
+
+        log::debug!("Restored {} mempool transactions", restored);
+        self.persist();
+
+        restored
+    }
+
     /// Checks whether a transaction has been filtered
     pub fn is_filtered(&self, hash: &Blake2bHash) -> bool {
         self.filter.read().blacklisted(hash)
@@ -450,6 +604,19 @@ impl Mempool {
         self.filter.read().rules.clone()
     }
 
+    /// Returns the minimum fee per byte that a transaction currently needs to pay to be accepted
+    /// into the mempool. This is the fee per byte floor of the mempool rules, unless the mempool
+    /// is full, in which case a transaction needs to pay more than the lowest paying transaction
+    /// in the mempool.
+    pub fn get_min_fee_per_byte(&self) -> f64 {
+        let floor = self.filter.read().rules.tx_fee_per_byte;
+
+        match self.state.read().lowest_fee_per_byte() {
+            Some(lowest_fee) => floor.max(lowest_fee),
+            None => floor,
+        }
+    }
+
     /// Checks if a transactions is in the mempool, by its hash.
     pub fn contains_transaction_by_hash(&self, hash: &Blake2bHash) -> bool {
         self.state.read().contains(hash)
@@ -460,6 +627,18 @@ impl Mempool {
         self.state.read().get(hash).cloned()
     }
 
+    /// Gets a transaction by its hash, together with the time it has been pending in the mempool.
+    pub fn get_pending_transaction(&self, hash: &Blake2bHash) -> Option<(Transaction, Duration)> {
+        let state = self.state.read();
+        let tx = state.get(hash)?.clone();
+        let pending_time = state
+            .insertion_times
+            .get(hash)
+            .map(|inserted| inserted.elapsed())
+            .unwrap_or_default();
+        Some((tx, pending_time))
+    }
+
     /// Gets all transaction hashes in the mempool.
     pub fn get_transaction_hashes(&self) -> Vec<Blake2bHash> {
         self.state.read().transactions.keys().cloned().collect()
@@ -486,11 +665,14 @@ pub(crate) struct MempoolState {
     // A hashmap containing the transactions indexed by their hash.
     pub(crate) transactions: HashMap<Blake2bHash, Transaction>,
 
-    // Transactions ordered by fee (higher fee transactions pop first)
-    pub(crate) transactions_by_fee: KeyedPriorityQueue<Blake2bHash, FeeWrapper>,
+    // Transactions ordered by fee (lower fee transactions pop first), used for eviction
+    pub(crate) transactions_by_lowest_fee: KeyedPriorityQueue<Blake2bHash, Reverse<FeeWrapper>>,
 
     // Transactions ordered by age (older transactions pop first)
-    pub(crate) transactions_by_age: KeyedPriorityQueue<Blake2bHash, u32>,
+    pub(crate) transactions_by_age: KeyedPriorityQueue<Blake2bHash, Reverse<u32>>,
+
+    // The time at which each transaction was added to the mempool
+    pub(crate) insertion_times: HashMap<Blake2bHash, Instant>,
 
     // The in-fly balance per sender
     pub(crate) state_by_sender: HashMap<Address, SenderPendingState>,
@@ -506,6 +688,9 @@ pub(crate) struct MempoolState {
     // sure that the creation staking transactions do not interfere with one another.
     pub(crate) creating_validators: HashSet<Address>,
     pub(crate) creating_stakers: HashSet<Address>,
+
+    // The maximum number of transactions in the mempool
+    pub(crate) size_limit: usize,
 }
 
 impl MempoolState {
@@ -517,6 +702,80 @@ impl MempoolState {
         self.transactions.get(hash)
     }
 
+    /// Returns the pending transaction with the given hash if it can be replaced by the given
+    /// transaction, i.e. if it is from the same sender and has the same validity start height.
+    pub fn get_replaceable(
+        &self,
+        tx: &Transaction,
+        replaced_hash: &Blake2bHash,
+    ) -> Option<&Transaction> {
+        self.transactions.get(replaced_hash).filter(|pending_tx| {
+            pending_tx.sender == tx.sender
+                && pending_tx.validity_start_height == tx.validity_start_height
+        })
+    }
+
+    /// Returns the hashes of all pending transactions in the given order.
+    pub fn ordered_hashes(&self, ordering: &dyn TransactionOrdering) -> Vec<Blake2bHash> {
+        let now = Instant::now();
+        let mut pending: Vec<PendingTransaction> = self
+            .transactions
+            .iter()
+            .map(|(hash, transaction)| PendingTransaction {
+                transaction,
+                hash,
+                inserted: self.insertion_times.get(hash).cloned().unwrap_or(now),
+            })
+            .collect();
+
+        pending.sort_by(|a, b| ordering.compare(a, b));
+
+        pending
+            .into_iter()
+            .map(|pending_tx| pending_tx.hash.clone())
+            .collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.transactions.len() >= self.size_limit
+    }
+
+    /// Returns the lowest fee per byte in the mempool if the mempool is full, i.e. the fee per
+    /// byte that a new transaction needs to exceed in order to be accepted.
+    pub fn lowest_fee_per_byte(&self) -> Option<f64> {
+        if !self.is_full() {
+            return None;
+        }
+
+        self.transactions_by_lowest_fee
+            .peek()
+            .map(|(_, Reverse(fee))| fee.0)
+    }
+
+    /// Adds a verified transaction to the mempool, replacing the pending transaction with the
+    /// given hash, if any.
+    pub(crate) fn insert(
+        &mut self,
+        tx: &Transaction,
+        replaced_hash: Option<&Blake2bHash>,
+    ) -> MempoolInsertion {
+        if let Some(replaced_hash) = replaced_hash {
+            log::debug!(
+                "Transaction {} replaces transaction {}",
+                tx.hash::<Blake2bHash>(),
+                replaced_hash
+            );
+            self.remove(replaced_hash);
+        }
+
+        self.put(tx);
+
+        match replaced_hash {
+            Some(replaced_hash) => MempoolInsertion::Replaced(replaced_hash.clone()),
+            None => MempoolInsertion::Inserted,
+        }
+    }
+
     pub(crate) fn put(&mut self, tx: &Transaction) -> bool {
         let tx_hash = tx.hash();
 
@@ -526,11 +785,13 @@ impl MempoolState {
 
         self.transactions.insert(tx_hash.clone(), tx.clone());
 
-        self.transactions_by_fee
-            .push(tx_hash.clone(), FeeWrapper(tx.fee_per_byte()));
+        self.transactions_by_lowest_fee
+            .push(tx_hash.clone(), Reverse(FeeWrapper(tx.fee_per_byte())));
 
         self.transactions_by_age
-            .push(tx_hash.clone(), tx.validity_start_height);
+            .push(tx_hash.clone(), Reverse(tx.validity_start_height));
+
+        self.insertion_times.insert(tx_hash.clone(), Instant::now());
 
         match self.state_by_sender.get_mut(&tx.sender) {
             None => {
@@ -586,14 +847,44 @@ impl MempoolState {
             }
         }
 
+        // If the mempool is over its limit, evict the transactions with the lowest fee.
+        while self.transactions.len() > self.size_limit {
+            let lowest_hash = match self.transactions_by_lowest_fee.peek() {
+                None => break,
+                Some((hash, _)) => hash.clone(),
+            };
+
+            log::debug!("Mempool is full, evicting transaction {}", lowest_hash);
+            self.remove(&lowest_hash);
+        }
+
         true
     }
 
+    /// Removes the transactions that are no longer valid at the given block height due to age.
+    pub(crate) fn remove_expired(&mut self, block_height: u32) {
+        // Get the hash of the oldest transaction.
+        while let Some((tx_hash, _)) = self.transactions_by_age.peek() {
+            let tx_hash = tx_hash.clone();
+
+            // No need to process more transactions once we arrive at the oldest one that is
+            // still valid. Transactions that aren't valid yet are kept as well.
+            let tx = self.get(&tx_hash).unwrap();
+            if block_height < tx.validity_start_height + policy::TRANSACTION_VALIDITY_WINDOW {
+                break;
+            }
+
+            log::debug!("Removing expired transaction {}", tx_hash);
+            self.remove(&tx_hash);
+        }
+    }
+
     pub(crate) fn remove(&mut self, tx_hash: &Blake2bHash) -> Option<Transaction> {
         let tx = self.transactions.remove(tx_hash)?;
 
         self.transactions_by_age.remove(tx_hash);
-        self.transactions_by_fee.remove(tx_hash);
+        self.transactions_by_lowest_fee.remove(tx_hash);
+        self.insertion_times.remove(tx_hash);
 
         let sender_state = self.state_by_sender.get_mut(&tx.sender).unwrap();
 
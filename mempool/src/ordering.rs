@@ -0,0 +1,66 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::Instant;
+
+use nimiq_hash::Blake2bHash;
+use nimiq_transaction::Transaction;
+
+/// A pending transaction, as it is seen by a transaction ordering strategy
+pub struct PendingTransaction<'a> {
+    /// The transaction
+    pub transaction: &'a Transaction,
+    /// The hash of the transaction
+    pub hash: &'a Blake2bHash,
+    /// The time at which the transaction was added to the mempool
+    pub inserted: Instant,
+}
+
+/// Strategy that determines the order in which pending transactions are taken from the mempool
+/// when producing a block.
+///
+/// Any closure comparing two pending transactions can be used as a strategy as well.
+pub trait TransactionOrdering: Send + Sync {
+    /// Compares two pending transactions. Transactions that compare as `Less` are included in a
+    /// block first.
+    fn compare(&self, a: &PendingTransaction, b: &PendingTransaction) -> Ordering;
+}
+
+impl<F> TransactionOrdering for F
+where
+    F: Fn(&PendingTransaction, &PendingTransaction) -> Ordering + Send + Sync,
+{
+    fn compare(&self, a: &PendingTransaction, b: &PendingTransaction) -> Ordering {
+        self(a, b)
+    }
+}
+
+impl fmt::Debug for dyn TransactionOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TransactionOrdering")
+    }
+}
+
+/// Orders transactions by fee per byte, highest first. Transactions paying the same fee per byte
+/// are ordered by their hash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FeePerByteOrdering;
+
+impl TransactionOrdering for FeePerByteOrdering {
+    fn compare(&self, a: &PendingTransaction, b: &PendingTransaction) -> Ordering {
+        b.transaction
+            .fee_per_byte()
+            .total_cmp(&a.transaction.fee_per_byte())
+            .then_with(|| a.hash.cmp(b.hash))
+    }
+}
+
+/// Orders transactions by the time they were added to the mempool, oldest first. Transactions
+/// added at the same time are ordered by their hash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ArrivalTimeOrdering;
+
+impl TransactionOrdering for ArrivalTimeOrdering {
+    fn compare(&self, a: &PendingTransaction, b: &PendingTransaction) -> Ordering {
+        a.inserted.cmp(&b.inserted).then_with(|| a.hash.cmp(b.hash))
+    }
+}
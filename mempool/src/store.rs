@@ -0,0 +1,72 @@
+use beserial::{Deserialize, Serialize};
+use nimiq_database::cursor::{ReadCursor, WriteCursor};
+use nimiq_database::{Database, DatabaseError, Environment, ReadTransaction, WriteTransaction};
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_transaction::Transaction;
+
+/// Database backed store for the pending transactions of the mempool, used to keep them across
+/// restarts.
+#[derive(Debug)]
+pub struct MempoolStore {
+    env: Environment,
+    // A database of serialized transactions indexed by their transaction hashes.
+    tx_db: Database,
+}
+
+impl MempoolStore {
+    const TX_DB_NAME: &'static str = "MempoolTransactions";
+
+    /// Creates a new mempool store in the given database environment
+    pub fn new(env: Environment) -> Self {
+        let tx_db = env.open_database(Self::TX_DB_NAME.to_string());
+
+        MempoolStore { env, tx_db }
+    }
+
+    /// Replaces the stored transactions with the given ones. Fails if the database can't be
+    /// written to, in which case the previously stored transactions are kept.
+    pub fn put_transactions(&self, transactions: &[Transaction]) -> Result<(), DatabaseError> {
+        let mut txn = WriteTransaction::new(&self.env);
+
+        Self::clear(&mut txn, &self.tx_db);
+
+        for transaction in transactions {
+            txn.put(
+                &self.tx_db,
+                &transaction.hash::<Blake2bHash>(),
+                &transaction.serialize_to_vec(),
+            );
+        }
+
+        txn.try_commit()
+    }
+
+    /// Returns all stored transactions. Entries that can't be deserialized are skipped.
+    pub fn get_transactions(&self) -> Vec<Transaction> {
+        let txn = ReadTransaction::new(&self.env);
+        let mut cursor = txn.cursor(&self.tx_db);
+
+        let mut transactions = vec![];
+        let mut pos: Option<(Blake2bHash, Vec<u8>)> = cursor.first();
+
+        while let Some((hash, bytes)) = pos {
+            match Transaction::deserialize_from_vec(&bytes) {
+                Ok(transaction) => transactions.push(transaction),
+                Err(e) => log::warn!("Failed to deserialize stored transaction {}: {}", hash, e),
+            }
+            pos = cursor.next();
+        }
+
+        transactions
+    }
+
+    fn clear(txn: &mut WriteTransaction, tx_db: &Database) {
+        let mut cursor = txn.write_cursor(tx_db);
+        let mut pos: Option<(Blake2bHash, Vec<u8>)> = cursor.first();
+
+        while pos.is_some() {
+            cursor.remove();
+            pos = cursor.next();
+        }
+    }
+}
@@ -5,19 +5,21 @@ use std::{
     sync::Arc,
 };
 
-use nimiq_account::{Account, BasicAccount, StakingContract};
+use beserial::Serialize;
+use nimiq_account::{Account, AccountError, BasicAccount, StakingContract};
 use nimiq_blockchain::{AbstractBlockchain, Blockchain};
-use nimiq_hash::Hash;
+use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_primitives::account::AccountType;
 use nimiq_primitives::coin::Coin;
+use nimiq_primitives::policy;
 use nimiq_transaction::account::staking_contract::{
     IncomingStakingTransactionData, OutgoingStakingTransactionProof,
 };
 
-use nimiq_transaction::Transaction;
+use nimiq_transaction::{Transaction, TransactionError};
 
 use crate::filter::MempoolFilter;
-use crate::mempool::MempoolState;
+use crate::mempool::{Mempool, MempoolState};
 
 /// Return codes for transaction signature verification
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -39,6 +41,12 @@ pub enum VerifyErr {
     Known,
     /// Transaction is filtered
     Filtered,
+    /// Transaction would replace a pending transaction but doesn't pay enough fee
+    ReplacementFeeTooLow,
+    /// The transaction to be replaced is not pending or can't be replaced by this transaction
+    NotReplaceable,
+    /// Transaction exceeds the maximum transaction size
+    TransactionTooLarge,
 }
 
 impl Display for VerifyErr {
@@ -56,6 +64,103 @@ impl Display for VerifyErr {
             VerifyErr::Filtered => {
                 write!(f, "Filtered")
             }
+            VerifyErr::ReplacementFeeTooLow => {
+                write!(f, "Replacement fee too low")
+            }
+            VerifyErr::NotReplaceable => {
+                write!(f, "Not replaceable")
+            }
+            VerifyErr::TransactionTooLarge => {
+                write!(f, "Transaction too large")
+            }
+        }
+    }
+}
+
+/// Verdicts for validating a transaction against the current chain state, without adding it to
+/// the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionVerdict {
+    /// Transaction would be accepted into the next block
+    Valid,
+    /// Transaction signature or proof is invalid
+    InvalidSignature,
+    /// Sender doesn't have enough funds
+    InsufficientBalance,
+    /// The validity start height of the transaction is in the future
+    NotYetValid,
+    /// The validity window of the transaction has passed
+    Expired,
+    /// Transaction has already been included in the validity window
+    AlreadyIncluded,
+    /// Transaction exceeds the maximum transaction size
+    TooLarge,
+    /// Transaction is invalid for any other reason
+    Invalid,
+}
+
+/// Checks whether a transaction is within its validity window at the given block height.
+pub fn check_validity_window(transaction: &Transaction, block_height: u32) -> TransactionVerdict {
+    if block_height < transaction.validity_start_height {
+        TransactionVerdict::NotYetValid
+    } else if !transaction.is_valid_at(block_height) {
+        TransactionVerdict::Expired
+    } else {
+        TransactionVerdict::Valid
+    }
+}
+
+/// Validates a transaction against the current state of the blockchain.
+///
+/// Balances and account specific rules are checked by committing the transaction to the accounts
+/// tree in a transaction that is aborted afterwards. Pending transactions in the mempool are not
+/// taken into account.
+pub fn validate_tx(transaction: &Transaction, blockchain: &Blockchain) -> TransactionVerdict {
+    // 1. Check the size of the transaction
+    if transaction.serialized_size() > policy::MAX_SIZE_TRANSACTION {
+        return TransactionVerdict::TooLarge;
+    }
+
+    // 2. Verify transaction signature (and other stuff)
+    match transaction.verify(blockchain.network_id) {
+        Ok(()) => {}
+        Err(TransactionError::InvalidProof) => return TransactionVerdict::InvalidSignature,
+        Err(err) => {
+            log::debug!("Intrinsic tx verification failed {:?}", err);
+            return TransactionVerdict::Invalid;
+        }
+    }
+
+    // 3. Check validity window and already included
+    let block_height = blockchain.block_number() + 1;
+
+    let verdict = check_validity_window(transaction, block_height);
+    if verdict != TransactionVerdict::Valid {
+        return verdict;
+    }
+
+    if blockchain.contains_tx_in_validity_window(&transaction.hash(), None) {
+        return TransactionVerdict::AlreadyIncluded;
+    }
+
+    // 4. Dry-run the transaction on the accounts tree
+    let result = blockchain.state().accounts.get_root_with(
+        &[transaction.clone()],
+        &[],
+        block_height,
+        blockchain.timestamp(),
+    );
+
+    match result {
+        Ok(_) => TransactionVerdict::Valid,
+        Err(AccountError::InsufficientFunds { .. }) => TransactionVerdict::InsufficientBalance,
+        Err(AccountError::NonExistentAddress { address }) if address == transaction.sender => {
+            TransactionVerdict::InsufficientBalance
+        }
+        Err(AccountError::InvalidSignature) => TransactionVerdict::InvalidSignature,
+        Err(err) => {
+            log::debug!("Transaction failed to commit to the accounts {:?}", err);
+            TransactionVerdict::Invalid
         }
     }
 }
@@ -66,14 +171,23 @@ impl Display for VerifyErr {
 /// returns a result of a RwLockUpgradableReadGuard of the mempool such that in
 /// case of an accepted transaction (`Ok(RwLockUpgradableReadGuard)`), the
 /// caller can upgrade the lock and add the transaction to the mempool.
+///
+/// If `replaced_hash` is given, the transaction is verified as a replacement of the pending
+/// transaction with that hash.
 pub(crate) async fn verify_tx<'a>(
     transaction: &Transaction,
+    replaced_hash: Option<&Blake2bHash>,
     blockchain: Arc<RwLock<Blockchain>>,
     network_id: Arc<NetworkId>,
     mempool_state: &'a Arc<RwLock<MempoolState>>,
     filter: Arc<RwLock<MempoolFilter>>,
 ) -> Result<RwLockUpgradableReadGuard<'a, MempoolState>, VerifyErr> {
-    // 1. Verify transaction signature (and other stuff)
+    // 1. Check the size of the transaction
+    if transaction.serialized_size() > policy::MAX_SIZE_TRANSACTION {
+        return Err(VerifyErr::TransactionTooLarge);
+    }
+
+    // 2. Verify transaction signature (and other stuff)
     let mut tx = transaction.clone();
 
     let sign_verification_handle = tokio::task::spawn_blocking(move || {
@@ -97,17 +211,53 @@ pub(crate) async fn verify_tx<'a>(
         }
     };
 
-    // 2. Acquire the mempool state upgradable read lock
+    // 3. Acquire the mempool state upgradable read lock
     let blockchain = blockchain.read();
     let mempool_state = mempool_state.upgradable_read();
 
-    // 3. Check if we already know the transaction
+    // 4. Check if we already know the transaction
     if mempool_state.contains(&transaction.hash()) {
         // We already know this transaction, no need to process
         return Err(VerifyErr::Known);
     }
 
-    // 4. Check if the transaction is going to be filtered.
+    // 5. Check if the transaction can replace the given pending transaction and pays enough to
+    //    do so. Otherwise, check if the mempool is full and the transaction doesn't pay enough
+    //    to evict the lowest paying one.
+    let replaced_total = match replaced_hash {
+        Some(replaced_hash) => {
+            let pending_tx = match mempool_state.get_replaceable(transaction, replaced_hash) {
+                Some(pending_tx) => pending_tx,
+                None => {
+                    log::debug!("Transaction can't replace transaction {}", replaced_hash);
+                    return Err(VerifyErr::NotReplaceable);
+                }
+            };
+
+            let min_fee_per_byte = pending_tx.fee_per_byte()
+                * (100 + Mempool::MIN_REPLACEMENT_FEE_BUMP) as f64
+                / 100.0;
+
+            if transaction.fee <= pending_tx.fee || transaction.fee_per_byte() < min_fee_per_byte {
+                log::debug!("Transaction doesn't pay enough fee to replace a pending transaction");
+                return Err(VerifyErr::ReplacementFeeTooLow);
+            }
+
+            pending_tx.total_value()
+        }
+        None => {
+            if let Some(lowest_fee_per_byte) = mempool_state.lowest_fee_per_byte() {
+                if transaction.fee_per_byte() <= lowest_fee_per_byte {
+                    log::debug!("Transaction filtered: Mempool is full and fee is too low");
+                    return Err(VerifyErr::Filtered);
+                }
+            }
+
+            Coin::ZERO
+        }
+    };
+
+    // 6. Check if the transaction is going to be filtered.
     {
         let filter = filter.read();
         if !filter.accepts_transaction(transaction) || filter.blacklisted(&transaction.hash()) {
@@ -116,9 +266,9 @@ pub(crate) async fn verify_tx<'a>(
         }
     }
 
-    // 5. Acquire Blockchain read lock
+    // 7. Acquire Blockchain read lock
 
-    // 6. Check Validity Window and already included
+    // 8. Check Validity Window and already included
     let block_height = blockchain.block_number() + 1;
 
     if !transaction.is_valid_at(block_height) {
@@ -131,7 +281,7 @@ pub(crate) async fn verify_tx<'a>(
         return Err(VerifyErr::Invalid);
     }
 
-    // 7. Sequentialize per Sender to Check Balances and acquire the upgradable from the blockchain.
+    // 9. Sequentialize per Sender to Check Balances and acquire the upgradable from the blockchain.
     //    Perform all balances checks.
     let sender_account = match blockchain.get_account(&transaction.sender).or_else(|| {
         if transaction.total_value() != Coin::ZERO {
@@ -152,7 +302,7 @@ pub(crate) async fn verify_tx<'a>(
         Some(account) => account,
     };
 
-    // 8. Get recipient account to later check against filter rules.
+    // 10. Get recipient account to later check against filter rules.
     let recipient_account = match blockchain.get_account(&transaction.recipient) {
         None => Account::Basic(BasicAccount {
             balance: Coin::ZERO,
@@ -231,7 +381,7 @@ pub(crate) async fn verify_tx<'a>(
         }
     }
 
-    // 9. Drop the blockchain lock since it is no longer needed
+    // 11. Drop the blockchain lock since it is no longer needed
     drop(blockchain);
 
     let blockchain_sender_balance = sender_account.balance();
@@ -242,7 +392,8 @@ pub(crate) async fn verify_tx<'a>(
     let mut recipient_current_balance = blockchain_recipient_balance;
 
     if let Some(sender_state) = mempool_state.state_by_sender.get(&transaction.sender) {
-        sender_current_balance = sender_state.total;
+        // The replaced transaction (if any) is removed when this one is added.
+        sender_current_balance = sender_state.total - replaced_total;
     }
 
     if let Some(recipient_state) = mempool_state.state_by_sender.get(&transaction.recipient) {
@@ -8,19 +8,25 @@ use rand::SeedableRng;
 
 use beserial::{Deserialize, Serialize};
 use nimiq_block::{Block, MicroBlock, MicroBody, MicroHeader};
-use nimiq_blockchain::Blockchain;
+use nimiq_blockchain::{Blockchain, ExtendedTransaction};
 use nimiq_bls::KeyPair as BlsKeyPair;
 use nimiq_build_tools::genesis::GenesisBuilder;
 use nimiq_database::volatile::VolatileEnvironment;
+use nimiq_database::Environment;
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::{
     Address, KeyPair as SchnorrKeyPair, PublicKey as SchnorrPublicKey, SecureGenerate,
 };
 use nimiq_mempool::config::MempoolConfig;
-use nimiq_mempool::mempool::Mempool;
+use nimiq_mempool::filter::MempoolRules;
+use nimiq_mempool::mempool::{Mempool, MempoolInsertion};
+use nimiq_mempool::verify::{check_validity_window, TransactionVerdict, VerifyErr};
+use nimiq_network_interface::network::MsgAcceptance;
 use nimiq_network_mock::{MockHub, MockId, MockNetwork, MockPeerId};
+use nimiq_primitives::account::AccountType;
 use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
+use nimiq_primitives::policy;
 use nimiq_transaction::{SignatureProof, Transaction};
 use nimiq_utils::time::OffsetTime;
 use nimiq_vrf::VrfSeed;
@@ -573,6 +579,643 @@ async fn mempool_get_txn_ordered() {
     }
 }
 
+#[tokio::test]
+async fn mempool_evicts_lowest_fee_when_full() {
+    if ENABLE_LOG {
+        simple_logger::SimpleLogger::new()
+            .with_level(Debug)
+            .init()
+            .ok();
+    }
+
+    // Generate and sign transaction from an address
+    let mut rng = StdRng::seed_from_u64(0);
+    let balance = 40;
+    let num_txns = 3;
+    let size_limit = 2;
+    let mut mempool_transactions = vec![];
+    let sender_balances = vec![balance + num_txns * 3; 1];
+    let recipient_balances = vec![0; num_txns as usize];
+    let mut genesis_builder = GenesisBuilder::default();
+
+    // Generate recipient accounts
+    let recipient_accounts = generate_accounts(recipient_balances, &mut genesis_builder, false);
+    // Generate sender accounts
+    let sender_accounts = generate_accounts(sender_balances, &mut genesis_builder, true);
+
+    // Generate transactions
+    for i in 0..num_txns {
+        let mempool_transaction = MempoolTransaction {
+            fee: (i + 1) as u64,
+            value: balance / num_txns,
+            recipient: recipient_accounts[i as usize].clone(),
+            sender: sender_accounts[0].clone(),
+        };
+        mempool_transactions.push(mempool_transaction);
+    }
+    let (txns, _) = generate_transactions(mempool_transactions);
+
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+
+    // Add a validator to genesis
+    genesis_builder.with_genesis_validator(
+        Address::from(&SchnorrKeyPair::generate(&mut rng)),
+        SchnorrPublicKey::from([0u8; 32]),
+        BlsKeyPair::generate(&mut rng).public_key,
+        Address::default(),
+    );
+
+    let genesis_info = genesis_builder.generate().unwrap();
+
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::with_genesis(
+            env.clone(),
+            time,
+            NetworkId::UnitAlbatross,
+            genesis_info.block,
+            genesis_info.accounts,
+        )
+        .unwrap(),
+    ));
+
+    let mempool_config = MempoolConfig {
+        size_limit,
+        ..Default::default()
+    };
+    let mempool = Mempool::new(Arc::clone(&blockchain), mempool_config);
+
+    // Fill the mempool with the two lowest paying transactions
+    mempool.add_transaction(txns[0].clone()).await.unwrap();
+    mempool.add_transaction(txns[1].clone()).await.unwrap();
+    assert_eq!(mempool.num_transactions(), size_limit);
+
+    // The mempool is full, so a transaction needs to pay more than the lowest fee
+    assert_eq!(
+        mempool.get_min_fee_per_byte(),
+        txns[0].fee_per_byte(),
+        "Min fee per byte should be the lowest fee in a full mempool"
+    );
+
+    // A higher paying transaction evicts the lowest paying one
+    mempool.add_transaction(txns[2].clone()).await.unwrap();
+    assert_eq!(mempool.num_transactions(), size_limit);
+    assert!(!mempool.contains_transaction_by_hash(&txns[0].hash()));
+    assert!(mempool.contains_transaction_by_hash(&txns[1].hash()));
+    assert!(mempool.contains_transaction_by_hash(&txns[2].hash()));
+
+    // The evicted transaction doesn't pay enough to get back in
+    assert!(mempool.add_transaction(txns[0].clone()).await.is_err());
+    assert_eq!(mempool.num_transactions(), size_limit);
+}
+
+#[tokio::test]
+async fn min_fee_per_byte_rises_when_mempool_fills() {
+    // Generate and sign transaction from an address
+    let mut rng = StdRng::seed_from_u64(0);
+    let balance = 40;
+    let num_txns = 3;
+    let size_limit = 2;
+    let mut mempool_transactions = vec![];
+    let sender_balances = vec![balance + num_txns * 3; 1];
+    let recipient_balances = vec![0; num_txns as usize];
+    let mut genesis_builder = GenesisBuilder::default();
+
+    // Generate recipient accounts
+    let recipient_accounts = generate_accounts(recipient_balances, &mut genesis_builder, false);
+    // Generate sender accounts
+    let sender_accounts = generate_accounts(sender_balances, &mut genesis_builder, true);
+
+    // Generate transactions with increasing fees
+    for i in 0..num_txns {
+        let mempool_transaction = MempoolTransaction {
+            fee: (i + 1) as u64,
+            value: balance / num_txns,
+            recipient: recipient_accounts[i as usize].clone(),
+            sender: sender_accounts[0].clone(),
+        };
+        mempool_transactions.push(mempool_transaction);
+    }
+    let (txns, _) = generate_transactions(mempool_transactions);
+
+    let time = Arc::new(OffsetTime::new());
+    let env = VolatileEnvironment::new(10).unwrap();
+
+    // Add a validator to genesis
+    genesis_builder.with_genesis_validator(
+        Address::from(&SchnorrKeyPair::generate(&mut rng)),
+        SchnorrPublicKey::from([0u8; 32]),
+        BlsKeyPair::generate(&mut rng).public_key,
+        Address::default(),
+    );
+
+    let genesis_info = genesis_builder.generate().unwrap();
+
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::with_genesis(
+            env.clone(),
+            time,
+            NetworkId::UnitAlbatross,
+            genesis_info.block,
+            genesis_info.accounts,
+        )
+        .unwrap(),
+    ));
+
+    // A static floor below the fees of all transactions
+    let floor = txns[0].fee_per_byte() / 2.0;
+    let mempool_config = MempoolConfig {
+        size_limit,
+        filter_rules: MempoolRules {
+            tx_fee_per_byte: floor,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mempool = Mempool::new(Arc::clone(&blockchain), mempool_config);
+
+    // As long as the mempool isn't full, the static floor applies
+    assert_eq!(mempool.get_min_fee_per_byte(), floor);
+    mempool.add_transaction(txns[0].clone()).await.unwrap();
+    assert_eq!(mempool.get_min_fee_per_byte(), floor);
+
+    // Once it is full, the lowest fee in the mempool is the floor
+    mempool.add_transaction(txns[1].clone()).await.unwrap();
+    let congested_min_fee = mempool.get_min_fee_per_byte();
+    assert_eq!(congested_min_fee, txns[0].fee_per_byte());
+    assert!(congested_min_fee > floor);
+
+    // Evicting the lowest paying transaction raises the floor further
+    mempool.add_transaction(txns[2].clone()).await.unwrap();
+    assert_eq!(mempool.get_min_fee_per_byte(), txns[1].fee_per_byte());
+    assert!(mempool.get_min_fee_per_byte() > congested_min_fee);
+}
+
+fn create_signed_transaction(
+    sender: &MempoolAccount,
+    recipient: &MempoolAccount,
+    value: u64,
+    fee: u64,
+    validity_start_height: u32,
+) -> Transaction {
+    let mut txn = Transaction::new_basic(
+        sender.address.clone(),
+        recipient.address.clone(),
+        Coin::from_u64_unchecked(value),
+        Coin::from_u64_unchecked(fee),
+        validity_start_height,
+        NetworkId::UnitAlbatross,
+    );
+
+    let signature_proof = SignatureProof::from(
+        sender.keypair.public,
+        sender.keypair.sign(&txn.serialize_content()),
+    );
+
+    txn.proof = signature_proof.serialize_to_vec();
+    txn
+}
+
+/// Creates a signed extended transaction whose data is padded so that the serialized transaction
+/// has exactly the given size.
+fn create_signed_transaction_with_size(
+    sender: &MempoolAccount,
+    recipient: &MempoolAccount,
+    size: usize,
+) -> Transaction {
+    let sign = |data: Vec<u8>| {
+        let mut txn = Transaction::new_extended(
+            sender.address.clone(),
+            AccountType::Basic,
+            recipient.address.clone(),
+            AccountType::Basic,
+            Coin::from_u64_unchecked(100),
+            Coin::from_u64_unchecked(10),
+            data,
+            1,
+            NetworkId::UnitAlbatross,
+        );
+
+        let signature_proof = SignatureProof::from(
+            sender.keypair.public,
+            sender.keypair.sign(&txn.serialize_content()),
+        );
+
+        txn.proof = signature_proof.serialize_to_vec();
+        txn
+    };
+
+    // The size grows linearly with the data, so measure the size with a single byte of data first.
+    let base_size = sign(vec![0]).serialized_size();
+    let txn = sign(vec![0; size - base_size + 1]);
+    assert_eq!(txn.serialized_size(), size);
+    txn
+}
+
+fn single_sender_blockchain(
+    env: Environment,
+) -> (Arc<RwLock<Blockchain>>, MempoolAccount, MempoolAccount) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut genesis_builder = GenesisBuilder::default();
+
+    let recipient = generate_accounts(vec![0], &mut genesis_builder, false).remove(0);
+    let sender = generate_accounts(vec![1000], &mut genesis_builder, true).remove(0);
+
+    let time = Arc::new(OffsetTime::new());
+
+    // Add a validator to genesis
+    genesis_builder.with_genesis_validator(
+        Address::from(&SchnorrKeyPair::generate(&mut rng)),
+        SchnorrPublicKey::from([0u8; 32]),
+        BlsKeyPair::generate(&mut rng).public_key,
+        Address::default(),
+    );
+
+    let genesis_info = genesis_builder.generate().unwrap();
+
+    let blockchain = Arc::new(RwLock::new(
+        Blockchain::with_genesis(
+            env,
+            time,
+            NetworkId::UnitAlbatross,
+            genesis_info.block,
+            genesis_info.accounts,
+        )
+        .unwrap(),
+    ));
+
+    (blockchain, sender, recipient)
+}
+
+fn replacement_test_mempool() -> (Mempool, MempoolAccount, MempoolAccount) {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let (blockchain, sender, recipient) = single_sender_blockchain(env);
+
+    let mempool = Mempool::new(blockchain, MempoolConfig::default());
+
+    (mempool, sender, recipient)
+}
+
+#[tokio::test]
+async fn replace_tx_with_higher_fee() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    let replacement = create_signed_transaction(&sender, &recipient, 100, 20, 1);
+
+    assert_eq!(
+        mempool.add_transaction(txn.clone()).await,
+        Ok(MempoolInsertion::Inserted)
+    );
+    assert_eq!(
+        mempool
+            .replace_transaction(replacement.clone(), &txn.hash())
+            .await,
+        Ok(MempoolInsertion::Replaced(txn.hash()))
+    );
+
+    // Only the replacement is left in the mempool
+    assert_eq!(mempool.num_transactions(), 1);
+    assert!(!mempool.contains_transaction_by_hash(&txn.hash()));
+    assert!(mempool.contains_transaction_by_hash(&replacement.hash()));
+}
+
+#[tokio::test]
+async fn replace_tx_with_insufficient_fee_bump() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 100, 1);
+    // A 5% fee increase is below the minimum fee bump
+    let replacement = create_signed_transaction(&sender, &recipient, 100, 105, 1);
+
+    assert_eq!(
+        mempool.add_transaction(txn.clone()).await,
+        Ok(MempoolInsertion::Inserted)
+    );
+    assert_eq!(
+        mempool
+            .replace_transaction(replacement.clone(), &txn.hash())
+            .await,
+        Err(VerifyErr::ReplacementFeeTooLow)
+    );
+
+    // The original transaction is still in the mempool
+    assert_eq!(mempool.num_transactions(), 1);
+    assert!(mempool.contains_transaction_by_hash(&txn.hash()));
+    assert!(!mempool.contains_transaction_by_hash(&replacement.hash()));
+}
+
+#[tokio::test]
+async fn get_pending_transaction() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    assert!(mempool.get_pending_transaction(&txn.hash()).is_none());
+
+    mempool.add_transaction(txn.clone()).await.unwrap();
+    let (pending_txn, pending_time) = mempool.get_pending_transaction(&txn.hash()).unwrap();
+    assert_eq!(pending_txn, txn);
+    assert!(pending_time < std::time::Duration::from_secs(60));
+
+    // A replaced transaction isn't pending anymore.
+    let replacement = create_signed_transaction(&sender, &recipient, 100, 20, 1);
+    mempool
+        .replace_transaction(replacement.clone(), &txn.hash())
+        .await
+        .unwrap();
+    assert!(mempool.get_pending_transaction(&txn.hash()).is_none());
+    assert!(mempool
+        .get_pending_transaction(&replacement.hash())
+        .is_some());
+}
+
+#[tokio::test]
+async fn no_replacement_for_different_validity_start_height() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    let other_txn = create_signed_transaction(&sender, &recipient, 100, 20, 0);
+
+    assert_eq!(
+        mempool.add_transaction(txn.clone()).await,
+        Ok(MempoolInsertion::Inserted)
+    );
+    assert_eq!(
+        mempool
+            .replace_transaction(other_txn.clone(), &txn.hash())
+            .await,
+        Err(VerifyErr::NotReplaceable)
+    );
+
+    // The original transaction is still in the mempool
+    assert_eq!(mempool.num_transactions(), 1);
+    assert!(mempool.contains_transaction_by_hash(&txn.hash()));
+    assert!(!mempool.contains_transaction_by_hash(&other_txn.hash()));
+}
+
+#[tokio::test]
+async fn distinct_payments_with_different_fees_are_not_replaced() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    // Two payments of the same value to the same recipient that only differ in their fee
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    let other_txn = create_signed_transaction(&sender, &recipient, 100, 20, 1);
+
+    assert_eq!(
+        mempool.add_transaction(txn.clone()).await,
+        Ok(MempoolInsertion::Inserted)
+    );
+    assert_eq!(
+        mempool.add_transaction(other_txn.clone()).await,
+        Ok(MempoolInsertion::Inserted)
+    );
+
+    // Both payments are in the mempool
+    assert_eq!(mempool.num_transactions(), 2);
+    assert!(mempool.contains_transaction_by_hash(&txn.hash()));
+    assert!(mempool.contains_transaction_by_hash(&other_txn.hash()));
+}
+
+#[tokio::test]
+async fn reject_tx_outside_validity_window() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 100);
+    assert_eq!(
+        mempool.add_transaction(txn.clone()).await,
+        Err(VerifyErr::Invalid)
+    );
+    assert!(!mempool.contains_transaction_by_hash(&txn.hash()));
+}
+
+#[tokio::test]
+async fn reject_tx_exceeding_max_size() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn =
+        create_signed_transaction_with_size(&sender, &recipient, policy::MAX_SIZE_TRANSACTION);
+    assert_eq!(
+        mempool.add_transaction(txn.clone()).await,
+        Ok(MempoolInsertion::Inserted)
+    );
+
+    let too_large_txn =
+        create_signed_transaction_with_size(&sender, &recipient, policy::MAX_SIZE_TRANSACTION + 1);
+    assert_eq!(
+        mempool.validate_transaction(&too_large_txn),
+        TransactionVerdict::TooLarge
+    );
+    assert_eq!(
+        mempool.add_transaction(too_large_txn.clone()).await,
+        Err(VerifyErr::TransactionTooLarge)
+    );
+    assert!(mempool.contains_transaction_by_hash(&txn.hash()));
+    assert!(!mempool.contains_transaction_by_hash(&too_large_txn.hash()));
+}
+
+#[tokio::test]
+async fn relay_tx_received_twice_only_once() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+
+    let mut hub = MockHub::new();
+    let mock_network = Arc::new(hub.new_network());
+    let peer1 = hub.new_address().into();
+    let peer2 = hub.new_address().into();
+
+    // Receive the same transaction from two different peers.
+    let (mut txn_stream_tx, txn_stream_rx) = mpsc::channel(64);
+    mempool
+        .start_executor_with_txn_stream::<MockNetwork>(
+            Box::pin(txn_stream_rx),
+            Arc::clone(&mock_network),
+        )
+        .await;
+    for peer in [peer1, peer2] {
+        txn_stream_tx
+            .send((txn.clone(), MockId::new(peer)))
+            .await
+            .unwrap();
+    }
+
+    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    mempool.stop_executor_without_unsuscribe().await;
+
+    // Only the first copy is accepted and thus relayed, the second one is ignored.
+    let validated_messages = mock_network.validated_messages();
+    assert_eq!(validated_messages.len(), 2);
+    let accepted: Vec<MockPeerId> = validated_messages
+        .into_iter()
+        .filter(|(_, acceptance)| matches!(acceptance, MsgAcceptance::Accept))
+        .map(|(peer, _)| peer)
+        .collect();
+    assert_eq!(accepted, vec![peer1]);
+    assert!(mempool.contains_transaction_by_hash(&txn.hash()));
+}
+
+#[tokio::test]
+async fn remove_expired_transactions() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let old_txn = create_signed_transaction(&sender, &recipient, 100, 10, 0);
+    let new_txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    mempool.add_transaction(new_txn.clone()).await.unwrap();
+    mempool.add_transaction(old_txn.clone()).await.unwrap();
+
+    // Only the transaction with the lower validity start height has expired.
+    mempool.remove_expired_transactions(policy::TRANSACTION_VALIDITY_WINDOW);
+    assert!(!mempool.contains_transaction_by_hash(&old_txn.hash()));
+    assert!(mempool.contains_transaction_by_hash(&new_txn.hash()));
+    assert!(mempool.get_pending_transaction(&old_txn.hash()).is_none());
+
+    mempool.remove_expired_transactions(1 + policy::TRANSACTION_VALIDITY_WINDOW);
+    assert_eq!(mempool.num_transactions(), 0);
+}
+
+#[test]
+fn validate_valid_tx() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+
+    assert_eq!(
+        mempool.validate_transaction(&txn),
+        TransactionVerdict::Valid
+    );
+    // Validating doesn't add the transaction to the mempool
+    assert_eq!(mempool.num_transactions(), 0);
+}
+
+#[test]
+fn validate_tx_with_wrong_signature() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let mut txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    // Sign the transaction with the key of the recipient instead
+    txn.proof = SignatureProof::from(
+        recipient.keypair.public,
+        recipient.keypair.sign(&txn.serialize_content()),
+    )
+    .serialize_to_vec();
+
+    assert_eq!(
+        mempool.validate_transaction(&txn),
+        TransactionVerdict::InvalidSignature
+    );
+}
+
+#[test]
+fn validate_tx_with_insufficient_balance() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 1000, 10, 1);
+    assert_eq!(
+        mempool.validate_transaction(&txn),
+        TransactionVerdict::InsufficientBalance
+    );
+
+    // The recipient doesn't have an account
+    let txn = create_signed_transaction(&recipient, &sender, 100, 10, 1);
+    assert_eq!(
+        mempool.validate_transaction(&txn),
+        TransactionVerdict::InsufficientBalance
+    );
+}
+
+#[test]
+fn validate_tx_outside_validity_window() {
+    let (mempool, sender, recipient) = replacement_test_mempool();
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 100);
+    assert_eq!(
+        mempool.validate_transaction(&txn),
+        TransactionVerdict::NotYetValid
+    );
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    assert_eq!(check_validity_window(&txn, 1), TransactionVerdict::Valid);
+    assert_eq!(
+        check_validity_window(&txn, 1 + policy::TRANSACTION_VALIDITY_WINDOW),
+        TransactionVerdict::Expired
+    );
+}
+
+#[test]
+fn validate_tx_already_included() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let (blockchain, sender, recipient) = single_sender_blockchain(env);
+
+    let txn = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+
+    // Add the transaction to the history, as if it was included in the genesis block
+    {
+        let blockchain = blockchain.read();
+        let mut db_txn = blockchain.write_transaction();
+        blockchain.history_store.add_to_history(
+            &mut db_txn,
+            0,
+            &ExtendedTransaction::from(NetworkId::UnitAlbatross, 0, 0, vec![txn.clone()], vec![]),
+        );
+        db_txn.commit();
+    }
+
+    let mempool = Mempool::new(blockchain, MempoolConfig::default());
+    assert_eq!(
+        mempool.validate_transaction(&txn),
+        TransactionVerdict::AlreadyIncluded
+    );
+}
+
+#[tokio::test]
+async fn persist_and_restore_mempool() {
+    let env = VolatileEnvironment::new(11).unwrap();
+    let (blockchain, sender, recipient) = single_sender_blockchain(env.clone());
+
+    let mempool_config = MempoolConfig {
+        persistent: true,
+        ..Default::default()
+    };
+
+    let txn1 = create_signed_transaction(&sender, &recipient, 100, 10, 1);
+    let txn2 = create_signed_transaction(&sender, &recipient, 200, 10, 1);
+
+    // Dropping the mempool doesn't persist anything
+    let mempool = Mempool::with_env(Arc::clone(&blockchain), mempool_config.clone(), env.clone());
+    mempool.add_transaction(txn1.clone()).await.unwrap();
+    drop(mempool);
+
+    let mempool = Mempool::with_env(Arc::clone(&blockchain), mempool_config.clone(), env.clone());
+    assert_eq!(mempool.restore().await, 0);
+    drop(mempool);
+
+    // Pending transactions are persisted explicitly
+    let mempool = Mempool::with_env(Arc::clone(&blockchain), mempool_config.clone(), env.clone());
+    mempool.add_transaction(txn1.clone()).await.unwrap();
+    mempool.add_transaction(txn2.clone()).await.unwrap();
+    mempool.persist();
+    drop(mempool);
+
+    // A new mempool starts empty and restores the persisted transactions, but only once
+    let mempool = Mempool::with_env(Arc::clone(&blockchain), mempool_config.clone(), env.clone());
+    assert_eq!(mempool.num_transactions(), 0);
+    assert_eq!(mempool.restore().await, 2);
+    assert!(mempool.contains_transaction_by_hash(&txn1.hash()));
+    assert!(mempool.contains_transaction_by_hash(&txn2.hash()));
+    assert_eq!(mempool.restore().await, 0);
+    assert_eq!(mempool.num_transactions(), 2);
+    drop(mempool);
+
+    // The restored transactions replaced the persisted ones
+    let mempool = Mempool::with_env(Arc::clone(&blockchain), mempool_config, env.clone());
+    assert_eq!(mempool.restore().await, 2);
+    drop(mempool);
+
+    // A mempool without persistence doesn't restore anything
+    let mempool = Mempool::with_env(blockchain, MempoolConfig::default(), env);
+    assert_eq!(mempool.restore().await, 0);
+    assert_eq!(mempool.num_transactions(), 0);
+}
+
 #[tokio::test]
 async fn push_tx_with_insufficient_balance() {
     if ENABLE_LOG {
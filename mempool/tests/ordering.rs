@@ -0,0 +1,92 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+use nimiq_hash::{Blake2bHash, Hash};
+use nimiq_keys::Address;
+use nimiq_mempool::ordering::{
+    ArrivalTimeOrdering, FeePerByteOrdering, PendingTransaction, TransactionOrdering,
+};
+use nimiq_primitives::coin::Coin;
+use nimiq_primitives::networks::NetworkId;
+use nimiq_transaction::Transaction;
+
+/// A sample transaction together with its hash and the offset of its arrival time.
+struct Sample {
+    tx: Transaction,
+    hash: Blake2bHash,
+    arrival: u64,
+}
+
+fn sample(sender: u8, value: u64, fee: u64, arrival: u64) -> Sample {
+    let tx = Transaction::new_basic(
+        Address::from([sender; Address::SIZE]),
+        Address::from([213u8; Address::SIZE]),
+        Coin::try_from(value).unwrap(),
+        Coin::try_from(fee).unwrap(),
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    let hash = tx.hash();
+    Sample { tx, hash, arrival }
+}
+
+/// Sorts the samples with the given strategy and returns the senders in the resulting order.
+fn order(samples: &[Sample], ordering: &dyn TransactionOrdering) -> Vec<u8> {
+    let start = Instant::now();
+    let mut pending: Vec<PendingTransaction> = samples
+        .iter()
+        .map(|sample| PendingTransaction {
+            transaction: &sample.tx,
+            hash: &sample.hash,
+            inserted: start + Duration::from_secs(sample.arrival),
+        })
+        .collect();
+
+    pending.sort_by(|a, b| ordering.compare(a, b));
+
+    pending
+        .iter()
+        .map(|pending_tx| pending_tx.transaction.sender.as_bytes()[0])
+        .collect()
+}
+
+fn samples() -> Vec<Sample> {
+    vec![
+        sample(1, 300, 100, 0),
+        sample(2, 100, 300, 1),
+        sample(3, 200, 200, 2),
+    ]
+}
+
+#[test]
+fn fee_per_byte_ordering_takes_highest_fee_first() {
+    assert_eq!(order(&samples(), &FeePerByteOrdering), vec![2, 3, 1]);
+}
+
+#[test]
+fn fee_per_byte_ordering_breaks_ties_by_hash() {
+    let samples = vec![sample(1, 100, 100, 0), sample(2, 100, 100, 1)];
+    let mut expected = vec![(&samples[0].hash, 1), (&samples[1].hash, 2)];
+    expected.sort();
+    let expected: Vec<u8> = expected.into_iter().map(|(_, sender)| sender).collect();
+
+    assert_eq!(order(&samples, &FeePerByteOrdering), expected);
+}
+
+#[test]
+fn arrival_time_ordering_takes_oldest_first() {
+    assert_eq!(order(&samples(), &ArrivalTimeOrdering), vec![1, 2, 3]);
+
+    let mut samples = samples();
+    samples[0].arrival = 3;
+    assert_eq!(order(&samples, &ArrivalTimeOrdering), vec![2, 3, 1]);
+}
+
+#[test]
+fn custom_ordering_is_used() {
+    let by_value = |a: &PendingTransaction, b: &PendingTransaction| -> Ordering {
+        b.transaction.value.cmp(&a.transaction.value)
+    };
+    assert_eq!(order(&samples(), &by_value), vec![1, 3, 2]);
+}
@@ -1,15 +1,17 @@
+use ark_ff::Zero;
 use ark_mnt4_753::Fr as MNT4Fr;
 use ark_mnt6_753::constraints::{FqVar, G1Var, G2Var};
 use ark_mnt6_753::{Fq, G1Projective, G2Projective};
 use ark_r1cs_std::prelude::{AllocVar, Boolean, CondSelectGadget, CurveVar, EqGadget};
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 
-use nimiq_bls::pedersen::pedersen_generators;
-use nimiq_nano_primitives::{PK_TREE_BREADTH, PK_TREE_DEPTH};
+use nimiq_bls::pedersen::{pedersen_generators, pedersen_hash};
+use nimiq_bls::utils::{byte_to_le_bits, bytes_to_bits};
+use nimiq_nano_primitives::{serialize_g1_mnt6, serialize_g2_mnt6, PK_TREE_BREADTH, PK_TREE_DEPTH};
 use nimiq_primitives::policy::SLOTS;
 
 use crate::gadgets::mnt4::{MerkleTreeGadget, PedersenHashGadget, SerializeGadget};
-use crate::utils::unpack_inputs;
+use crate::utils::{pack_inputs, unpack_inputs};
 
 /// This is the leaf subcircuit of the PKTreeCircuit. This circuit main function is to process the
 /// validator's public keys and "return" the aggregate public key for the Macro Block. At a
@@ -66,6 +68,78 @@ impl PKTreeLeafCircuit {
             path,
         }
     }
+
+    /// Builds the circuit for the leaf at the given position of the PK tree. It takes the full list
+    /// of public keys, the Merkle proof nodes for the leaf, the PK tree root (in bytes) and the full
+    /// signer's bitmap, and computes all of the public inputs for the circuit (the packed PK tree
+    /// root, the aggregate public key commitment for the leaf's chunk, the signer's bitmap chunk
+    /// and the path).
+    pub fn from_leaf(
+        position: usize,
+        pks: &[G2Projective],
+        pk_tree_nodes: &[G1Projective],
+        pk_tree_root: &[u8],
+        signer_bitmap: &[bool],
+    ) -> Self {
+        let chunk_start = position * SLOTS as usize / PK_TREE_BREADTH;
+
+        let chunk_end = (position + 1) * SLOTS as usize / PK_TREE_BREADTH;
+
+        // Calculate the aggregate public key commitment.
+        let mut agg_pk = G2Projective::zero();
+
+        for (pk, signed) in pks[chunk_start..chunk_end]
+            .iter()
+            .zip(&signer_bitmap[chunk_start..chunk_end])
+        {
+            if *signed {
+                agg_pk += pk;
+            }
+        }
+
+        let agg_pk_bits = bytes_to_bits(&serialize_g2_mnt6(&agg_pk));
+
+        let hash = pedersen_hash(agg_pk_bits, pedersen_generators(5));
+
+        let agg_pk_comm = bytes_to_bits(&serialize_g1_mnt6(&hash));
+
+        // Get the relevant chunk of the signer's bitmap.
+        let signer_bitmap_chunk = &signer_bitmap[chunk_start..chunk_end];
+
+        // Calculate inputs.
+        let pk_tree_root = pack_inputs(bytes_to_bits(pk_tree_root));
+
+        let agg_pk_commitment = pack_inputs(agg_pk_comm);
+
+        let signer_bitmap_chunk: Fq = pack_inputs(signer_bitmap_chunk.to_vec()).pop().unwrap();
+
+        let path: Fq = pack_inputs(byte_to_le_bits(position as u8)).pop().unwrap();
+
+        Self::new(
+            pks[chunk_start..chunk_end].to_vec(),
+            pk_tree_nodes.to_vec(),
+            pk_tree_root,
+            agg_pk_commitment,
+            signer_bitmap_chunk,
+            path,
+        )
+    }
+
+    /// Returns the public inputs of the circuit, in the same order in which they are allocated.
+    /// This is what needs to be given to the verifier together with the proof.
+    pub fn inputs(&self) -> Vec<Fq> {
+        let mut inputs = vec![];
+
+        inputs.extend_from_slice(&self.pk_tree_root);
+
+        inputs.extend_from_slice(&self.agg_pk_commitment);
+
+        inputs.push(self.signer_bitmap_chunk);
+
+        inputs.push(self.path);
+
+        inputs
+    }
 }
 
 impl ConstraintSynthesizer<MNT4Fr> for PKTreeLeafCircuit {
@@ -152,3 +226,98 @@ impl ConstraintSynthesizer<MNT4Fr> for PKTreeLeafCircuit {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::{test_rng, UniformRand};
+
+    use nimiq_nano_primitives::{merkle_tree_construct, merkle_tree_prove};
+
+    use super::*;
+
+    /// Creates random public keys, a signer's bitmap in which every other validator signed and the
+    /// PK tree (root and Merkle proof nodes) for the leaf at the given position.
+    fn pk_tree(position: usize) -> (Vec<G2Projective>, Vec<bool>, Vec<u8>, Vec<G1Projective>) {
+        let rng = &mut test_rng();
+
+        let pks: Vec<G2Projective> = (0..SLOTS).map(|_| G2Projective::rand(rng)).collect();
+
+        let signer_bitmap: Vec<bool> = (0..SLOTS).map(|i| i % 2 == 0).collect();
+
+        // Serialize the public keys into bits and chunk them into the number of leaves.
+        let mut bytes = Vec::new();
+
+        for pk in &pks {
+            bytes.extend_from_slice(&serialize_g2_mnt6(pk));
+        }
+
+        let bits = bytes_to_bits(&bytes);
+
+        let mut pks_bits = Vec::new();
+
+        for i in 0..PK_TREE_BREADTH {
+            pks_bits.push(
+                bits[i * bits.len() / PK_TREE_BREADTH..(i + 1) * bits.len() / PK_TREE_BREADTH]
+                    .to_vec(),
+            );
+        }
+
+        // Calculate the PK tree root and the Merkle proof for the leaf.
+        let pk_tree_root = merkle_tree_construct(pks_bits.clone());
+
+        let mut path = byte_to_le_bits(position as u8);
+
+        path.truncate(PK_TREE_DEPTH);
+
+        let pk_tree_nodes = merkle_tree_prove(pks_bits, path);
+
+        (pks, signer_bitmap, pk_tree_root, pk_tree_nodes)
+    }
+
+    // This test takes a very long time to finish, so run it only when necessary.
+    #[test]
+    #[ignore]
+    fn pk_tree_leaf_circuit_works() {
+        let position = 3;
+
+        let (pks, signer_bitmap, pk_tree_root, pk_tree_nodes) = pk_tree(position);
+
+        let circuit = PKTreeLeafCircuit::from_leaf(
+            position,
+            &pks,
+            &pk_tree_nodes,
+            &pk_tree_root,
+            &signer_bitmap,
+        );
+
+        let inputs = circuit.inputs();
+
+        // Generate the constraints and check that they are satisfied.
+        let cs = ConstraintSystem::<MNT4Fr>::new_ref();
+
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(cs.is_satisfied().unwrap());
+
+        // The public inputs are allocated in the order given by `inputs`, after the constant one.
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], inputs[..]);
+    }
+
+    // This test takes a very long time to finish, so run it only when necessary.
+    #[test]
+    #[ignore]
+    fn pk_tree_leaf_circuit_rejects_wrong_position() {
+        let (pks, signer_bitmap, pk_tree_root, pk_tree_nodes) = pk_tree(3);
+
+        // Build the circuit for another leaf, but with the Merkle proof for leaf 3.
+        let circuit =
+            PKTreeLeafCircuit::from_leaf(4, &pks, &pk_tree_nodes, &pk_tree_root, &signer_bitmap);
+
+        let cs = ConstraintSystem::<MNT4Fr>::new_ref();
+
+        circuit.generate_constraints(cs.clone()).unwrap();
+
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -68,6 +68,8 @@ mod tests {
     use nimiq_bls::utils::bytes_to_bits;
     use nimiq_nano_primitives::{serialize_g1_mnt6, serialize_g2_mnt6};
 
+    use crate::gadgets::test_utils::ConstraintCounter;
+
     use super::*;
 
     #[test]
@@ -89,8 +91,12 @@ mod tests {
         let primitive_bits = bytes_to_bits(&primitive_bytes);
 
         // Serialize using the gadget version.
+        let mut counter = ConstraintCounter::new(cs.clone());
+
         let gadget_bits = SerializeGadget::serialize_g1(cs, &g1_point_var).unwrap();
 
+        counter.assert_constraints("serialize_g1", 4_916);
+
         // Compare the two versions bit by bit.
         assert_eq!(primitive_bits.len(), gadget_bits.len());
         for i in 0..primitive_bits.len() {
@@ -117,8 +123,12 @@ mod tests {
         let primitive_bits = bytes_to_bits(&primitive_bytes);
 
         // Serialize using the gadget version.
+        let mut counter = ConstraintCounter::new(cs.clone());
+
         let gadget_bits = SerializeGadget::serialize_g2(cs, &g2_point_var).unwrap();
 
+        counter.assert_constraints("serialize_g2", 14_774);
+
         // Compare the two versions bit by bit.
         assert_eq!(primitive_bits.len(), gadget_bits.len());
         for i in 0..primitive_bits.len() {
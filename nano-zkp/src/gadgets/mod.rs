@@ -4,3 +4,6 @@
 pub mod mnt4;
 pub mod mnt6;
 pub mod y_to_bit;
+
+#[cfg(test)]
+pub(crate) mod test_utils;
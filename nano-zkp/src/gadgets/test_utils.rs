@@ -0,0 +1,41 @@
+//! This module contains helpers that are only meant to be used in the tests of the gadgets.
+
+use ark_ff::PrimeField;
+use ark_relations::r1cs::ConstraintSystemRef;
+
+/// Keeps track of the number of constraints in a constraint system, so that tests can assert that
+/// a gadget stays within a declared constraint budget. Each assertion checks the number of
+/// constraints added since the last checkpoint and then moves the checkpoint forward.
+pub struct ConstraintCounter<F: PrimeField> {
+    cs: ConstraintSystemRef<F>,
+    checkpoint: usize,
+}
+
+impl<F: PrimeField> ConstraintCounter<F> {
+    /// Creates a new counter with the checkpoint set at the current number of constraints.
+    pub fn new(cs: ConstraintSystemRef<F>) -> Self {
+        let checkpoint = cs.num_constraints();
+
+        Self { cs, checkpoint }
+    }
+
+    /// Asserts that the number of constraints added since the last checkpoint doesn't exceed the
+    /// given budget. Returns the number of constraints that were added.
+    pub fn assert_constraints(&mut self, label: &str, budget: usize) -> usize {
+        let current = self.cs.num_constraints();
+
+        let delta = current - self.checkpoint;
+
+        assert!(
+            delta <= budget,
+            "{} used {} constraints, but its budget is {}",
+            label,
+            delta,
+            budget
+        );
+
+        self.checkpoint = current;
+
+        delta
+    }
+}
@@ -295,47 +295,12 @@ impl NanoZKP {
 
         let proving_key = ProvingKey::deserialize_unchecked(&mut file)?;
 
-        // Calculate the aggregate public key commitment.
-        let mut agg_pk = G2MNT6::zero();
-
-        for i in position * SLOTS as usize / PK_TREE_BREADTH
-            ..(position + 1) * SLOTS as usize / PK_TREE_BREADTH
-        {
-            if signer_bitmap[i] {
-                agg_pk += pks[i];
-            }
-        }
-
-        let agg_pk_bits = bytes_to_bits(&serialize_g2_mnt6(&agg_pk));
-
-        let hash = pedersen_hash(agg_pk_bits, pedersen_generators(5));
-
-        let agg_pk_comm = bytes_to_bits(&serialize_g1_mnt6(&hash));
-
-        // Get the relevant chunk of the signer's bitmap.
-        let signer_bitmap_chunk = &signer_bitmap[position * SLOTS as usize / PK_TREE_BREADTH
-            ..(position + 1) * SLOTS as usize / PK_TREE_BREADTH];
-
-        // Calculate inputs.
-        let mut pk_tree_root = pack_inputs(bytes_to_bits(pk_tree_root));
-
-        let mut agg_pk_commitment = pack_inputs(agg_pk_comm);
-
-        let signer_bitmap_chunk: MNT4Fr = pack_inputs(signer_bitmap_chunk.to_vec()).pop().unwrap();
-
-        let path: MNT4Fr = pack_inputs(byte_to_le_bits(position as u8)).pop().unwrap();
-
         // Create the circuit.
-        let circuit = LeafMNT4::new(
-            pks[position * SLOTS as usize / PK_TREE_BREADTH
-                ..(position + 1) * SLOTS as usize / PK_TREE_BREADTH]
-                .to_vec(),
-            pk_tree_nodes.to_vec(),
-            pk_tree_root.clone(),
-            agg_pk_commitment.clone(),
-            signer_bitmap_chunk,
-            path,
-        );
+        let circuit =
+            LeafMNT4::from_leaf(position, pks, pk_tree_nodes, pk_tree_root, signer_bitmap);
+
+        // Get the public inputs before the circuit is consumed by the prover.
+        let inputs = circuit.inputs();
 
         // Create the proof.
         let proof = Groth16::<MNT4_753>::prove(&proving_key, circuit, rng)?;
@@ -347,17 +312,6 @@ impl NanoZKP {
 
             let verifying_key = VerifyingKey::deserialize_unchecked(&mut file)?;
 
-            // Prepare the inputs.
-            let mut inputs = vec![];
-
-            inputs.append(&mut pk_tree_root);
-
-            inputs.append(&mut agg_pk_commitment);
-
-            inputs.push(signer_bitmap_chunk);
-
-            inputs.push(path);
-
             // Verify proof.
             assert!(Groth16::<MNT4_753>::verify(
                 &verifying_key,
@@ -1,4 +1,4 @@
-use std::{pin::Pin, sync::Arc};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
 
 use async_trait::async_trait;
 use futures::{
@@ -7,6 +7,7 @@ use futures::{
     task::{Context, Poll},
     Stream, StreamExt, TryFutureExt,
 };
+use pin_project::pin_project;
 use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 
 use beserial::{Deserialize, Serialize};
@@ -17,6 +18,9 @@ use crate::peer::*;
 pub enum NetworkEvent<P> {
     PeerJoined(Arc<P>),
     PeerLeft(Arc<P>),
+    /// All peers were lost and the network attempts to reconnect. Contains the number of the
+    /// attempt, starting at 0.
+    Reconnecting(u32),
 }
 
 pub trait Topic {
@@ -32,6 +36,12 @@ impl<P: Peer> std::fmt::Debug for NetworkEvent<P> {
         let (event_name, peer) = match self {
             NetworkEvent::PeerJoined(peer) => ("PeerJoined", peer),
             NetworkEvent::PeerLeft(peer) => ("PeerLeft", peer),
+            NetworkEvent::Reconnecting(attempt) => {
+                return f
+                    .debug_struct("Reconnecting")
+                    .field("attempt", attempt)
+                    .finish()
+            }
         };
 
         f.debug_struct(event_name)
@@ -45,6 +55,7 @@ impl<P> Clone for NetworkEvent<P> {
         match self {
             NetworkEvent::PeerJoined(peer) => NetworkEvent::PeerJoined(Arc::clone(peer)),
             NetworkEvent::PeerLeft(peer) => NetworkEvent::PeerLeft(Arc::clone(peer)),
+            NetworkEvent::Reconnecting(attempt) => NetworkEvent::Reconnecting(*attempt),
         }
     }
 }
@@ -130,13 +141,53 @@ pub trait Network: Send + Sync + 'static {
     async fn dial_address(&self, address: Self::AddressType) -> Result<(), Self::Error>;
 
     fn get_local_peer_id(&self) -> <Self::PeerType as Peer>::Id;
+
+    /// Returns the scores of the peers that misbehaved recently. Peers that are not included have
+    /// a clean score of 0, which is the best score a peer can have. Networks that don't keep track
+    /// of the behaviour of their peers don't return any scores.
+    async fn get_peer_scores(&self) -> HashMap<<Self::PeerType as Peer>::Id, f64> {
+        HashMap::new()
+    }
 }
 
 // .next() To get next item of stream.
 
+/// Tags every item of a peer's stream with the peer it was received from. It ends when the peer's
+/// stream ends, e.g. because the peer was closed.
+#[pin_project]
+pub struct PeerStream<S, P> {
+    #[pin]
+    inner: S,
+    peer: Arc<P>,
+}
+
+impl<S, P> PeerStream<S, P> {
+    pub fn new(inner: S, peer: Arc<P>) -> Self {
+        PeerStream { inner, peer }
+    }
+}
+
+impl<S: Stream, P> Stream for PeerStream<S, P> {
+    type Item = (S::Item, Arc<P>);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        match ready!(this.inner.poll_next(cx)) {
+            Some(item) => Poll::Ready(Some((item, Arc::clone(this.peer)))),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+type MessageStream<T> = Pin<Box<dyn Stream<Item = T> + Send>>;
+
 /// A wrapper around `SelectAll` that automatically subscribes to new peers.
 pub struct ReceiveFromAll<T: Message, P> {
-    inner: SelectAll<Pin<Box<dyn Stream<Item = (T, Arc<P>)> + Send>>>,
+    inner: SelectAll<PeerStream<MessageStream<T>, P>>,
     event_stream:
         Pin<Box<dyn FusedStream<Item = Result<NetworkEvent<P>, BroadcastStreamRecvError>> + Send>>,
 }
@@ -146,12 +197,11 @@ impl<T: Message, P: Peer + 'static> ReceiveFromAll<T, P> {
         let (peers, updates) = network.get_peer_updates();
 
         ReceiveFromAll {
-            inner: stream::select_all(peers.into_iter().map(|peer| {
-                let peer_inner = Arc::clone(&peer);
-                peer.receive::<T>()
-                    .map(move |item| (item, Arc::clone(&peer_inner)))
-                    .boxed()
-            })),
+            inner: stream::select_all(
+                peers
+                    .into_iter()
+                    .map(|peer| PeerStream::new(peer.receive::<T>(), peer)),
+            ),
             event_stream: Box::pin(updates.fuse()),
         }
     }
@@ -166,12 +216,7 @@ impl<T: Message, P: Peer + 'static> Stream for ReceiveFromAll<T, P> {
                 Poll::Pending => break,
                 Poll::Ready(Some(Ok(NetworkEvent::PeerJoined(peer)))) => {
                     // We have a new peer to receive from.
-                    let peer_inner = Arc::clone(&peer);
-                    self.inner.push(
-                        peer.receive::<T>()
-                            .map(move |item| (item, Arc::clone(&peer_inner)))
-                            .boxed(),
-                    )
+                    self.inner.push(PeerStream::new(peer.receive::<T>(), peer))
                 }
                 #[allow(unreachable_patterns)]
                 Poll::Ready(Some(Ok(_))) => {} // Ignore others.
@@ -203,3 +248,27 @@ impl<T: Message, P: Peer + 'static> FusedStream for ReceiveFromAll<T, P> {
         self.event_stream.is_terminated()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn peer_stream_forwards_messages_and_errors_until_closed() {
+        let peer = Arc::new(1u32);
+        let items: Vec<Result<u8, &str>> = vec![Ok(1), Err("error"), Ok(2)];
+        let mut stream = PeerStream::new(stream::iter(items), Arc::clone(&peer));
+
+        block_on(async {
+            // Messages and errors are forwarded in order, tagged with the peer.
+            assert_eq!(stream.next().await, Some((Ok(1), Arc::clone(&peer))));
+            assert_eq!(stream.next().await, Some((Err("error"), Arc::clone(&peer))));
+            assert_eq!(stream.next().await, Some((Ok(2), Arc::clone(&peer))));
+
+            // The stream ends once the peer's stream is closed.
+            assert_eq!(stream.next().await, None);
+        });
+    }
+}
@@ -17,6 +17,26 @@ pub enum CloseReason {
     Other,
     RemoteClosed,
     Error,
+    /// The connection turned out to be to ourselves, e.g. because our own address is in the seed
+    /// list.
+    SelfConnection,
+}
+
+/// Whether a connection was established by the remote peer or by us.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionDirection {
+    Inbound,
+    Outbound,
+}
+
+impl ConnectionDirection {
+    pub fn is_inbound(&self) -> bool {
+        matches!(self, ConnectionDirection::Inbound)
+    }
+
+    pub fn is_outbound(&self) -> bool {
+        matches!(self, ConnectionDirection::Outbound)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -25,6 +45,8 @@ pub enum SendError {
     Serialization(#[from] SerializingError),
     #[error("Peer connection already closed")]
     AlreadyClosed,
+    #[error("Peer send buffer is full")]
+    BufferFull,
 }
 
 pub trait RequestResponse {
@@ -35,13 +57,20 @@ pub trait RequestResponse {
 #[async_trait]
 // TODO: Use Peer::Error for returned error for send, etc.
 pub trait Peer: Send + Sync + Hash + Eq {
-    type Id: Clone + Debug + Send + Sync + Hash + Eq + Unpin;
+    type Id: Clone + Debug + Send + Sync + Hash + Eq + Ord + Unpin;
     type Error: std::error::Error;
 
     fn id(&self) -> Self::Id;
 
+    /// Returns whether the connection to this peer is inbound or outbound.
+    fn direction(&self) -> ConnectionDirection;
+
     async fn send<T: Message>(&self, msg: T) -> Result<(), SendError>;
 
+    /// Sends a message without waiting for buffer space. Fails with `SendError::BufferFull` if the
+    /// peer doesn't keep up with the messages sent to it.
+    fn try_send<T: Message>(&self, msg: T) -> Result<(), SendError>;
+
     async fn send_or_close<T: Message, F: FnOnce(&SendError) -> CloseReason + Send>(
         &self,
         msg: T,
@@ -173,7 +173,9 @@ mod tests {
     use crate::{
         message::Message,
         network::NetworkEvent,
-        peer::{CloseReason, Peer as PeerInterface, RequestResponse, SendError},
+        peer::{
+            CloseReason, ConnectionDirection, Peer as PeerInterface, RequestResponse, SendError,
+        },
     };
 
     #[derive(Debug, Error)]
@@ -199,9 +201,16 @@ mod tests {
             self.id
         }
 
+        fn direction(&self) -> ConnectionDirection {
+            ConnectionDirection::Inbound
+        }
+
         async fn send<T: Message>(&self, _msg: T) -> Result<(), SendError> {
             unreachable!();
         }
+        fn try_send<T: Message>(&self, _msg: T) -> Result<(), SendError> {
+            unreachable!();
+        }
         fn receive<T: Message>(&self) -> Pin<Box<dyn Stream<Item = T> + Send>> {
             unreachable!();
         }
@@ -166,7 +166,13 @@ impl NimiqBehaviour {
         );
 
         // Connection pool behaviour
-        let pool = ConnectionPoolBehaviour::new(Arc::clone(&contacts), config.seeds, peers);
+        let pool = ConnectionPoolBehaviour::new(
+            Arc::clone(&contacts),
+            config.seeds,
+            config.reconnect,
+            config.peer_limits,
+            peers,
+        );
 
         Self {
             dht,
@@ -2,13 +2,130 @@ use libp2p::{
     gossipsub::{GossipsubConfig, GossipsubConfigBuilder},
     identity::Keypair,
     kad::{KademliaBucketInserts, KademliaConfig, KademliaStoreInserts},
+    websocket::tls,
     Multiaddr,
 };
-use std::time::Duration;
+use std::{fs::File, io::BufReader, path::Path, time::Duration};
+
+use rustls_pemfile::Item;
 
 use nimiq_hash::Blake2bHash;
 
 use crate::discovery::{behaviour::DiscoveryConfig, peer_contacts::PeerContact};
+use crate::error::NetworkError;
+
+/// Parameters of the exponential backoff used to reconnect after losing all peers.
+#[derive(Clone, Debug)]
+pub struct ReconnectConfig {
+    /// The delay before the first reconnection attempt.
+    pub initial_interval: Duration,
+    /// The maximum delay between two reconnection attempts.
+    pub max_interval: Duration,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Limits for the number of established connections, separately for both directions.
+#[derive(Clone, Debug)]
+pub struct PeerLimitsConfig {
+    /// The maximum number of inbound connections. Further inbound connections are closed.
+    pub max_inbound: usize,
+    /// The maximum number of outbound connections. The node actively dials peers until this
+    /// number is reached.
+    pub max_outbound: usize,
+}
+
+impl Default for PeerLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_inbound: 4000,
+            max_outbound: 12,
+        }
+    }
+}
+
+/// Certificate chain and private key used to accept secure websocket connections on `/wss`
+/// listen addresses.
+///
+/// TLS is terminated by the websocket transport. The noise handshake and the yamux upgrade are
+/// negotiated on top of the secured websocket stream exactly as for plain websocket connections,
+/// so peers are still authenticated by their noise identity and not by the certificate. The
+/// certificate only needs to be trusted by browser clients connecting from HTTPS pages.
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    /// The DER encoded private key.
+    pub private_key: Vec<u8>,
+    /// The DER encoded certificate chain, starting with the end-entity certificate.
+    pub certificates: Vec<Vec<u8>>,
+}
+
+impl TlsConfig {
+    /// Loads the private key and the certificate chain from PEM files. The key and the
+    /// certificates are validated, so that a misconfigured node fails at startup.
+    pub fn from_pem_files<P: AsRef<Path>, Q: AsRef<Path>>(
+        private_key_file: P,
+        certificates_file: Q,
+    ) -> Result<Self, NetworkError> {
+        let private_key = read_pem_file(private_key_file.as_ref())?
+            .into_iter()
+            .find_map(|item| match item {
+                Item::RSAKey(key) | Item::PKCS8Key(key) => Some(key),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                NetworkError::InvalidTlsConfig(format!(
+                    "No private key found in {}",
+                    private_key_file.as_ref().display()
+                ))
+            })?;
+
+        let certificates: Vec<Vec<u8>> = read_pem_file(certificates_file.as_ref())?
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::X509Certificate(certificate) => Some(certificate),
+                _ => None,
+            })
+            .collect();
+        if certificates.is_empty() {
+            return Err(NetworkError::InvalidTlsConfig(format!(
+                "No certificates found in {}",
+                certificates_file.as_ref().display()
+            )));
+        }
+
+        let config = Self {
+            private_key,
+            certificates,
+        };
+        config.to_websocket_config()?;
+
+        Ok(config)
+    }
+
+    pub(crate) fn to_websocket_config(&self) -> Result<tls::Config, NetworkError> {
+        let config = tls::Config::new(
+            tls::PrivateKey::new(self.private_key.clone()),
+            self.certificates.iter().cloned().map(tls::Certificate::new),
+        )?;
+        Ok(config)
+    }
+}
+
+fn read_pem_file(path: &Path) -> Result<Vec<Item>, NetworkError> {
+    let file = File::open(path).map_err(|e| {
+        NetworkError::InvalidTlsConfig(format!("Can't open {}: {}", path.display(), e))
+    })?;
+    rustls_pemfile::read_all(&mut BufReader::new(file)).map_err(|e| {
+        NetworkError::InvalidTlsConfig(format!("Can't parse {}: {}", path.display(), e))
+    })
+}
 
 pub struct Config {
     pub keypair: Keypair,
@@ -17,6 +134,9 @@ pub struct Config {
     pub discovery: DiscoveryConfig,
     pub kademlia: KademliaConfig,
     pub gossipsub: GossipsubConfig,
+    pub reconnect: ReconnectConfig,
+    pub peer_limits: PeerLimitsConfig,
+    pub tls: Option<TlsConfig>,
 }
 
 impl Config {
@@ -53,6 +173,9 @@ impl Config {
             discovery: DiscoveryConfig::new(genesis_hash),
             kademlia,
             gossipsub,
+            reconnect: ReconnectConfig::default(),
+            peer_limits: PeerLimitsConfig::default(),
+            tls: None,
         }
     }
 }
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::config::ReconnectConfig;
+
+/// Computes the delays between consecutive reconnection attempts. The delay starts at the
+/// configured initial interval and doubles with every attempt, up to the configured maximum.
+pub(crate) struct ExponentialBackoff {
+    config: ReconnectConfig,
+    attempts: u32,
+}
+
+impl ExponentialBackoff {
+    pub fn new(config: ReconnectConfig) -> Self {
+        Self {
+            config,
+            attempts: 0,
+        }
+    }
+
+    /// The number of intervals handed out since the last reset.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Returns the delay before the next reconnection attempt.
+    pub fn next_interval(&mut self) -> Duration {
+        let factor = 2u32.saturating_pow(self.attempts);
+        self.attempts = self.attempts.saturating_add(1);
+
+        self.config
+            .initial_interval
+            .saturating_mul(factor)
+            .min(self.config.max_interval)
+    }
+
+    /// Resets the backoff after a connection was established successfully.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_grow_and_are_capped() {
+        let mut backoff = ExponentialBackoff::new(ReconnectConfig {
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(10),
+        });
+
+        assert_eq!(backoff.next_interval(), Duration::from_secs(1));
+        assert_eq!(backoff.next_interval(), Duration::from_secs(2));
+        assert_eq!(backoff.next_interval(), Duration::from_secs(4));
+        assert_eq!(backoff.next_interval(), Duration::from_secs(8));
+        assert_eq!(backoff.next_interval(), Duration::from_secs(10));
+        assert_eq!(backoff.next_interval(), Duration::from_secs(10));
+        assert_eq!(backoff.attempts(), 6);
+
+        // The interval stays capped, even after many attempts.
+        for _ in 0..100 {
+            assert_eq!(backoff.next_interval(), Duration::from_secs(10));
+        }
+
+        backoff.reset();
+        assert_eq!(backoff.attempts(), 0);
+        assert_eq!(backoff.next_interval(), Duration::from_secs(1));
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    pin::Pin,
     sync::Arc,
     time::{Duration, Instant, SystemTime},
 };
@@ -8,6 +9,7 @@ use bytes::Bytes;
 use futures::{
     channel::mpsc,
     task::{noop_waker_ref, Context, Poll, Waker},
+    Future,
 };
 use ip_network::IpNetwork;
 use libp2p::swarm::dial_opts::PeerCondition;
@@ -22,16 +24,20 @@ use libp2p::{
 use parking_lot::RwLock;
 use rand::seq::IteratorRandom;
 use rand::thread_rng;
-use tokio::time::Interval;
+use tokio::time::{Interval, Sleep};
 
 use nimiq_network_interface::{
     message::MessageType, peer::CloseReason, peer_map::ObservablePeerMap,
 };
 
+use crate::config::{PeerLimitsConfig, ReconnectConfig};
 use crate::discovery::peer_contacts::{PeerContactBook, Services};
 use crate::peer::Peer;
 
+use super::backoff::ExponentialBackoff;
 use super::handler::{ConnectionPoolHandler, HandlerInEvent, HandlerOutEvent};
+use super::scores::{BanList, PeerScores};
+use super::slots::ConnectionSlots;
 
 #[derive(Clone, Debug)]
 struct ConnectionPoolLimits {
@@ -42,7 +48,6 @@ struct ConnectionPoolLimits {
 
 #[derive(Clone, Debug)]
 struct ConnectionPoolConfig {
-    peer_count_desired: usize,
     peer_count_max: usize,
     peer_count_per_ip_max: usize,
     peer_count_per_subnet_max: usize,
@@ -51,12 +56,14 @@ struct ConnectionPoolConfig {
     dialing_count_max: usize,
     retry_down_after: Duration,
     housekeeping_interval: Duration,
+    score_decay_factor: f64,
+    ban_threshold: f64,
+    ban_duration: Duration,
 }
 
 impl Default for ConnectionPoolConfig {
     fn default() -> Self {
         Self {
-            peer_count_desired: 12,
             peer_count_max: 4000,
             peer_count_per_ip_max: 20,
             peer_count_per_subnet_max: 20,
@@ -65,6 +72,9 @@ impl Default for ConnectionPoolConfig {
             dialing_count_max: 3,
             retry_down_after: Duration::from_secs(60 * 10), // 10 minutes
             housekeeping_interval: Duration::from_secs(60 * 2), // 2 minutes
+            score_decay_factor: 0.5,
+            ban_threshold: -100.0,
+            ban_duration: Duration::from_secs(60 * 10), // 10 minutes
         }
     }
 }
@@ -137,6 +147,11 @@ impl<T: Ord> ConnectionState<T> {
         self.connected.len()
     }
 
+    fn retry_all(&mut self) {
+        self.failed.clear();
+        self.down.clear();
+    }
+
     fn housekeeping(&mut self) {
         // Remove all down peers that we haven't dialed in a while from the `down` map to dial them again.
         let retry_down_after = self.retry_down_after;
@@ -161,6 +176,7 @@ impl<T> std::fmt::Display for ConnectionState<T> {
 #[derive(Clone, Debug)]
 pub enum ConnectionPoolEvent {
     PeerJoined { peer: Arc<Peer> },
+    Reconnecting { attempt: u32 },
 }
 
 type PoolNetworkBehaviourAction =
@@ -180,9 +196,14 @@ pub struct ConnectionPoolBehaviour {
 
     limits: ConnectionPoolLimits,
     config: ConnectionPoolConfig,
-    banned: HashMap<IpNetwork, SystemTime>,
+    banned: BanList,
+    scores: PeerScores,
+    slots: ConnectionSlots,
+    peer_ips: HashMap<PeerId, IpNetwork>,
     waker: Option<Waker>,
     housekeeping_timer: Interval,
+    reconnect_backoff: ExponentialBackoff,
+    reconnect_timer: Option<Pin<Box<Sleep>>>,
 
     message_receivers: HashMap<MessageType, mpsc::Sender<(Bytes, Arc<Peer>)>>,
 }
@@ -191,6 +212,8 @@ impl ConnectionPoolBehaviour {
     pub fn new(
         contacts: Arc<RwLock<PeerContactBook>>,
         seeds: Vec<Multiaddr>,
+        reconnect_config: ReconnectConfig,
+        peer_limits: PeerLimitsConfig,
         peers: ObservablePeerMap<Peer>,
     ) -> Self {
         let limits = ConnectionPoolLimits {
@@ -210,10 +233,15 @@ impl ConnectionPoolBehaviour {
             actions: VecDeque::new(),
             active: false,
             limits,
+            scores: PeerScores::new(config.score_decay_factor, config.ban_threshold),
             config,
-            banned: HashMap::new(),
+            banned: BanList::default(),
+            slots: ConnectionSlots::new(peer_limits),
+            peer_ips: HashMap::new(),
             waker: None,
             housekeeping_timer,
+            reconnect_backoff: ExponentialBackoff::new(reconnect_config),
+            reconnect_timer: None,
             message_receivers: HashMap::new(),
         }
     }
@@ -225,9 +253,9 @@ impl ConnectionPoolBehaviour {
             self.addresses
         );
 
-        // Try to maintain at least `peer_count_desired` connections.
+        // Try to maintain `max_outbound` outbound connections.
         if self.active
-            && self.peer_ids.num_connected() < self.config.peer_count_desired
+            && self.slots.outbound_to_dial(self.peer_ids.num_dialing()) > 0
             && self.peer_ids.num_dialing() < self.config.dialing_count_max
         {
             // Dial peers from the contact book.
@@ -267,7 +295,7 @@ impl ConnectionPoolBehaviour {
 
     fn choose_peers_to_dial(&self) -> Vec<PeerId> {
         let num_peers = usize::min(
-            self.config.peer_count_desired - self.peer_ids.num_connected(),
+            self.slots.outbound_to_dial(self.peer_ids.num_dialing()),
             self.config.dialing_count_max - self.peer_ids.num_dialing(),
         );
         let contacts = self.contacts.read();
@@ -330,20 +358,71 @@ impl ConnectionPoolBehaviour {
         self.peer_ids.housekeeping();
         self.addresses.housekeeping();
 
-        for (ip, time) in self.banned.clone() {
-            if time < SystemTime::now() {
-                self.banned.remove(&ip);
-            }
-        }
+        self.banned.expire(SystemTime::now());
+        self.scores.decay();
+
+        // Keep the IPs of disconnected peers only as long as they might still be banned.
+        let connected = &self.peer_ids.connected;
+        let scores = &self.scores;
+        self.peer_ips
+            .retain(|peer_id, _| connected.contains(peer_id) || scores.get(peer_id) < 0.0);
+
+        self.maintain_peers();
+    }
+
+    fn reconnect(&mut self) {
+        let attempt = self.reconnect_backoff.attempts();
+        log::debug!("No peers connected, reconnecting (attempt {})", attempt);
+
+        // Give all peers and seeds that failed or were marked as down another chance.
+        self.peer_ids.retry_all();
+        self.addresses.retry_all();
+
+        self.actions
+            .push_back(NetworkBehaviourAction::GenerateEvent(
+                ConnectionPoolEvent::Reconnecting { attempt },
+            ));
 
         self.maintain_peers();
     }
 
-    pub fn _ban_ip(&mut self, ip: IpNetwork) {
+    /// Applies the penalty for a protocol violation to the score of a peer. If its score falls
+    /// below the ban threshold, the peer is disconnected and its IP is banned for a while.
+    pub fn penalize_peer(&mut self, peer_id: PeerId, penalty: f64) {
+        if !self.scores.penalize(peer_id, penalty) {
+            return;
+        }
+
+        log::debug!("Score of peer {} fell below the ban threshold", peer_id);
+        self.scores.remove(&peer_id);
+
+        if let Some(ip) = self.peer_ips.get(&peer_id).copied() {
+            self.ban_ip(ip);
+        }
+
+        self.actions
+            .push_back(NetworkBehaviourAction::NotifyHandler {
+                peer_id,
+                handler: NotifyHandler::Any,
+                event: HandlerInEvent::Close {
+                    reason: CloseReason::Other,
+                },
+            });
+
+        if let Some(waker) = &self.waker {
+            waker.wake_by_ref();
+        }
+    }
+
+    /// Returns the scores of all peers that committed protocol violations recently.
+    pub fn peer_scores(&self) -> HashMap<PeerId, f64> {
+        self.scores.all().clone()
+    }
+
+    pub fn ban_ip(&mut self, ip: IpNetwork) {
         if self
             .banned
-            .insert(ip, SystemTime::now() + Duration::from_secs(60 * 10)) // 10 minutes
-            .is_none()
+            .ban(ip, SystemTime::now(), self.config.ban_duration)
         {
             log::debug!("{:?} added to banned set of peers", ip);
         } else {
@@ -351,8 +430,12 @@ impl ConnectionPoolBehaviour {
         }
     }
 
+    fn is_own_peer_id(&self, peer_id: &PeerId) -> bool {
+        self.contacts.read().get_own_contact().peer_id() == peer_id
+    }
+
     pub fn _unban_ip(&mut self, ip: IpNetwork) {
-        if self.banned.remove(&ip).is_some() {
+        if self.banned.unban(&ip) {
             log::debug!("{:?} removed from banned set of peers", ip);
         } else {
             log::debug!("{:?} was not part of banned set of peers", ip);
@@ -423,11 +506,21 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
     }
 
     fn inject_connected(&mut self, peer_id: &PeerId) {
+        if self.is_own_peer_id(peer_id) {
+            return;
+        }
+
         self.peer_ids.mark_connected(*peer_id);
+        self.reconnect_backoff.reset();
+        self.reconnect_timer = None;
         self.maintain_peers();
     }
 
     fn inject_disconnected(&mut self, peer_id: &PeerId) {
+        if self.is_own_peer_id(peer_id) {
+            return;
+        }
+
         self.peer_ids.mark_closed(*peer_id);
         // If the connection was closed for any reason, don't dial the peer again.
         // FIXME We want to be more selective here and only mark peers as down for specific CloseReasons.
@@ -442,6 +535,28 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
         endpoint: &ConnectedPoint,
         failed_addresses: Option<&Vec<Multiaddr>>,
     ) {
+        // Reject connections to ourselves. They don't get a peer and don't occupy any slots. If we
+        // dialed the address, don't dial it again.
+        if self.is_own_peer_id(peer_id) {
+            log::debug!(
+                "Rejecting connection to ourselves: address={}",
+                endpoint.get_remote_address()
+            );
+            if endpoint.is_dialer() {
+                self.addresses
+                    .mark_down(endpoint.get_remote_address().clone());
+            }
+            self.actions
+                .push_back(NetworkBehaviourAction::NotifyHandler {
+                    peer_id: *peer_id,
+                    handler: NotifyHandler::One(*connection_id),
+                    event: HandlerInEvent::Close {
+                        reason: CloseReason::SelfConnection,
+                    },
+                });
+            return;
+        }
+
         // Send an event to the handler that tells it if this is an inbound or outbound connection, and the registered
         // messages handlers, that receive from all peers.
         self.actions
@@ -480,7 +595,7 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
 
         let mut close_connection = false;
 
-        if self.banned.get(&ip).is_some() {
+        if self.banned.is_banned(&ip, SystemTime::now()) {
             log::debug!("IP is banned, {}", ip);
             close_connection = true;
         }
@@ -507,6 +622,19 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
             log::debug!("Max peer connections per IPv6 subnet limit reached");
             close_connection = true;
         }
+        if !self.slots.has_free_slot(endpoint.is_dialer()) {
+            log::debug!(
+                "Max {} connections limit reached (inbound={}, outbound={})",
+                if endpoint.is_dialer() {
+                    "outbound"
+                } else {
+                    "inbound"
+                },
+                self.slots.num_inbound(),
+                self.slots.num_outbound()
+            );
+            close_connection = true;
+        }
         if self.config.peer_count_max
             < self
                 .limits
@@ -540,17 +668,25 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
                 }
             };
 
+            self.slots.insert(*connection_id, endpoint.is_dialer());
             self.addresses.mark_connected(address.clone());
+            self.peer_ips.insert(*peer_id, ip);
         }
     }
 
     fn inject_connection_closed(
         &mut self,
         peer_id: &PeerId,
-        _conn: &ConnectionId,
+        connection_id: &ConnectionId,
         endpoint: &ConnectedPoint,
         _handler: <Self::ProtocolsHandler as IntoProtocolsHandler>::Handler,
     ) {
+        // Connections that were rejected when they were established didn't occupy a slot and
+        // weren't counted.
+        if !self.slots.remove(connection_id) {
+            return;
+        }
+
         let address = endpoint.get_remote_address();
 
         let ip = match address.iter().next() {
@@ -595,6 +731,11 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
     ) {
         match event {
             HandlerOutEvent::PeerJoined { peer } => {
+                if self.is_own_peer_id(&peer_id) {
+                    log::error!("Connection to ourselves joined as a peer, ignoring it");
+                    return;
+                }
+
                 log::trace!("Peer {:?} joined, inserting it into our map", peer_id);
                 {
                     let mut dispatch = peer.dispatch.lock();
@@ -672,6 +813,23 @@ impl NetworkBehaviour for ConnectionPoolBehaviour {
             self.housekeeping();
         }
 
+        // If we lost all peers, try to reconnect with an exponential backoff.
+        if self.active && self.peer_ids.num_connected() == 0 {
+            let backoff = &mut self.reconnect_backoff;
+            let timer = self
+                .reconnect_timer
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(backoff.next_interval())));
+
+            if timer.as_mut().poll(cx).is_ready() {
+                self.reconnect_timer = None;
+                self.reconnect();
+
+                if let Some(action) = self.actions.pop_front() {
+                    return Poll::Ready(action);
+                }
+            }
+        }
+
         store_waker!(self, waker, cx);
 
         Poll::Pending
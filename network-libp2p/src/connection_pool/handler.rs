@@ -19,7 +19,10 @@ use libp2p::{
 use thiserror::Error;
 
 use beserial::SerializingError;
-use nimiq_network_interface::{message::MessageType, peer::CloseReason};
+use nimiq_network_interface::{
+    message::MessageType,
+    peer::{CloseReason, ConnectionDirection},
+};
 
 use crate::dispatch::message_dispatch::MessageDispatch;
 use crate::peer::Peer;
@@ -62,6 +65,9 @@ pub enum HandlerError {
 pub struct ConnectionPoolHandler {
     peer_id: Option<PeerId>,
 
+    // The direction of the connection. This is set together with the peer ID.
+    direction: Option<ConnectionDirection>,
+
     peer: Option<Arc<Peer>>,
 
     // Receives the close reason when `close()` is called on the peer.
@@ -85,6 +91,7 @@ impl ConnectionPoolHandler {
     pub fn new() -> Self {
         Self {
             peer_id: None,
+            direction: None,
             peer: None,
             close_rx: None,
             waker: None,
@@ -158,6 +165,14 @@ impl ProtocolsHandler for ConnectionPoolHandler {
                         self.closing = Some(reason);
                         self.close_rx = None;
                     }
+                } else if let CloseReason::SelfConnection = reason {
+                    // There is no peer for a connection to ourselves, so we close the connection
+                    // right away.
+                    log::debug!("ConnectionPoolHandler: Closing connection to ourselves");
+                    self.events.push_back(ProtocolsHandlerEvent::Close(
+                        HandlerError::ConnectionClosed { reason },
+                    ));
+                    self.wake();
                 }
             }
             HandlerInEvent::PeerConnected {
@@ -170,6 +185,11 @@ impl ProtocolsHandler for ConnectionPoolHandler {
                 assert!(self.receive_from_all.is_none());
 
                 self.peer_id = Some(peer_id);
+                self.direction = Some(if outbound {
+                    ConnectionDirection::Outbound
+                } else {
+                    ConnectionDirection::Inbound
+                });
                 self.receive_from_all = Some(receive_from_all);
 
                 if outbound {
@@ -307,6 +327,7 @@ impl ProtocolsHandler for ConnectionPoolHandler {
 
             // Take inbound and outbound and create a peer from it.
             let peer_id = self.peer_id.unwrap();
+            let direction = self.direction.unwrap();
             let mut socket = self.socket.take().unwrap();
 
             // Create a channel that is used to receive the close signal from the `Peer` struct (when `Peer::close` is called).
@@ -316,7 +337,7 @@ impl ProtocolsHandler for ConnectionPoolHandler {
             let receive_from_all = self.receive_from_all.take().expect("global receivers");
             socket.receive_multiple_raw(receive_from_all);
 
-            let peer = Arc::new(Peer::new(peer_id, socket, close_tx));
+            let peer = Arc::new(Peer::new(peer_id, direction, socket, close_tx));
             log::debug!("New peer: {:?}", peer);
 
             self.close_rx = Some(close_rx);
@@ -1,3 +1,6 @@
+pub(crate) mod backoff;
 pub mod behaviour;
 pub mod handler;
 pub mod protocol;
+pub(crate) mod scores;
+pub(crate) mod slots;
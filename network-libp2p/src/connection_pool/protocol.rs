@@ -1,4 +1,4 @@
-use std::iter;
+use std::array;
 
 use futures::{future, AsyncRead, AsyncWrite};
 use libp2p::{core::UpgradeInfo, InboundUpgrade, OutboundUpgrade};
@@ -6,7 +6,7 @@ use libp2p::{core::UpgradeInfo, InboundUpgrade, OutboundUpgrade};
 use beserial::SerializingError;
 
 use crate::dispatch::message_dispatch::MessageDispatch;
-use crate::MESSAGE_PROTOCOL;
+use crate::{MESSAGE_PROTOCOL, MESSAGE_PROTOCOL_DEFLATE};
 
 #[derive(Debug, Default)]
 pub struct MessageProtocol {}
@@ -17,10 +17,12 @@ impl MessageProtocol {
 
 impl UpgradeInfo for MessageProtocol {
     type Info = &'static [u8];
-    type InfoIter = iter::Once<Self::Info>;
+    type InfoIter = array::IntoIter<Self::Info, 2>;
 
+    /// Compression is negotiated by offering the deflate variant of the message protocol first. Peers
+    /// that don't support it fall back to the uncompressed protocol.
     fn protocol_info(&self) -> Self::InfoIter {
-        iter::once(MESSAGE_PROTOCOL)
+        [MESSAGE_PROTOCOL_DEFLATE, MESSAGE_PROTOCOL].into_iter()
     }
 }
 
@@ -32,8 +34,9 @@ where
     type Error = SerializingError;
     type Future = future::Ready<Result<MessageDispatch<C>, SerializingError>>;
 
-    fn upgrade_inbound(self, socket: C, _info: Self::Info) -> Self::Future {
-        future::ok(MessageDispatch::new(socket, Self::BUFFER_SIZE))
+    fn upgrade_inbound(self, socket: C, info: Self::Info) -> Self::Future {
+        let compression = info == MESSAGE_PROTOCOL_DEFLATE;
+        future::ok(MessageDispatch::new(socket, Self::BUFFER_SIZE, compression))
     }
 }
 
@@ -45,7 +48,8 @@ where
     type Error = SerializingError;
     type Future = future::Ready<Result<MessageDispatch<C>, SerializingError>>;
 
-    fn upgrade_outbound(self, socket: C, _info: Self::Info) -> Self::Future {
-        future::ok(MessageDispatch::new(socket, Self::BUFFER_SIZE))
+    fn upgrade_outbound(self, socket: C, info: Self::Info) -> Self::Future {
+        let compression = info == MESSAGE_PROTOCOL_DEFLATE;
+        future::ok(MessageDispatch::new(socket, Self::BUFFER_SIZE, compression))
     }
 }
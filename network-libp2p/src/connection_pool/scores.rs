@@ -0,0 +1,149 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use ip_network::IpNetwork;
+use libp2p::PeerId;
+
+/// Penalty for a peer that sent a message which failed validation, e.g. an invalid block.
+pub(crate) const INVALID_MESSAGE_PENALTY: f64 = -20.0;
+/// Penalty for a peer whose connection failed with a protocol error.
+pub(crate) const PROTOCOL_ERROR_PENALTY: f64 = -10.0;
+
+/// Keeps track of the protocol violations of peers. Every violation decreases the score of the
+/// peer, while the scores decay towards zero over time, such that peers that stop misbehaving
+/// eventually regain a clean score.
+pub(crate) struct PeerScores {
+    scores: HashMap<PeerId, f64>,
+    decay_factor: f64,
+    ban_threshold: f64,
+}
+
+impl PeerScores {
+    pub fn new(decay_factor: f64, ban_threshold: f64) -> Self {
+        Self {
+            scores: HashMap::new(),
+            decay_factor,
+            ban_threshold,
+        }
+    }
+
+    /// Returns the score of a peer. Peers without any violations have a score of 0.
+    pub fn get(&self, peer_id: &PeerId) -> f64 {
+        self.scores.get(peer_id).copied().unwrap_or(0.0)
+    }
+
+    pub fn all(&self) -> &HashMap<PeerId, f64> {
+        &self.scores
+    }
+
+    /// Applies the given penalty to the score of a peer. Returns `true` if the score fell below the
+    /// ban threshold.
+    pub fn penalize(&mut self, peer_id: PeerId, penalty: f64) -> bool {
+        let score = self.scores.entry(peer_id).or_insert(0.0);
+        *score += penalty;
+        *score < self.ban_threshold
+    }
+
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        self.scores.remove(peer_id);
+    }
+
+    /// Decays all scores towards zero and forgets about peers whose score is close enough to zero.
+    pub fn decay(&mut self) {
+        let decay_factor = self.decay_factor;
+        self.scores.retain(|_, score| {
+            *score *= decay_factor;
+            *score < -0.1
+        });
+    }
+}
+
+/// A list of IP networks that are banned until a certain point in time.
+#[derive(Default)]
+pub(crate) struct BanList {
+    banned: HashMap<IpNetwork, SystemTime>,
+}
+
+impl BanList {
+    /// Bans the given IP network for the given duration. Returns `false` if it was already banned.
+    pub fn ban(&mut self, ip: IpNetwork, now: SystemTime, duration: Duration) -> bool {
+        self.banned.insert(ip, now + duration).is_none()
+    }
+
+    /// Lifts the ban of the given IP network. Returns `false` if it wasn't banned.
+    pub fn unban(&mut self, ip: &IpNetwork) -> bool {
+        self.banned.remove(ip).is_some()
+    }
+
+    pub fn is_banned(&self, ip: &IpNetwork, now: SystemTime) -> bool {
+        self.banned.get(ip).map_or(false, |until| *until > now)
+    }
+
+    /// Removes all bans that expired.
+    pub fn expire(&mut self, now: SystemTime) {
+        self.banned.retain(|_, until| *until > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn scores_decay() {
+        let mut scores = PeerScores::new(0.5, -100.0);
+        let peer_id = PeerId::random();
+        let other_peer_id = PeerId::random();
+
+        assert!(!scores.penalize(peer_id, INVALID_MESSAGE_PENALTY));
+        assert!(!scores.penalize(peer_id, INVALID_MESSAGE_PENALTY));
+        assert_eq!(scores.get(&peer_id), -40.0);
+        assert_eq!(scores.get(&other_peer_id), 0.0);
+
+        scores.decay();
+        assert_eq!(scores.get(&peer_id), -20.0);
+
+        // Eventually, the peer is forgotten.
+        for _ in 0..10 {
+            scores.decay();
+        }
+        assert_eq!(scores.get(&peer_id), 0.0);
+        assert!(scores.all().is_empty());
+    }
+
+    #[test]
+    fn scores_below_threshold_are_reported() {
+        let mut scores = PeerScores::new(0.5, -50.0);
+        let peer_id = PeerId::random();
+
+        assert!(!scores.penalize(peer_id, INVALID_MESSAGE_PENALTY));
+        assert!(!scores.penalize(peer_id, INVALID_MESSAGE_PENALTY));
+        assert!(scores.penalize(peer_id, INVALID_MESSAGE_PENALTY));
+    }
+
+    #[test]
+    fn bans_expire() {
+        let mut ban_list = BanList::default();
+        let ip = IpNetwork::new_truncate(Ipv4Addr::new(10, 0, 0, 1), 24).unwrap();
+        let other_ip = IpNetwork::new_truncate(Ipv4Addr::new(10, 0, 1, 1), 24).unwrap();
+        let now = SystemTime::now();
+
+        assert!(ban_list.ban(ip, now, Duration::from_secs(60)));
+        assert!(!ban_list.ban(ip, now, Duration::from_secs(60)));
+
+        assert!(ban_list.is_banned(&ip, now));
+        assert!(!ban_list.is_banned(&other_ip, now));
+        assert!(ban_list.is_banned(&ip, now + Duration::from_secs(59)));
+        assert!(!ban_list.is_banned(&ip, now + Duration::from_secs(60)));
+
+        ban_list.expire(now + Duration::from_secs(30));
+        assert!(ban_list.is_banned(&ip, now + Duration::from_secs(30)));
+
+        ban_list.expire(now + Duration::from_secs(61));
+        assert!(!ban_list.unban(&ip));
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+
+use libp2p::core::connection::ConnectionId;
+
+use crate::config::PeerLimitsConfig;
+
+/// Keeps track of the established inbound and outbound connections and enforces the
+/// configured limits for both directions independently.
+pub(crate) struct ConnectionSlots {
+    config: PeerLimitsConfig,
+    inbound: HashSet<ConnectionId>,
+    outbound: HashSet<ConnectionId>,
+}
+
+impl ConnectionSlots {
+    pub fn new(config: PeerLimitsConfig) -> Self {
+        Self {
+            config,
+            inbound: HashSet::new(),
+            outbound: HashSet::new(),
+        }
+    }
+
+    pub fn num_inbound(&self) -> usize {
+        self.inbound.len()
+    }
+
+    pub fn num_outbound(&self) -> usize {
+        self.outbound.len()
+    }
+
+    /// Returns whether another connection in the given direction would still be within the limits.
+    pub fn has_free_slot(&self, outbound: bool) -> bool {
+        if outbound {
+            self.outbound.len() < self.config.max_outbound
+        } else {
+            self.inbound.len() < self.config.max_inbound
+        }
+    }
+
+    /// The number of additional outbound connections needed to reach `max_outbound`, taking
+    /// the connections that are currently being dialed into account.
+    pub fn outbound_to_dial(&self, num_dialing: usize) -> usize {
+        self.config
+            .max_outbound
+            .saturating_sub(self.outbound.len())
+            .saturating_sub(num_dialing)
+    }
+
+    /// Occupies a slot for the given connection. Returns `false` if the limit for its direction
+    /// is reached, in which case the connection must be closed.
+    pub fn insert(&mut self, connection_id: ConnectionId, outbound: bool) -> bool {
+        if !self.has_free_slot(outbound) {
+            return false;
+        }
+
+        if outbound {
+            self.outbound.insert(connection_id)
+        } else {
+            self.inbound.insert(connection_id)
+        }
+    }
+
+    /// Frees the slot occupied by the given connection, if any. Returns whether the connection
+    /// occupied a slot.
+    pub fn remove(&mut self, connection_id: &ConnectionId) -> bool {
+        self.inbound.remove(connection_id) || self.outbound.remove(connection_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(max_inbound: usize, max_outbound: usize) -> ConnectionSlots {
+        ConnectionSlots::new(PeerLimitsConfig {
+            max_inbound,
+            max_outbound,
+        })
+    }
+
+    #[test]
+    fn inbound_connections_beyond_limit_are_refused() {
+        let mut slots = slots(2, 1);
+
+        assert!(slots.insert(ConnectionId::new(0), false));
+        assert!(slots.insert(ConnectionId::new(1), false));
+        assert!(!slots.insert(ConnectionId::new(2), false));
+        assert_eq!(slots.num_inbound(), 2);
+
+        // A full inbound side doesn't affect outbound connections.
+        assert!(slots.insert(ConnectionId::new(3), true));
+        assert_eq!(slots.num_outbound(), 1);
+
+        // Closing an inbound connection frees its slot.
+        slots.remove(&ConnectionId::new(0));
+        assert!(slots.insert(ConnectionId::new(4), false));
+
+        // Refused connections don't occupy a slot that could be freed later.
+        slots.remove(&ConnectionId::new(2));
+        assert_eq!(slots.num_inbound(), 2);
+    }
+
+    #[test]
+    fn outbound_dialing_respects_limit() {
+        let mut slots = slots(2, 3);
+
+        assert_eq!(slots.outbound_to_dial(0), 3);
+        assert_eq!(slots.outbound_to_dial(2), 1);
+        assert_eq!(slots.outbound_to_dial(5), 0);
+
+        assert!(slots.insert(ConnectionId::new(0), true));
+        assert!(slots.insert(ConnectionId::new(1), true));
+        assert_eq!(slots.outbound_to_dial(0), 1);
+        assert_eq!(slots.outbound_to_dial(1), 0);
+
+        assert!(slots.insert(ConnectionId::new(2), true));
+        assert!(!slots.insert(ConnectionId::new(3), true));
+        assert!(!slots.has_free_slot(true));
+        assert!(slots.has_free_slot(false));
+        assert_eq!(slots.outbound_to_dial(0), 0);
+
+        // Inbound connections don't count towards the outbound limit.
+        assert!(slots.insert(ConnectionId::new(4), false));
+        assert_eq!(slots.num_outbound(), 3);
+    }
+}
@@ -25,6 +25,7 @@ use nimiq_utils::time::OffsetTime;
 
 use super::{
     handler::{DiscoveryHandler, HandlerInEvent, HandlerOutEvent},
+    metrics::HandshakeMetrics,
     peer_contacts::{PeerContactBook, Protocols, Services},
 };
 
@@ -57,6 +58,9 @@ pub struct DiscoveryConfig {
 
     /// Whether to keep the connection alive, even if no other behaviour uses it.
     pub keep_alive: KeepAlive,
+
+    /// Time after which a connection is closed if the handshake hasn't completed yet.
+    pub handshake_timeout: Duration,
 }
 
 impl DiscoveryConfig {
@@ -71,6 +75,7 @@ impl DiscoveryConfig {
             services_filter: Services::all(),
             house_keeping_interval: Duration::from_secs(60),
             keep_alive: KeepAlive::Yes,
+            handshake_timeout: Duration::from_secs(30),
         }
     }
 }
@@ -113,6 +118,9 @@ pub struct DiscoveryBehaviour {
 
     /// Timer to do house-keeping in the peer address book.
     house_keeping_timer: Interval,
+
+    /// Statistics about the handshakes of all connections.
+    handshake_metrics: Arc<RwLock<HandshakeMetrics>>,
 }
 
 impl DiscoveryBehaviour {
@@ -133,12 +141,17 @@ impl DiscoveryBehaviour {
             clock,
             events: VecDeque::new(),
             house_keeping_timer,
+            handshake_metrics: Arc::new(RwLock::new(HandshakeMetrics::default())),
         }
     }
 
     pub fn peer_contact_book(&self) -> Arc<RwLock<PeerContactBook>> {
         Arc::clone(&self.peer_contact_book)
     }
+
+    pub fn handshake_metrics(&self) -> Arc<RwLock<HandshakeMetrics>> {
+        Arc::clone(&self.handshake_metrics)
+    }
 }
 
 impl NetworkBehaviour for DiscoveryBehaviour {
@@ -150,6 +163,7 @@ impl NetworkBehaviour for DiscoveryBehaviour {
             self.config.clone(),
             self.keypair.clone(),
             self.peer_contact_book(),
+            self.handshake_metrics(),
         )
     }
 
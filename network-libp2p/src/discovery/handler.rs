@@ -6,7 +6,7 @@ use std::{
 
 use futures::{
     task::{Context, Poll, Waker},
-    Sink, SinkExt, StreamExt,
+    FutureExt, Sink, SinkExt, StreamExt,
 };
 use libp2p::{
     identity::Keypair,
@@ -19,7 +19,7 @@ use libp2p::{
 use parking_lot::RwLock;
 use rand::{seq::IteratorRandom, thread_rng};
 use thiserror::Error;
-use wasm_timer::Interval;
+use wasm_timer::{Delay, Interval};
 
 use beserial::SerializingError;
 use nimiq_hash::Blake2bHash;
@@ -28,6 +28,7 @@ use nimiq_utils::tagged_signing::TaggedKeypair;
 use super::{
     behaviour::DiscoveryConfig,
     message_codec::{MessageReader, MessageWriter},
+    metrics::{HandshakeFailure, HandshakeMetrics},
     peer_contacts::{PeerContactBook, Protocols, Services, SignedPeerContact},
     protocol::{ChallengeNonce, DiscoveryMessage, DiscoveryProtocol},
 };
@@ -78,6 +79,9 @@ pub enum HandlerError {
 
     #[error("Received update with too many peer contacts: {num_peer_contacts}")]
     UpdateLimitExceeded { num_peer_contacts: usize },
+
+    #[error("Handshake timed out")]
+    HandshakeTimeout,
 }
 
 impl HandlerError {
@@ -153,6 +157,18 @@ pub struct DiscoveryHandler {
 
     /// Waker used when opening a substream.
     waker: Option<Waker>,
+
+    /// Statistics about the handshakes of all connections.
+    handshake_metrics: Arc<RwLock<HandshakeMetrics>>,
+
+    /// Time when the connection was accepted or dialed, i.e. when this handler was created.
+    handshake_start: Instant,
+
+    /// Timer after which the connection is closed if the handshake hasn't completed yet.
+    handshake_timeout: Delay,
+
+    /// Whether the outcome of the handshake was already recorded in the metrics.
+    handshake_recorded: bool,
 }
 
 impl DiscoveryHandler {
@@ -160,7 +176,10 @@ impl DiscoveryHandler {
         config: DiscoveryConfig,
         keypair: Keypair,
         peer_contact_book: Arc<RwLock<PeerContactBook>>,
+        handshake_metrics: Arc<RwLock<HandshakeMetrics>>,
     ) -> Self {
+        let handshake_timeout = Delay::new(config.handshake_timeout);
+
         Self {
             config,
             keypair,
@@ -177,6 +196,10 @@ impl DiscoveryHandler {
             inbound: None,
             outbound: None,
             waker: None,
+            handshake_metrics,
+            handshake_start: Instant::now(),
+            handshake_timeout,
+            handshake_recorded: false,
         }
     }
 
@@ -209,6 +232,23 @@ impl DiscoveryHandler {
             .collect()
     }
 
+    /// Records a failed handshake, unless the outcome of the handshake was already recorded.
+    fn record_handshake_failure(&mut self, failure: HandshakeFailure) {
+        if !self.handshake_recorded && self.state != HandlerState::Established {
+            self.handshake_recorded = true;
+            self.handshake_metrics.write().record_failure(failure);
+        }
+    }
+
+    /// Closes the connection with the given error. If this happens during the handshake, the failure is recorded.
+    fn close(
+        &mut self,
+        error: HandlerError,
+    ) -> Poll<ProtocolsHandlerEvent<DiscoveryProtocol, (), HandlerOutEvent, HandlerError>> {
+        self.record_handshake_failure((&error).into());
+        Poll::Ready(ProtocolsHandlerEvent::Close(error))
+    }
+
     /// Checks if both inbound and outbound are available and transitions to sending a handshake. This includes waking
     /// waker to continue polling.
     fn check_connected(&mut self) {
@@ -285,6 +325,7 @@ impl ProtocolsHandler for DiscoveryHandler {
         error: ProtocolsHandlerUpgrErr<SerializingError>,
     ) {
         log::error!("inject_dial_upgrade_error: {:?}", error);
+        self.record_handshake_failure((&error).into());
     }
 
     fn connection_keep_alive(&self) -> KeepAlive {
@@ -296,14 +337,19 @@ impl ProtocolsHandler for DiscoveryHandler {
         cx: &mut Context,
     ) -> Poll<ProtocolsHandlerEvent<Self::OutboundProtocol, (), HandlerOutEvent, HandlerError>>
     {
+        // Close the connection if the handshake takes too long.
+        if self.state != HandlerState::Established
+            && self.handshake_timeout.poll_unpin(cx).is_ready()
+        {
+            return self.close(HandlerError::HandshakeTimeout);
+        }
+
         loop {
             // Send message
             // This should be done first, so we can flush the outbound sink's buffer.
             if let Some(outbound) = self.outbound.as_mut() {
                 match outbound.poll_ready_unpin(cx) {
-                    Poll::Ready(Err(e)) => {
-                        return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()))
-                    }
+                    Poll::Ready(Err(e)) => return self.close(e.into()),
 
                     // Make sure the outbound sink is ready before we continue.
                     Poll::Pending => break,
@@ -349,7 +395,7 @@ impl ProtocolsHandler for DiscoveryHandler {
                     };
 
                     if let Err(e) = self.send(&msg) {
-                        return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()));
+                        return self.close(e.into());
                     }
 
                     self.state = HandlerState::ReceiveHandshake;
@@ -371,12 +417,10 @@ impl ProtocolsHandler for DiscoveryHandler {
                                 } => {
                                     // Check if the received genesis hash matches.
                                     if genesis_hash != self.config.genesis_hash {
-                                        return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                            HandlerError::GenesisHashMismatch {
-                                                expected: self.config.genesis_hash.clone(),
-                                                received: genesis_hash,
-                                            },
-                                        ));
+                                        return self.close(HandlerError::GenesisHashMismatch {
+                                            expected: self.config.genesis_hash.clone(),
+                                            received: genesis_hash,
+                                        });
                                     }
 
                                     let mut peer_contact_book = self.peer_contact_book.write();
@@ -408,7 +452,7 @@ impl ProtocolsHandler for DiscoveryHandler {
                                     drop(peer_contact_book);
 
                                     if let Err(e) = self.send(&msg) {
-                                        return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()));
+                                        return self.close(e.into());
                                     }
 
                                     self.state = HandlerState::ReceiveHandshakeAck;
@@ -419,23 +463,15 @@ impl ProtocolsHandler for DiscoveryHandler {
                                 }
 
                                 _ => {
-                                    return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                        HandlerError::UnexpectedMessage {
-                                            message,
-                                            state: self.state,
-                                        },
-                                    ))
+                                    return self.close(HandlerError::UnexpectedMessage {
+                                        message,
+                                        state: self.state,
+                                    })
                                 }
                             }
                         }
-                        Poll::Ready(None) => {
-                            return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                HandlerError::connection_reset(),
-                            ))
-                        }
-                        Poll::Ready(Some(Err(e))) => {
-                            return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()))
-                        }
+                        Poll::Ready(None) => return self.close(HandlerError::connection_reset()),
+                        Poll::Ready(Some(Err(e))) => return self.close(e.into()),
                         Poll::Pending => break,
                     }
                 }
@@ -453,11 +489,11 @@ impl ProtocolsHandler for DiscoveryHandler {
                                 } => {
                                     // Check the peer contact for a valid signature.
                                     if !peer_contact.verify() {
-                                        return Poll::Ready(ProtocolsHandlerEvent::Close(
+                                        return self.close(
                                             HandlerError::InvalidPeerContactSignature {
                                                 peer_contact,
                                             },
-                                        ));
+                                        );
                                     }
 
                                     // TODO: Do we need to check other stuff in the peer contact?
@@ -469,9 +505,7 @@ impl ProtocolsHandler for DiscoveryHandler {
                                         &self.challenge_nonce,
                                         peer_contact.public_key(),
                                     ) {
-                                        return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                            HandlerError::ChallengeResponseFailed,
-                                        ));
+                                        return self.close(HandlerError::ChallengeResponseFailed);
                                     }
 
                                     let mut peer_contact_book = self.peer_contact_book.write();
@@ -505,6 +539,13 @@ impl ProtocolsHandler for DiscoveryHandler {
                                     // Switch to established state
                                     self.state = HandlerState::Established;
 
+                                    if !self.handshake_recorded {
+                                        self.handshake_recorded = true;
+                                        self.handshake_metrics
+                                            .write()
+                                            .record_success(self.handshake_start.elapsed());
+                                    }
+
                                     // TODO: Return an event that we established PEX with a new peer.
                                     return Poll::Ready(ProtocolsHandlerEvent::Custom(
                                         HandlerOutEvent::PeerExchangeEstablished { peer_contact },
@@ -512,23 +553,15 @@ impl ProtocolsHandler for DiscoveryHandler {
                                 }
 
                                 _ => {
-                                    return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                        HandlerError::UnexpectedMessage {
-                                            message,
-                                            state: self.state,
-                                        },
-                                    ))
+                                    return self.close(HandlerError::UnexpectedMessage {
+                                        message,
+                                        state: self.state,
+                                    })
                                 }
                             }
                         }
-                        Poll::Ready(None) => {
-                            return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                HandlerError::connection_reset(),
-                            ))
-                        }
-                        Poll::Ready(Some(Err(e))) => {
-                            return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()))
-                        }
+                        Poll::Ready(None) => return self.close(HandlerError::connection_reset()),
+                        Poll::Ready(Some(Err(e))) => return self.close(e.into()),
                         Poll::Pending => break,
                     }
                 }
@@ -545,20 +578,18 @@ impl ProtocolsHandler for DiscoveryHandler {
                                         let interval = now - last_update_time;
                                         if interval < self.config.min_recv_update_interval {
                                             // TODO: Should we just close, or ban?
-                                            return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                                HandlerError::TooFrequentUpdates { interval },
-                                            ));
+                                            return self.close(HandlerError::TooFrequentUpdates {
+                                                interval,
+                                            });
                                         }
                                     }
                                     self.last_update_time = Some(now);
 
                                     // Check if the update is not too large.
                                     if peer_contacts.len() > self.config.update_limit as usize {
-                                        return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                            HandlerError::UpdateLimitExceeded {
-                                                num_peer_contacts: peer_contacts.len(),
-                                            },
-                                        ));
+                                        return self.close(HandlerError::UpdateLimitExceeded {
+                                            num_peer_contacts: peer_contacts.len(),
+                                        });
                                     }
 
                                     // Insert the new peer contacts into the peer contact book.
@@ -574,23 +605,15 @@ impl ProtocolsHandler for DiscoveryHandler {
                                 }
 
                                 _ => {
-                                    return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                        HandlerError::UnexpectedMessage {
-                                            message,
-                                            state: self.state,
-                                        },
-                                    ))
+                                    return self.close(HandlerError::UnexpectedMessage {
+                                        message,
+                                        state: self.state,
+                                    })
                                 }
                             }
                         }
-                        Poll::Ready(None) => {
-                            return Poll::Ready(ProtocolsHandlerEvent::Close(
-                                HandlerError::connection_reset(),
-                            ))
-                        }
-                        Poll::Ready(Some(Err(e))) => {
-                            return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()))
-                        }
+                        Poll::Ready(None) => return self.close(HandlerError::connection_reset()),
+                        Poll::Ready(Some(Err(e))) => return self.close(e.into()),
                         Poll::Pending => {}
                     }
 
@@ -605,7 +628,7 @@ impl ProtocolsHandler for DiscoveryHandler {
                                     let msg = DiscoveryMessage::PeerAddresses { peer_contacts };
 
                                     if let Err(e) = self.send(&msg) {
-                                        return Poll::Ready(ProtocolsHandlerEvent::Close(e.into()));
+                                        return self.close(e.into());
                                     }
                                 }
                             }
@@ -0,0 +1,185 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use libp2p::{
+    core::upgrade::{NegotiationError, UpgradeError},
+    swarm::ProtocolsHandlerUpgrErr,
+};
+
+use super::handler::HandlerError;
+
+/// The upper bounds of the buckets by which successful handshakes are counted.
+pub const HANDSHAKE_DURATION_BUCKETS: [Duration; 6] = [
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+];
+
+/// Why a discovery handshake failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandshakeFailure {
+    /// The peer doesn't support our version of the discovery protocol.
+    VersionMismatch,
+
+    /// The handshake didn't complete within the handshake timeout.
+    Timeout,
+
+    /// We rejected the peer's handshake, e.g. because it is for a different network or the peer failed the challenge.
+    Rejected,
+
+    /// The connection failed during the handshake.
+    ConnectionError,
+}
+
+impl From<&HandlerError> for HandshakeFailure {
+    fn from(error: &HandlerError) -> Self {
+        match error {
+            HandlerError::HandshakeTimeout => HandshakeFailure::Timeout,
+            HandlerError::Io(_) | HandlerError::Serialization(_) | HandlerError::Signing(_) => {
+                HandshakeFailure::ConnectionError
+            }
+            HandlerError::UnexpectedMessage { .. }
+            | HandlerError::GenesisHashMismatch { .. }
+            | HandlerError::InvalidPeerContactSignature { .. }
+            | HandlerError::ChallengeResponseFailed
+            | HandlerError::TooFrequentUpdates { .. }
+            | HandlerError::UpdateLimitExceeded { .. } => HandshakeFailure::Rejected,
+        }
+    }
+}
+
+impl<E> From<&ProtocolsHandlerUpgrErr<E>> for HandshakeFailure {
+    fn from(error: &ProtocolsHandlerUpgrErr<E>) -> Self {
+        match error {
+            ProtocolsHandlerUpgrErr::Timeout | ProtocolsHandlerUpgrErr::Timer => {
+                HandshakeFailure::Timeout
+            }
+            ProtocolsHandlerUpgrErr::Upgrade(UpgradeError::Select(NegotiationError::Failed)) => {
+                HandshakeFailure::VersionMismatch
+            }
+            ProtocolsHandlerUpgrErr::Upgrade(_) => HandshakeFailure::ConnectionError,
+        }
+    }
+}
+
+/// Statistics about the discovery handshakes of all connections. A handshake is timed from the moment the connection
+/// is accepted or dialed until the peer exchange is established.
+#[derive(Clone, Debug, Default)]
+pub struct HandshakeMetrics {
+    /// The number of successful handshakes per duration bucket. The last bucket counts the handshakes that took
+    /// longer than the largest bound in `HANDSHAKE_DURATION_BUCKETS`.
+    duration_buckets: [u64; HANDSHAKE_DURATION_BUCKETS.len() + 1],
+
+    /// The sum of the durations of all successful handshakes.
+    total_duration: Duration,
+
+    /// The number of failed handshakes per reason.
+    failures: BTreeMap<HandshakeFailure, u64>,
+}
+
+impl HandshakeMetrics {
+    pub fn record_success(&mut self, duration: Duration) {
+        let bucket = HANDSHAKE_DURATION_BUCKETS
+            .iter()
+            .position(|bound| duration <= *bound)
+            .unwrap_or(HANDSHAKE_DURATION_BUCKETS.len());
+
+        self.duration_buckets[bucket] += 1;
+        self.total_duration += duration;
+    }
+
+    pub fn record_failure(&mut self, failure: HandshakeFailure) {
+        *self.failures.entry(failure).or_default() += 1;
+    }
+
+    /// Returns the number of successful handshakes.
+    pub fn num_successes(&self) -> u64 {
+        self.duration_buckets.iter().sum()
+    }
+
+    /// Returns the number of successful handshakes per duration bucket, as pairs of the bucket's upper bound and the
+    /// count. The bound of the last bucket is `None`, it counts all handshakes that took longer than the other bounds.
+    pub fn duration_buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        HANDSHAKE_DURATION_BUCKETS
+            .iter()
+            .copied()
+            .map(Some)
+            .chain(std::iter::once(None))
+            .zip(self.duration_buckets.iter().copied())
+    }
+
+    /// Returns the average duration of the successful handshakes, or `None` if there weren't any.
+    pub fn average_duration(&self) -> Option<Duration> {
+        match self.num_successes() {
+            0 => None,
+            n => Some(self.total_duration / n as u32),
+        }
+    }
+
+    /// Returns the number of handshakes that failed for the given reason.
+    pub fn num_failures(&self, failure: HandshakeFailure) -> u64 {
+        self.failures.get(&failure).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of failed handshakes per reason.
+    pub fn failures(&self) -> &BTreeMap<HandshakeFailure, u64> {
+        &self.failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nimiq_hash::Blake2bHash;
+
+    use super::*;
+
+    #[test]
+    fn it_records_handshake_durations() {
+        let mut metrics = HandshakeMetrics::default();
+        assert_eq!(metrics.average_duration(), None);
+
+        metrics.record_success(Duration::from_millis(20));
+        metrics.record_success(Duration::from_millis(100));
+        metrics.record_success(Duration::from_secs(6));
+
+        assert_eq!(metrics.num_successes(), 3);
+        assert_eq!(
+            metrics.average_duration(),
+            Some(Duration::from_millis(2040))
+        );
+
+        let buckets: Vec<_> = metrics.duration_buckets().collect();
+        assert_eq!(buckets[0], (Some(Duration::from_millis(50)), 1));
+        assert_eq!(buckets[1], (Some(Duration::from_millis(100)), 1));
+        assert_eq!(buckets[6], (None, 1));
+        assert!(metrics.failures().is_empty());
+    }
+
+    #[test]
+    fn it_records_handshake_failure_reasons() {
+        let mut metrics = HandshakeMetrics::default();
+
+        metrics.record_failure((&HandlerError::HandshakeTimeout).into());
+        metrics.record_failure(
+            (&HandlerError::GenesisHashMismatch {
+                expected: Blake2bHash::default(),
+                received: Blake2bHash::default(),
+            })
+                .into(),
+        );
+        metrics.record_failure(
+            (&ProtocolsHandlerUpgrErr::<()>::Upgrade(UpgradeError::Select(
+                NegotiationError::Failed,
+            )))
+                .into(),
+        );
+
+        assert_eq!(metrics.num_successes(), 0);
+        assert_eq!(metrics.num_failures(HandshakeFailure::Timeout), 1);
+        assert_eq!(metrics.num_failures(HandshakeFailure::Rejected), 1);
+        assert_eq!(metrics.num_failures(HandshakeFailure::VersionMismatch), 1);
+        assert_eq!(metrics.num_failures(HandshakeFailure::ConnectionError), 0);
+    }
+}
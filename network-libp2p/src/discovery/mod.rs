@@ -1,5 +1,6 @@
 pub mod behaviour;
 pub mod handler;
 pub mod message_codec;
+pub mod metrics;
 pub mod peer_contacts;
 pub mod protocol;
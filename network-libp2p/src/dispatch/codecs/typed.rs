@@ -5,13 +5,17 @@
 //! Note that this doesn't actually serialize/deserialize the message content, but only handles reading/writing the
 //! message, extracting the type ID and performing consistency checks.
 //!
+//! If compression was negotiated with the peer, the body is prefixed with a flags byte, which denotes whether the
+//! remaining payload is deflate-compressed. Only large messages are compressed.
+//!
 
 use std::{
     fmt::Debug,
-    io::{self, Cursor},
+    io::{self, Cursor, Read, Write},
 };
 
 use bytes::{Buf, BytesMut};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use thiserror::Error;
 use tokio_util::codec::{Decoder, Encoder};
 
@@ -36,6 +40,15 @@ pub enum Error {
 
     #[error("Checksum mismatch. Expected: {0}, obtained: {1}")]
     ChecksumMismatch(u32, u32),
+
+    #[error("Invalid frame flags: {0:x}")]
+    InvalidFlags(u8),
+
+    #[error("Decompressed message exceeds {0} bytes")]
+    DecompressedTooLarge(usize),
+
+    #[error("Outbound buffer is full")]
+    BufferFull,
 }
 
 impl Error {
@@ -63,6 +76,11 @@ impl From<Error> for SendError {
             Error::ChecksumMismatch(_, _) => {
                 SendError::Serialization(SerializingError::InvalidValue)
             }
+            Error::InvalidFlags(_) => SendError::Serialization(SerializingError::InvalidValue),
+            Error::DecompressedTooLarge(_) => {
+                SendError::Serialization(SerializingError::InvalidValue)
+            }
+            Error::BufferFull => SendError::BufferFull,
         }
     }
 }
@@ -110,6 +128,26 @@ impl Header {
     }
 }
 
+/// Flags byte preceding the body of a frame if compression was negotiated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+enum FrameFlags {
+    /// The payload is not compressed.
+    None = 0,
+    /// The payload is deflate-compressed.
+    Deflate = 1,
+}
+
+impl FrameFlags {
+    fn from_u8(flags: u8) -> Result<Self, Error> {
+        match flags {
+            0 => Ok(FrameFlags::None),
+            1 => Ok(FrameFlags::Deflate),
+            flags => Err(Error::InvalidFlags(flags)),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 enum DecodeState {
     Head,
@@ -129,9 +167,86 @@ impl Default for DecodeState {
 #[derive(Clone, Debug, Default)]
 pub struct MessageCodec {
     state: DecodeState,
+    compression: bool,
 }
 
 impl MessageCodec {
+    /// Messages with a serialized size of at least this many bytes are compressed.
+    pub const COMPRESSION_THRESHOLD: usize = 1024;
+    /// The maximum size of a decompressed message.
+    pub const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Creates a codec. If `compression` is set, the frame bodies carry a flags byte and large messages are
+    /// compressed. This must only be enabled if the peer negotiated compression as well.
+    pub fn new(compression: bool) -> Self {
+        Self {
+            state: DecodeState::default(),
+            compression,
+        }
+    }
+
+    fn decompress(mut data: BytesMut) -> Result<BytesMut, Error> {
+        if !data.has_remaining() {
+            return Err(Error::eof());
+        }
+
+        match FrameFlags::from_u8(data.get_u8())? {
+            FrameFlags::None => Ok(data),
+            FrameFlags::Deflate => {
+                let mut decompressed = Vec::new();
+                DeflateDecoder::new(data.as_ref())
+                    .take(Self::MAX_DECOMPRESSED_SIZE as u64 + 1)
+                    .read_to_end(&mut decompressed)?;
+
+                if decompressed.len() > Self::MAX_DECOMPRESSED_SIZE {
+                    return Err(Error::DecompressedTooLarge(Self::MAX_DECOMPRESSED_SIZE));
+                }
+
+                Ok(BytesMut::from(&decompressed[..]))
+            }
+        }
+    }
+
+    /// Writes a frame with the given type ID and body to `dst` and fills in the length and checksum.
+    fn write_frame<F>(
+        type_id: u64,
+        body_length: usize,
+        dst: &mut BytesMut,
+        write_body: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Cursor<&mut [u8]>) -> Result<(), Error>,
+    {
+        let mut header = Header::new(type_id);
+        let message_length = Header::SIZE + body_length;
+        header.length = message_length as u32;
+
+        let existing_length = dst.len();
+        dst.reserve(message_length);
+        dst.resize(existing_length + message_length, 0);
+
+        // Go to the bottom of the buffer to write the data
+        let mut c = Cursor::new(dst.as_mut());
+        c.set_position(existing_length as u64);
+
+        // Write header
+        header.serialize(&mut c)?;
+
+        // Write body
+        write_body(&mut c)?;
+
+        // Calculate the CRC
+        let crc = Crc32Computer::default()
+            .update(&c.get_ref()[existing_length..])
+            .result();
+
+        // Write the CRC in the respective field in the header
+        c.set_position((existing_length + Header::SIZE - 4) as u64);
+        crc.serialize(&mut c)?;
+
+        Ok(())
+    }
+
     fn verify(&self, declared_crc: u32, data: &mut BytesMut) -> Result<(), Error> {
         let mut crc_comp = Crc32Computer::default();
 
@@ -220,6 +335,10 @@ impl Decoder for MessageCodec {
 
                         self.state = DecodeState::Head;
 
+                        if self.compression {
+                            data = Self::decompress(data)?;
+                        }
+
                         return Ok(Some((MessageType::new(message_type), data)));
                     } else {
                         // We still need to read more of the message body
@@ -243,33 +362,112 @@ impl<M: Message> Encoder<&M> for MessageCodec {
     type Error = Error;
 
     fn encode(&mut self, message: &M, dst: &mut BytesMut) -> Result<(), Error> {
-        let mut header = Header::new(M::TYPE_ID);
-        let message_length = Header::SIZE + message.serialized_size();
-        header.length = message_length as u32;
+        let serialized_size = message.serialized_size();
 
-        let existing_length = dst.len();
-        dst.reserve(message_length);
-        dst.resize(existing_length + message_length, 0);
+        if !self.compression {
+            return Self::write_frame(M::TYPE_ID, serialized_size, dst, |c| {
+                message.serialize(c)?;
+                Ok(())
+            });
+        }
 
-        // Go to the bottom of the buffer to write the data
-        let mut c = Cursor::new(dst.as_mut());
-        c.set_position(existing_length as u64);
+        let mut payload = message.serialize_to_vec();
+        let mut flags = FrameFlags::None;
 
-        // Write header
-        header.serialize(&mut c)?;
+        // Only send the compressed payload if it's actually smaller.
+        if serialized_size >= Self::COMPRESSION_THRESHOLD {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&payload)?;
+            let compressed = encoder.finish()?;
 
-        // Serialize message
-        message.serialize(&mut c)?;
+            if compressed.len() < payload.len() {
+                payload = compressed;
+                flags = FrameFlags::Deflate;
+            }
+        }
 
-        // Calculate the CRC
-        let crc = Crc32Computer::default()
-            .update(&c.get_ref()[existing_length..])
-            .result();
+        Self::write_frame(M::TYPE_ID, 1 + payload.len(), dst, |c| {
+            (flags as u8).serialize(c)?;
+            c.write_all(&payload)?;
+            Ok(())
+        })
+    }
+}
 
-        // Write the CRC in the respective field in the header
-        c.set_position((existing_length + Header::SIZE - 4) as u64);
-        crc.serialize(&mut c)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(())
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct TestMessage {
+        #[beserial(len_type(u32))]
+        data: Vec<u8>,
+    }
+
+    impl Message for TestMessage {
+        const TYPE_ID: u64 = 42;
+    }
+
+    impl TestMessage {
+        /// A message resembling a block, i.e. large and with a lot of redundancy.
+        fn large() -> Self {
+            Self {
+                data: (0..64 * 1024).map(|i| (i % 7) as u8).collect(),
+            }
+        }
+    }
+
+    fn round_trip(codec: &mut MessageCodec, message: &TestMessage) -> usize {
+        let mut buf = BytesMut::new();
+        codec.encode(message, &mut buf).unwrap();
+        let frame_size = buf.len();
+
+        let (message_type, data) = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(message_type, MessageType::new(TestMessage::TYPE_ID));
+        assert_eq!(&TestMessage::deserialize_from_vec(&data).unwrap(), message);
+        assert!(buf.is_empty());
+
+        frame_size
+    }
+
+    #[test]
+    fn it_round_trips_compressed_messages() {
+        let mut codec = MessageCodec::new(true);
+
+        let small = TestMessage {
+            data: vec![1, 2, 3],
+        };
+        assert_eq!(
+            round_trip(&mut codec, &small),
+            Header::SIZE + 1 + small.serialized_size()
+        );
+
+        round_trip(&mut codec, &TestMessage::large());
+    }
+
+    #[test]
+    fn large_messages_shrink_on_the_wire() {
+        let message = TestMessage::large();
+
+        let uncompressed_size = round_trip(&mut MessageCodec::new(false), &message);
+        let compressed_size = round_trip(&mut MessageCodec::new(true), &message);
+
+        assert_eq!(uncompressed_size, Header::SIZE + message.serialized_size());
+        assert!(compressed_size < uncompressed_size / 10);
+    }
+
+    #[test]
+    fn it_rejects_invalid_flags() {
+        let mut buf = BytesMut::new();
+        MessageCodec::write_frame(TestMessage::TYPE_ID, 2, &mut buf, |c| {
+            c.write_all(&[0x80, 0])?;
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(matches!(
+            MessageCodec::new(true).decode(&mut buf),
+            Err(Error::InvalidFlags(0x80))
+        ));
     }
 }
@@ -80,17 +80,21 @@ impl<C> MessageDispatch<C>
 where
     C: AsyncRead + AsyncWrite + Send + Sync + 'static + Unpin,
 {
+    /// Maximum number of outbound messages queued for the socket before `send` fails.
+    const MAX_OUTBOUND_MESSAGES: usize = 1024;
+
     ///
     /// # Arguments
     ///
     ///  - `socket`: The underlying socket
     ///  - `max_buffered`: Maximum number of buffered messages. Must be at least 1.
+    ///  - `compression`: Whether message compression was negotiated with the peer.
     ///
-    pub fn new(socket: C, channel_size: usize) -> Self {
+    pub fn new(socket: C, channel_size: usize, compression: bool) -> Self {
         Self {
             framed: Box::pin(Framed::new(
                 TokioAdapter::new(socket),
-                MessageCodec::default(),
+                MessageCodec::new(compression),
             )),
             channels: HashMap::new(),
             buffer: None,
@@ -101,6 +105,11 @@ where
     }
 
     pub fn send<M: Message>(&mut self, message: M) -> Result<(), Error> {
+        // Don't buffer an unbounded number of messages for a peer that doesn't read them.
+        if self.outbound_messages.len() >= Self::MAX_OUTBOUND_MESSAGES {
+            return Err(Error::BufferFull);
+        }
+
         self.outbound_messages
             .push_back(Box::new(move |sink: Pin<&mut FramedStream<C>>| {
                 Sink::<&M>::start_send(sink, &message)
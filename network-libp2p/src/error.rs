@@ -7,6 +7,13 @@ pub enum NetworkError {
     #[error("Dial error: {0}")]
     Dial(#[from] libp2p::swarm::DialError),
 
+    #[error("Failed to listen on {address}: {error}")]
+    Listen {
+        address: libp2p::Multiaddr,
+        #[source]
+        error: libp2p::TransportError<std::io::Error>,
+    },
+
     #[error("Failed to send action to swarm task: {0}")]
     Send(#[from] futures::channel::mpsc::SendError),
 
@@ -39,6 +46,12 @@ pub enum NetworkError {
 
     #[error("Already unsubscribed to topic: {topic_name}")]
     AlreadyUnsubscribed { topic_name: &'static str },
+
+    #[error("TLS error: {0}")]
+    Tls(#[from] libp2p::websocket::tls::Error),
+
+    #[error("Invalid TLS config: {0}")]
+    InvalidTlsConfig(String),
 }
 
 impl From<libp2p::kad::store::Error> for NetworkError {
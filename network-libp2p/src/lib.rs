@@ -16,10 +16,11 @@ mod network;
 pub mod peer;
 
 pub const MESSAGE_PROTOCOL: &[u8] = b"/nimiq/message/0.0.1";
+pub const MESSAGE_PROTOCOL_DEFLATE: &[u8] = b"/nimiq/message/0.0.1/deflate";
 pub const DISCOVERY_PROTOCOL: &[u8] = b"/nimiq/discovery/0.0.1";
 
 pub use libp2p::{self, core::network::NetworkInfo, identity::Keypair, Multiaddr, PeerId};
 
-pub use config::Config;
+pub use config::{Config, PeerLimitsConfig, ReconnectConfig, TlsConfig};
 pub use error::NetworkError;
 pub use network::Network;
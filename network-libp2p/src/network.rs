@@ -16,7 +16,9 @@ use libp2p::core::transport::MemoryTransport;
 use libp2p::{
     core,
     core::{
-        connection::ConnectionLimits, muxing::StreamMuxerBox, network::NetworkInfo,
+        connection::{ConnectionError, ConnectionLimits},
+        muxing::StreamMuxerBox,
+        network::NetworkInfo,
         transport::Boxed,
     },
     dns,
@@ -30,9 +32,12 @@ use libp2p::{
         Quorum, Record,
     },
     noise,
-    swarm::{dial_opts::DialOpts, SwarmBuilder, SwarmEvent},
+    swarm::{
+        dial_opts::DialOpts, protocols_handler::NodeHandlerWrapperError, SwarmBuilder, SwarmEvent,
+    },
     tcp, websocket, yamux, Multiaddr, PeerId, Swarm, Transport,
 };
+use parking_lot::RwLock;
 use tokio::sync::broadcast;
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::Instrument;
@@ -50,9 +55,13 @@ use nimiq_validator_network::validator_record::SignedValidatorRecord;
 
 use crate::{
     behaviour::{NimiqBehaviour, NimiqEvent, NimiqNetworkBehaviourError},
-    connection_pool::behaviour::ConnectionPoolEvent,
+    connection_pool::{
+        behaviour::ConnectionPoolEvent,
+        scores::{INVALID_MESSAGE_PENALTY, PROTOCOL_ERROR_PENALTY},
+    },
+    discovery::metrics::HandshakeMetrics,
     peer::Peer,
-    Config, NetworkError,
+    Config, NetworkError, TlsConfig,
 };
 
 /// Maximum simultaneous libp2p connections per peer
@@ -110,8 +119,12 @@ pub(crate) enum NetworkAction {
     },
     ListenOn {
         listen_addresses: Vec<Multiaddr>,
+        output: oneshot::Sender<Result<(), NetworkError>>,
     },
     StartConnecting,
+    PeerScores {
+        output: oneshot::Sender<HashMap<PeerId, f64>>,
+    },
 }
 
 #[derive(Default)]
@@ -139,6 +152,7 @@ pub struct Network {
     events_tx: broadcast::Sender<NetworkEvent<Peer>>,
     action_tx: mpsc::Sender<NetworkAction>,
     peers: ObservablePeerMap<Peer>,
+    handshake_metrics: Arc<RwLock<HandshakeMetrics>>,
 }
 
 impl Network {
@@ -155,6 +169,7 @@ impl Network {
         let swarm = Self::new_swarm(clock, config, peers.clone());
 
         let local_peer_id = *Swarm::local_peer_id(&swarm);
+        let handshake_metrics = swarm.behaviour().discovery.handshake_metrics();
 
         let (events_tx, _) = broadcast::channel(64);
         let (action_tx, action_rx) = mpsc::channel(64);
@@ -166,23 +181,31 @@ impl Network {
             events_tx,
             action_tx,
             peers,
+            handshake_metrics,
         }
     }
 
-    fn new_transport(keypair: &Keypair) -> std::io::Result<Boxed<(PeerId, StreamMuxerBox)>> {
+    fn new_transport(
+        keypair: &Keypair,
+        tls: Option<&TlsConfig>,
+    ) -> std::io::Result<Boxed<(PeerId, StreamMuxerBox)>> {
         // Websocket over TCP/DNS
-        #[cfg(not(test))]
-        let transport = websocket::WsConfig::new(dns::TokioDnsConfig::system(
+        let mut transport = websocket::WsConfig::new(dns::TokioDnsConfig::system(
             tcp::TokioTcpConfig::new().nodelay(true),
         )?);
 
+        // Secure websockets are terminated here, below the noise handshake.
+        if let Some(tls) = tls {
+            let tls_config = tls
+                .to_websocket_config()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            transport.set_tls_config(tls_config);
+        }
+
         // Memory transport for testing
         // TODO: Use websocket over the memory transport
         #[cfg(test)]
-        let transport = websocket::WsConfig::new(dns::TokioDnsConfig::system(
-            tcp::TokioTcpConfig::new().nodelay(true),
-        )?)
-        .or_transport(MemoryTransport::default());
+        let transport = transport.or_transport(MemoryTransport::default());
 
         let noise_keys = noise::Keypair::<noise::X25519Spec>::new()
             .into_authentic(keypair)
@@ -203,7 +226,7 @@ impl Network {
     ) -> Swarm<NimiqBehaviour> {
         let local_peer_id = PeerId::from(config.keypair.public());
 
-        let transport = Self::new_transport(&config.keypair).unwrap();
+        let transport = Self::new_transport(&config.keypair, config.tls.as_ref()).unwrap();
 
         let behaviour = NimiqBehaviour::new(config, clock, peers);
 
@@ -227,6 +250,11 @@ impl Network {
         &self.local_peer_id
     }
 
+    /// Returns a snapshot of the durations and failure reasons of the discovery handshakes.
+    pub fn handshake_metrics(&self) -> HandshakeMetrics {
+        self.handshake_metrics.read().clone()
+    }
+
     async fn swarm_task(
         mut swarm: NimiqSwarm,
         events_tx: broadcast::Sender<NetworkEvent<Peer>>,
@@ -293,8 +321,9 @@ impl Network {
                     }
                 }
 
-                // Save dialed peer addresses
-                if endpoint.is_dialer() {
+                // Save dialed peer addresses. Connections to ourselves are rejected by the
+                // connection pool, so we don't save our own address.
+                if endpoint.is_dialer() && peer_id != *Swarm::local_peer_id(swarm) {
                     let listen_addr = endpoint.get_remote_address();
 
                     tracing::debug!("Saving peer {} listen address: {:?}", peer_id, listen_addr);
@@ -327,12 +356,15 @@ impl Network {
                     num_established
                 );
 
+                let behavior = swarm.behaviour_mut();
+
                 if let Some(cause) = cause {
                     tracing::info!("Connection closed because: {:?}", cause);
+                    if is_protocol_error(&cause) {
+                        behavior.pool.penalize_peer(peer_id, PROTOCOL_ERROR_PENALTY);
+                    }
                 }
 
-                let behavior = swarm.behaviour_mut();
-
                 // Remove Peer
                 if let Some(peer) = behavior.pool.peers.remove(&peer_id) {
                     // Remove peer addresses from the DHT if they are present
@@ -565,6 +597,11 @@ impl Network {
                             ConnectionPoolEvent::PeerJoined { peer } => {
                                 events_tx.send(NetworkEvent::<Peer>::PeerJoined(peer)).ok();
                             }
+                            ConnectionPoolEvent::Reconnecting { attempt } => {
+                                events_tx
+                                    .send(NetworkEvent::<Peer>::Reconnecting(attempt))
+                                    .ok();
+                            }
                         };
                     }
                 }
@@ -710,6 +747,13 @@ impl Network {
                 acceptance,
                 output,
             } => {
+                if let MessageAcceptance::Reject = acceptance {
+                    swarm
+                        .behaviour_mut()
+                        .pool
+                        .penalize_peer(source, INVALID_MESSAGE_PENALTY);
+                }
+
                 output
                     .send(
                         swarm
@@ -723,15 +767,23 @@ impl Network {
             NetworkAction::ReceiveFromAll { type_id, output } => {
                 swarm.behaviour_mut().pool.receive_from_all(type_id, output);
             }
-            NetworkAction::ListenOn { listen_addresses } => {
-                for listen_address in listen_addresses {
-                    Swarm::listen_on(swarm, listen_address)
-                        .expect("Failed to listen on provided address");
-                }
+            NetworkAction::ListenOn {
+                listen_addresses,
+                output,
+            } => {
+                let result = listen_addresses.into_iter().try_for_each(|address| {
+                    Swarm::listen_on(swarm, address.clone())
+                        .map(|_| ())
+                        .map_err(|error| NetworkError::Listen { address, error })
+                });
+                output.send(result).ok();
             }
             NetworkAction::StartConnecting => {
                 swarm.behaviour_mut().pool.start_connecting();
             }
+            NetworkAction::PeerScores { output } => {
+                output.send(swarm.behaviour().pool.peer_scores()).ok();
+            }
         }
     }
 
@@ -745,13 +797,32 @@ impl Network {
         Ok(output_rx.await?)
     }
 
-    pub async fn listen_on(&self, listen_addresses: Vec<Multiaddr>) {
+    /// Returns the scores of all peers that committed protocol violations recently. Peers that
+    /// are not included have a clean score of 0.
+    pub async fn peer_scores(&self) -> Result<HashMap<PeerId, f64>, NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
         self.action_tx
             .clone()
-            .send(NetworkAction::ListenOn { listen_addresses })
-            .await
-            .map_err(|e| tracing::error!("Failed to send NetworkAction::ListenOnAddress: {:?}", e))
-            .ok();
+            .send(NetworkAction::PeerScores { output: output_tx })
+            .await?;
+        Ok(output_rx.await?)
+    }
+
+    /// Listens on all of the given addresses. Connections accepted on any of them end up in the
+    /// same peer pool. Fails with the first address that isn't supported by the transport or that
+    /// can't be bound.
+    pub async fn listen_on(&self, listen_addresses: Vec<Multiaddr>) -> Result<(), NetworkError> {
+        let (output_tx, output_rx) = oneshot::channel();
+
+        self.action_tx
+            .clone()
+            .send(NetworkAction::ListenOn {
+                listen_addresses,
+                output: output_tx,
+            })
+            .await?;
+        output_rx.await?
     }
 
     pub async fn start_connecting(&self) {
@@ -1003,6 +1074,23 @@ impl NetworkInterface for Network {
     fn get_local_peer_id(&self) -> <Self::PeerType as PeerInterface>::Id {
         self.local_peer_id
     }
+
+    async fn get_peer_scores(&self) -> HashMap<PeerId, f64> {
+        self.peer_scores().await.unwrap_or_else(|error| {
+            tracing::error!("Failed to get peer scores: {:?}", error);
+            HashMap::new()
+        })
+    }
+}
+
+/// Returns whether a connection was closed because a protocol handler failed, e.g. because the
+/// peer violated a protocol. Transport errors and idle connections timing out are not the peer's
+/// fault.
+fn is_protocol_error<E>(cause: &ConnectionError<NodeHandlerWrapperError<E>>) -> bool {
+    matches!(
+        cause,
+        ConnectionError::Handler(NodeHandlerWrapperError::Handler(_))
+    )
 }
 
 #[cfg(test)]
@@ -1034,6 +1122,7 @@ mod tests {
             peer_contacts::{PeerContact, Protocols, Services},
         },
         peer::Peer,
+        NetworkError,
     };
 
     use super::{Config, Network};
@@ -1087,9 +1176,13 @@ mod tests {
                 min_send_update_interval: Duration::from_secs(30),
                 house_keeping_interval: Duration::from_secs(60),
                 keep_alive: KeepAlive::No,
+                handshake_timeout: Duration::from_secs(30),
             },
             kademlia: Default::default(),
             gossipsub,
+            reconnect: Default::default(),
+            peer_limits: Default::default(),
+            tls: None,
         }
     }
 
@@ -1129,7 +1222,7 @@ mod tests {
 
             let clock = Arc::new(OffsetTime::new());
             let net = Network::new(clock, network_config(address.clone())).await;
-            net.listen_on(vec![address.clone()]).await;
+            net.listen_on(vec![address.clone()]).await.unwrap();
 
             tracing::debug!(address = ?address, peer_id = ?net.local_peer_id, "creating node");
 
@@ -1165,10 +1258,10 @@ mod tests {
         let addr2 = multiaddr![Memory(thread_rng().gen::<u64>())];
 
         let net1 = Network::new(Arc::new(OffsetTime::new()), network_config(addr1.clone())).await;
-        net1.listen_on(vec![addr1.clone()]).await;
+        net1.listen_on(vec![addr1.clone()]).await.unwrap();
 
         let net2 = Network::new(Arc::new(OffsetTime::new()), network_config(addr2.clone())).await;
-        net2.listen_on(vec![addr2.clone()]).await;
+        net2.listen_on(vec![addr2.clone()]).await.unwrap();
 
         tracing::debug!(address = ?addr1, peer_id = ?net1.local_peer_id, "Network 1");
         tracing::debug!(address = ?addr2, peer_id = ?net2.local_peer_id, "Network 2");
@@ -1209,7 +1302,7 @@ mod tests {
 
             let network =
                 Network::new(Arc::new(OffsetTime::new()), network_config(addr.clone())).await;
-            network.listen_on(vec![addr.clone()]).await;
+            network.listen_on(vec![addr.clone()]).await.unwrap();
 
             tracing::debug!(address = ?addr, peer_id = ?network.local_peer_id, "Network {}",peer);
             networks.push(network);
@@ -1347,6 +1440,53 @@ mod tests {
         assert_eq!(peer1.id(), net1.local_peer_id);
     }
 
+    #[tokio::test]
+    async fn connections_on_all_listen_addresses_share_a_pool() {
+        let addr1a = multiaddr![Memory(thread_rng().gen::<u64>())];
+        let addr1b = multiaddr![Memory(thread_rng().gen::<u64>())];
+        let addr2 = multiaddr![Memory(thread_rng().gen::<u64>())];
+        let addr3 = multiaddr![Memory(thread_rng().gen::<u64>())];
+
+        let net1 = Network::new(Arc::new(OffsetTime::new()), network_config(addr1a.clone())).await;
+        net1.listen_on(vec![addr1a.clone(), addr1b.clone()])
+            .await
+            .unwrap();
+
+        let net2 = Network::new(Arc::new(OffsetTime::new()), network_config(addr2.clone())).await;
+        net2.listen_on(vec![addr2]).await.unwrap();
+
+        let net3 = Network::new(Arc::new(OffsetTime::new()), network_config(addr3.clone())).await;
+        net3.listen_on(vec![addr3]).await.unwrap();
+
+        let mut events1 = net1.subscribe_events();
+
+        // Connect to network 1 once through each of its listen addresses.
+        net2.dial_address(addr1a).await.unwrap();
+        let event = events1.next().await.unwrap().unwrap();
+        assert_peer_joined(&event, &net2.local_peer_id);
+
+        net3.dial_address(addr1b).await.unwrap();
+        let event = events1.next().await.unwrap().unwrap();
+        assert_peer_joined(&event, &net3.local_peer_id);
+
+        assert_eq!(net1.get_peers().len(), 2);
+        assert!(net1.get_peer(*net2.local_peer_id()).is_some());
+        assert!(net1.get_peer(*net3.local_peer_id()).is_some());
+    }
+
+    #[tokio::test]
+    async fn listening_on_an_unsupported_address_fails() {
+        let addr1 = multiaddr![Memory(thread_rng().gen::<u64>())];
+        let unsupported: Multiaddr = "/ip4/127.0.0.1/udp/8443".parse().unwrap();
+
+        let net = Network::new(Arc::new(OffsetTime::new()), network_config(addr1.clone())).await;
+
+        match net.listen_on(vec![addr1, unsupported.clone()]).await {
+            Err(NetworkError::Listen { address, .. }) => assert_eq!(address, unsupported),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+
     #[tokio::test]
     async fn one_peer_can_talk_to_another() {
         let (net1, net2) = create_connected_networks().await;
@@ -1527,4 +1667,41 @@ mod tests {
         }
         net1.network_info().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn connections_to_ourselves_are_rejected() {
+        let addr = multiaddr![Memory(thread_rng().gen::<u64>())];
+        let net = Network::new(Arc::new(OffsetTime::new()), network_config(addr.clone())).await;
+        net.listen_on(vec![addr.clone()]).await.unwrap();
+
+        let mut events = net.subscribe_events();
+
+        // Depending on when the loopback is detected, the dial itself might fail already.
+        let _ = net.dial_address(addr).await;
+
+        if let Ok(Some(Ok(NetworkEvent::PeerJoined(peer)))) =
+            tokio::time::timeout(Duration::from_secs(1), events.next()).await
+        {
+            panic!("Connection to ourselves joined as peer {}", peer.id);
+        }
+        assert!(net.get_peers().is_empty());
+        assert!(net.get_peer(net.local_peer_id).is_none());
+    }
+
+    #[test]
+    fn only_protocol_errors_are_penalized() {
+        use libp2p::{
+            core::connection::ConnectionError, swarm::protocols_handler::NodeHandlerWrapperError,
+        };
+
+        assert!(super::is_protocol_error(&ConnectionError::Handler(
+            NodeHandlerWrapperError::Handler(())
+        )));
+        assert!(!super::is_protocol_error::<()>(&ConnectionError::Handler(
+            NodeHandlerWrapperError::KeepAliveTimeout
+        )));
+        assert!(!super::is_protocol_error::<()>(&ConnectionError::IO(
+            std::io::ErrorKind::ConnectionReset.into()
+        )));
+    }
 }
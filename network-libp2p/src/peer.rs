@@ -15,7 +15,7 @@ use parking_lot::Mutex;
 
 use nimiq_network_interface::message::Message;
 use nimiq_network_interface::peer::{
-    CloseReason, Peer as PeerInterface, RequestResponse, SendError,
+    CloseReason, ConnectionDirection, Peer as PeerInterface, RequestResponse, SendError,
 };
 
 use crate::{
@@ -26,6 +26,9 @@ use crate::{
 pub struct Peer {
     pub id: PeerId,
 
+    /// Whether the connection to this peer was established by the peer or by us.
+    direction: ConnectionDirection,
+
     pub(crate) dispatch: Arc<Mutex<MessageDispatch<NegotiatedSubstream>>>,
 
     /// Channel used to pass the close reason the the network handler.
@@ -35,11 +38,13 @@ pub struct Peer {
 impl Peer {
     pub fn new(
         id: PeerId,
+        direction: ConnectionDirection,
         dispatch: MessageDispatch<NegotiatedSubstream>,
         close_tx: oneshot::Sender<CloseReason>,
     ) -> Self {
         Self {
             id,
+            direction,
             dispatch: Arc::new(Mutex::new(dispatch)),
             close_tx: Mutex::new(Some(close_tx)),
         }
@@ -65,6 +70,7 @@ impl std::fmt::Debug for Peer {
         let mut debug = f.debug_struct("Peer");
 
         debug.field("peer_id", &self.id());
+        debug.field("direction", &self.direction);
 
         if self.close_tx.lock().is_none() {
             debug.field("closed", &true);
@@ -97,7 +103,15 @@ impl PeerInterface for Peer {
         self.id
     }
 
+    fn direction(&self) -> ConnectionDirection {
+        self.direction
+    }
+
     async fn send<M: Message>(&self, message: M) -> Result<(), SendError> {
+        self.try_send(message)
+    }
+
+    fn try_send<M: Message>(&self, message: M) -> Result<(), SendError> {
         self.dispatch.lock().send(message).map_err(|e| e.into())
     }
 
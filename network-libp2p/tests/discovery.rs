@@ -22,6 +22,7 @@ use nimiq_network_libp2p::discovery::peer_contacts::{
 };
 use nimiq_network_libp2p::discovery::{
     behaviour::{DiscoveryBehaviour, DiscoveryConfig, DiscoveryEvent},
+    metrics::HandshakeFailure,
     peer_contacts::{PeerContact, Protocols, Services},
 };
 use nimiq_utils::time::OffsetTime;
@@ -35,6 +36,10 @@ struct TestNode {
 
 impl TestNode {
     pub fn new() -> Self {
+        Self::with_genesis_hash(Blake2bHash::default())
+    }
+
+    pub fn with_genesis_hash(genesis_hash: Blake2bHash) -> Self {
         let keypair = Keypair::generate_ed25519();
         let peer_id = PeerId::from(keypair.public());
 
@@ -55,7 +60,7 @@ impl TestNode {
             .boxed();
 
         let config = DiscoveryConfig {
-            genesis_hash: Blake2bHash::default(),
+            genesis_hash,
             update_interval: Duration::from_secs(10),
             min_send_update_interval: Duration::from_secs(5),
             update_limit: 64,
@@ -64,6 +69,7 @@ impl TestNode {
             min_recv_update_interval: Duration::from_secs(1),
             house_keeping_interval: Duration::from_secs(1),
             keep_alive: KeepAlive::Yes,
+            handshake_timeout: Duration::from_secs(30),
         };
 
         let peer_contact = PeerContact {
@@ -231,6 +237,59 @@ pub async fn test_dialing_peer_from_contacts() {
     }
 }
 
+#[tokio::test]
+pub async fn test_handshake_duration_is_recorded() {
+    let mut node1 = TestNode::new();
+    let node2 = TestNode::new();
+
+    let handshake_metrics = node1.swarm.behaviour().handshake_metrics();
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    tokio::spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    // Run node 1 until the handshake completed.
+    while let Some(event) = node1.swarm.next().await {
+        if let SwarmEvent::Behaviour(DiscoveryEvent::Established { .. }) = event {
+            break;
+        }
+    }
+
+    let metrics = handshake_metrics.read();
+    assert_eq!(metrics.num_successes(), 1);
+    assert!(metrics.average_duration().is_some());
+    assert!(metrics.failures().is_empty());
+}
+
+#[tokio::test]
+pub async fn test_handshake_failure_reason_is_recorded() {
+    let mut node1 = TestNode::new();
+    let node2 = TestNode::with_genesis_hash(Blake2bHash::from([1u8; 32]));
+
+    let handshake_metrics = node1.swarm.behaviour().handshake_metrics();
+
+    node1.dial(node2.address.clone());
+
+    // Just run node 2
+    tokio::spawn(async move {
+        node2.swarm.for_each(|_| async {}).await;
+    });
+
+    // Run node 1 until the connection is closed because of the genesis hash mismatch.
+    while let Some(event) = node1.swarm.next().await {
+        if let SwarmEvent::ConnectionClosed { .. } = event {
+            break;
+        }
+    }
+
+    let metrics = handshake_metrics.read();
+    assert_eq!(metrics.num_successes(), 0);
+    assert_eq!(metrics.num_failures(HandshakeFailure::Rejected), 1);
+}
+
 #[test]
 fn test_housekeeping() {
     let mut config = PeerContactBookConfig::default();
@@ -74,7 +74,7 @@ pub mod tests {
     use nimiq_network_interface::{
         message::Message,
         network::{Network, NetworkEvent, Topic},
-        peer::Peer,
+        peer::{ConnectionDirection, Peer},
     };
 
     use super::network::MockNetworkError;
@@ -143,6 +143,34 @@ pub mod tests {
         assert_eq!(peer_ids, expected_peer_ids);
     }
 
+    #[tokio::test]
+    async fn peers_report_connection_direction() {
+        let mut hub = MockHub::default();
+
+        let net1 = hub.new_network();
+        let net2 = hub.new_network();
+        let mut events = net2.subscribe_events();
+
+        net1.dial_mock(&net2);
+
+        // net1 dialed net2, so the connection is outbound for net1 and inbound for net2.
+        let peer = net1.get_peer(net2.peer_id()).unwrap();
+        assert_eq!(peer.direction(), ConnectionDirection::Outbound);
+        assert!(peer.direction().is_outbound());
+
+        let peer = net2.get_peer(net1.peer_id()).unwrap();
+        assert_eq!(peer.direction(), ConnectionDirection::Inbound);
+        assert!(peer.direction().is_inbound());
+
+        // The direction is also available on the peer of the join event.
+        match events.next().await {
+            Some(Ok(NetworkEvent::PeerJoined(peer))) => {
+                assert_eq!(peer.direction(), ConnectionDirection::Inbound)
+            }
+            _ => panic!("Expected PeerJoined event with id={}", net1.peer_id()),
+        }
+    }
+
     // Copied straight from nimiq_network_libp2p::network
 
     #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
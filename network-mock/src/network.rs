@@ -12,7 +12,7 @@ use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 
 use beserial::{Deserialize, Serialize};
 use nimiq_network_interface::network::{MsgAcceptance, NetworkEvent, PubsubId, Topic};
-use nimiq_network_interface::peer::Peer;
+use nimiq_network_interface::peer::{ConnectionDirection, Peer};
 use nimiq_network_interface::{network::Network, peer_map::ObservablePeerMap};
 
 use crate::{hub::MockHubInner, peer::MockPeer, MockAddress, MockPeerId};
@@ -58,6 +58,7 @@ pub struct MockNetwork {
     peers: ObservablePeerMap<MockPeer>,
     hub: Arc<Mutex<MockHubInner>>,
     is_connected: Arc<AtomicBool>,
+    validated_messages: Arc<Mutex<Vec<(MockPeerId, MsgAcceptance)>>>,
 }
 
 impl MockNetwork {
@@ -87,6 +88,7 @@ impl MockNetwork {
             peers,
             hub,
             is_connected,
+            validated_messages: Default::default(),
         }
     }
 
@@ -98,6 +100,12 @@ impl MockNetwork {
         self.address.into()
     }
 
+    /// Returns the propagation source and acceptance of all pubsub messages validated so far, in
+    /// order. Only accepted messages would be relayed to other peers.
+    pub fn validated_messages(&self) -> Vec<(MockPeerId, MsgAcceptance)> {
+        self.validated_messages.lock().clone()
+    }
+
     fn dial_mock_address(&self, address: MockAddress) -> Result<(), MockNetworkError> {
         let hub = self.hub.lock();
 
@@ -112,6 +120,7 @@ impl MockNetwork {
             .insert(MockPeer {
                 network_address: address,
                 peer_id: self.address.into(),
+                direction: ConnectionDirection::Inbound,
                 hub: Arc::clone(&self.hub),
             });
 
@@ -120,6 +129,7 @@ impl MockNetwork {
             self.peers.insert(MockPeer {
                 network_address: self.address,
                 peer_id: address.into(),
+                direction: ConnectionDirection::Outbound,
                 hub: Arc::clone(&self.hub),
             });
 
@@ -307,10 +317,12 @@ impl Network for MockNetwork {
 
     async fn validate_message(
         &self,
-        _id: Self::PubsubId,
-        _acceptance: MsgAcceptance,
+        id: Self::PubsubId,
+        acceptance: MsgAcceptance,
     ) -> Result<bool, Self::Error> {
-        // TODO implement
+        self.validated_messages
+            .lock()
+            .push((id.propagation_source(), acceptance));
         Ok(true)
     }
 
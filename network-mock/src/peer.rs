@@ -15,7 +15,7 @@ use parking_lot::Mutex;
 
 use nimiq_network_interface::{
     message::Message,
-    peer::{CloseReason, Peer, RequestResponse, SendError},
+    peer::{CloseReason, ConnectionDirection, Peer, RequestResponse, SendError},
 };
 
 use crate::{
@@ -32,6 +32,9 @@ pub struct MockPeer {
     /// The peer's peer ID
     pub(crate) peer_id: MockPeerId,
 
+    /// Whether the peer dialed the network that sees it, or the other way around
+    pub(crate) direction: ConnectionDirection,
+
     pub(crate) hub: Arc<Mutex<MockHubInner>>,
 }
 
@@ -44,6 +47,10 @@ impl Peer for MockPeer {
         self.peer_id
     }
 
+    fn direction(&self) -> ConnectionDirection {
+        self.direction
+    }
+
     async fn send<T: Message>(&self, msg: T) -> Result<(), SendError> {
         let k = SenderKey {
             network_recipient: self.peer_id.into(),
@@ -74,6 +81,35 @@ impl Peer for MockPeer {
         Ok(())
     }
 
+    fn try_send<T: Message>(&self, msg: T) -> Result<(), SendError> {
+        let k = SenderKey {
+            network_recipient: self.peer_id.into(),
+            sender_peer: self.network_address.into(),
+            message_type: T::TYPE_ID,
+        };
+
+        log::trace!("Sending message: {:?}", msg);
+
+        let mut data = vec![];
+        msg.serialize_message(&mut data).unwrap();
+
+        // Use the hub's sender itself rather than a clone, since every fresh clone of a sender is
+        // guaranteed a slot in the channel and would never see it as full.
+        let mut hub = self.hub.lock();
+        if let Some(sender) = hub.network_senders.get_mut(&k) {
+            sender.try_send(data).map_err(|e| {
+                if e.is_full() {
+                    SendError::BufferFull
+                } else {
+                    SendError::AlreadyClosed
+                }
+            })
+        } else {
+            log::warn!("No such sender: {:?}", k);
+            Ok(())
+        }
+    }
+
     fn receive<T: Message>(&self) -> Pin<Box<dyn Stream<Item = T> + Send>> {
         let mut hub = self.hub.lock();
 
@@ -1,3 +1,6 @@
+use std::io::{Read, Write};
+
+use beserial::{Deserialize, Serialize};
 use nimiq_database::{
     Environment, ReadTransaction, Transaction as DBTransaction, WriteTransaction,
 };
@@ -5,10 +8,12 @@ use nimiq_hash::Blake2bHash;
 use nimiq_transaction::{Transaction, TransactionFlags};
 use nimiq_trie::key_nibbles::KeyNibbles;
 use nimiq_trie::trie::MerkleRadixTrie;
+use nimiq_trie::trie_proof::TrieProof;
+use nimiq_trie::trie_stats::TrieStats;
 
 use crate::{
     Account, AccountError, AccountInherentInteraction, AccountTransactionInteraction, Inherent,
-    Receipt, Receipts,
+    Receipt, Receipts, SnapshotError,
 };
 
 /// An alias for the accounts tree.
@@ -25,6 +30,9 @@ pub struct Accounts {
 }
 
 impl Accounts {
+    /// The number of accounts that are read from the tree at once when exporting a snapshot.
+    const SNAPSHOT_CHUNK_SIZE: usize = 1000;
+
     /// Creates a new, completely empty Accounts.
     pub fn new(env: Environment) -> Self {
         let tree = AccountsTrie::new(env.clone(), "AccountsTrie");
@@ -53,6 +61,111 @@ impl Accounts {
         }
     }
 
+    /// Returns the number of accounts and branch nodes in the Accounts Trie. The counters are
+    /// updated on every commit and revert, so this doesn't traverse the tree.
+    pub fn stats(&self, txn_option: Option<&DBTransaction>) -> TrieStats {
+        match txn_option {
+            Some(txn) => self.tree.stats(txn),
+            None => self.tree.stats(&ReadTransaction::new(&self.env)),
+        }
+    }
+
+    /// Returns all accounts together with their keys. It will traverse the entire tree.
+    pub fn get_all(&self, txn_option: Option<&DBTransaction>) -> Vec<(KeyNibbles, Account)> {
+        match txn_option {
+            Some(txn) => self
+                .tree
+                .get_chunk_with_keys(txn, &KeyNibbles::root(), usize::MAX),
+            None => self.tree.get_chunk_with_keys(
+                &ReadTransaction::new(&self.env),
+                &KeyNibbles::root(),
+                usize::MAX,
+            ),
+        }
+    }
+
+    /// Writes a snapshot of the Accounts Trie to `writer`. The snapshot consists of the root hash
+    /// followed by all accounts in key order. The accounts are read in chunks, so the tree is never
+    /// loaded into memory as a whole. Returns the root hash of the exported tree.
+    pub fn export_snapshot<W: Write>(
+        &self,
+        writer: &mut W,
+        txn_option: Option<&DBTransaction>,
+    ) -> Result<Blake2bHash, SnapshotError> {
+        match txn_option {
+            Some(txn) => self.export_snapshot_with(writer, txn),
+            None => self.export_snapshot_with(writer, &ReadTransaction::new(&self.env)),
+        }
+    }
+
+    fn export_snapshot_with<W: Write>(
+        &self,
+        writer: &mut W,
+        txn: &DBTransaction,
+    ) -> Result<Blake2bHash, SnapshotError> {
+        let root = self.tree.root_hash(txn);
+        root.serialize(writer)?;
+
+        let mut last_key: Option<KeyNibbles> = None;
+        loop {
+            let start = last_key.clone().unwrap_or_else(KeyNibbles::root);
+            let chunk = self
+                .tree
+                .get_chunk_with_keys(txn, &start, Self::SNAPSHOT_CHUNK_SIZE);
+            let chunk_size = chunk.len();
+
+            for (key, account) in chunk {
+                // The chunk includes the start key, which has already been written.
+                if matches!(&last_key, Some(last_key) if key <= *last_key) {
+                    continue;
+                }
+                true.serialize(writer)?;
+                key.serialize(writer)?;
+                account.serialize(writer)?;
+                last_key = Some(key);
+            }
+
+            if chunk_size < Self::SNAPSHOT_CHUNK_SIZE {
+                break;
+            }
+        }
+        false.serialize(writer)?;
+
+        Ok(root)
+    }
+
+    /// Imports a snapshot written by `export_snapshot` into an empty Accounts Trie and verifies
+    /// that the resulting root hash matches the one of the snapshot. The caller has to abort the
+    /// transaction if this fails.
+    pub fn import_snapshot<R: Read>(
+        &self,
+        txn: &mut WriteTransaction,
+        reader: &mut R,
+    ) -> Result<Blake2bHash, SnapshotError> {
+        if !self
+            .tree
+            .get_chunk_with_keys(txn, &KeyNibbles::root(), 1)
+            .is_empty()
+        {
+            return Err(SnapshotError::NotEmpty);
+        }
+
+        let expected: Blake2bHash = Deserialize::deserialize(reader)?;
+        while Deserialize::deserialize(reader)? {
+            let key: KeyNibbles = Deserialize::deserialize(reader)?;
+            let account: Account = Deserialize::deserialize(reader)?;
+            self.tree.put(txn, &key, account);
+        }
+        self.tree.update_root(txn);
+
+        let actual = self.get_root(Some(&*txn));
+        if actual != expected {
+            return Err(SnapshotError::RootMismatch { expected, actual });
+        }
+
+        Ok(actual)
+    }
+
     pub fn get(&self, key: &KeyNibbles, txn_option: Option<&DBTransaction>) -> Option<Account> {
         match txn_option {
             Some(txn) => self.tree.get(txn, key),
@@ -60,6 +173,30 @@ impl Accounts {
         }
     }
 
+    /// Produces a Merkle proof for the account with the given key against the current root of the
+    /// Accounts Trie. If the account exists, this is an inclusion proof containing the account.
+    /// Otherwise, it is an exclusion proof which can be checked with `TrieProof::verify_exclusion`.
+    pub fn get_proof(
+        &self,
+        key: &KeyNibbles,
+        txn_option: Option<&DBTransaction>,
+    ) -> TrieProof<Account> {
+        match txn_option {
+            Some(txn) => self.get_proof_with(key, txn),
+            None => self.get_proof_with(key, &ReadTransaction::new(&self.env)),
+        }
+    }
+
+    fn get_proof_with(&self, key: &KeyNibbles, txn: &DBTransaction) -> TrieProof<Account> {
+        let proof = if self.tree.get(txn, key).is_some() {
+            self.tree.get_proof(txn, vec![key])
+        } else {
+            self.tree.get_exclusion_proof(txn, key)
+        };
+
+        proof.expect("Either the inclusion or the exclusion of an account can always be proven")
+    }
+
     pub fn get_root(&self, txn_option: Option<&DBTransaction>) -> Blake2bHash {
         match txn_option {
             Some(txn) => self.tree.root_hash(txn),
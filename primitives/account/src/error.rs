@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 use beserial::SerializingError;
+use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 use nimiq_primitives::account::AccountType;
 use nimiq_primitives::coin::{Coin, CoinConvertError, CoinParseError};
@@ -42,3 +43,16 @@ pub enum AccountError {
     #[error("There is already an account at address {address} in the Accounts Tree.")]
     AlreadyExistentAddress { address: Address },
 }
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Failed to (de)serialize snapshot: {0}")]
+    Serializing(#[from] SerializingError),
+    #[error("The Accounts Tree is not empty")]
+    NotEmpty,
+    #[error("Root hash mismatch: snapshot has {expected}, but imported tree has {actual}")]
+    RootMismatch {
+        expected: Blake2bHash,
+        actual: Blake2bHash,
+    },
+}
@@ -7,7 +7,7 @@ pub use crate::account::Account;
 pub use crate::accounts::{Accounts, AccountsTrie};
 pub use crate::accounts_list::AccountsList;
 pub use crate::basic_account::BasicAccount;
-pub use crate::error::AccountError;
+pub use crate::error::{AccountError, SnapshotError};
 pub use crate::htlc_contract::*;
 pub use crate::inherent::{Inherent, InherentType};
 pub use crate::interaction_traits::*;
@@ -1,6 +1,7 @@
 use std::convert::TryFrom;
 
-use nimiq_account::{Accounts, Inherent, InherentType};
+use beserial::{Deserialize, Serialize};
+use nimiq_account::{Account, Accounts, BasicAccount, Inherent, InherentType, SnapshotError};
 use nimiq_account::{Receipt, Receipts};
 use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_database::WriteTransaction;
@@ -9,6 +10,8 @@ use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
 use nimiq_transaction::Transaction;
 use nimiq_trie::key_nibbles::KeyNibbles;
+use nimiq_trie::trie_proof::TrieProof;
+use nimiq_trie::trie_stats::TrieStats;
 
 #[test]
 fn it_can_commit_and_revert_a_block_body() {
@@ -150,6 +153,74 @@ fn it_can_commit_and_revert_a_block_body() {
     assert_eq!(hash1, accounts.get_root(None));
 }
 
+#[test]
+fn it_updates_the_stats_on_commit_and_revert() {
+    let env = VolatileEnvironment::new(10).unwrap();
+
+    let accounts = Accounts::new(env.clone());
+
+    let address_validator = Address::from([1u8; Address::SIZE]);
+
+    let address_recipient = Address::from([2u8; Address::SIZE]);
+
+    let reward = Inherent {
+        ty: InherentType::Reward,
+        target: address_validator.clone(),
+        value: Coin::from_u64_unchecked(10000),
+        data: vec![],
+    };
+
+    let stats = |num_leaves, num_branches| TrieStats {
+        num_leaves,
+        num_branches,
+    };
+
+    // The empty tree only consists of the root node.
+    assert_eq!(accounts.stats(None), stats(0, 1));
+
+    let mut txn = WriteTransaction::new(&env);
+    accounts
+        .commit(&mut txn, &[], &[reward.clone()], 1, 1)
+        .unwrap();
+    txn.commit();
+
+    assert_eq!(accounts.stats(None), stats(1, 1));
+
+    let tx = Transaction::new_basic(
+        address_validator,
+        address_recipient.clone(),
+        Coin::from_u64_unchecked(10),
+        Coin::ZERO,
+        1,
+        NetworkId::Main,
+    );
+    let transactions = vec![tx];
+
+    // The keys of both accounts start with the same nibble, so a branch node is added.
+    let mut txn = WriteTransaction::new(&env);
+    let receipts = accounts
+        .commit(&mut txn, &transactions, &[reward.clone()], 2, 2)
+        .unwrap();
+    txn.commit();
+
+    assert_eq!(accounts.stats(None), stats(2, 2));
+    assert_eq!(accounts.size(None), 2);
+
+    // Reverting removes the recipient again, together with the branch node.
+    let mut txn = WriteTransaction::new(&env);
+    accounts
+        .revert(&mut txn, &transactions, &[reward], 2, 2, &receipts)
+        .unwrap();
+    txn.commit();
+
+    assert_eq!(
+        accounts.get(&KeyNibbles::from(&address_recipient), None),
+        None
+    );
+    assert_eq!(accounts.stats(None), stats(1, 1));
+    assert_eq!(accounts.size(None), 1);
+}
+
 #[test]
 fn it_correctly_rewards_validators() {
     let env = VolatileEnvironment::new(10).unwrap();
@@ -410,3 +481,125 @@ fn it_checks_for_sufficient_funds() {
 
     assert_eq!(hash2, accounts.get_root(None));
 }
+
+#[test]
+fn it_can_export_and_import_snapshots() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let accounts = Accounts::new(env.clone());
+
+    // Use enough accounts to span multiple export chunks.
+    let genesis_accounts = (0..2500u32)
+        .map(|i| {
+            let mut address = [0u8; Address::SIZE];
+            address[..4].copy_from_slice(&i.to_be_bytes());
+            (
+                KeyNibbles::from(&Address::from(address)),
+                Account::Basic(BasicAccount {
+                    balance: Coin::from_u64_unchecked(i as u64 + 1),
+                }),
+            )
+        })
+        .collect();
+    let mut txn = WriteTransaction::new(&env);
+    accounts.init(&mut txn, genesis_accounts);
+    txn.commit();
+
+    let path = std::env::temp_dir().join("nimiq-accounts-snapshot.bin");
+    let root = {
+        let mut file = std::fs::File::create(&path).unwrap();
+        accounts.export_snapshot(&mut file, None).unwrap()
+    };
+    assert_eq!(root, accounts.get_root(None));
+
+    // Import into a fresh environment.
+    let imported_env = VolatileEnvironment::new(10).unwrap();
+    let imported = Accounts::new(imported_env.clone());
+    let mut txn = WriteTransaction::new(&imported_env);
+    let mut file = std::fs::File::open(&path).unwrap();
+    assert_eq!(imported.import_snapshot(&mut txn, &mut file).unwrap(), root);
+    txn.commit();
+
+    assert_eq!(imported.get_root(None), accounts.get_root(None));
+    assert_eq!(imported.get_all(None), accounts.get_all(None));
+
+    // Importing into a non-empty tree fails.
+    let mut txn = WriteTransaction::new(&imported_env);
+    let mut file = std::fs::File::open(&path).unwrap();
+    assert!(matches!(
+        imported.import_snapshot(&mut txn, &mut file),
+        Err(SnapshotError::NotEmpty)
+    ));
+    txn.abort();
+
+    // A snapshot that doesn't match its root hash is rejected.
+    let mut snapshot = std::fs::read(&path).unwrap();
+    let last_balance_byte = snapshot.len() - 2;
+    snapshot[last_balance_byte] ^= 0xff;
+    let corrupted_env = VolatileEnvironment::new(10).unwrap();
+    let corrupted = Accounts::new(corrupted_env.clone());
+    let mut txn = WriteTransaction::new(&corrupted_env);
+    assert!(matches!(
+        corrupted.import_snapshot(&mut txn, &mut &snapshot[..]),
+        Err(SnapshotError::RootMismatch { .. })
+    ));
+    txn.abort();
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn it_can_prove_inclusion_and_exclusion_of_accounts() {
+    let env = VolatileEnvironment::new(10).unwrap();
+    let accounts = Accounts::new(env.clone());
+
+    let genesis_accounts = (1..=10u8)
+        .map(|i| {
+            (
+                KeyNibbles::from(&Address::from([i; Address::SIZE])),
+                Account::Basic(BasicAccount {
+                    balance: Coin::from_u64_unchecked(i as u64 * 1000),
+                }),
+            )
+        })
+        .collect();
+    let mut txn = WriteTransaction::new(&env);
+    accounts.init(&mut txn, genesis_accounts);
+    txn.commit();
+
+    let root = accounts.get_root(None);
+
+    // An existing account is proven by an inclusion proof containing the account.
+    let key = KeyNibbles::from(&Address::from([3u8; Address::SIZE]));
+    let proof = accounts.get_proof(&key, None);
+    let proof: TrieProof<Account> = Deserialize::deserialize_from_vec(&proof.serialize_to_vec())
+        .expect("Proof must round-trip");
+    assert!(proof.verify(&root));
+    let leaf = proof
+        .leaf_nodes()
+        .into_iter()
+        .find(|node| node.key() == &key)
+        .expect("Proof must include the account");
+    assert_eq!(
+        leaf.value().unwrap(),
+        Account::Basic(BasicAccount {
+            balance: Coin::from_u64_unchecked(3000),
+        })
+    );
+    assert!(!proof.verify_exclusion(&key, &root));
+
+    // A non-existent account is proven by an exclusion proof.
+    let missing_key = KeyNibbles::from(&Address::from([42u8; Address::SIZE]));
+    let proof = accounts.get_proof(&missing_key, None);
+    let proof: TrieProof<Account> = Deserialize::deserialize_from_vec(&proof.serialize_to_vec())
+        .expect("Proof must round-trip");
+    assert!(proof
+        .leaf_nodes()
+        .iter()
+        .all(|node| node.key() != &missing_key));
+    assert!(proof.verify_exclusion(&missing_key, &root));
+    assert!(!proof.verify_exclusion(&key, &root));
+
+    // Neither proof holds against a different root.
+    let other_root = Accounts::new(VolatileEnvironment::new(10).unwrap()).get_root(None);
+    assert!(!proof.verify_exclusion(&missing_key, &other_root));
+}
@@ -277,6 +277,14 @@ impl Block {
         self.unwrap_micro().body.unwrap().transactions
     }
 
+    /// Returns true if the body of the block is present, false otherwise.
+    pub fn has_body(&self) -> bool {
+        match self {
+            Block::Macro(ref block) => block.body.is_some(),
+            Block::Micro(ref block) => block.body.is_some(),
+        }
+    }
+
     /// Returns true if the block is a Micro block, false otherwise.
     pub fn is_micro(&self) -> bool {
         matches!(self, Block::Micro(_))
@@ -294,6 +302,64 @@ impl Block {
             Block::Micro(_) => false,
         }
     }
+
+    /// Returns the serialized size of the block broken down into its parts.
+    pub fn size_breakdown(&self) -> BlockSizeBreakdown {
+        let (header, justification, body, transactions) = match self {
+            Block::Macro(block) => (
+                block.header.serialized_size(),
+                block.justification.serialized_size(),
+                block.body.serialized_size(),
+                vec![],
+            ),
+            Block::Micro(block) => {
+                let transactions: Vec<usize> = block
+                    .body
+                    .as_ref()
+                    .map(|body| {
+                        body.transactions
+                            .iter()
+                            .map(|tx| tx.serialized_size())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (
+                    block.header.serialized_size(),
+                    block.justification.serialized_size(),
+                    block.body.serialized_size() - transactions.iter().sum::<usize>(),
+                    transactions,
+                )
+            }
+        };
+
+        BlockSizeBreakdown {
+            header: self.ty().serialized_size() + header,
+            justification,
+            body,
+            transactions,
+        }
+    }
+}
+
+/// The serialized size of a block broken down into its parts. The parts don't overlap, so they add
+/// up to the serialized size of the whole block.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockSizeBreakdown {
+    /// The size of the header, including the block type.
+    pub header: usize,
+    /// The size of the justification, or of the marker for a missing justification.
+    pub justification: usize,
+    /// The size of the body without its transactions, or of the marker for a missing body.
+    pub body: usize,
+    /// The sizes of the transactions in the body, in the order they appear in the block.
+    pub transactions: Vec<usize>,
+}
+
+impl BlockSizeBreakdown {
+    /// Returns the sum of the sizes of all parts, which equals the serialized size of the block.
+    pub fn total(&self) -> usize {
+        self.header + self.justification + self.body + self.transactions.iter().sum::<usize>()
+    }
 }
 
 impl Serialize for Block {
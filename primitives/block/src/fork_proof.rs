@@ -6,6 +6,7 @@ use nimiq_hash::{Blake2bHash, Hash, HashOutput, SerializeContent};
 use nimiq_keys::{PublicKey as SchnorrPublicKey, Signature as SchnorrSignature};
 use nimiq_primitives::policy;
 use nimiq_vrf::VrfSeed;
+use thiserror::Error;
 
 use crate::MicroHeader;
 
@@ -137,9 +138,16 @@ impl std::hash::Hash for ForkProof {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
 pub enum ForkProofError {
+    #[error("Headers are for different slots")]
     SlotMismatch,
+    #[error("Invalid justification")]
     InvalidJustification,
+    #[error("Headers are equal")]
     SameHeader,
+    #[error("Fork is outside of the reporting window")]
+    OutsideReportingWindow,
+    #[error("Slot owner could not be determined")]
+    UnknownSlotOwner,
 }
@@ -1,13 +1,17 @@
 use std::str::FromStr;
 
 use beserial::{Deserialize, Serialize};
-use nimiq_block::{IndividualSignature, MacroBlock, MacroBody, MacroHeader, MultiSignature};
+use nimiq_block::{
+    Block, IndividualSignature, MacroBlock, MacroBody, MacroHeader, MicroBlock, MicroBody,
+    MicroHeader, MicroJustification, MultiSignature,
+};
 use nimiq_bls::{CompressedPublicKey, KeyPair};
 use nimiq_collections::bitset::BitSet;
 use nimiq_handel::update::LevelUpdate;
-use nimiq_hash::{Blake2bHasher, Hasher};
-use nimiq_keys::{Address, PublicKey};
-use nimiq_primitives::slots::ValidatorsBuilder;
+use nimiq_hash::{Blake2bHash, Blake2bHasher, Hasher};
+use nimiq_keys::{Address, PublicKey, Signature};
+use nimiq_primitives::{coin::Coin, networks::NetworkId, slots::ValidatorsBuilder};
+use nimiq_transaction::Transaction;
 use nimiq_vrf::VrfSeed;
 
 #[test]
@@ -140,3 +144,65 @@ fn test_serialize_deserialize_with_message() {
     let update = LevelUpdate::new(create_multisig(), None, 2, 3).with_tag(42u64);
     assert_eq!(update.serialized_size(), 108 + 8);
 }
+
+#[test]
+fn block_size_breakdown_adds_up_to_serialized_size() {
+    let transaction = Transaction::new_basic(
+        Address::from([1u8; 20]),
+        Address::from([2u8; 20]),
+        Coin::from_u64_unchecked(100),
+        Coin::ZERO,
+        1,
+        NetworkId::UnitAlbatross,
+    );
+    let mut transaction_with_data = transaction.clone();
+    transaction_with_data.data = vec![0u8; 64];
+    let transactions = vec![transaction, transaction_with_data];
+    let transaction_sizes: Vec<usize> =
+        transactions.iter().map(|tx| tx.serialized_size()).collect();
+
+    let mut micro_block = MicroBlock {
+        header: MicroHeader {
+            version: 1,
+            block_number: 1,
+            view_number: 0,
+            timestamp: 0,
+            parent_hash: Blake2bHash::default(),
+            seed: VrfSeed::default(),
+            extra_data: vec![1, 2, 3],
+            state_root: Blake2bHash::default(),
+            body_root: Blake2bHash::default(),
+            history_root: Blake2bHash::default(),
+        },
+        justification: Some(MicroJustification {
+            signature: Signature::default(),
+            view_change_proof: None,
+        }),
+        body: Some(MicroBody {
+            fork_proofs: vec![],
+            transactions,
+        }),
+    };
+
+    let block = Block::Micro(micro_block.clone());
+    let breakdown = block.size_breakdown();
+    assert_eq!(breakdown.total(), block.serialized_size());
+    assert_eq!(breakdown.transactions, transaction_sizes);
+    // The header includes the block type, just like a serialized `BlockHeader`.
+    assert_eq!(breakdown.header, block.header().serialized_size());
+
+    // Without a body there are no transactions to report.
+    micro_block.body = None;
+    let block = Block::Micro(micro_block);
+    let breakdown = block.size_breakdown();
+    assert_eq!(breakdown.total(), block.serialized_size());
+    assert!(breakdown.transactions.is_empty());
+
+    let block = Block::Macro(MacroBlock {
+        body: Some(MacroBody::default()),
+        ..Default::default()
+    });
+    let breakdown = block.size_breakdown();
+    assert_eq!(breakdown.total(), block.serialized_size());
+    assert!(breakdown.transactions.is_empty());
+}
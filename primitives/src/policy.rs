@@ -25,6 +25,9 @@ pub const TRANSACTION_VALIDITY_WINDOW: u32 = 7200;
 /// The maximum allowed size, in bytes, for a micro block body.
 pub const MAX_SIZE_MICRO_BODY: usize = 100_000;
 
+/// The maximum allowed size, in bytes, for a serialized transaction.
+pub const MAX_SIZE_TRANSACTION: usize = 10_000;
+
 /// The current version number of the protocol. Changing this always results in a hard fork.
 pub const VERSION: u16 = 1;
 
@@ -122,6 +122,15 @@ impl SignatureProof {
     }
 }
 
+/// The result of checking the signature of a transaction against its sender.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureVerification {
+    /// The address derived from the public key (and merkle path) in the signature proof.
+    pub signer: Address,
+    /// Whether the signer is the sender of the transaction and the signature is valid.
+    pub valid: bool,
+}
+
 impl Default for SignatureProof {
     fn default() -> Self {
         SignatureProof {
@@ -344,6 +353,23 @@ impl Transaction {
         u64::from(self.fee) as f64 / self.serialized_size() as f64
     }
 
+    /// Checks the signature of a transaction sent from a basic account or a vesting contract,
+    /// without looking at any account state. A well-formed signature proof yields the address
+    /// that signed the transaction and whether the signature is valid for the sender, while a
+    /// malformed proof or a sender that isn't authorized by a single signature is an error.
+    pub fn verify_signature(&self) -> Result<SignatureVerification, TransactionError> {
+        if self.sender_type != AccountType::Basic && self.sender_type != AccountType::Vesting {
+            return Err(TransactionError::InvalidForSender);
+        }
+
+        let signature_proof: SignatureProof = Deserialize::deserialize(&mut &self.proof[..])?;
+        let signer = signature_proof.compute_signer();
+        let valid =
+            signer == self.sender && signature_proof.verify(self.serialize_content().as_slice());
+
+        Ok(SignatureVerification { signer, valid })
+    }
+
     pub fn serialize_content(&self) -> Vec<u8> {
         let mut res: Vec<u8> = self.data.serialize_to_vec::<u16>();
         res.append(&mut self.sender.serialize_to_vec());
@@ -1,7 +1,7 @@
 use std::convert::{TryFrom, TryInto};
 
 use beserial::{Deserialize, Serialize, SerializingError};
-use nimiq_keys::Address;
+use nimiq_keys::{Address, KeyPair, SecureGenerate};
 use nimiq_primitives::account::AccountType;
 use nimiq_primitives::coin::Coin;
 use nimiq_primitives::networks::NetworkId;
@@ -83,3 +83,58 @@ fn it_can_serialize_basic_transaction() {
     assert_eq!(size, t.serialized_size());
     assert_eq!(hex::encode(v2), BASIC_TRANSACTION);
 }
+
+fn signed_basic_transaction(key_pair: &KeyPair) -> Transaction {
+    let mut tx = Transaction::new_basic(
+        Address::from(&key_pair.public),
+        Address::from([1u8; Address::SIZE]),
+        Coin::try_from(1000u64).unwrap(),
+        Coin::try_from(10u64).unwrap(),
+        1,
+        NetworkId::Dummy,
+    );
+    let signature = key_pair.sign(&tx.serialize_content()[..]);
+    tx.proof = SignatureProof::from(key_pair.public, signature).serialize_to_vec();
+    tx
+}
+
+#[test]
+fn it_verifies_the_signature_of_a_basic_transaction() {
+    let key_pair = KeyPair::generate_default_csprng();
+    let tx = signed_basic_transaction(&key_pair);
+
+    let verification = tx.verify_signature().unwrap();
+    assert_eq!(verification.signer, tx.sender);
+    assert!(verification.valid);
+}
+
+#[test]
+fn it_detects_a_tampered_transaction() {
+    let key_pair = KeyPair::generate_default_csprng();
+
+    let mut tx = signed_basic_transaction(&key_pair);
+    tx.value = Coin::try_from(2000u64).unwrap();
+    let verification = tx.verify_signature().unwrap();
+    assert_eq!(verification.signer, tx.sender);
+    assert!(!verification.valid);
+
+    // Signed by someone else than the sender.
+    let mut tx = signed_basic_transaction(&key_pair);
+    let other_key_pair = KeyPair::generate_default_csprng();
+    tx.sender = Address::from(&other_key_pair.public);
+    let verification = tx.verify_signature().unwrap();
+    assert_eq!(verification.signer, Address::from(&key_pair.public));
+    assert!(!verification.valid);
+}
+
+#[test]
+fn it_rejects_malformed_signature_proofs() {
+    let key_pair = KeyPair::generate_default_csprng();
+    let mut tx = signed_basic_transaction(&key_pair);
+    tx.proof.truncate(10);
+
+    assert!(matches!(
+        tx.verify_signature(),
+        Err(TransactionError::InvalidSerialization(_))
+    ));
+}
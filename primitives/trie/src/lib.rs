@@ -6,3 +6,4 @@ pub mod key_nibbles;
 pub mod trie;
 pub mod trie_node;
 pub mod trie_proof;
+pub mod trie_stats;
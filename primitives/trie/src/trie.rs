@@ -9,6 +9,7 @@ use nimiq_hash::{Blake2bHash, Hash};
 use crate::key_nibbles::KeyNibbles;
 use crate::trie_node::TrieNode;
 use crate::trie_proof::TrieProof;
+use crate::trie_stats::TrieStats;
 
 /// A Merkle Radix Trie is a hybrid between a Merkle tree and a Radix trie. Like a Merkle tree each
 /// node contains the hashes of all its children. That creates a tree that is resistant to
@@ -26,6 +27,10 @@ pub struct MerkleRadixTrie<A: Serialize + Deserialize + Clone> {
 }
 
 impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
+    /// The database key of the trie stats. It can't collide with a node key: the second byte of a
+    /// serialized node key is its length in bytes, which is at most 62.
+    const STATS_KEY: &'static str = "stats";
+
     /// Start a new Merkle Radix Trie with the given Environment and the given name.
     pub fn new(env: Environment, name: &str) -> Self {
         let db = env.open_database(name.to_string());
@@ -41,6 +46,21 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
             let root = KeyNibbles::root();
 
             txn.put_reserve(&tree.db, &root, &TrieNode::<A>::new_branch(root.clone()));
+            txn.put_reserve(
+                &tree.db,
+                Self::STATS_KEY,
+                &TrieStats {
+                    num_leaves: 0,
+                    num_branches: 1,
+                },
+            );
+        } else if txn
+            .get::<str, TrieStats>(&tree.db, Self::STATS_KEY)
+            .is_none()
+        {
+            // The trie was created before its stats were tracked, so count the nodes once.
+            let stats = tree.count_nodes(&txn);
+            txn.put_reserve(&tree.db, Self::STATS_KEY, &stats);
         }
 
         txn.commit();
@@ -53,9 +73,28 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         self.get_root(txn).unwrap().hash()
     }
 
+    /// Computes the root hash of the Merkle Radix Trie from scratch, ignoring the hashes that are
+    /// stored in the branch nodes. It will traverse the entire tree, so it is only meant to
+    /// cross-check the root hash that is maintained incrementally by `update_root`.
+    pub fn compute_root_hash(&self, txn: &Transaction) -> Blake2bHash {
+        self.compute_hash(txn, &KeyNibbles::root())
+    }
+
     /// Returns the number of leaf nodes in the Merkle Radix Trie. It will traverse the entire tree.
     pub fn size(&self, txn: &Transaction) -> usize {
-        let mut size = 0;
+        self.count_nodes(txn).num_leaves as usize
+    }
+
+    /// Returns the number of leaf and branch nodes in the Merkle Radix Trie. Unlike `size`, this
+    /// doesn't traverse the tree, the counters are updated on every modification.
+    pub fn stats(&self, txn: &Transaction) -> TrieStats {
+        txn.get(&self.db, Self::STATS_KEY)
+            .expect("Merkle Radix Trie must have stats!")
+    }
+
+    /// Counts the leaf and branch nodes in the Merkle Radix Trie. It will traverse the entire tree.
+    fn count_nodes(&self, txn: &Transaction) -> TrieStats {
+        let mut stats = TrieStats::default();
 
         let mut stack = vec![self
             .get_root(txn)
@@ -64,6 +103,8 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         while let Some(item) = stack.pop() {
             match item {
                 TrieNode::BranchNode { children, key } => {
+                    stats.num_branches += 1;
+
                     for child in children.iter().flatten().rev() {
                         let combined = &key + &child.suffix;
 
@@ -72,12 +113,12 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
                     }
                 }
                 TrieNode::LeafNode { .. } => {
-                    size += 1;
+                    stats.num_leaves += 1;
                 }
             }
         }
 
-        size
+        stats
     }
 
     /// Get the value at the given key. If there's no leaf node at the given key then it returns None.
@@ -99,6 +140,21 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         chunk.iter().map(|node| node.value().unwrap()).collect()
     }
 
+    /// Same as `get_chunk`, but also returns the key of every value in the chunk.
+    pub fn get_chunk_with_keys(
+        &self,
+        txn: &Transaction,
+        start: &KeyNibbles,
+        size: usize,
+    ) -> Vec<(KeyNibbles, A)> {
+        let chunk = self.get_trie_chunk(txn, start, size);
+
+        chunk
+            .into_iter()
+            .map(|node| (node.key().clone(), node.value().unwrap()))
+            .collect()
+    }
+
     /// Insert a value into the Merkle Radix Trie at the given key. If the key already exists then
     /// it will overwrite it. You can't use this function to check the existence of a given key.
     pub fn put(&self, txn: &mut WriteTransaction, key: &KeyNibbles, value: A) {
@@ -130,6 +186,11 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
                 // Push the parent node into the root path.
                 root_path.push(new_parent);
 
+                self.update_stats(txn, |stats| {
+                    stats.num_leaves += 1;
+                    stats.num_branches += 1;
+                });
+
                 break;
             }
 
@@ -167,6 +228,8 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
                     // Push the parent node into the root path.
                     root_path.push(cur_node);
 
+                    self.update_stats(txn, |stats| stats.num_leaves += 1);
+
                     break;
                 }
                 // If there's a child, then we update the current node and the root path, and
@@ -214,6 +277,8 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
                 // Remove the node from the database.
                 txn.remove(&self.db, key);
 
+                self.update_stats(txn, |stats| stats.num_leaves -= 1);
+
                 break;
             }
 
@@ -250,6 +315,8 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
                 // Remove the node from the database.
                 txn.remove(&self.db, parent_node.key());
 
+                self.update_stats(txn, |stats| stats.num_branches -= 1);
+
                 // Get the node's only child and add it to the root path.
                 let only_child_key =
                     parent_node.key() + &parent_node.iter_children().next().unwrap().suffix.clone();
@@ -298,9 +365,8 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
     ///     1. Unlike Merkle proofs we don't need the adjacent branch nodes. That's because our
     ///        branch nodes already include the hashes of its children.
     ///     2. The nodes are always returned in post-order.
-    /// If any of the given keys doesn't exist this function just returns None.
-    /// The exclusion (non-inclusion) of keys in the Merkle Radix Trie could also be proven, but it
-    /// requires some light refactoring to the way proofs work.
+    /// If any of the given keys doesn't exist this function just returns None. Use
+    /// `get_exclusion_proof` to prove that a key is not part of the trie.
     pub fn get_proof(&self, txn: &Transaction, mut keys: Vec<&KeyNibbles>) -> Option<TrieProof<A>> {
         // We sort the keys to simplify traversal in post-order.
         keys.sort();
@@ -401,9 +467,61 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         Some(TrieProof::new(proof_nodes))
     }
 
+    /// Produces a Merkle proof of the exclusion of the given key from the Merkle Radix Trie. The
+    /// proof consists of the path from the root down to the deepest node whose key is a prefix of
+    /// the given key. That node proves the exclusion: either it is a branch node without a child
+    /// in the slot of our key, or the child in that slot has a key that diverges from ours. Like
+    /// inclusion proofs, the nodes are returned in post-order.
+    /// If the key does exist in the trie this function returns None.
+    pub fn get_exclusion_proof(&self, txn: &Transaction, key: &KeyNibbles) -> Option<TrieProof<A>> {
+        // Initialize the pointer node, we always start at the root.
+        let mut pointer_node = self
+            .get_root(txn)
+            .expect("Merkle Radix Trie must have a root node!");
+
+        // Initialize the root path.
+        let mut root_path: Vec<TrieNode<A>> = vec![];
+
+        // Go down the trie until we can't go any further.
+        loop {
+            // If the key fully matches, it is either a leaf node, which means that the key is
+            // part of the trie, or a branch node, which can't hold a value.
+            if pointer_node.key() == key {
+                if pointer_node.is_leaf() {
+                    error!(
+                        "Key {} is a part of the trie. Can't produce an exclusion proof.",
+                        key
+                    );
+                    return None;
+                }
+
+                break;
+            }
+
+            // Otherwise, try to find a child of the pointer node that matches our key. If there's
+            // no such child, the pointer node already proves the exclusion.
+            match pointer_node.get_child_key(key) {
+                Ok(child_key) if child_key.is_prefix_of(key) => {
+                    let child_node = txn.get(&self.db, &child_key).unwrap();
+                    root_path.push(pointer_node);
+                    pointer_node = child_node;
+                }
+                _ => break,
+            }
+        }
+
+        // The proof consists of the pointer node followed by the root path in reverse order.
+        let mut proof_nodes = vec![pointer_node];
+        root_path.reverse();
+        proof_nodes.append(&mut root_path);
+
+        Some(TrieProof::new(proof_nodes))
+    }
+
     /// Creates a proof for the chunk of the Merkle Radix Trie that starts at the key `start` (which
     /// might or not be a part of the trie, if it is then it will be part of the chunk) and contains
-    /// at most `size` leaf nodes.
+    /// at most `size` leaf nodes. If there are no leaf nodes after `start`, the proof only
+    /// consists of the root node.
     pub fn get_chunk_proof(
         &self,
         txn: &Transaction,
@@ -412,6 +530,10 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
     ) -> Option<TrieProof<A>> {
         let chunk = self.get_trie_chunk(txn, start, size);
 
+        if chunk.is_empty() {
+            return self.get_root(txn).map(|root| TrieProof::new(vec![root]));
+        }
+
         let chunk_keys = chunk.iter().map(|node| node.key()).collect();
 
         self.get_proof(txn, chunk_keys)
@@ -421,6 +543,13 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         self.update_hashes(txn, &KeyNibbles::root());
     }
 
+    /// Updates the stored node counters.
+    fn update_stats<F: FnOnce(&mut TrieStats)>(&self, txn: &mut WriteTransaction, f: F) {
+        let mut stats = self.stats(txn);
+        f(&mut stats);
+        txn.put_reserve(&self.db, Self::STATS_KEY, &stats);
+    }
+
     /// Returns the root node, if there is one.
     fn get_root(&self, txn: &Transaction) -> Option<TrieNode<A>> {
         txn.get(&self.db, &KeyNibbles::root())
@@ -464,6 +593,21 @@ impl<A: Serialize + Deserialize + Clone> MerkleRadixTrie<A> {
         node.hash()
     }
 
+    /// Computes the hash of the subtree specified by `key` from its leaf nodes.
+    fn compute_hash(&self, txn: &Transaction, key: &KeyNibbles) -> Blake2bHash {
+        let mut node: TrieNode<A> = txn.get(&self.db, key).expect(
+            "Failed to find the child of a Merkle Radix Trie node. The database must be corrupt!",
+        );
+        if node.is_leaf() {
+            return node.hash();
+        }
+
+        for mut child in node.iter_children_mut() {
+            child.hash = self.compute_hash(txn, &(key + &child.suffix));
+        }
+        node.hash()
+    }
+
     /// Returns the nodes of the chunk of the Merkle Radix Trie that starts at the key `start` and
     /// has size `size`. This is used by the `get_chunk` and `get_chunk_proof` functions.
     fn get_trie_chunk(
@@ -557,6 +701,86 @@ mod tests {
         assert_eq!(trie.get(&txn, &key_3), None);
     }
 
+    #[test]
+    fn stats_are_updated_incrementally() {
+        let key_1 = "413f22b3e".parse().unwrap();
+        let key_2 = "413b39931".parse().unwrap();
+        let key_3 = "413b397fa".parse().unwrap();
+        let key_4 = "cfb986f5a".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileEnvironment::new(10).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        let mut txn = WriteTransaction::new(&env);
+
+        let stats = |num_leaves, num_branches| TrieStats {
+            num_leaves,
+            num_branches,
+        };
+        assert_eq!(trie.stats(&txn), stats(0, 1));
+
+        trie.put(&mut txn, &key_1, 80085);
+        trie.put(&mut txn, &key_2, 999);
+        trie.put(&mut txn, &key_3, 1337);
+        trie.put(&mut txn, &key_4, 42);
+        assert_eq!(trie.stats(&txn), stats(4, 3));
+        assert_eq!(trie.stats(&txn), trie.count_nodes(&txn));
+
+        // Overwriting a value doesn't change the structure of the trie.
+        trie.put(&mut txn, &key_4, 43);
+        assert_eq!(trie.stats(&txn), stats(4, 3));
+
+        trie.remove(&mut txn, &key_2);
+        assert_eq!(trie.stats(&txn), stats(3, 2));
+        assert_eq!(trie.stats(&txn), trie.count_nodes(&txn));
+
+        trie.remove(&mut txn, &key_1);
+        trie.remove(&mut txn, &key_3);
+        trie.remove(&mut txn, &key_4);
+        assert_eq!(trie.stats(&txn), stats(0, 1));
+        assert_eq!(trie.stats(&txn), trie.count_nodes(&txn));
+    }
+
+    #[test]
+    fn incremental_root_hash_matches_full_recompute() {
+        let key_1 = "413f22b3e".parse().unwrap();
+        let key_2 = "413b39931".parse().unwrap();
+        let key_3 = "413b397fa".parse().unwrap();
+        let key_4 = "cfb986f5a".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileEnvironment::new(10).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        let mut txn = WriteTransaction::new(&env);
+        let empty_hash = trie.root_hash(&txn);
+
+        trie.put(&mut txn, &key_1, 80085);
+        trie.put(&mut txn, &key_2, 999);
+        trie.update_root(&mut txn);
+        assert_eq!(trie.root_hash(&txn), trie.compute_root_hash(&txn));
+        let hash_1 = trie.root_hash(&txn);
+
+        trie.put(&mut txn, &key_3, 1337);
+        trie.put(&mut txn, &key_4, 42);
+        trie.put(&mut txn, &key_1, 1);
+        trie.update_root(&mut txn);
+        assert_eq!(trie.root_hash(&txn), trie.compute_root_hash(&txn));
+        assert_ne!(trie.root_hash(&txn), hash_1);
+
+        // Revert the second batch of changes.
+        trie.put(&mut txn, &key_1, 80085);
+        trie.remove(&mut txn, &key_4);
+        trie.remove(&mut txn, &key_3);
+        trie.update_root(&mut txn);
+        assert_eq!(trie.root_hash(&txn), trie.compute_root_hash(&txn));
+        assert_eq!(trie.root_hash(&txn), hash_1);
+
+        // Revert the first batch of changes.
+        trie.remove(&mut txn, &key_2);
+        trie.remove(&mut txn, &key_1);
+        trie.update_root(&mut txn);
+        assert_eq!(trie.root_hash(&txn), trie.compute_root_hash(&txn));
+        assert_eq!(trie.root_hash(&txn), empty_hash);
+    }
+
     #[test]
     fn get_proof_works() {
         let key_1 = "cfb986f5a".parse().unwrap();
@@ -604,6 +828,54 @@ mod tests {
         assert!(proof.is_none());
     }
 
+    #[test]
+    fn get_exclusion_proof_works() {
+        let key_1 = "cfb986f5a".parse().unwrap();
+        let key_2 = "cfb986ab9".parse().unwrap();
+        let key_3 = "cfb98e0f6".parse().unwrap();
+        let key_4 = "cfb98e0f5".parse().unwrap();
+        let key_5 = "cfb986f5b".parse().unwrap();
+        let key_6 = "0000".parse().unwrap();
+        let key_7 = "cfb986".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileEnvironment::new(10).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        let mut txn = WriteTransaction::new(&env);
+
+        trie.put(&mut txn, &key_1, 9);
+        trie.put(&mut txn, &key_2, 8);
+        trie.put(&mut txn, &key_3, 7);
+        trie.update_root(&mut txn);
+
+        let root_hash = trie.root_hash(&txn);
+
+        let proof = trie.get_exclusion_proof(&txn, &key_4).unwrap();
+        assert_eq!(proof.nodes.len(), 2);
+        assert!(proof.verify_exclusion(&key_4, &root_hash));
+
+        let proof = trie.get_exclusion_proof(&txn, &key_5).unwrap();
+        assert_eq!(proof.nodes.len(), 3);
+        assert!(proof.verify_exclusion(&key_5, &root_hash));
+        assert!(!proof.verify_exclusion(&key_1, &root_hash));
+
+        let proof = trie.get_exclusion_proof(&txn, &key_6).unwrap();
+        assert_eq!(proof.nodes.len(), 1);
+        assert!(proof.verify_exclusion(&key_6, &root_hash));
+
+        let proof = trie.get_exclusion_proof(&txn, &key_7).unwrap();
+        assert_eq!(proof.nodes.len(), 3);
+        assert!(proof.verify_exclusion(&key_7, &root_hash));
+
+        assert!(trie.get_exclusion_proof(&txn, &key_1).is_none());
+
+        // Inclusion proofs don't prove the exclusion of their own keys, nor of keys that lie
+        // below nodes which are missing from the proof.
+        let proof = trie.get_proof(&txn, vec![&key_3]).unwrap();
+        assert!(!proof.verify_exclusion(&key_3, &root_hash));
+        assert!(!proof.verify_exclusion(&key_5, &root_hash));
+        assert!(proof.verify_exclusion(&key_4, &root_hash));
+    }
+
     #[test]
     fn get_chunk_works() {
         let key_1 = "cfb986f5a".parse().unwrap();
@@ -641,5 +913,34 @@ mod tests {
         let chunk = trie.get_chunk_proof(&txn, &key_4, 100).unwrap();
         assert_eq!(chunk.nodes.len(), 3);
         assert_eq!(chunk.verify(&trie.root_hash(&txn)), true);
+
+        let chunk = trie
+            .get_chunk_proof(&txn, &"d".parse().unwrap(), 100)
+            .unwrap();
+        assert_eq!(chunk.nodes.len(), 1);
+        assert!(chunk.leaf_nodes().is_empty());
+        assert_eq!(chunk.verify(&trie.root_hash(&txn)), true);
+    }
+
+    #[test]
+    fn get_chunk_with_keys_works() {
+        let key_1: KeyNibbles = "cfb986f5a".parse().unwrap();
+        let key_2: KeyNibbles = "cfb986ab9".parse().unwrap();
+        let key_3: KeyNibbles = "cfb98e0f6".parse().unwrap();
+
+        let env = nimiq_database::volatile::VolatileEnvironment::new(10).unwrap();
+        let trie = MerkleRadixTrie::new(env.clone(), "database");
+        let mut txn = WriteTransaction::new(&env);
+
+        trie.put(&mut txn, &key_1, 9);
+        trie.put(&mut txn, &key_2, 8);
+        trie.put(&mut txn, &key_3, 7);
+        trie.update_root(&mut txn);
+
+        let chunk = trie.get_chunk_with_keys(&txn, &KeyNibbles::root(), 100);
+        assert_eq!(chunk, vec![(key_2, 8), (key_1, 9), (key_3.clone(), 7)]);
+
+        let chunk = trie.get_chunk_with_keys(&txn, &key_3, 100);
+        assert_eq!(chunk, vec![(key_3, 7)]);
     }
 }
@@ -21,9 +21,9 @@ use crate::trie_node::TrieNode;
 ///     1. Unlike Merkle proofs we don't need the adjacent branch nodes. That's because our
 ///        branch nodes already include the hashes of its children.
 ///     2. The nodes are always returned in post-order.
-/// If any of the given keys doesn't exist this function just returns None.
-/// The exclusion (non-inclusion) of keys in the Merkle Radix Trie could also be proven, but it
-/// requires some light refactoring to the way proofs work.
+/// A proof can also show the exclusion (non-inclusion) of a key. In that case it consists of the
+/// path from the root down to the deepest node whose key is a prefix of the excluded key, see
+/// `verify_exclusion`.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TrieProof<A: Serialize + Deserialize + Clone> {
     #[beserial(len_type(u16))]
@@ -137,6 +137,52 @@ impl<A: Serialize + Deserialize + Clone> TrieProof<A> {
         // The proof is valid!
         true
     }
+
+    /// Verifies that the proof is valid for the given root hash and that it proves the exclusion
+    /// of the given key from the trie. The deepest node in the proof that is a prefix of the key
+    /// must not lead any further towards the key: it has to be a leaf node with a different key or
+    /// a branch node without a child on the way to the key.
+    pub fn verify_exclusion(&self, key: &KeyNibbles, root_hash: &Blake2bHash) -> bool {
+        if !self.verify(root_hash) {
+            return false;
+        }
+
+        // Find the deepest node that is a prefix of the key. There is always one, since the root
+        // node is a prefix of every key.
+        let deepest_node = match self
+            .nodes
+            .iter()
+            .filter(|node| node.key().is_prefix_of(key))
+            .max_by_key(|node| node.key().len())
+        {
+            Some(node) => node,
+            None => return false,
+        };
+
+        if deepest_node.is_leaf() {
+            if deepest_node.key() == key {
+                error!("Key {} is included in the proof.", key);
+                return false;
+            }
+            return true;
+        }
+
+        if deepest_node.key() == key {
+            return true;
+        }
+
+        match deepest_node.get_child_key(key) {
+            // The child on the way to our key is not part of the proof, so the proof is incomplete.
+            Ok(child_key) if child_key.is_prefix_of(key) => {
+                error!(
+                    "The proof doesn't include the child node with key {}.",
+                    child_key
+                );
+                false
+            }
+            _ => true,
+        }
+    }
 }
 
 #[cfg(test)]
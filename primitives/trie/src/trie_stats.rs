@@ -0,0 +1,34 @@
+use std::io;
+
+use beserial::{Deserialize, Serialize};
+use nimiq_database::{FromDatabaseValue, IntoDatabaseValue};
+
+/// Statistics about the nodes of a Merkle Radix Trie. They are updated together with the trie, so
+/// they can be read without traversing it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieStats {
+    /// The number of leaf nodes, i.e. the number of values stored in the trie.
+    pub num_leaves: u64,
+    /// The number of branch nodes, including the root node.
+    pub num_branches: u64,
+}
+
+impl IntoDatabaseValue for TrieStats {
+    fn database_byte_size(&self) -> usize {
+        self.serialized_size()
+    }
+
+    fn copy_into_database(&self, mut bytes: &mut [u8]) {
+        Serialize::serialize(&self, &mut bytes).unwrap();
+    }
+}
+
+impl FromDatabaseValue for TrieStats {
+    fn copy_from_database(bytes: &[u8]) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut cursor = io::Cursor::new(bytes);
+        Ok(Deserialize::deserialize(&mut cursor)?)
+    }
+}
@@ -8,7 +8,9 @@ use nimiq_keys::Address;
 use nimiq_primitives::coin::Coin;
 
 use crate::types::{
-    Account, Block, Inherent, ParkedSet, SlashedSlots, Slot, Staker, Transaction, Validator,
+    Account, AccountProof, Block, BlockNumberOrHash, BlockReward, BlockSizeBreakdown, BlockStatus,
+    Inherent, NetworkInfo, ParkedSet, SlashedSlots, Slot, Slots, Staker, StateStats, Transaction,
+    TransactionReceipt, Validator, ValidatorParticipation,
 };
 
 #[nimiq_jsonrpc_derive::proxy(name = "BlockchainProxy", rename_all = "camelCase")]
@@ -18,9 +20,11 @@ pub trait BlockchainInterface {
 
     async fn get_block_number(&mut self) -> Result<u32, Self::Error>;
 
-    async fn get_batch_number(&mut self) -> Result<u32, Self::Error>;
+    async fn get_batch_number(&mut self, block_number: Option<u32>) -> Result<u32, Self::Error>;
 
-    async fn get_epoch_number(&mut self) -> Result<u32, Self::Error>;
+    async fn get_epoch_number(&mut self, block_number: Option<u32>) -> Result<u32, Self::Error>;
+
+    async fn is_macro_block(&mut self, block_number: u32) -> Result<bool, Self::Error>;
 
     async fn get_block_by_hash(
         &mut self,
@@ -34,11 +38,49 @@ pub trait BlockchainInterface {
         include_transactions: Option<bool>,
     ) -> Result<Block, Self::Error>;
 
+    async fn get_blocks(
+        &mut self,
+        start_block_number: u32,
+        count: u32,
+        include_transactions: Option<bool>,
+    ) -> Result<Vec<Block>, Self::Error>;
+
     async fn get_latest_block(
         &mut self,
         include_transactions: Option<bool>,
     ) -> Result<Block, Self::Error>;
 
+    async fn get_genesis_block(
+        &mut self,
+        include_transactions: Option<bool>,
+    ) -> Result<Block, Self::Error>;
+
+    async fn get_network_info(&mut self) -> Result<NetworkInfo, Self::Error>;
+
+    async fn get_raw_block(&mut self, block: BlockNumberOrHash) -> Result<String, Self::Error>;
+
+    async fn get_block_status(&mut self, hash: Blake2bHash) -> Result<BlockStatus, Self::Error>;
+
+    async fn get_block_size_breakdown(
+        &mut self,
+        block: BlockNumberOrHash,
+    ) -> Result<BlockSizeBreakdown, Self::Error>;
+
+    async fn get_block_justification(
+        &mut self,
+        block: BlockNumberOrHash,
+    ) -> Result<String, Self::Error>;
+
+    async fn get_block_transaction_count_by_hash(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<Option<usize>, Self::Error>;
+
+    async fn get_block_transaction_count_by_number(
+        &mut self,
+        block_number: u32,
+    ) -> Result<Option<usize>, Self::Error>;
+
     async fn get_slot_at(
         &mut self,
         block_number: u32,
@@ -65,6 +107,13 @@ pub trait BlockchainInterface {
         batch_number: u32,
     ) -> Result<Vec<Inherent>, Self::Error>;
 
+    async fn get_block_reward(&mut self, block_number: u32) -> Result<BlockReward, Self::Error>;
+
+    async fn get_transaction_receipt(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<Option<TransactionReceipt>, Self::Error>;
+
     // TODO: includes reward txs
     async fn get_transaction_hashes_by_address(
         &mut self,
@@ -76,18 +125,30 @@ pub trait BlockchainInterface {
         &mut self,
         address: Address,
         max: Option<u16>,
+        offset: Option<u32>,
     ) -> Result<Vec<Transaction>, Self::Error>;
 
     async fn get_account_by_address(&mut self, address: Address) -> Result<Account, Self::Error>;
 
+    async fn get_account_proof(&mut self, address: Address) -> Result<AccountProof, Self::Error>;
+
+    async fn get_state_stats(&mut self) -> Result<StateStats, Self::Error>;
+
     async fn get_active_validators(&mut self) -> Result<HashMap<Address, Coin>, Self::Error>;
 
+    async fn get_validators(&mut self) -> Result<Vec<Slots>, Self::Error>;
+
     async fn get_current_slashed_slots(&mut self) -> Result<SlashedSlots, Self::Error>;
 
     async fn get_previous_slashed_slots(&mut self) -> Result<SlashedSlots, Self::Error>;
 
     async fn get_parked_validators(&mut self) -> Result<ParkedSet, Self::Error>;
 
+    async fn get_validator_participation(
+        &mut self,
+        epoch_number: u32,
+    ) -> Result<Vec<ValidatorParticipation>, Self::Error>;
+
     async fn get_validator_by_address(
         &mut self,
         address: Address,
@@ -5,7 +5,7 @@ use nimiq_keys::Address;
 use nimiq_primitives::coin::Coin;
 use nimiq_transaction::account::htlc_contract::{AnyHash, HashAlgorithm};
 
-use crate::types::{Transaction, ValidityStartHeight};
+use crate::types::{Transaction, TransactionSignatureVerification, ValidityStartHeight};
 
 #[nimiq_jsonrpc_derive::proxy(name = "ConsensusProxy", rename_all = "camelCase")]
 #[async_trait]
@@ -21,6 +21,11 @@ pub trait ConsensusInterface {
 
     async fn send_raw_transaction(&mut self, raw_tx: String) -> Result<Blake2bHash, Self::Error>;
 
+    async fn verify_transaction_signature(
+        &mut self,
+        raw_tx: String,
+    ) -> Result<TransactionSignatureVerification, Self::Error>;
+
     async fn create_basic_transaction(
         &mut self,
         wallet: Address,
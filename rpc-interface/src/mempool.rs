@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 
-use crate::types::{HashOrTx, MempoolInfo, Transaction};
+use crate::types::{HashOrTx, MempoolInfo, PendingTransaction, Transaction, TransactionValidation};
 use nimiq_hash::Blake2bHash;
 
 #[nimiq_jsonrpc_derive::proxy(name = "MempoolProxy", rename_all = "camelCase")]
@@ -10,12 +10,22 @@ pub trait MempoolInterface {
 
     async fn push_transaction(&mut self, raw_tx: String) -> Result<Blake2bHash, Self::Error>;
 
+    async fn validate_transaction(
+        &mut self,
+        raw_tx: String,
+    ) -> Result<TransactionValidation, Self::Error>;
+
     async fn get_transaction_by_hash(
         &mut self,
         hash: Blake2bHash,
         check_mempool: Option<bool>,
     ) -> Result<Transaction, Self::Error>;
 
+    async fn get_pending_transaction(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<Option<PendingTransaction>, Self::Error>;
+
     async fn mempool_content(
         &mut self,
         include_transactions: bool,
@@ -23,5 +33,7 @@ pub trait MempoolInterface {
 
     async fn mempool(&mut self) -> Result<MempoolInfo, Self::Error>;
 
-    async fn get_min_fee_per_byte(&mut self) -> Result<f64, Self::Error>;
+    async fn get_min_fee_per_byte(&mut self) -> Result<String, Self::Error>;
+
+    async fn estimate_fee(&mut self, target_blocks: u32) -> Result<String, Self::Error>;
 }
@@ -1,5 +1,7 @@
 use async_trait::async_trait;
 
+use crate::types::Peer;
+
 #[nimiq_jsonrpc_derive::proxy(name = "NetworkProxy", rename_all = "camelCase")]
 #[async_trait]
 pub trait NetworkInterface {
@@ -10,4 +12,6 @@ pub trait NetworkInterface {
     async fn get_peer_count(&mut self) -> Result<usize, Self::Error>;
 
     async fn get_peer_list(&mut self) -> Result<Vec<String>, Self::Error>;
+
+    async fn get_peer_scores(&mut self) -> Result<Vec<Peer>, Self::Error>;
 }
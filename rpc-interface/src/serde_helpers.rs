@@ -21,3 +21,56 @@ pub mod hex {
         hex::decode(s).map_err(Error::custom)
     }
 }
+
+/// Serializes integer quantities (e.g. `u64` or `u128`) as decimal strings. JSON numbers are
+/// usually parsed as doubles, so values above 2^53 would lose precision otherwise.
+pub mod decimal {
+    use std::{fmt::Display, str::FromStr};
+
+    use serde::{
+        de::{Deserialize, Deserializer, Error},
+        ser::Serializer,
+    };
+
+    pub fn serialize<T, S>(x: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        serializer.collect_str(x)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        s.parse().map_err(Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Quantity {
+        #[serde(with = "super::decimal")]
+        value: u64,
+    }
+
+    #[test]
+    fn decimal_round_trips_large_values_as_strings() {
+        let quantity = Quantity {
+            value: (1u64 << 53) + 1,
+        };
+
+        let json = serde_json::to_string(&quantity).unwrap();
+        assert_eq!(json, r#"{"value":"9007199254740993"}"#);
+
+        let parsed: Quantity = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, quantity);
+    }
+}
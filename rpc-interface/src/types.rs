@@ -12,15 +12,20 @@ use serde_with::{DeserializeFromStr, SerializeDisplay};
 
 use beserial::Serialize as BeSerialize;
 use nimiq_block::{MultiSignature, ViewChangeProof};
-use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain, ChainStatus};
 use nimiq_bls::CompressedPublicKey;
 use nimiq_collections::BitSet;
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::{Address, PublicKey, Signature};
+use nimiq_primitives::account::AccountType;
 use nimiq_primitives::coin::Coin;
+use nimiq_primitives::networks::NetworkId;
 use nimiq_primitives::policy;
 use nimiq_primitives::slots::Validators;
 use nimiq_transaction::account::htlc_contract::AnyHash;
+use nimiq_transaction::account::staking_contract::{
+    IncomingStakingTransactionData, OutgoingStakingTransactionProof,
+};
 use nimiq_vrf::VrfSeed;
 
 use crate::error::Error;
@@ -44,7 +49,7 @@ impl From<nimiq_transaction::Transaction> for HashOrTx {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, SerializeDisplay, DeserializeFromStr)]
 pub enum BlockNumberOrHash {
     Number(u32),
     Hash(Blake2bHash),
@@ -325,6 +330,119 @@ impl Block {
     }
 }
 
+/// Whether a block is on the main chain, on a fork or unknown to the node.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BlockStatus {
+    Main,
+    Fork,
+    Unknown,
+}
+
+impl From<ChainStatus> for BlockStatus {
+    fn from(chain_status: ChainStatus) -> Self {
+        match chain_status {
+            ChainStatus::Main => BlockStatus::Main,
+            ChainStatus::Fork => BlockStatus::Fork,
+            ChainStatus::Unknown => BlockStatus::Unknown,
+        }
+    }
+}
+
+/// The serialized size of a block broken down into its parts. The header, justification, body and
+/// transaction sizes add up to `size`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockSizeBreakdown {
+    pub hash: Blake2bHash,
+    pub number: u32,
+    pub size: u32,
+    /// The size of the header, including the block type.
+    pub header_size: u32,
+    /// The size of the justification.
+    pub justification_size: u32,
+    /// The size of the body without its transactions.
+    pub body_size: u32,
+    /// The sizes of the transactions, in the order they appear in the block.
+    pub transaction_sizes: Vec<u32>,
+}
+
+impl BlockSizeBreakdown {
+    pub fn from_block(block: &nimiq_block::Block) -> Self {
+        let breakdown = block.size_breakdown();
+
+        BlockSizeBreakdown {
+            hash: block.hash(),
+            number: block.block_number(),
+            size: breakdown.total() as u32,
+            header_size: breakdown.header as u32,
+            justification_size: breakdown.justification as u32,
+            body_size: breakdown.body as u32,
+            transaction_sizes: breakdown
+                .transactions
+                .iter()
+                .map(|size| *size as u32)
+                .collect(),
+        }
+    }
+}
+
+/// Whether this node participates as a validator in the current epoch, so that operators can
+/// confirm that their validator is actually active.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorStatus {
+    pub address: Address,
+    pub is_active: bool,
+    /// The slot band of the validator, i.e. its ID in the active set. Only present if active.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validator_id: Option<u16>,
+    /// Whether the connected validators, including this one, own at least two thirds of the slots.
+    /// Only present once the validator network determined it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_quorum: Option<bool>,
+}
+
+/// The network a node is connected to, so that clients can verify they talk to the right network
+/// before sending transactions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkInfo {
+    pub network_id: NetworkId,
+    pub name: String,
+    pub genesis_hash: Blake2bHash,
+    pub batch_length: u32,
+    pub epoch_length: u32,
+    pub slots: u16,
+    pub transaction_validity_window: u32,
+    pub max_transaction_size: usize,
+}
+
+impl NetworkInfo {
+    pub fn new(network_id: NetworkId, name: String, genesis_hash: Blake2bHash) -> Self {
+        NetworkInfo {
+            network_id,
+            name,
+            genesis_hash,
+            batch_length: policy::BATCH_LENGTH,
+            epoch_length: policy::EPOCH_LENGTH,
+            slots: policy::SLOTS,
+            transaction_validity_window: policy::TRANSACTION_VALIDITY_WINDOW,
+            max_transaction_size: policy::MAX_SIZE_TRANSACTION,
+        }
+    }
+}
+
+/// Size of the accounts tree at the current head. Operators can use it to monitor the growth of the
+/// state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateStats {
+    pub block_number: u32,
+    pub num_accounts: u64,
+    pub num_branch_nodes: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TendermintProof {
@@ -387,10 +505,21 @@ pub struct Slots {
     pub num_slots: u16,
     pub validator: Address,
     pub public_key: CompressedPublicKey,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward_address: Option<Address>,
 }
 
 impl Slots {
     pub fn from_slots(validators: Validators) -> Vec<Slots> {
+        Self::from_slots_with_reward_addresses(validators, |_| None)
+    }
+
+    /// Like `from_slots`, but also includes the reward address of each validator, as returned by
+    /// the given function.
+    pub fn from_slots_with_reward_addresses<F: Fn(&Address) -> Option<Address>>(
+        validators: Validators,
+        reward_address: F,
+    ) -> Vec<Slots> {
         let mut slots = vec![];
 
         for validator in validators.iter() {
@@ -399,6 +528,7 @@ impl Slots {
                 num_slots: validator.num_slots(),
                 validator: validator.address.clone(),
                 public_key: validator.voting_key.compressed().clone(),
+                reward_address: reward_address(&validator.address),
             })
         }
 
@@ -421,6 +551,30 @@ pub struct ParkedSet {
     pub validators: Vec<Address>,
 }
 
+/// How often a validator signed the view change proofs and macro block justifications of an
+/// epoch. `rate` is the share of those aggregations that the validator signed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorParticipation {
+    pub address: Address,
+    pub num_slots: u16,
+    pub num_signed: u32,
+    pub num_aggregations: u32,
+    pub rate: f64,
+}
+
+impl From<nimiq_blockchain::ValidatorParticipation> for ValidatorParticipation {
+    fn from(participation: nimiq_blockchain::ValidatorParticipation) -> Self {
+        ValidatorParticipation {
+            rate: participation.rate(),
+            address: participation.validator_address,
+            num_slots: participation.num_slots,
+            num_signed: participation.num_signed,
+            num_aggregations: participation.num_aggregations,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ForkProof {
@@ -462,6 +616,9 @@ pub struct Transaction {
     pub validity_start_height: u32,
     #[serde(with = "crate::serde_helpers::hex")]
     pub proof: Vec<u8>,
+    /// The decoded data of transactions with the staking contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staking_data: Option<StakingData>,
 }
 
 impl Transaction {
@@ -489,6 +646,8 @@ impl Transaction {
         timestamp: Option<u64>,
         head_height: Option<u32>,
     ) -> Self {
+        let staking_data = StakingData::from_transaction(&transaction);
+
         Transaction {
             hash: transaction.hash(),
             block_number,
@@ -505,10 +664,179 @@ impl Transaction {
             data: transaction.data,
             validity_start_height: transaction.validity_start_height,
             proof: transaction.proof,
+            staking_data,
+        }
+    }
+}
+
+/// The staking specific part of a transaction with the staking contract. For incoming
+/// transactions it is decoded from the data field, for outgoing transactions from the proof.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum StakingData {
+    #[serde(rename_all = "camelCase")]
+    CreateValidator {
+        validator_address: Address,
+        signing_key: PublicKey,
+        voting_key: CompressedPublicKey,
+        reward_address: Address,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        signal_data: Option<Blake2bHash>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UpdateValidator {
+        validator_address: Address,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        new_signing_key: Option<PublicKey>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        new_voting_key: Option<CompressedPublicKey>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        new_reward_address: Option<Address>,
+        /// `null` if the signal data is removed.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        new_signal_data: Option<Option<Blake2bHash>>,
+    },
+    #[serde(rename_all = "camelCase")]
+    InactivateValidator { validator_address: Address },
+    #[serde(rename_all = "camelCase")]
+    ReactivateValidator { validator_address: Address },
+    #[serde(rename_all = "camelCase")]
+    UnparkValidator { validator_address: Address },
+    #[serde(rename_all = "camelCase")]
+    DeleteValidator { validator_address: Address },
+    #[serde(rename_all = "camelCase")]
+    CreateStaker {
+        staker_address: Address,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delegation: Option<Address>,
+        amount: Coin,
+    },
+    #[serde(rename_all = "camelCase")]
+    Stake {
+        staker_address: Address,
+        amount: Coin,
+    },
+    #[serde(rename_all = "camelCase")]
+    UpdateStaker {
+        staker_address: Address,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        new_delegation: Option<Address>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unstake {
+        staker_address: Address,
+        amount: Coin,
+    },
+}
+
+impl StakingData {
+    /// Decodes the staking data of the given transaction. Returns `None` if the transaction
+    /// doesn't interact with the staking contract or its data can't be decoded.
+    pub fn from_transaction(transaction: &nimiq_transaction::Transaction) -> Option<Self> {
+        match (transaction.recipient_type, transaction.sender_type) {
+            (AccountType::Staking, _) => {
+                let data = IncomingStakingTransactionData::parse(transaction).ok()?;
+                Some(Self::from_incoming(data, transaction))
+            }
+            (_, AccountType::Staking) => {
+                let proof = OutgoingStakingTransactionProof::parse(transaction).ok()?;
+                Some(Self::from_outgoing(proof, transaction))
+            }
+            _ => None,
+        }
+    }
+
+    fn from_incoming(
+        data: IncomingStakingTransactionData,
+        transaction: &nimiq_transaction::Transaction,
+    ) -> Self {
+        match data {
+            IncomingStakingTransactionData::CreateValidator {
+                signing_key,
+                voting_key,
+                reward_address,
+                signal_data,
+                proof,
+                ..
+            } => StakingData::CreateValidator {
+                validator_address: proof.compute_signer(),
+                signing_key,
+                voting_key,
+                reward_address,
+                signal_data,
+            },
+            IncomingStakingTransactionData::UpdateValidator {
+                new_signing_key,
+                new_voting_key,
+                new_reward_address,
+                new_signal_data,
+                proof,
+                ..
+            } => StakingData::UpdateValidator {
+                validator_address: proof.compute_signer(),
+                new_signing_key,
+                new_voting_key,
+                new_reward_address,
+                new_signal_data,
+            },
+            IncomingStakingTransactionData::InactivateValidator {
+                validator_address, ..
+            } => StakingData::InactivateValidator { validator_address },
+            IncomingStakingTransactionData::ReactivateValidator {
+                validator_address, ..
+            } => StakingData::ReactivateValidator { validator_address },
+            IncomingStakingTransactionData::UnparkValidator {
+                validator_address, ..
+            } => StakingData::UnparkValidator { validator_address },
+            IncomingStakingTransactionData::CreateStaker { delegation, proof } => {
+                StakingData::CreateStaker {
+                    staker_address: proof.compute_signer(),
+                    delegation,
+                    amount: transaction.value,
+                }
+            }
+            IncomingStakingTransactionData::Stake { staker_address } => StakingData::Stake {
+                staker_address,
+                amount: transaction.value,
+            },
+            IncomingStakingTransactionData::UpdateStaker {
+                new_delegation,
+                proof,
+            } => StakingData::UpdateStaker {
+                staker_address: proof.compute_signer(),
+                new_delegation,
+            },
+        }
+    }
+
+    fn from_outgoing(
+        proof: OutgoingStakingTransactionProof,
+        transaction: &nimiq_transaction::Transaction,
+    ) -> Self {
+        match proof {
+            OutgoingStakingTransactionProof::DeleteValidator { proof } => {
+                StakingData::DeleteValidator {
+                    validator_address: proof.compute_signer(),
+                }
+            }
+            OutgoingStakingTransactionProof::Unstake { proof } => StakingData::Unstake {
+                staker_address: proof.compute_signer(),
+                amount: transaction.value,
+            },
         }
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceipt {
+    pub transaction_hash: Blake2bHash,
+    pub block_hash: Blake2bHash,
+    pub block_number: u32,
+    pub transaction_index: u32,
+    pub success: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Inherent {
@@ -543,6 +871,45 @@ impl Inherent {
     }
 }
 
+/// The rewards paid out by a macro block. A macro block distributes the rewards of the batch
+/// preceding it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockReward {
+    pub block_number: u32,
+    /// The sum of the rewards paid out to the validators.
+    pub total_reward: Coin,
+    /// The reward that was burned because slots were slashed or a reward address couldn't accept
+    /// the reward.
+    pub burned_reward: Coin,
+    /// The rewards per reward address of the validators.
+    pub rewards: HashMap<Address, Coin>,
+}
+
+impl BlockReward {
+    pub fn from_inherents(block_number: u32, inherents: Vec<nimiq_account::Inherent>) -> Self {
+        let mut total_reward = Coin::ZERO;
+        let mut burned_reward = Coin::ZERO;
+        let mut rewards = HashMap::new();
+
+        for inherent in inherents {
+            if inherent.target == Address::burn_address() {
+                burned_reward += inherent.value;
+            } else {
+                total_reward += inherent.value;
+                *rewards.entry(inherent.target).or_insert(Coin::ZERO) += inherent.value;
+            }
+        }
+
+        BlockReward {
+            block_number,
+            total_reward,
+            burned_reward,
+            rewards,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Account {
     pub address: Address,
@@ -565,8 +932,10 @@ pub enum AccountAdditionalFields {
         /// User friendly address (NQ-address) of the owner of the vesting contract.
         owner: Address,
         /// The block that the vesting contracted commenced.
+        #[serde(with = "crate::serde_helpers::decimal")]
         vesting_start: u64,
         /// The number of blocks after which some part of the vested funds is released.
+        #[serde(with = "crate::serde_helpers::decimal")]
         vesting_step_blocks: u64,
         /// The amount (in Luna) released every vestingStepBlocks blocks.
         vesting_step_amount: Coin,
@@ -587,6 +956,7 @@ pub enum AccountAdditionalFields {
         /// Number of hashes this HTLC is split into
         hash_count: u8,
         /// Block after which the contract can only be used by the original sender to recover funds.
+        #[serde(with = "crate::serde_helpers::decimal")]
         timeout: u64,
         /// The total amount (in smallest unit) that was provided at the contract creation.
         total_amount: Coin,
@@ -637,6 +1007,23 @@ impl Account {
     }
 }
 
+/// An account together with a Merkle proof of its inclusion in (or, if the account doesn't exist,
+/// its exclusion from) the accounts tree of the given block. The proof can be verified against the
+/// block's `stateHash`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProof {
+    pub account: Account,
+    /// Whether the account exists, i.e. whether `proof` is an inclusion proof.
+    pub included: bool,
+    /// Hex-encoded serialized trie proof, with the nodes in post-order.
+    #[serde(with = "crate::serde_helpers::hex")]
+    pub proof: Vec<u8>,
+    pub block_number: u32,
+    pub block_hash: Blake2bHash,
+    pub state_hash: Blake2bHash,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Staker {
@@ -692,6 +1079,41 @@ impl Validator {
     }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Peer {
+    pub id: String,
+    /// The score of the peer. It decreases with protocol violations and recovers over time. A
+    /// peer whose score falls too low gets banned.
+    pub score: f64,
+}
+
+/// A transaction that is pending in the mempool.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingTransaction {
+    #[serde(flatten)]
+    pub transaction: Transaction,
+    /// The number of seconds the transaction has been pending in the mempool.
+    pub pending_time: u64,
+    pub fee_per_byte: f64,
+}
+
+impl PendingTransaction {
+    pub fn from_transaction(
+        transaction: nimiq_transaction::Transaction,
+        pending_time: u64,
+    ) -> Self {
+        let fee_per_byte = transaction.fee_per_byte();
+
+        PendingTransaction {
+            transaction: Transaction::from_transaction(transaction),
+            pending_time,
+            fee_per_byte,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MempoolInfo {
@@ -727,6 +1149,79 @@ pub struct MempoolInfo {
     pub buckets: Vec<u32>,
 }
 
+/// The verdict of `validateTransaction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransactionVerdict {
+    Valid,
+    InvalidSignature,
+    InsufficientBalance,
+    NotYetValid,
+    Expired,
+    AlreadyIncluded,
+    Invalid,
+    TooLarge,
+}
+
+impl TransactionVerdict {
+    /// A stable numeric code for each verdict, so that clients can map it to localized messages.
+    pub fn code(&self) -> u8 {
+        match self {
+            TransactionVerdict::Valid => 0,
+            TransactionVerdict::InvalidSignature => 1,
+            TransactionVerdict::InsufficientBalance => 2,
+            TransactionVerdict::NotYetValid => 3,
+            TransactionVerdict::Expired => 4,
+            TransactionVerdict::AlreadyIncluded => 5,
+            TransactionVerdict::Invalid => 6,
+            TransactionVerdict::TooLarge => 7,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionValidation {
+    pub valid: bool,
+    pub code: u8,
+    pub verdict: TransactionVerdict,
+}
+
+impl From<TransactionVerdict> for TransactionValidation {
+    fn from(verdict: TransactionVerdict) -> Self {
+        Self {
+            valid: verdict == TransactionVerdict::Valid,
+            code: verdict.code(),
+            verdict,
+        }
+    }
+}
+
+/// The result of `verifyTransactionSignature`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionSignatureVerification {
+    /// The sender declared in the transaction.
+    pub sender: Address,
+    /// The address that signed the transaction.
+    pub signer: Address,
+    /// Whether the transaction is validly signed by its sender.
+    pub valid: bool,
+}
+
+impl TransactionSignatureVerification {
+    pub fn new(
+        transaction: &nimiq_transaction::Transaction,
+        verification: nimiq_transaction::SignatureVerification,
+    ) -> Self {
+        Self {
+            sender: transaction.sender.clone(),
+            signer: verification.signer,
+            valid: verification.valid,
+        }
+    }
+}
+
 impl MempoolInfo {
     pub fn from_txs(transactions: Vec<nimiq_transaction::Transaction>) -> Self {
         let mut info = MempoolInfo {
@@ -870,3 +1365,145 @@ impl MempoolInfo {
         info
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nimiq_keys::{KeyPair, PrivateKey};
+    use nimiq_primitives::networks::NetworkId;
+    use nimiq_primitives::policy::STAKING_CONTRACT_ADDRESS;
+    use nimiq_transaction::SignatureProof;
+
+    use super::*;
+
+    fn key_pair() -> KeyPair {
+        KeyPair::from(PrivateKey::from_bytes(&[1u8; PrivateKey::SIZE]).unwrap())
+    }
+
+    #[test]
+    fn stake_transaction_has_staking_data() {
+        let staker_address = Address::from(&key_pair().public);
+        let data = IncomingStakingTransactionData::Stake {
+            staker_address: staker_address.clone(),
+        }
+        .serialize_to_vec();
+        let transaction = nimiq_transaction::Transaction::new_extended(
+            Address::from([1u8; Address::SIZE]),
+            AccountType::Basic,
+            STAKING_CONTRACT_ADDRESS,
+            AccountType::Staking,
+            Coin::from_u64_unchecked(1000),
+            Coin::ZERO,
+            data.clone(),
+            1,
+            NetworkId::UnitAlbatross,
+        );
+
+        let transaction = Transaction::from_transaction(transaction);
+        match transaction.staking_data {
+            Some(StakingData::Stake {
+                staker_address: ref address,
+                amount,
+            }) => {
+                assert_eq!(address, &staker_address);
+                assert_eq!(amount, Coin::from_u64_unchecked(1000));
+            }
+            ref staking_data => panic!("Unexpected staking data: {:?}", staking_data),
+        }
+        assert_eq!(transaction.data, data);
+
+        let json = serde_json::to_value(&transaction).unwrap();
+        assert_eq!(json["stakingData"]["type"], "stake");
+        assert_eq!(
+            json["stakingData"]["stakerAddress"],
+            serde_json::to_value(&staker_address).unwrap()
+        );
+    }
+
+    #[test]
+    fn unstake_transaction_has_staking_data() {
+        let key_pair = key_pair();
+        let recipient = Address::from([1u8; Address::SIZE]);
+        let mut transaction = nimiq_transaction::Transaction::new_extended(
+            STAKING_CONTRACT_ADDRESS,
+            AccountType::Staking,
+            recipient,
+            AccountType::Basic,
+            Coin::from_u64_unchecked(500),
+            Coin::ZERO,
+            Vec::new(),
+            1,
+            NetworkId::UnitAlbatross,
+        );
+        let signature = key_pair.sign(&transaction.serialize_content());
+        transaction.proof = OutgoingStakingTransactionProof::Unstake {
+            proof: SignatureProof::from(key_pair.public, signature),
+        }
+        .serialize_to_vec();
+
+        let transaction = Transaction::from_transaction(transaction);
+        match transaction.staking_data {
+            Some(StakingData::Unstake {
+                ref staker_address,
+                amount,
+            }) => {
+                assert_eq!(staker_address, &Address::from(&key_pair.public));
+                assert_eq!(amount, Coin::from_u64_unchecked(500));
+            }
+            ref staking_data => panic!("Unexpected staking data: {:?}", staking_data),
+        }
+
+        let json = serde_json::to_value(&transaction).unwrap();
+        assert_eq!(json["stakingData"]["type"], "unstake");
+    }
+
+    #[test]
+    fn block_reward_sums_rewards_per_address() {
+        let reward = |target: Address, value: u64| nimiq_account::Inherent {
+            ty: nimiq_account::InherentType::Reward,
+            target,
+            value: Coin::from_u64_unchecked(value),
+            data: vec![],
+        };
+        let validator1 = Address::from([1u8; Address::SIZE]);
+        let validator2 = Address::from([2u8; Address::SIZE]);
+
+        let block_reward = BlockReward::from_inherents(
+            64,
+            vec![
+                reward(validator1.clone(), 100),
+                reward(validator2.clone(), 50),
+                reward(validator1.clone(), 25),
+                reward(Address::burn_address(), 10),
+            ],
+        );
+
+        assert_eq!(block_reward.block_number, 64);
+        assert_eq!(block_reward.total_reward, Coin::from_u64_unchecked(175));
+        assert_eq!(block_reward.burned_reward, Coin::from_u64_unchecked(10));
+        assert_eq!(block_reward.rewards.len(), 2);
+        assert_eq!(
+            block_reward.rewards[&validator1],
+            Coin::from_u64_unchecked(125)
+        );
+        assert_eq!(
+            block_reward.rewards[&validator2],
+            Coin::from_u64_unchecked(50)
+        );
+    }
+
+    #[test]
+    fn basic_transaction_has_no_staking_data() {
+        let transaction = nimiq_transaction::Transaction::new_basic(
+            Address::from([1u8; Address::SIZE]),
+            Address::from([2u8; Address::SIZE]),
+            Coin::from_u64_unchecked(1000),
+            Coin::ZERO,
+            1,
+            NetworkId::UnitAlbatross,
+        );
+
+        let json = serde_json::to_value(&Transaction::from_transaction(transaction)).unwrap();
+        assert!(json.get("stakingData").is_none());
+        assert_eq!(json["data"], "");
+    }
+}
@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 
+use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 
+use crate::types::ValidatorStatus;
+
 #[nimiq_jsonrpc_derive::proxy(name = "ValidatorProxy", rename_all = "camelCase")]
 #[async_trait]
 pub trait ValidatorInterface {
@@ -12,4 +15,11 @@ pub trait ValidatorInterface {
     async fn get_signing_key(&mut self) -> Result<String, Self::Error>;
 
     async fn get_voting_key(&mut self) -> Result<String, Self::Error>;
+
+    async fn get_validator_status(&mut self) -> Result<ValidatorStatus, Self::Error>;
+
+    async fn report_fork_proof(
+        &mut self,
+        raw_fork_proof: String,
+    ) -> Result<Blake2bHash, Self::Error>;
 }
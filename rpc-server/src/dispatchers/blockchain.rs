@@ -4,19 +4,27 @@ use async_trait::async_trait;
 use futures::stream::{BoxStream, StreamExt};
 use parking_lot::RwLock;
 
+use beserial::Serialize;
 use nimiq_account::StakingContract;
 use nimiq_blockchain::{AbstractBlockchain, Blockchain, BlockchainEvent};
 use nimiq_hash::Blake2bHash;
 use nimiq_keys::Address;
 use nimiq_primitives::{coin::Coin, policy};
-use nimiq_rpc_interface::types::{ParkedSet, Validator};
+use nimiq_rpc_interface::types::{ParkedSet, Validator, ValidatorParticipation};
 use nimiq_rpc_interface::{
     blockchain::BlockchainInterface,
-    types::{Account, Block, Inherent, SlashedSlots, Slot, Staker, Transaction},
+    types::{
+        Account, AccountProof, Block, BlockNumberOrHash, BlockReward, BlockSizeBreakdown,
+        BlockStatus, Inherent, NetworkInfo, SlashedSlots, Slot, Slots, Staker, StateStats,
+        Transaction, TransactionReceipt,
+    },
 };
 
 use crate::error::Error;
 
+/// The maximum number of blocks that can be fetched with a single `getBlocks` request.
+pub const MAX_BLOCKS_PER_REQUEST: u32 = 100;
+
 pub struct BlockchainDispatcher {
     blockchain: Arc<RwLock<Blockchain>>,
 }
@@ -37,14 +45,22 @@ impl BlockchainInterface for BlockchainDispatcher {
         Ok(self.blockchain.read().block_number())
     }
 
-    /// Returns the batch number for the current head.
-    async fn get_batch_number(&mut self) -> Result<u32, Error> {
-        Ok(policy::batch_at(self.blockchain.read().block_number()))
+    /// Returns the batch number for the given block number, which defaults to the current head.
+    async fn get_batch_number(&mut self, block_number: Option<u32>) -> Result<u32, Error> {
+        let block_number = block_number.unwrap_or_else(|| self.blockchain.read().block_number());
+        Ok(policy::batch_at(validate_block_number(block_number)?))
+    }
+
+    /// Returns the epoch number for the given block number, which defaults to the current head.
+    async fn get_epoch_number(&mut self, block_number: Option<u32>) -> Result<u32, Error> {
+        let block_number = block_number.unwrap_or_else(|| self.blockchain.read().block_number());
+        Ok(policy::epoch_at(validate_block_number(block_number)?))
     }
 
-    /// Returns the epoch number for the current head.
-    async fn get_epoch_number(&mut self) -> Result<u32, Error> {
-        Ok(policy::epoch_at(self.blockchain.read().block_number()))
+    /// Returns whether the block at the given block number is a macro block.
+    async fn is_macro_block(&mut self, block_number: u32) -> Result<bool, Error> {
+        let block_number = validate_block_number(block_number)?;
+        Ok(policy::is_macro_block_at(block_number))
     }
 
     /// Tries to fetch a block given its hash. It has an option to include the transactions in the
@@ -56,16 +72,17 @@ impl BlockchainInterface for BlockchainDispatcher {
     ) -> Result<Block, Error> {
         let blockchain = self.blockchain.read();
 
-        blockchain
-            .get_block(&hash, true, None)
-            .map(|block| {
-                Block::from_block(
-                    blockchain.deref(),
-                    block,
-                    include_transactions.unwrap_or(false),
-                )
-            })
-            .ok_or_else(|| Error::BlockNotFound(hash.into()))
+        let block = blockchain
+            .get_block(&hash, false, None)
+            .ok_or_else(|| Error::BlockNotFound(hash.into()))?;
+        let include_transactions = include_transactions.unwrap_or(false);
+        let block = load_block_body(&blockchain, block, include_transactions)?;
+
+        Ok(Block::from_block(
+            blockchain.deref(),
+            block,
+            include_transactions,
+        ))
     }
 
     /// Tries to fetch a block given its number. It has an option to include the transactions in the
@@ -81,14 +98,34 @@ impl BlockchainInterface for BlockchainDispatcher {
         let block = blockchain
             .get_block_at(block_number, true, None)
             .ok_or_else(|| Error::BlockNotFound(block_number.into()))?;
+        let include_transactions = include_transactions.unwrap_or(false);
+        let block = load_block_body(&blockchain, block, include_transactions)?;
 
         Ok(Block::from_block(
             blockchain.deref(),
             block,
-            include_transactions.unwrap_or(false),
+            include_transactions,
         ))
     }
 
+    /// Fetches up to `count` consecutive blocks of the main chain, starting at the given block
+    /// number. It has an option to include the transactions in the blocks, which defaults to
+    /// false. Blocks past the head of the chain are omitted, so fewer blocks than requested may
+    /// be returned. At most `MAX_BLOCKS_PER_REQUEST` blocks can be requested at once.
+    async fn get_blocks(
+        &mut self,
+        start_block_number: u32,
+        count: u32,
+        include_transactions: Option<bool>,
+    ) -> Result<Vec<Block>, Error> {
+        get_blocks(
+            &self.blockchain.read(),
+            start_block_number,
+            count,
+            include_transactions.unwrap_or(false),
+        )
+    }
+
     /// Returns the block at the head of the main chain. It has an option to include the
     /// transactions in the block, which defaults to false.
     async fn get_latest_block(
@@ -96,15 +133,105 @@ impl BlockchainInterface for BlockchainDispatcher {
         include_transactions: Option<bool>,
     ) -> Result<Block, Error> {
         let blockchain = self.blockchain.read();
-        let block = blockchain.head();
+        let include_transactions = include_transactions.unwrap_or(false);
+        let block = load_block_body(&blockchain, blockchain.head(), include_transactions)?;
 
         Ok(Block::from_block(
             blockchain.deref(),
             block,
+            include_transactions,
+        ))
+    }
+
+    /// Returns the genesis block of the network the node is running on. It has an option to
+    /// include the transactions in the block, which defaults to false.
+    async fn get_genesis_block(
+        &mut self,
+        include_transactions: Option<bool>,
+    ) -> Result<Block, Error> {
+        let blockchain = self.blockchain.read();
+
+        Ok(Block::from_block(
+            blockchain.deref(),
+            get_genesis_block(&blockchain),
             include_transactions.unwrap_or(false),
         ))
     }
 
+    /// Returns the network the node is running on, including its genesis hash and the policy
+    /// parameters. Clients can use it to verify they are connected to the intended network.
+    async fn get_network_info(&mut self) -> Result<NetworkInfo, Error> {
+        Ok(get_network_info(&self.blockchain.read()))
+    }
+
+    /// Tries to fetch a block given its number or hash and returns its serialization, encoded as a
+    /// hex string. The serialization always includes the block body and justification. Note that
+    /// when given a number, this function will only fetch blocks that are part of the main chain.
+    async fn get_raw_block(&mut self, block: BlockNumberOrHash) -> Result<String, Error> {
+        let raw_block = get_block(&self.blockchain.read(), block)?;
+
+        Ok(hex::encode(raw_block.serialize_to_vec()))
+    }
+
+    /// Returns whether the block with the given hash is on the main chain (`main`), on a fork
+    /// (`fork`) or unknown to this node (`unknown`). Blocks that were reverted by a rebranch are
+    /// reported as `fork`.
+    async fn get_block_status(&mut self, hash: Blake2bHash) -> Result<BlockStatus, Error> {
+        Ok(self.blockchain.read().get_chain_status(&hash).into())
+    }
+
+    /// Returns the serialized size of the block with the given number or hash broken down into its
+    /// header, justification, body and transactions.
+    async fn get_block_size_breakdown(
+        &mut self,
+        block: BlockNumberOrHash,
+    ) -> Result<BlockSizeBreakdown, Error> {
+        let block = get_block(&self.blockchain.read(), block)?;
+
+        Ok(BlockSizeBreakdown::from_block(&block))
+    }
+
+    /// Returns the serialized Tendermint proof that finalized the macro block with the given number
+    /// or hash. Light clients can verify it against the validators of the block's epoch.
+    async fn get_block_justification(&mut self, block: BlockNumberOrHash) -> Result<String, Error> {
+        let justification = get_block_justification(&self.blockchain.read(), block)?;
+
+        Ok(hex::encode(justification.serialize_to_vec()))
+    }
+
+    /// Returns the number of transactions in the block with the given hash. Macro blocks never
+    /// contain transactions, so for them it always returns zero. If the body of a micro block isn't
+    /// available, it returns `null`.
+    async fn get_block_transaction_count_by_hash(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<Option<usize>, Error> {
+        let block = self
+            .blockchain
+            .read()
+            .get_block(&hash, true, None)
+            .ok_or_else(|| Error::BlockNotFound(hash.into()))?;
+
+        Ok(block_transaction_count(&block))
+    }
+
+    /// Returns the number of transactions in the block at the given height. Macro blocks never
+    /// contain transactions, so for them it always returns zero. If the body of a micro block isn't
+    /// available, it returns `null`. Note that this function only considers blocks that are part
+    /// of the main chain.
+    async fn get_block_transaction_count_by_number(
+        &mut self,
+        block_number: u32,
+    ) -> Result<Option<usize>, Error> {
+        let block = self
+            .blockchain
+            .read()
+            .get_block_at(block_number, true, None)
+            .ok_or_else(|| Error::BlockNotFound(block_number.into()))?;
+
+        Ok(block_transaction_count(&block))
+    }
+
     /// Returns the information for the slot owner at the given block height and view number. The
     /// view number is optional, it will default to getting the view number for the existing block
     /// at the given height.
@@ -135,6 +262,7 @@ impl BlockchainInterface for BlockchainDispatcher {
         block_number: u32,
     ) -> Result<Vec<Transaction>, Error> {
         let blockchain = self.blockchain.read();
+        blockchain.check_history_available(block_number)?;
 
         // Get all the extended transactions that correspond to this block.
         let extended_tx_vec = blockchain
@@ -170,6 +298,7 @@ impl BlockchainInterface for BlockchainDispatcher {
         block_number: u32,
     ) -> Result<Vec<Inherent>, Self::Error> {
         let blockchain = self.blockchain.read();
+        blockchain.check_history_available(block_number)?;
 
         // Get all the extended transactions that correspond to this block.
         let extended_tx_vec = blockchain
@@ -207,6 +336,7 @@ impl BlockchainInterface for BlockchainDispatcher {
         // Calculate the numbers for the micro blocks in the batch.
         let first_block = policy::first_block_of_batch(batch_number);
         let last_block = policy::macro_block_of(batch_number);
+        blockchain.check_history_available(first_block)?;
 
         // Search all micro blocks of the batch to find the transactions.
         let mut transactions = vec![];
@@ -244,6 +374,7 @@ impl BlockchainInterface for BlockchainDispatcher {
         let blockchain = self.blockchain.read();
 
         let macro_block_number = policy::macro_block_of(batch_number);
+        blockchain.check_history_available(macro_block_number)?;
 
         // Check the batch's macro block to see if the batch includes slashes.
         let macro_block = blockchain
@@ -291,6 +422,74 @@ impl BlockchainInterface for BlockchainDispatcher {
             .collect())
     }
 
+    /// Returns the rewards paid out by the macro block at the given block number, i.e. the rewards
+    /// of the preceding batch, broken down by the reward addresses of the validators.
+    async fn get_block_reward(&mut self, block_number: u32) -> Result<BlockReward, Error> {
+        if !policy::is_macro_block_at(block_number) {
+            return Err(Error::NotMacroBlock(block_number.into()));
+        }
+
+        let inherents = self
+            .blockchain
+            .read()
+            .get_reward_inherents(block_number)
+            .ok_or_else(|| Error::BlockNotFound(block_number.into()))?;
+
+        Ok(BlockReward::from_inherents(block_number, inherents))
+    }
+
+    /// Returns the receipt of a transaction given its hash. The receipt contains the hash and
+    /// number of the block that included the transaction, as well as the index of the transaction
+    /// within that block. If the transaction hasn't been included in a block yet, it returns `null`.
+    /// Included transactions are always reported as successful, since blocks can't contain failed
+    /// transactions.
+    async fn get_transaction_receipt(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<Option<TransactionReceipt>, Error> {
+        let blockchain = self.blockchain.read();
+
+        // Get all the extended transactions that correspond to this hash.
+        let mut extended_tx_vec = blockchain.history_store.get_ext_tx_by_hash(&hash, None);
+
+        // Unpack the transaction, return nothing if it isn't mined yet.
+        let extended_tx = match extended_tx_vec.len() {
+            0 => {
+                return Ok(None);
+            }
+            1 => extended_tx_vec.pop().unwrap(),
+            _ => {
+                return Err(Error::MultipleTransactionsFound(hash));
+            }
+        };
+
+        let block_number = extended_tx.block_number;
+
+        let block_hash = blockchain
+            .get_block_at(block_number, false, None)
+            .ok_or_else(|| Error::BlockNotFound(block_number.into()))?
+            .hash();
+
+        // Find the position of the transaction within its block.
+        let transaction_index = blockchain
+            .history_store
+            .get_block_transactions(block_number, None)
+            .iter()
+            .position(|ext_tx| ext_tx.tx_hash() == hash)
+            .ok_or_else(|| Error::TransactionNotFound(hash.clone()))?;
+
+        Ok(Some(TransactionReceipt {
+            transaction_hash: hash,
+            block_hash,
+            block_number,
+            transaction_index: transaction_index as u32,
+            // The chain stores no execution status. A block is only valid if all of its
+            // transactions can be applied to the accounts, and there is no fee-only execution, so
+            // every transaction in the history store succeeded.
+            success: true,
+        }))
+    }
+
     /// Returns the hashes for the latest transactions for a given address. All the transactions
     /// where the given address is listed as a recipient or as a sender are considered. Reward
     /// transactions are also returned. It has an option to specify the maximum number of hashes to
@@ -310,37 +509,27 @@ impl BlockchainInterface for BlockchainDispatcher {
     /// Returns the latest transactions for a given address. All the transactions
     /// where the given address is listed as a recipient or as a sender are considered. Reward
     /// transactions are also returned. It has an option to specify the maximum number of transactions
-    /// to fetch, it defaults to 500.
+    /// to fetch, it defaults to 500, and an option to skip the given number of most recent
+    /// transactions, which allows to paginate through the history of an address.
     async fn get_transactions_by_address(
         &mut self,
         address: Address,
         max: Option<u16>,
+        offset: Option<u32>,
     ) -> Result<Vec<Transaction>, Error> {
         let blockchain = self.blockchain.read();
 
-        // Get the transaction hashes for this address.
-        let tx_hashes =
-            blockchain
-                .history_store
-                .get_tx_hashes_by_address(&address, max.unwrap_or(500), None);
+        // Get the extended transactions for this address.
+        let extended_txs = blockchain.history_store.get_transactions_by_address(
+            &address,
+            offset.unwrap_or(0),
+            max.unwrap_or(500),
+            None,
+        );
 
         let mut txs = vec![];
 
-        for hash in tx_hashes {
-            // Get all the extended transactions that correspond to this hash.
-            let mut extended_tx_vec = blockchain.history_store.get_ext_tx_by_hash(&hash, None);
-
-            // Unpack the transaction or raise an error.
-            let extended_tx = match extended_tx_vec.len() {
-                0 => {
-                    return Err(Error::TransactionNotFound(hash));
-                }
-                1 => extended_tx_vec.pop().unwrap(),
-                _ => {
-                    return Err(Error::MultipleTransactionsFound(hash));
-                }
-            };
-
+        for extended_tx in extended_txs {
             // Convert the extended transaction into a regular transaction. This will also convert
             // reward inherents.
             let block_number = extended_tx.block_number;
@@ -369,6 +558,36 @@ impl BlockchainInterface for BlockchainDispatcher {
         }
     }
 
+    /// Returns the account at the given address together with a Merkle proof of its inclusion in
+    /// the accounts tree of the current head. For non-existent accounts, the proof shows their
+    /// exclusion instead.
+    async fn get_account_proof(&mut self, address: Address) -> Result<AccountProof, Error> {
+        let blockchain = self.blockchain.read();
+
+        let head = blockchain.head_snapshot();
+        let state_hash = blockchain.state().accounts.get_root(None);
+        let account = blockchain.get_account(&address);
+        let proof = blockchain.get_account_proof(&address);
+
+        Ok(AccountProof {
+            included: account.is_some(),
+            account: match account {
+                Some(account) => Account::from_account(address, account),
+                None => Account::empty(address),
+            },
+            proof: proof.serialize_to_vec(),
+            block_number: head.block_number,
+            block_hash: head.hash,
+            state_hash,
+        })
+    }
+
+    /// Returns the number of accounts and branch nodes in the accounts tree of the current head.
+    /// The counts are maintained incrementally, so this is cheap even for a large state.
+    async fn get_state_stats(&mut self) -> Result<StateStats, Error> {
+        Ok(get_state_stats(&self.blockchain.read()))
+    }
+
     /// Returns a map of the currently active validator's addresses and balances.
     async fn get_active_validators(&mut self) -> Result<HashMap<Address, Coin>, Error> {
         let staking_contract = self.blockchain.read().get_staking_contract();
@@ -382,6 +601,28 @@ impl BlockchainInterface for BlockchainDispatcher {
         Ok(active_validators)
     }
 
+    /// Returns the validators of the current epoch along with their slots, voting keys and reward
+    /// addresses. Returns an empty list if there are no validators yet.
+    async fn get_validators(&mut self) -> Result<Vec<Slots>, Error> {
+        let blockchain = self.blockchain.read();
+
+        let validators = match blockchain.current_validators() {
+            Some(validators) => validators,
+            None => return Ok(vec![]),
+        };
+
+        let accounts_tree = &blockchain.state().accounts.tree;
+        let db_txn = blockchain.read_transaction();
+
+        Ok(Slots::from_slots_with_reward_addresses(
+            validators,
+            |address| {
+                StakingContract::get_validator(accounts_tree, &db_txn, address)
+                    .map(|validator| validator.reward_address)
+            },
+        ))
+    }
+
     /// Returns information about the currently slashed slots. This includes slots that lost rewards
     /// and that were disabled.
     async fn get_current_slashed_slots(&mut self) -> Result<SlashedSlots, Self::Error> {
@@ -428,6 +669,25 @@ impl BlockchainInterface for BlockchainDispatcher {
         })
     }
 
+    /// Returns how often each validator of the given epoch signed the view change proofs and
+    /// macro block justifications of that epoch, so that underperforming validators can be
+    /// identified. For the current epoch, the blocks up to the head are taken into account.
+    async fn get_validator_participation(
+        &mut self,
+        epoch_number: u32,
+    ) -> Result<Vec<ValidatorParticipation>, Error> {
+        let participation = self
+            .blockchain
+            .read()
+            .get_validator_participation(epoch_number, None)
+            .ok_or(Error::EpochNotAvailable(epoch_number))?;
+
+        Ok(participation
+            .into_iter()
+            .map(ValidatorParticipation::from)
+            .collect())
+    }
+
     /// Tries to fetch a validator information given its address. It has an option to include a map
     /// containing the addresses and stakes of all the stakers that are delegating to the validator.
     async fn get_validator_by_address(
@@ -496,3 +756,288 @@ impl BlockchainInterface for BlockchainDispatcher {
             .boxed())
     }
 }
+
+/// Looks up a block including its body by its number or hash.
+fn get_block(
+    blockchain: &Blockchain,
+    block: BlockNumberOrHash,
+) -> Result<nimiq_block::Block, Error> {
+    match block {
+        BlockNumberOrHash::Number(block_number) => blockchain
+            .get_block_at(block_number, true, None)
+            .ok_or_else(|| Error::BlockNotFound(block_number.into())),
+        BlockNumberOrHash::Hash(hash) => blockchain
+            .get_block(&hash, true, None)
+            .ok_or_else(|| Error::BlockNotFound(hash.into())),
+    }
+}
+
+/// Returns the genesis block of the blockchain. If the chain started at a checkpoint, the genesis
+/// block isn't stored, so the one of the configured network is returned instead.
+fn get_genesis_block(blockchain: &Blockchain) -> nimiq_block::Block {
+    blockchain.get_block_at(0, true, None).unwrap_or_else(|| {
+        nimiq_genesis::NetworkInfo::from_network_id(blockchain.network_id).genesis_block()
+    })
+}
+
+fn get_network_info(blockchain: &Blockchain) -> NetworkInfo {
+    let network_info = nimiq_genesis::NetworkInfo::from_network_id(blockchain.network_id);
+
+    NetworkInfo::new(
+        blockchain.network_id,
+        network_info.name(),
+        get_genesis_block(blockchain).hash(),
+    )
+}
+
+fn get_state_stats(blockchain: &Blockchain) -> StateStats {
+    let stats = blockchain.state().accounts.stats(None);
+
+    StateStats {
+        block_number: blockchain.block_number(),
+        num_accounts: stats.num_leaves,
+        num_branch_nodes: stats.num_branches,
+    }
+}
+
+/// Returns the justification of a macro block. Fails for micro blocks and for macro blocks without a
+/// justification, like the genesis block.
+fn get_block_justification(
+    blockchain: &Blockchain,
+    block: BlockNumberOrHash,
+) -> Result<nimiq_block::TendermintProof, Error> {
+    match get_block(blockchain, block.clone())? {
+        nimiq_block::Block::Macro(macro_block) => macro_block
+            .justification
+            .ok_or(Error::JustificationNotFound(block)),
+        nimiq_block::Block::Micro(_) => Err(Error::NotMacroBlock(block)),
+    }
+}
+
+/// Rejects block numbers for which the epoch and batch computations of the policy would overflow.
+fn validate_block_number(block_number: u32) -> Result<u32, Error> {
+    if block_number > u32::MAX - (policy::EPOCH_LENGTH - 1) {
+        return Err(Error::InvalidBlockNumber(block_number));
+    }
+    Ok(block_number)
+}
+
+/// Fetches up to `count` main chain blocks starting at `start_block_number`, stopping at the head.
+fn get_blocks(
+    blockchain: &Blockchain,
+    start_block_number: u32,
+    count: u32,
+    include_transactions: bool,
+) -> Result<Vec<Block>, Error> {
+    if count > MAX_BLOCKS_PER_REQUEST {
+        return Err(Error::TooManyBlocks(count, MAX_BLOCKS_PER_REQUEST));
+    }
+
+    let end_block_number = start_block_number
+        .saturating_add(count)
+        .min(blockchain.block_number().saturating_add(1));
+
+    (start_block_number..end_block_number)
+        .map(|block_number| {
+            let block = blockchain
+                .get_block_at(block_number, true, None)
+                .ok_or_else(|| Error::BlockNotFound(block_number.into()))?;
+            let block = load_block_body(blockchain, block, include_transactions)?;
+            Ok(Block::from_block(blockchain, block, include_transactions))
+        })
+        .collect()
+}
+
+/// Loads the body of a block if it isn't present. If the transactions were requested, a missing
+/// body is an error, so that they are never silently omitted.
+fn load_block_body(
+    blockchain: &Blockchain,
+    mut block: nimiq_block::Block,
+    include_transactions: bool,
+) -> Result<nimiq_block::Block, Error> {
+    match blockchain.load_block_body(&mut block, None) {
+        Err(e) if include_transactions => Err(e.into()),
+        _ => Ok(block),
+    }
+}
+
+/// Returns the number of transactions in a block, or `None` if it is a micro block without a body.
+fn block_transaction_count(block: &nimiq_block::Block) -> Option<usize> {
+    if block.is_macro() {
+        Some(0)
+    } else {
+        block.transactions().map(|txs| txs.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use beserial::Deserialize;
+    use nimiq_block_production::BlockProducer;
+    use nimiq_database::volatile::VolatileEnvironment;
+    use nimiq_primitives::networks::NetworkId;
+    use nimiq_test_utils::blockchain::{produce_macro_blocks, signing_key, voting_key};
+    use nimiq_utils::time::OffsetTime;
+
+    use super::*;
+
+    fn genesis_blockchain() -> Blockchain {
+        let time = Arc::new(OffsetTime::new());
+        let env = VolatileEnvironment::new(10).unwrap();
+        Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap()
+    }
+
+    #[test]
+    fn it_returns_a_range_of_blocks() {
+        let blockchain = genesis_blockchain();
+
+        let blocks = get_blocks(&blockchain, 0, 1, true).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].number, 0);
+        assert_eq!(blocks[0].hash, blockchain.head_hash());
+        assert!(serde_json::to_value(&blocks[0]).unwrap()["transactions"].is_array());
+
+        let blocks = get_blocks(&blockchain, 0, 1, false).unwrap();
+        assert!(serde_json::to_value(&blocks[0]).unwrap()["transactions"].is_null());
+    }
+
+    #[test]
+    fn it_reports_the_genesis_of_the_configured_network() {
+        let blockchain = genesis_blockchain();
+        let expected = nimiq_genesis::NetworkInfo::from_network_id(NetworkId::UnitAlbatross);
+
+        let genesis_block = get_genesis_block(&blockchain);
+        assert_eq!(genesis_block.block_number(), 0);
+        assert_eq!(&genesis_block.hash(), expected.genesis_hash());
+
+        let network_info = get_network_info(&blockchain);
+        assert_eq!(network_info.network_id, NetworkId::UnitAlbatross);
+        assert_eq!(network_info.name, expected.name());
+        assert_eq!(&network_info.genesis_hash, expected.genesis_hash());
+        assert_eq!(network_info.epoch_length, policy::EPOCH_LENGTH);
+    }
+
+    #[test]
+    fn it_reports_the_state_stats() {
+        let blockchain = genesis_blockchain();
+
+        let stats = get_state_stats(&blockchain);
+        assert_eq!(stats.block_number, 0);
+        assert_eq!(
+            stats.num_accounts,
+            blockchain.state().accounts.size(None) as u64
+        );
+        assert!(stats.num_branch_nodes >= 1);
+    }
+
+    #[test]
+    fn it_rejects_ranges_over_the_cap() {
+        let blockchain = genesis_blockchain();
+
+        assert!(get_blocks(&blockchain, 0, MAX_BLOCKS_PER_REQUEST, false).is_ok());
+        assert!(matches!(
+            get_blocks(&blockchain, 0, MAX_BLOCKS_PER_REQUEST + 1, false),
+            Err(Error::TooManyBlocks(count, MAX_BLOCKS_PER_REQUEST))
+                if count == MAX_BLOCKS_PER_REQUEST + 1
+        ));
+    }
+
+    #[test]
+    fn it_stops_at_the_chain_tip() {
+        let blockchain = genesis_blockchain();
+
+        assert_eq!(get_blocks(&blockchain, 0, 10, false).unwrap().len(), 1);
+        assert!(get_blocks(&blockchain, 1, 10, false).unwrap().is_empty());
+        assert!(get_blocks(&blockchain, u32::MAX, 10, false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn it_computes_batch_and_epoch_boundaries() {
+        let batch = policy::BATCH_LENGTH;
+        let epoch = policy::EPOCH_LENGTH;
+
+        // The genesis block is a macro block and is part of neither a batch nor an epoch.
+        let block_number = validate_block_number(0).unwrap();
+        assert_eq!(policy::batch_at(block_number), 0);
+        assert_eq!(policy::epoch_at(block_number), 0);
+        assert!(policy::is_macro_block_at(block_number));
+
+        for (block_number, batch_number, epoch_number, is_macro) in [
+            (1, 1, 1, false),
+            (batch - 1, 1, 1, false),
+            (batch, 1, 1, true),
+            (batch + 1, 2, 1, false),
+            (epoch - 1, epoch / batch, 1, false),
+            (epoch, epoch / batch, 1, true),
+            (epoch + 1, epoch / batch + 1, 2, false),
+        ] {
+            let block_number = validate_block_number(block_number).unwrap();
+            assert_eq!(policy::batch_at(block_number), batch_number);
+            assert_eq!(policy::epoch_at(block_number), epoch_number);
+            assert_eq!(policy::is_macro_block_at(block_number), is_macro);
+        }
+    }
+
+    #[test]
+    fn it_rejects_block_numbers_that_overflow() {
+        let max_block_number = u32::MAX - (policy::EPOCH_LENGTH - 1);
+
+        let block_number = validate_block_number(max_block_number).unwrap();
+        assert!(policy::batch_at(block_number) >= policy::epoch_at(block_number));
+
+        assert!(matches!(
+            validate_block_number(max_block_number + 1),
+            Err(Error::InvalidBlockNumber(block_number)) if block_number == max_block_number + 1
+        ));
+        assert!(validate_block_number(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn it_returns_the_justification_of_macro_blocks() {
+        let blockchain = Arc::new(RwLock::new(genesis_blockchain()));
+        let producer = BlockProducer::new(signing_key(), voting_key());
+        produce_macro_blocks(1, &producer, &blockchain);
+
+        let blockchain = blockchain.read();
+        let macro_block = blockchain
+            .get_block_at(policy::BATCH_LENGTH, true, None)
+            .unwrap()
+            .unwrap_macro();
+
+        let justification =
+            get_block_justification(&blockchain, policy::BATCH_LENGTH.into()).unwrap();
+        let raw_justification = hex::encode(justification.serialize_to_vec());
+
+        let justification: nimiq_block::TendermintProof =
+            Deserialize::deserialize_from_vec(&hex::decode(raw_justification).unwrap()).unwrap();
+        assert_eq!(Some(justification), macro_block.justification);
+
+        let justification =
+            get_block_justification(&blockchain, macro_block.hash().into()).unwrap();
+        assert_eq!(Some(justification), macro_block.justification);
+    }
+
+    #[test]
+    fn it_rejects_blocks_without_justification() {
+        let blockchain = Arc::new(RwLock::new(genesis_blockchain()));
+        let producer = BlockProducer::new(signing_key(), voting_key());
+        produce_macro_blocks(1, &producer, &blockchain);
+
+        let blockchain = blockchain.read();
+        assert!(matches!(
+            get_block_justification(&blockchain, 1.into()),
+            Err(Error::NotMacroBlock(_))
+        ));
+        // The genesis block isn't finalized by the validators.
+        assert!(matches!(
+            get_block_justification(&blockchain, 0.into()),
+            Err(Error::JustificationNotFound(_))
+        ));
+        assert!(matches!(
+            get_block_justification(&blockchain, (policy::BATCH_LENGTH + 1).into()),
+            Err(Error::BlockNotFound(_))
+        ));
+    }
+}
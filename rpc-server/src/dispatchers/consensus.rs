@@ -13,7 +13,7 @@ use nimiq_network_libp2p::Network;
 use nimiq_primitives::{coin::Coin, networks::NetworkId};
 use nimiq_rpc_interface::{
     consensus::ConsensusInterface,
-    types::{Transaction as RPCTransaction, ValidityStartHeight},
+    types::{Transaction as RPCTransaction, TransactionSignatureVerification, ValidityStartHeight},
 };
 use nimiq_transaction::account::htlc_contract::{AnyHash, HashAlgorithm};
 use nimiq_transaction::{SignatureProof, Transaction};
@@ -93,6 +93,21 @@ impl ConsensusInterface for ConsensusDispatcher {
         }
     }
 
+    /// Checks the signature of the given serialized transaction against its sender, without
+    /// looking at any account state. A malformed signature proof is an error, whereas a
+    /// well-formed but invalid signature is reported as not valid.
+    async fn verify_transaction_signature(
+        &mut self,
+        raw_tx: String,
+    ) -> Result<TransactionSignatureVerification, Error> {
+        let transaction: Transaction = Deserialize::deserialize_from_vec(&hex::decode(&raw_tx)?)?;
+        let verification = transaction.verify_signature()?;
+        Ok(TransactionSignatureVerification::new(
+            &transaction,
+            verification,
+        ))
+    }
+
     /// Returns a serialized basic transaction.
     async fn create_basic_transaction(
         &mut self,
@@ -1,25 +1,34 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use beserial::Deserialize;
+use beserial::{Deserialize, Serialize};
 
 use nimiq_blockchain::AbstractBlockchain;
 use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_mempool::mempool::Mempool;
+use nimiq_mempool::verify::TransactionVerdict as MempoolVerdict;
 
 use nimiq_rpc_interface::mempool::MempoolInterface;
-use nimiq_rpc_interface::types::{HashOrTx, MempoolInfo, Transaction};
+use nimiq_rpc_interface::types::{
+    HashOrTx, MempoolInfo, PendingTransaction, Transaction, TransactionValidation,
+    TransactionVerdict,
+};
 
 use crate::error::Error;
+use crate::fee_estimator::FeeEstimator;
 
 #[allow(dead_code)]
 pub struct MempoolDispatcher {
     mempool: Arc<Mempool>,
+    fee_estimator: FeeEstimator,
 }
 
 impl MempoolDispatcher {
     pub fn new(mempool: Arc<Mempool>) -> Self {
-        MempoolDispatcher { mempool }
+        MempoolDispatcher {
+            mempool,
+            fee_estimator: FeeEstimator::default(),
+        }
     }
 }
 
@@ -40,6 +49,29 @@ impl MempoolInterface for MempoolDispatcher {
         }
     }
 
+    /// Validates the given serialized transaction against the current state of the blockchain,
+    /// without adding it to the mempool.
+    async fn validate_transaction(
+        &mut self,
+        raw_tx: String,
+    ) -> Result<TransactionValidation, Self::Error> {
+        let tx: nimiq_transaction::Transaction =
+            Deserialize::deserialize_from_vec(&hex::decode(&raw_tx)?)?;
+
+        let verdict = match self.mempool.validate_transaction(&tx) {
+            MempoolVerdict::Valid => TransactionVerdict::Valid,
+            MempoolVerdict::InvalidSignature => TransactionVerdict::InvalidSignature,
+            MempoolVerdict::InsufficientBalance => TransactionVerdict::InsufficientBalance,
+            MempoolVerdict::NotYetValid => TransactionVerdict::NotYetValid,
+            MempoolVerdict::Expired => TransactionVerdict::Expired,
+            MempoolVerdict::AlreadyIncluded => TransactionVerdict::AlreadyIncluded,
+            MempoolVerdict::TooLarge => TransactionVerdict::TooLarge,
+            MempoolVerdict::Invalid => TransactionVerdict::Invalid,
+        };
+
+        Ok(verdict.into())
+    }
+
     /// Tries to fetch a transaction (including reward transactions) given its hash. It has an option
     /// to also search the mempool for the transaction, it defaults to false.
     async fn get_transaction_by_hash(
@@ -87,6 +119,20 @@ impl MempoolInterface for MempoolDispatcher {
         };
     }
 
+    /// Returns a transaction that is pending in the mempool, together with the time it has been
+    /// pending and its fee per byte. Returns `null` if the transaction isn't in the mempool.
+    async fn get_pending_transaction(
+        &mut self,
+        hash: Blake2bHash,
+    ) -> Result<Option<PendingTransaction>, Self::Error> {
+        Ok(self
+            .mempool
+            .get_pending_transaction(&hash)
+            .map(|(tx, pending_time)| {
+                PendingTransaction::from_transaction(tx, pending_time.as_secs())
+            }))
+    }
+
     async fn mempool_content(
         &mut self,
         include_transactions: bool,
@@ -111,7 +157,34 @@ impl MempoolInterface for MempoolDispatcher {
         Ok(MempoolInfo::from_txs(self.mempool.get_transactions()))
     }
 
-    async fn get_min_fee_per_byte(&mut self) -> Result<f64, Self::Error> {
-        Ok(self.mempool.get_rules().tx_fee_per_byte)
+    /// Returns the minimum fee per byte that a transaction currently needs to pay to be accepted
+    /// into the mempool, as a decimal string. This is the static floor of the mempool rules,
+    /// unless the mempool is congested.
+    async fn get_min_fee_per_byte(&mut self) -> Result<String, Self::Error> {
+        Ok(self.mempool.get_min_fee_per_byte().to_string())
+    }
+
+    /// Returns a suggested fee per byte for a transaction to be included within the given number
+    /// of blocks, as a decimal string. The estimate is based on the fees of recent blocks and the
+    /// transactions pending in the mempool, and is never lower than `getMinFeePerByte`.
+    async fn estimate_fee(&mut self, target_blocks: u32) -> Result<String, Self::Error> {
+        let max_target_blocks = FeeEstimator::WINDOW_SIZE as u32;
+        if target_blocks == 0 || target_blocks > max_target_blocks {
+            return Err(Error::InvalidFeeTarget(target_blocks, max_target_blocks));
+        }
+
+        self.fee_estimator.update(&self.mempool.blockchain.read());
+
+        let pending: Vec<_> = self
+            .mempool
+            .get_transactions()
+            .iter()
+            .map(|tx| (tx.fee_per_byte(), tx.serialized_size()))
+            .collect();
+
+        Ok(self
+            .fee_estimator
+            .estimate(target_blocks, self.mempool.get_min_fee_per_byte(), &pending)
+            .to_string())
     }
 }
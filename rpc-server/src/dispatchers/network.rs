@@ -4,7 +4,7 @@ use async_trait::async_trait;
 
 use nimiq_network_interface::network::Network as InterfaceNetwork;
 use nimiq_network_libp2p::Network;
-use nimiq_rpc_interface::network::NetworkInterface;
+use nimiq_rpc_interface::{network::NetworkInterface, types::Peer};
 
 use crate::error::Error;
 
@@ -42,4 +42,19 @@ impl NetworkInterface for NetworkDispatcher {
             .map(|peer| peer.id.to_string())
             .collect())
     }
+
+    /// Returns a list with the IDs and scores of all our peers.
+    async fn get_peer_scores(&mut self) -> Result<Vec<Peer>, Self::Error> {
+        let scores = self.network.peer_scores().await?;
+
+        Ok(self
+            .network
+            .get_peers()
+            .iter()
+            .map(|peer| Peer {
+                id: peer.id.to_string(),
+                score: scores.get(&peer.id).copied().unwrap_or(0.0),
+            })
+            .collect())
+    }
 }
@@ -1,19 +1,40 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use beserial::Serialize;
+use parking_lot::RwLock;
 
+use beserial::{Deserialize, Serialize};
+use nimiq_block::ForkProof;
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_hash::{Blake2bHash, Hash};
 use nimiq_keys::Address;
-use nimiq_rpc_interface::validator::ValidatorInterface;
+use nimiq_rpc_interface::{types::ValidatorStatus, validator::ValidatorInterface};
 use nimiq_validator::validator::ValidatorProxy;
 
 use crate::error::Error;
 
 pub struct ValidatorDispatcher {
     validator: ValidatorProxy,
+    blockchain: Arc<RwLock<Blockchain>>,
 }
 
 impl ValidatorDispatcher {
-    pub fn new(validator: ValidatorProxy) -> Self {
-        ValidatorDispatcher { validator }
+    pub fn new(validator: ValidatorProxy, blockchain: Arc<RwLock<Blockchain>>) -> Self {
+        ValidatorDispatcher {
+            validator,
+            blockchain,
+        }
+    }
+}
+
+fn validator_status(validator: &ValidatorProxy) -> ValidatorStatus {
+    let validator_id = *validator.active_slot_band.read();
+
+    ValidatorStatus {
+        address: validator.validator_address.read().clone(),
+        is_active: validator_id.is_some(),
+        validator_id,
+        has_quorum: *validator.has_quorum.read(),
     }
 }
 
@@ -44,4 +65,85 @@ impl ValidatorInterface for ValidatorDispatcher {
                 .serialize_to_vec(),
         ))
     }
+
+    /// Returns whether we are an active validator in the current epoch, our validator ID in the
+    /// active set, and whether we are connected to enough validators to finalize macro blocks.
+    async fn get_validator_status(&mut self) -> Result<ValidatorStatus, Self::Error> {
+        Ok(validator_status(&self.validator))
+    }
+
+    /// Reports a serialized fork proof, e.g. one observed by an external watcher. The proof is
+    /// verified against the current chain and added to the fork proofs that our validator
+    /// includes in the micro blocks it produces. Returns the hash of the fork proof.
+    async fn report_fork_proof(&mut self, raw_fork_proof: String) -> Result<Blake2bHash, Error> {
+        let fork_proof: ForkProof =
+            Deserialize::deserialize_from_vec(&hex::decode(&raw_fork_proof)?)?;
+        let hash = fork_proof.hash::<Blake2bHash>();
+
+        // The proof has to be valid for inclusion in the next block.
+        {
+            let blockchain = self.blockchain.read();
+            blockchain
+                .verify_fork_proof(&fork_proof, blockchain.block_number() + 1, None)
+                .map_err(Error::InvalidForkProof)?;
+        }
+
+        if !self.validator.fork_proofs.write().insert(fork_proof) {
+            return Err(Error::ForkProofAlreadyKnown(hash));
+        }
+
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nimiq_test_utils::blockchain::{signing_key, voting_key};
+    use nimiq_validator::slash::ForkProofPool;
+
+    use super::*;
+
+    fn validator_proxy() -> ValidatorProxy {
+        ValidatorProxy {
+            validator_address: Arc::new(RwLock::new(Address::from(&signing_key().public))),
+            signing_key: Arc::new(RwLock::new(signing_key())),
+            voting_key: Arc::new(RwLock::new(voting_key())),
+            fee_key: Arc::new(RwLock::new(signing_key())),
+            fork_proofs: Arc::new(RwLock::new(ForkProofPool::new())),
+            active_slot_band: Arc::new(RwLock::new(None)),
+            has_quorum: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    #[test]
+    fn it_reports_an_inactive_validator() {
+        let validator = validator_proxy();
+
+        let status = validator_status(&validator);
+        assert_eq!(status.address, Address::from(&signing_key().public));
+        assert!(!status.is_active);
+        assert_eq!(status.validator_id, None);
+        assert_eq!(status.has_quorum, None);
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["isActive"], false);
+        assert!(json.get("validatorId").is_none());
+    }
+
+    #[test]
+    fn it_reports_an_active_validator() {
+        let validator = validator_proxy();
+        *validator.active_slot_band.write() = Some(3);
+        *validator.has_quorum.write() = Some(true);
+
+        let status = validator_status(&validator);
+        assert!(status.is_active);
+        assert_eq!(status.validator_id, Some(3));
+        assert_eq!(status.has_quorum, Some(true));
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["isActive"], true);
+        assert_eq!(json["validatorId"], 3);
+        assert_eq!(json["hasQuorum"], true);
+    }
 }
@@ -20,12 +20,24 @@ pub enum Error {
     #[error("Block not found: {0}")]
     BlockNotFound(BlockNumberOrHash),
 
+    #[error("{0}")]
+    Blockchain(#[from] nimiq_blockchain::BlockchainError),
+
     #[error("Unexpected macro block: {0}")]
     UnexpectedMacroBlock(BlockNumberOrHash),
 
+    #[error("Not a macro block: {0}")]
+    NotMacroBlock(BlockNumberOrHash),
+
+    #[error("No justification for block: {0}")]
+    JustificationNotFound(BlockNumberOrHash),
+
     #[error("Method not implemented")]
     NotImplemented,
 
+    #[error("Invalid transaction: {0}")]
+    Transaction(#[from] nimiq_transaction::TransactionError),
+
     #[error("Invalid combination of transaction parameters")]
     InvalidTransactionParameters,
 
@@ -41,6 +53,18 @@ pub enum Error {
     #[error("No staker with address: {0}")]
     StakerNotFound(Address),
 
+    #[error("Too many blocks requested: {0} (at most {1} per request)")]
+    TooManyBlocks(u32, u32),
+
+    #[error("Invalid block number: {0}")]
+    InvalidBlockNumber(u32),
+
+    #[error("Invalid fee estimation target: {0} blocks (at most {1})")]
+    InvalidFeeTarget(u32, u32),
+
+    #[error("Epoch not available: {0}")]
+    EpochNotAvailable(u32),
+
     #[error("Wrong passphrase")]
     WrongPassphrase,
 
@@ -62,12 +86,111 @@ pub enum Error {
     #[error("Multiple transactions found: {0}")]
     MultipleTransactionsFound(Blake2bHash),
 
+    #[error("Invalid fork proof: {0}")]
+    InvalidForkProof(nimiq_block::ForkProofError),
+
+    #[error("Fork proof already known: {0}")]
+    ForkProofAlreadyKnown(Blake2bHash),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+impl Error {
+    /// JSON-RPC 2.0 error code for invalid method parameters.
+    pub const INVALID_PARAMS: i64 = -32602;
+
+    /// JSON-RPC 2.0 error code for a method that doesn't exist or isn't available.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+
+    /// JSON-RPC 2.0 error code for implementation-defined server errors.
+    pub const SERVER_ERROR: i64 = -32000;
+
+    /// Returns the JSON-RPC 2.0 error code that corresponds to this error.
+    pub fn code(&self) -> i64 {
+        match self {
+            Error::Core(_)
+            | Error::InvalidTransactionParameters
+            | Error::Transaction(_)
+            | Error::NotMacroBlock(_)
+            | Error::TooManyBlocks(_, _)
+            | Error::InvalidBlockNumber(_)
+            | Error::InvalidFeeTarget(_, _)
+            | Error::InvalidForkProof(_)
+            | Error::TransactionBuilder(_)
+            | Error::HexError(_)
+            | Error::Beserial(_) => Self::INVALID_PARAMS,
+            Error::NotImplemented => Self::METHOD_NOT_FOUND,
+            _ => Self::SERVER_ERROR,
+        }
+    }
+}
+
 impl From<Error> for nimiq_jsonrpc_core::RpcError {
     fn from(e: Error) -> Self {
-        RpcError::internal_error(Some(serde_json::value::Value::String(e.to_string())))
+        RpcError {
+            code: e.code(),
+            message: Some(e.to_string()),
+            data: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn invalid_params_error_shape() {
+        let error: RpcError = Error::InvalidTransactionParameters.into();
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": -32602,
+                "message": "Invalid combination of transaction parameters",
+            })
+        );
+    }
+
+    #[test]
+    fn too_many_blocks_error_shape() {
+        let error: RpcError = Error::TooManyBlocks(1000, 100).into();
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": -32602,
+                "message": "Too many blocks requested: 1000 (at most 100 per request)",
+            })
+        );
+    }
+
+    #[test]
+    fn method_not_found_error_shape() {
+        let error: RpcError = Error::NotImplemented.into();
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": -32601,
+                "message": "Method not implemented",
+            })
+        );
+    }
+
+    #[test]
+    fn server_error_shape() {
+        let error: RpcError = Error::BlockNotFound(42.into()).into();
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({
+                "code": -32000,
+                "message": "Block not found: 42",
+            })
+        );
     }
 }
@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+
+use beserial::Serialize;
+use nimiq_block::{Block, MicroBlock};
+use nimiq_blockchain::{AbstractBlockchain, Blockchain};
+use nimiq_hash::Blake2bHash;
+
+/// The fee statistics of a micro block that are relevant for fee estimation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockFeeStats {
+    pub block_number: u32,
+    pub block_hash: Blake2bHash,
+    /// The lowest fee per byte that a transaction in the block paid, if it contained any.
+    pub min_fee_per_byte: Option<f64>,
+    /// The number of bytes the transactions in the block used.
+    pub used_bytes: usize,
+}
+
+impl BlockFeeStats {
+    pub fn from_block(block: &MicroBlock) -> Option<Self> {
+        let transactions = &block.body.as_ref()?.transactions;
+
+        Some(BlockFeeStats {
+            block_number: block.header.block_number,
+            block_hash: block.hash(),
+            min_fee_per_byte: transactions
+                .iter()
+                .map(|tx| tx.fee_per_byte())
+                .reduce(f64::min),
+            used_bytes: transactions.iter().map(|tx| tx.serialized_size()).sum(),
+        })
+    }
+
+    /// The lowest fee per byte that was needed to be included in the block. If the block wasn't
+    /// (almost) full, any transaction accepted by the mempool could have been included.
+    fn clearing_fee_per_byte(&self) -> f64 {
+        let capacity = MicroBlock::get_available_bytes(0) as f64;
+
+        if self.used_bytes as f64 >= FeeEstimator::CONGESTED_BLOCK_USAGE * capacity {
+            self.min_fee_per_byte.unwrap_or(0.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Estimates the fee per byte that a transaction needs to pay to be included within a given
+/// number of blocks, from the fees in recent blocks and the transactions pending in the mempool.
+#[derive(Debug, Default)]
+pub struct FeeEstimator {
+    // The fee statistics of the most recent micro blocks in the main chain, oldest first.
+    blocks: VecDeque<BlockFeeStats>,
+}
+
+impl FeeEstimator {
+    /// The maximum number of recent micro blocks that are considered.
+    pub const WINDOW_SIZE: usize = 100;
+
+    /// The minimum number of recent micro blocks needed to estimate the fee from the history.
+    pub const MIN_BLOCKS: usize = 10;
+
+    /// The fee per byte that is suggested if there aren't enough recent blocks to estimate it.
+    pub const DEFAULT_FEE_PER_BYTE: f64 = 2.0;
+
+    /// The fraction of the available bytes a block needs to use to be considered congested.
+    pub const CONGESTED_BLOCK_USAGE: f64 = 0.9;
+
+    /// Adds the fee statistics of the next block to the window.
+    pub fn push_block(&mut self, stats: BlockFeeStats) {
+        self.blocks.push_back(stats);
+
+        while self.blocks.len() > Self::WINDOW_SIZE {
+            self.blocks.pop_front();
+        }
+    }
+
+    /// Returns the number of blocks in the window.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Updates the window with the micro blocks of the main chain that were added since the last
+    /// update. The window is rebuilt if the chain was rebranched.
+    pub fn update(&mut self, blockchain: &Blockchain) {
+        if let Some(last) = self.blocks.back() {
+            let last_hash = blockchain
+                .chain_store
+                .get_block_at(last.block_number, false, None)
+                .map(|block| block.hash());
+            if last_hash.as_ref() != Some(&last.block_hash) {
+                self.blocks.clear();
+            }
+        }
+
+        let head_block_number = blockchain.block_number();
+        let first_block_number = head_block_number
+            .saturating_sub(Self::WINDOW_SIZE as u32)
+            .max(1);
+        let start = self
+            .blocks
+            .back()
+            .map(|last| last.block_number + 1)
+            .unwrap_or(first_block_number)
+            .max(first_block_number);
+
+        for block_number in start..=head_block_number {
+            if let Some(Block::Micro(block)) = blockchain.get_block_at(block_number, true, None) {
+                if let Some(stats) = BlockFeeStats::from_block(&block) {
+                    self.push_block(stats);
+                }
+            }
+        }
+    }
+
+    /// Estimates the fee per byte needed to be included within `target_blocks` blocks. The
+    /// estimate is at least `min_fee_per_byte`, the fee needed to be accepted into the mempool.
+    /// `pending` are the fee per byte and size of the transactions pending in the mempool.
+    pub fn estimate(
+        &self,
+        target_blocks: u32,
+        min_fee_per_byte: f64,
+        pending: &[(f64, usize)],
+    ) -> f64 {
+        let target_blocks = target_blocks.max(1) as usize;
+
+        // A transaction paying a block's clearing fee would have been included in it. Paying the
+        // fee that a fraction of `1 / target_blocks` of the recent blocks cleared, it is expected
+        // to be included within `target_blocks` blocks.
+        let history_fee = if self.blocks.len() < Self::MIN_BLOCKS {
+            Self::DEFAULT_FEE_PER_BYTE
+        } else {
+            let mut clearing_fees: Vec<f64> = self
+                .blocks
+                .iter()
+                .map(BlockFeeStats::clearing_fee_per_byte)
+                .collect();
+            clearing_fees.sort_by(f64::total_cmp);
+
+            let rank = (clearing_fees.len() + target_blocks - 1) / target_blocks;
+            clearing_fees[rank - 1]
+        };
+
+        // If the mempool holds more transactions than fit into the target blocks, a transaction
+        // needs to outbid the ones that don't fit.
+        let mut pending = pending.to_vec();
+        pending.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let capacity = target_blocks * MicroBlock::get_available_bytes(0);
+        let mut size = 0;
+        let mut congestion_fee = 0.0;
+        for (fee_per_byte, tx_size) in pending {
+            size += tx_size;
+            if size > capacity {
+                congestion_fee = fee_per_byte;
+                break;
+            }
+        }
+
+        min_fee_per_byte.max(history_fee).max(congestion_fee)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_number: u32, min_fee_per_byte: Option<f64>, used_bytes: usize) -> BlockFeeStats {
+        BlockFeeStats {
+            block_number,
+            block_hash: Blake2bHash::default(),
+            min_fee_per_byte,
+            used_bytes,
+        }
+    }
+
+    fn estimator_with(blocks: impl IntoIterator<Item = BlockFeeStats>) -> FeeEstimator {
+        let mut estimator = FeeEstimator::default();
+        for stats in blocks {
+            estimator.push_block(stats);
+        }
+        estimator
+    }
+
+    #[test]
+    fn it_returns_the_default_without_enough_history() {
+        let estimator =
+            estimator_with((1..FeeEstimator::MIN_BLOCKS as u32).map(|i| block(i, None, 0)));
+
+        assert_eq!(
+            estimator.estimate(1, 0.0, &[]),
+            FeeEstimator::DEFAULT_FEE_PER_BYTE
+        );
+        assert_eq!(estimator.estimate(1, 5.0, &[]), 5.0);
+    }
+
+    #[test]
+    fn it_keeps_a_rolling_window() {
+        let estimator =
+            estimator_with((1..=2 * FeeEstimator::WINDOW_SIZE as u32).map(|i| block(i, None, 0)));
+
+        assert_eq!(estimator.num_blocks(), FeeEstimator::WINDOW_SIZE);
+        assert_eq!(
+            estimator.blocks.front().unwrap().block_number,
+            FeeEstimator::WINDOW_SIZE as u32 + 1
+        );
+    }
+
+    #[test]
+    fn it_follows_the_fees_of_full_blocks() {
+        let full = MicroBlock::get_available_bytes(0);
+
+        // Blocks with spare room don't raise the estimate.
+        let estimator = estimator_with((1..=20).map(|i| block(i, Some(3.0), 100)));
+        assert_eq!(estimator.estimate(1, 1.0, &[]), 1.0);
+
+        // Three out of four blocks were full and only included transactions paying at least 4.
+        let estimator = estimator_with((1..=20).map(|i| {
+            if i % 4 == 0 {
+                block(i, Some(1.0), 100)
+            } else {
+                block(i, Some(4.0), full)
+            }
+        }));
+        assert_eq!(estimator.estimate(1, 1.0, &[]), 4.0);
+        assert_eq!(estimator.estimate(3, 1.0, &[]), 4.0);
+        // Every fourth block had room for any transaction.
+        assert_eq!(estimator.estimate(4, 1.0, &[]), 1.0);
+    }
+
+    #[test]
+    fn it_responds_to_mempool_congestion() {
+        let full = MicroBlock::get_available_bytes(0);
+        let estimator = estimator_with((1..=20).map(|i| block(i, Some(1.0), 100)));
+
+        // Two blocks worth of transactions are pending, half of them paying 10 per byte.
+        let tx_size = 100;
+        let num_txs = full / tx_size;
+        let pending: Vec<_> = (0..2 * num_txs)
+            .map(|i| (if i < num_txs { 10.0 } else { 5.0 }, tx_size))
+            .collect();
+
+        // To be included in the next block, a transaction has to outbid the pending ones that
+        // don't fit into it.
+        assert_eq!(estimator.estimate(1, 1.0, &pending), 5.0);
+        // Within three blocks, all pending transactions fit.
+        assert_eq!(estimator.estimate(3, 1.0, &pending), 1.0);
+        // Without congestion, the estimate drops back to the floor.
+        assert_eq!(estimator.estimate(1, 1.0, &pending[..num_txs]), 1.0);
+    }
+}
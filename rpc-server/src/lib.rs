@@ -4,4 +4,5 @@ pub use error::Error;
 
 pub mod dispatchers;
 pub mod error;
+pub mod fee_estimator;
 pub mod wallets;
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::node::Node;
 use crate::test_network::TestNetwork;
 
@@ -5,6 +7,7 @@ use nimiq_build_tools::genesis::GenesisInfo;
 use nimiq_consensus::Consensus as AbstractConsensus;
 use nimiq_network_interface::network::Network as NetworkInterface;
 use nimiq_network_mock::MockHub;
+use nimiq_utils::time::Clock;
 
 pub async fn consensus<N: TestNetwork + NetworkInterface>(
     peer_id: u64,
@@ -14,3 +17,14 @@ pub async fn consensus<N: TestNetwork + NetworkInterface>(
     let node = Node::<N>::new(peer_id, genesis_info, hub).await;
     node.consensus.expect("Could not create consensus")
 }
+
+/// Like [`consensus`], but the blockchain uses the given clock.
+pub async fn consensus_with_clock<N: TestNetwork + NetworkInterface>(
+    peer_id: u64,
+    genesis_info: GenesisInfo,
+    hub: &mut Option<MockHub>,
+    clock: Arc<dyn Clock>,
+) -> AbstractConsensus<N> {
+    let node = Node::<N>::with_clock(peer_id, genesis_info, hub, clock).await;
+    node.consensus.expect("Could not create consensus")
+}
@@ -3,3 +3,4 @@ pub mod consensus;
 pub mod node;
 pub mod test_network;
 pub mod validator;
+pub mod validator_network;
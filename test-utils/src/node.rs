@@ -10,7 +10,7 @@ use nimiq_database::volatile::VolatileEnvironment;
 use nimiq_network_interface::network::Network as NetworkInterface;
 use nimiq_network_mock::MockHub;
 use nimiq_primitives::networks::NetworkId;
-use nimiq_utils::time::OffsetTime;
+use nimiq_utils::time::{Clock, OffsetTime};
 
 use crate::test_network::TestNetwork;
 
@@ -22,12 +22,21 @@ pub struct Node<N: NetworkInterface + TestNetwork> {
 
 impl<N: NetworkInterface + TestNetwork> Node<N> {
     pub async fn new(peer_id: u64, genesis_info: GenesisInfo, hub: &mut Option<MockHub>) -> Self {
+        Self::with_clock(peer_id, genesis_info, hub, Arc::new(OffsetTime::new())).await
+    }
+
+    /// Creates a node whose blockchain uses the given clock.
+    pub async fn with_clock(
+        peer_id: u64,
+        genesis_info: GenesisInfo,
+        hub: &mut Option<MockHub>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
         let env = VolatileEnvironment::new(12).unwrap();
-        let clock = Arc::new(OffsetTime::new());
         let blockchain = Arc::new(RwLock::new(
             Blockchain::with_genesis(
                 env.clone(),
-                Arc::clone(&clock),
+                clock,
                 NetworkId::UnitAlbatross,
                 genesis_info.block,
                 genesis_info.accounts,
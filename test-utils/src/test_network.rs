@@ -65,7 +65,7 @@ impl TestNetwork for Network {
         peer_contact.set_current_time();
         let config = Config::new(peer_key, peer_contact, Vec::new(), genesis_hash.clone());
         let network = Arc::new(Network::new(clock, config).await);
-        network.listen_on(vec![peer_address]).await;
+        network.listen_on(vec![peer_address]).await.unwrap();
         network
     }
 
@@ -5,18 +5,20 @@ use std::fmt::Display;
 use std::sync::Arc;
 use tokio_stream::wrappers::BroadcastStream;
 
-use crate::consensus::consensus;
+use crate::consensus::consensus_with_clock;
 use crate::test_network::TestNetwork;
 
 use beserial::{Deserialize, Serialize};
 use nimiq_blockchain::AbstractBlockchain;
 use nimiq_bls::KeyPair as BlsKeyPair;
 use nimiq_build_tools::genesis::{GenesisBuilder, GenesisInfo};
-use nimiq_consensus::{Consensus as AbstractConsensus, ConsensusEvent};
+use nimiq_consensus::{Consensus as AbstractConsensus, ConsensusEvent, ConsensusEventOf};
 use nimiq_keys::{Address, KeyPair as SchnorrKeyPair, SecureGenerate};
 use nimiq_mempool::config::MempoolConfig;
 use nimiq_network_interface::{network::Network as NetworkInterface, peer::Peer as PeerInterface};
 use nimiq_network_mock::MockHub;
+use nimiq_utils::time::{Clock, OffsetTime};
+use nimiq_validator::aggregation::AggregationConfig;
 use nimiq_validator::validator::Validator as AbstractValidator;
 use nimiq_validator_network::network_impl::ValidatorNetworkImpl;
 
@@ -40,8 +42,42 @@ where
     N::Error: Send,
     <N::PeerType as PeerInterface>::Id: Serialize + Deserialize + Clone,
 {
-    let consensus = consensus(peer_id, genesis_info, hub).await;
-    let validator_network = Arc::new(ValidatorNetworkImpl::new(Arc::clone(&consensus.network)));
+    build_validator_with_clock(
+        peer_id,
+        validator_address,
+        signing_key,
+        voting_key,
+        fee_key,
+        genesis_info,
+        hub,
+        Arc::new(OffsetTime::new()),
+    )
+    .await
+}
+
+// Ignoring clippy warning because this only adds the clock to the arguments of `build_validator`.
+#[allow(clippy::too_many_arguments)]
+async fn build_validator_with_clock<N: TestNetwork + NetworkInterface>(
+    peer_id: u64,
+    validator_address: Address,
+    signing_key: SchnorrKeyPair,
+    voting_key: BlsKeyPair,
+    fee_key: SchnorrKeyPair,
+    genesis_info: GenesisInfo,
+    hub: &mut Option<MockHub>,
+    clock: Arc<dyn Clock>,
+) -> (
+    AbstractValidator<N, ValidatorNetworkImpl<N>>,
+    AbstractConsensus<N>,
+)
+where
+    N::Error: Send,
+    <N::PeerType as PeerInterface>::Id: Serialize + Deserialize + Clone,
+{
+    let consensus = consensus_with_clock(peer_id, genesis_info, hub, Arc::clone(&clock)).await;
+    let validator_network =
+        Arc::new(ValidatorNetworkImpl::new(Arc::clone(&consensus.network)).with_clock(clock));
+    tokio::spawn(validator_network.monitor_connectivity());
     (
         AbstractValidator::<N, ValidatorNetworkImpl<N>>::new(
             &consensus,
@@ -51,6 +87,7 @@ where
             voting_key,
             fee_key,
             MempoolConfig::default(),
+            AggregationConfig::default(),
         ),
         consensus,
     )
@@ -60,6 +97,19 @@ pub async fn build_validators<N: TestNetwork + NetworkInterface>(
     num_validators: usize,
     hub: &mut Option<MockHub>,
 ) -> Vec<AbstractValidator<N, ValidatorNetworkImpl<N>>>
+where
+    N::Error: Send,
+    <N::PeerType as PeerInterface>::Id: Serialize + Deserialize + Clone + Display,
+{
+    build_validators_with_clock(num_validators, hub, Arc::new(OffsetTime::new())).await
+}
+
+/// Like [`build_validators`], but all validators share the given clock.
+pub async fn build_validators_with_clock<N: TestNetwork + NetworkInterface>(
+    num_validators: usize,
+    hub: &mut Option<MockHub>,
+    clock: Arc<dyn Clock>,
+) -> Vec<AbstractValidator<N, ValidatorNetworkImpl<N>>>
 where
     N::Error: Send,
     <N::PeerType as PeerInterface>::Id: Serialize + Deserialize + Clone + Display,
@@ -96,7 +146,7 @@ where
     let mut consensus = vec![];
     let mut networks = vec![];
     for id in 0..num_validators {
-        let (v, c) = build_validator(
+        let (v, c) = build_validator_with_clock(
             (id + 1) as u64,
             Address::from(&validator_keys[id]),
             signing_keys[id].clone(),
@@ -104,6 +154,7 @@ where
             fee_keys[id].clone(),
             genesis.clone(),
             hub,
+            Arc::clone(&clock),
         )
         .await;
         let network: Arc<N> = Arc::clone(&c.network);
@@ -121,7 +172,7 @@ where
     N::connect_network(&networks).await;
 
     // Wait until validators are connected.
-    let mut events: Vec<BroadcastStream<ConsensusEvent>> =
+    let mut events: Vec<BroadcastStream<ConsensusEventOf<N>>> =
         consensus.iter().map(|v| v.subscribe_events()).collect();
 
     // Start consensus
@@ -129,7 +180,14 @@ where
         tokio::spawn(consensus);
     }
 
-    future::join_all(events.iter_mut().map(|e| e.next())).await;
+    future::join_all(events.iter_mut().map(|e| async move {
+        while let Some(event) = e.next().await {
+            if let Ok(ConsensusEvent::Established) = event {
+                break;
+            }
+        }
+    }))
+    .await;
 
     validators
 }
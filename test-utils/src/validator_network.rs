@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use nimiq_bls::KeyPair as BlsKeyPair;
+use nimiq_keys::{Address, KeyPair as SchnorrKeyPair, SecureGenerate};
+use nimiq_network_mock::{MockHub, MockNetwork};
+use nimiq_primitives::{
+    policy,
+    slots::{Validators, ValidatorsBuilder},
+};
+use nimiq_validator_network::{network_impl::ValidatorNetworkImpl, ValidatorNetwork};
+
+use crate::test_network::TestNetwork;
+use crate::validator::seeded_rng;
+
+/// Builds a validator set in which the validators own (almost) equal shares of the slots. The
+/// validator with ID `i` has the voting key `voting_keys[i]`.
+pub fn equal_slot_validators(voting_keys: &[BlsKeyPair]) -> Validators {
+    let mut rng = seeded_rng(0);
+    let signing_keys: Vec<SchnorrKeyPair> = voting_keys
+        .iter()
+        .map(|_| SchnorrKeyPair::generate(&mut rng))
+        .collect();
+
+    let mut validators = ValidatorsBuilder::new();
+    for slot in 0..policy::SLOTS as usize {
+        let i = slot % voting_keys.len();
+        validators.push(
+            Address::from([i as u8; 20]),
+            voting_keys[i].public_key,
+            signing_keys[i].public,
+        );
+    }
+    validators.build()
+}
+
+/// Creates a validator network for every voting key on top of connected mock networks, without a
+/// blockchain or consensus. Every validator has published its record to the DHT and knows the
+/// voting keys of all validators, so messages can be sent by validator ID right away.
+pub async fn build_validator_networks(
+    voting_keys: &[BlsKeyPair],
+    hub: &mut MockHub,
+) -> Vec<Arc<ValidatorNetworkImpl<MockNetwork>>> {
+    let networks: Vec<Arc<MockNetwork>> = voting_keys
+        .iter()
+        .map(|_| Arc::new(hub.new_network()))
+        .collect();
+    MockNetwork::connect_network(&networks).await;
+
+    build_validator_networks_on(networks, voting_keys).await
+}
+
+/// Like `build_validator_networks`, but on top of the given mock networks, so that tests can
+/// disconnect and reconnect them. The validators own equal shares of the slots as given by
+/// `equal_slot_validators`.
+pub async fn build_validator_networks_on(
+    networks: Vec<Arc<MockNetwork>>,
+    voting_keys: &[BlsKeyPair],
+) -> Vec<Arc<ValidatorNetworkImpl<MockNetwork>>> {
+    let validator_networks: Vec<Arc<ValidatorNetworkImpl<MockNetwork>>> = networks
+        .into_iter()
+        .map(|network| Arc::new(ValidatorNetworkImpl::new(network)))
+        .collect();
+    for network in validator_networks.iter() {
+        tokio::spawn(network.monitor_connectivity());
+    }
+
+    // All records are published before the validators are set, so that every validator network
+    // can resolve the peer IDs of all validators.
+    for (network, key) in validator_networks.iter().zip(voting_keys) {
+        network
+            .set_public_key(&key.public_key.compress(), &key.secret_key)
+            .await
+            .expect("Failed to publish validator record");
+    }
+
+    let validator_keys: Vec<_> = voting_keys
+        .iter()
+        .map(|key| key.public_key.compress())
+        .collect();
+    let validator_slots: Vec<u16> = equal_slot_validators(voting_keys)
+        .iter()
+        .map(|validator| validator.num_slots())
+        .collect();
+    for network in validator_networks.iter() {
+        network
+            .set_validators(validator_keys.clone(), validator_slots.clone())
+            .await;
+    }
+
+    validator_networks
+}
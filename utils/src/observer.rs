@@ -12,6 +12,12 @@ pub type ListenerHandle = usize;
 
 pub trait Listener<E>: Send + Sync {
     fn on_event(&self, event: &E);
+
+    /// Returns whether the listener can still receive events. Listeners that aren't alive anymore
+    /// are removed from the notifier.
+    fn is_alive(&self) -> bool {
+        true
+    }
 }
 
 impl<E, F: Fn(&E)> Listener<E> for F
@@ -38,6 +44,10 @@ impl<E> NotifierState<E> {
     }
 
     pub fn register<T: Listener<E> + 'static>(&mut self, listener: T) -> ListenerHandle {
+        // Registering is a good opportunity to get rid of listeners that went away without
+        // deregistering.
+        self.prune();
+
         let handle = self.next_handle;
         self.listeners.push((handle, Box::new(listener)));
         self.next_handle += 1;
@@ -53,6 +63,15 @@ impl<E> NotifierState<E> {
         }
     }
 
+    /// Removes all listeners that aren't alive anymore.
+    pub fn prune(&mut self) {
+        self.listeners.retain(|(_, listener)| listener.is_alive());
+    }
+
+    pub fn num_listeners(&self) -> usize {
+        self.listeners.len()
+    }
+
     pub fn notify(&self, event: E) {
         for (_, listener) in &self.listeners {
             listener.on_event(&event);
@@ -118,6 +137,10 @@ impl<E> Notifier<E> {
         self.state.write().deregister(handle);
     }
 
+    pub fn num_listeners(&self) -> usize {
+        self.state.read().num_listeners()
+    }
+
     pub fn notify(&self, event: E) {
         self.state.read().notify(event);
     }
@@ -137,16 +160,35 @@ impl<E: Clone + Send + 'static> Notifier<E> {
     }
 }
 
-pub fn weak_listener<T, E, C>(weak_ref: Weak<T>, closure: C) -> impl Listener<E>
+/// A listener that only holds a weak reference to its target. Once the target is dropped, the
+/// listener is not alive anymore and gets removed from the notifier.
+struct WeakListener<T, C> {
+    weak_ref: Weak<T>,
+    closure: C,
+}
+
+impl<T, E, C> Listener<E> for WeakListener<T, C>
 where
     C: Fn(Arc<T>, &E) + Send + Sync,
     T: Send + Sync,
 {
-    move |event: &E| {
-        if let Some(arc) = weak_ref.upgrade() {
-            closure(arc, event);
+    fn on_event(&self, event: &E) {
+        if let Some(arc) = self.weak_ref.upgrade() {
+            (self.closure)(arc, event);
         }
     }
+
+    fn is_alive(&self) -> bool {
+        self.weak_ref.strong_count() > 0
+    }
+}
+
+pub fn weak_listener<T, E, C>(weak_ref: Weak<T>, closure: C) -> impl Listener<E>
+where
+    C: Fn(Arc<T>, &E) + Send + Sync,
+    T: Send + Sync,
+{
+    WeakListener { weak_ref, closure }
 }
 
 pub fn weak_passthru_listener<T, E, C>(weak_ref: Weak<T>, closure: C) -> impl PassThroughListener<E>
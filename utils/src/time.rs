@@ -1,7 +1,24 @@
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use atomic::{Atomic, Ordering};
+use futures::channel::oneshot;
+use futures::future::{self, BoxFuture, Either, FutureExt};
+use futures::stream::{self, BoxStream, StreamExt};
+use parking_lot::Mutex;
+
+/// A source of time. Components that need the current time or have to wait for a timeout take a
+/// clock, so that tests can replace the wall-clock by a [`MockClock`] that is advanced manually.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time as a unix timestamp in milliseconds.
+    fn now(&self) -> u64;
+
+    /// Returns a future that resolves once `duration` has passed on this clock.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
 
 /// Time with fixed offset from wall-clock, in milliseconds
 #[derive(Debug, Default)]
@@ -37,6 +54,124 @@ impl OffsetTime {
     }
 }
 
+/// The system clock, adjusted by the offset.
+impl Clock for OffsetTime {
+    fn now(&self) -> u64 {
+        OffsetTime::now(self)
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+}
+
+/// A clock that only advances when told to, for deterministic tests.
+#[derive(Debug)]
+pub struct MockClock {
+    state: Mutex<MockClockState>,
+}
+
+#[derive(Debug)]
+struct MockClockState {
+    now: u64,
+    timers: Vec<(u64, oneshot::Sender<()>)>,
+}
+
+impl MockClock {
+    /// Creates a clock that is stopped at the given unix timestamp in milliseconds.
+    pub fn new(now: u64) -> Self {
+        MockClock {
+            state: Mutex::new(MockClockState {
+                now,
+                timers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Creates a clock that is stopped at the current wall-clock time.
+    pub fn from_system_time() -> Self {
+        MockClock::new(systemtime_to_timestamp(SystemTime::now()))
+    }
+
+    /// Advances the clock by `duration` and fires all timers that expired in the meantime.
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state.lock();
+        state.now += duration.as_millis() as u64;
+
+        let now = state.now;
+        let (expired, pending) = state
+            .timers
+            .drain(..)
+            .partition(|(deadline, _)| *deadline <= now);
+        state.timers = pending;
+
+        for (_, timer) in expired {
+            // The sleeping future might have been dropped already.
+            let _ = timer.send(());
+        }
+    }
+
+    /// Returns the number of timers that are waiting for the clock to advance.
+    pub fn num_pending_timers(&self) -> usize {
+        let mut state = self.state.lock();
+        state.timers.retain(|(_, timer)| !timer.is_canceled());
+        state.timers.len()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> u64 {
+        self.state.lock().now
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        if duration.is_zero() {
+            return futures::future::ready(()).boxed();
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let mut state = self.state.lock();
+        let deadline = state.now + duration.as_millis() as u64;
+        state.timers.push((deadline, sender));
+
+        // If the clock is dropped, the timer never fires.
+        receiver
+            .then(|result| async move {
+                if result.is_err() {
+                    futures::future::pending::<()>().await;
+                }
+            })
+            .boxed()
+    }
+}
+
+/// Requires `future` to complete before `duration` has passed on the given clock. Returns `None`
+/// if the timeout elapsed first.
+pub fn timeout<F: Future>(
+    clock: &dyn Clock,
+    duration: Duration,
+    future: F,
+) -> impl Future<Output = Option<F::Output>> {
+    let sleep = clock.sleep(duration);
+    async move {
+        futures::pin_mut!(future);
+        match future::select(future, sleep).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(((), _)) => None,
+        }
+    }
+}
+
+/// Returns a stream that yields every `period` on the given clock, starting after the first
+/// period has passed.
+pub fn interval(clock: Arc<dyn Clock>, period: Duration) -> BoxStream<'static, ()> {
+    stream::unfold(clock, move |clock| async move {
+        clock.sleep(period).await;
+        Some(((), clock))
+    })
+    .boxed()
+}
+
 pub fn systemtime_to_timestamp(time: SystemTime) -> u64 {
     match time.duration_since(UNIX_EPOCH) {
         Ok(duration) => duration.as_secs() * 1000 + u64::from(duration.subsec_nanos()) / 1_000_000,
@@ -50,3 +185,53 @@ pub fn systemtime_to_timestamp(time: SystemTime) -> u64 {
 pub fn timestamp_to_systemtime(timestamp: u64) -> SystemTime {
     UNIX_EPOCH + Duration::from_millis(timestamp)
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn mock_clock_fires_timers_when_advanced() {
+        let clock = MockClock::new(1000);
+        let mut short = clock.sleep(Duration::from_millis(500));
+        let mut long = clock.sleep(Duration::from_secs(2));
+        assert_eq!(clock.num_pending_timers(), 2);
+
+        clock.advance(Duration::from_millis(499));
+        assert_eq!(clock.now(), 1499);
+        assert!((&mut short).now_or_never().is_none());
+
+        clock.advance(Duration::from_millis(1));
+        assert!((&mut short).now_or_never().is_some());
+        assert!((&mut long).now_or_never().is_none());
+        assert_eq!(clock.num_pending_timers(), 1);
+
+        clock.advance(Duration::from_secs(2));
+        assert!(long.now_or_never().is_some());
+        assert_eq!(clock.num_pending_timers(), 0);
+    }
+
+    #[test]
+    fn timeout_and_interval_follow_the_clock() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut timed_out = timeout(
+            clock.as_ref(),
+            Duration::from_secs(1),
+            future::pending::<()>(),
+        )
+        .boxed();
+        let mut ticks = interval(clock.clone(), Duration::from_secs(1));
+        assert!((&mut timed_out).now_or_never().is_none());
+        assert!(ticks.next().now_or_never().is_none());
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(timed_out.now_or_never(), Some(None));
+        assert_eq!(ticks.next().now_or_never(), Some(Some(())));
+        assert!(ticks.next().now_or_never().is_none());
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(ticks.next().now_or_never(), Some(Some(())));
+    }
+}
@@ -120,3 +120,27 @@ fn it_can_pass_through_notifications() {
     assert_eq!(*event1_rc1.read().unwrap(), 0);
     assert_eq!(*event2_rc1.read().unwrap(), 42);
 }
+
+#[test]
+fn it_removes_weak_listeners_of_dropped_targets() {
+    let notifier: Notifier<u32> = Notifier::new();
+
+    let target = Arc::new(RwLock::new(0));
+    notifier.register(weak_listener(
+        Arc::downgrade(&target),
+        |target: Arc<RwLock<u32>>, e: &u32| {
+            *target.write().unwrap() = *e;
+        },
+    ));
+    notifier.register(|_: &u32| {});
+    assert_eq!(notifier.num_listeners(), 2);
+
+    notifier.notify(42);
+    assert_eq!(*target.read().unwrap(), 42);
+
+    // The weak listener is removed the next time the notifier is modified.
+    drop(target);
+    assert_eq!(notifier.num_listeners(), 2);
+    notifier.register(|_: &u32| {});
+    assert_eq!(notifier.num_listeners(), 2);
+}
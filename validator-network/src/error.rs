@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 use beserial::SerializingError;
+use nimiq_bls::CompressedPublicKey;
 use nimiq_network_interface::peer::SendError;
 
 /// No notion of connected or disconnected!
@@ -27,6 +28,10 @@ where
     #[error("Unknown validator: {0}")]
     UnknownValidator(usize),
 
+    /// The stored public key of a validator is malformed and can't be uncompressed.
+    #[error("Invalid validator key: {0}")]
+    InvalidValidatorKey(CompressedPublicKey),
+
     #[error("Network error: {0}")]
     Network(#[from] TNetworkError),
 
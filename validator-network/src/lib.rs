@@ -22,6 +22,16 @@ pub use crate::error::NetworkError;
 pub type MessageStream<TMessage, TPeerId> =
     Pin<Box<dyn Stream<Item = (TMessage, TPeerId)> + Send + 'static>>;
 
+/// Events about the connectivity of the validator network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidatorNetworkEvent {
+    /// The connected validators (including this node) own less than two thirds of the slots, so
+    /// macro blocks can't be finalized.
+    QuorumLost,
+    /// The connected validators (including this node) own at least two thirds of the slots.
+    QuorumAvailable,
+}
+
 /// Fixed upper bound network.
 /// Peers are denoted by a usize identifier which deterministically identifies them.
 #[async_trait]
@@ -30,9 +40,13 @@ pub trait ValidatorNetwork: Send + Sync {
     type PeerType: Peer;
     type PubsubId: PubsubId<<Self::PeerType as Peer>::Id> + Send;
 
-    /// Tells the validator network the validator keys for the current set of active validators. The keys must be
-    /// ordered, such that the k-th entry is the validator with ID k.
-    async fn set_validators(&self, validator_keys: Vec<CompressedPublicKey>);
+    /// Tells the validator network the validator keys and the number of slots of each validator for the current set
+    /// of active validators. Both must be ordered, such that the k-th entry is the validator with ID k.
+    async fn set_validators(
+        &self,
+        validator_keys: Vec<CompressedPublicKey>,
+        validator_slots: Vec<u16>,
+    );
 
     async fn get_validator_peer(
         &self,
@@ -47,6 +61,10 @@ pub trait ValidatorNetwork: Send + Sync {
         msg: M,
     ) -> Vec<Result<(), Self::Error>>;
 
+    /// Returns a stream of connectivity events. An event is emitted when the quorum connectivity is
+    /// first determined and whenever it changes afterwards.
+    fn subscribe_events(&self) -> BoxStream<'static, ValidatorNetworkEvent>;
+
     /// Will receive from all connected peers
     fn receive<M: Message>(&self) -> MessageStream<M, <Self::PeerType as Peer>::Id>;
 
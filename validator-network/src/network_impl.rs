@@ -1,28 +1,58 @@
 use std::{
-    collections::{btree_map::Entry, BTreeMap},
-    sync::Arc,
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Weak},
     time::Duration,
 };
 
 use async_trait::async_trait;
-use futures::{future::join_all, lock::Mutex, stream::BoxStream, StreamExt};
+use futures::{future::join_all, lock::Mutex, stream, stream::BoxStream, Future, StreamExt};
+use tokio::sync::broadcast;
 
 use beserial::{Deserialize, Serialize};
-use nimiq_bls::{CompressedPublicKey, SecretKey};
+use nimiq_bls::{CompressedPublicKey, PublicKey, SecretKey};
+use nimiq_keys::KeyPair as NetworkKeyPair;
 use nimiq_network_interface::network::{MsgAcceptance, Network, Topic};
 use nimiq_network_interface::prelude::NetworkEvent;
-use nimiq_network_interface::{message::Message, peer::Peer};
+use nimiq_network_interface::{
+    message::Message,
+    peer::{CloseReason, Peer, SendError},
+};
+use nimiq_utils::time::{self, Clock, OffsetTime};
 
-use super::{MessageStream, NetworkError, ValidatorNetwork};
+use super::{MessageStream, NetworkError, ValidatorNetwork, ValidatorNetworkEvent};
 use crate::validator_record::{SignedValidatorRecord, ValidatorRecord};
 
 // Helper to get PeerId type from a network
 type PeerId<N> = <<N as Network>::PeerType as Peer>::Id;
 
+/// Uncompresses the public key of a validator, failing with `InvalidValidatorKey` if it is
+/// malformed.
+fn uncompress_validator_key<TNetworkError: std::error::Error + 'static>(
+    public_key: &CompressedPublicKey,
+) -> Result<PublicKey, NetworkError<TNetworkError>> {
+    public_key
+        .uncompress()
+        .map_err(|_| NetworkError::InvalidValidatorKey(public_key.clone()))
+}
+
 #[derive(Clone, Debug)]
 pub struct State<TPeerId> {
     validator_keys: Vec<CompressedPublicKey>,
+    validator_slots: Vec<u16>,
     validator_peer_id_cache: BTreeMap<CompressedPublicKey, TPeerId>,
+    /// The validators whose peer ID is currently being looked up in the DHT.
+    resolving: BTreeSet<CompressedPublicKey>,
+    own_public_key: Option<CompressedPublicKey>,
+    has_quorum: Option<bool>,
+}
+
+impl<TPeerId> State<TPeerId> {
+    /// Caches the peer ID of a validator, as long as the validator is still part of the active set.
+    fn cache_peer_id(&mut self, public_key: CompressedPublicKey, peer_id: TPeerId) {
+        if self.validator_keys.contains(&public_key) {
+            self.validator_peer_id_cache.insert(public_key, peer_id);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -32,7 +62,12 @@ where
     <N::PeerType as Peer>::Id: Send + Sync + Serialize + Deserialize,
 {
     network: Arc<N>,
-    state: Mutex<State<PeerId<N>>>,
+    state: Arc<Mutex<State<PeerId<N>>>>,
+    events: broadcast::Sender<ValidatorNetworkEvent>,
+    /// If set, our validator record is additionally signed with this network key.
+    network_key: Option<NetworkKeyPair>,
+    /// The clock driving the DHT lookup delay and dial timeouts.
+    clock: Arc<dyn Clock>,
 }
 
 impl<N> ValidatorNetworkImpl<N>
@@ -40,16 +75,213 @@ where
     N: Network,
     <N::PeerType as Peer>::Id: Send + Sync + Serialize + Deserialize + Clone,
 {
+    /// How long to wait for more peers to join before looking up the peer IDs of validators.
+    const RESOLVE_DELAY: Duration = Duration::from_secs(1);
+
     pub fn new(network: Arc<N>) -> Self {
+        Self::create(network, None)
+    }
+
+    /// Creates a validator network that binds our validator record to the given Ed25519 network
+    /// key, in addition to signing it with the BLS validator key.
+    pub fn with_network_key(network: Arc<N>, network_key: NetworkKeyPair) -> Self {
+        Self::create(network, Some(network_key))
+    }
+
+    fn create(network: Arc<N>, network_key: Option<NetworkKeyPair>) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            validator_keys: vec![],
+            validator_slots: vec![],
+            validator_peer_id_cache: BTreeMap::new(),
+            resolving: BTreeSet::new(),
+            own_public_key: None,
+            has_quorum: None,
+        }));
+        let (events, _) = broadcast::channel(16);
+
         Self {
             network,
-            state: Mutex::new(State {
-                validator_keys: vec![],
-                validator_peer_id_cache: BTreeMap::new(),
-            }),
+            state,
+            events,
+            network_key,
+            clock: Arc::new(OffsetTime::new()),
+        }
+    }
+
+    /// Runs the timers of the validator network on the given clock instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns a future that keeps track of whether the connected validators own enough slots to
+    /// finalize macro blocks, as peers join and leave. It needs to be spawned for the quorum events
+    /// to be emitted and runs until the validator network is dropped.
+    pub fn monitor_connectivity(&self) -> impl Future<Output = ()> + Send + 'static {
+        Self::run_connectivity_monitor(
+            Arc::clone(&self.network),
+            Arc::downgrade(&self.state),
+            self.events.clone(),
+            Arc::clone(&self.clock),
+        )
+    }
+
+    async fn run_connectivity_monitor(
+        network: Arc<N>,
+        state: Weak<Mutex<State<PeerId<N>>>>,
+        events: broadcast::Sender<ValidatorNetworkEvent>,
+        clock: Arc<dyn Clock>,
+    ) {
+        let mut network_events = network.subscribe_events();
+        // When to look up the peer IDs of the validators, after unknown peers joined, as a
+        // timestamp of the clock.
+        let mut resolve_at: Option<u64> = None;
+
+        loop {
+            let event = match resolve_at {
+                Some(deadline) => {
+                    let remaining = Duration::from_millis(deadline.saturating_sub(clock.now()));
+                    time::timeout(clock.as_ref(), remaining, network_events.next()).await
+                }
+                None => Some(network_events.next().await),
+            };
+
+            let state = match state.upgrade() {
+                Some(state) => state,
+                None => break,
+            };
+
+            let event = match event {
+                Some(Some(event)) => event,
+                Some(None) => break,
+                None => {
+                    // Unknown peers joined a while ago, look up the validators' peer IDs now.
+                    resolve_at = None;
+                    Self::resolve_validator_peer_ids(&network, &state).await;
+                    Self::update_quorum(&network, &state, &events).await;
+                    continue;
+                }
+            };
+
+            match event {
+                Ok(NetworkEvent::PeerJoined(peer)) => {
+                    // The peer might be a validator whose peer ID we don't know yet.
+                    let peer_id = peer.id();
+                    let is_known = state
+                        .lock()
+                        .await
+                        .validator_peer_id_cache
+                        .values()
+                        .any(|validator_peer_id| *validator_peer_id == peer_id);
+                    if !is_known {
+                        // Many peers usually join at once, so wait a bit before querying the DHT
+                        // for all of them together.
+                        resolve_at.get_or_insert_with(|| {
+                            clock.now() + Self::RESOLVE_DELAY.as_millis() as u64
+                        });
+                    }
+                }
+                Ok(NetworkEvent::PeerLeft(_)) => {}
+                Ok(_) => continue,
+                // We missed some events, so just check the connectivity again.
+                Err(_) => {}
+            }
+
+            Self::update_quorum(&network, &state, &events).await;
         }
     }
 
+    /// Looks up the peer IDs of all active validators that aren't cached yet in the DHT. Validators
+    /// that are already being looked up are skipped. The state isn't locked during the lookups.
+    async fn resolve_validator_peer_ids(network: &N, state: &Mutex<State<PeerId<N>>>) {
+        let public_keys: Vec<CompressedPublicKey> = {
+            let mut state = state.lock().await;
+            let public_keys: Vec<CompressedPublicKey> = state
+                .validator_keys
+                .iter()
+                .filter(|public_key| {
+                    !state.validator_peer_id_cache.contains_key(public_key)
+                        && !state.resolving.contains(public_key)
+                        && state.own_public_key.as_ref() != Some(public_key)
+                })
+                .cloned()
+                .collect();
+            state.resolving.extend(public_keys.iter().cloned());
+            public_keys
+        };
+        if public_keys.is_empty() {
+            return;
+        }
+
+        let peer_ids = join_all(
+            public_keys
+                .iter()
+                .map(|public_key| Self::resolve_peer_id(network, public_key)),
+        )
+        .await;
+
+        let mut state = state.lock().await;
+        for (public_key, peer_id) in public_keys.into_iter().zip(peer_ids) {
+            state.resolving.remove(&public_key);
+            if let Ok(Some(peer_id)) = peer_id {
+                state.cache_peer_id(public_key, peer_id);
+            }
+        }
+    }
+
+    /// Checks whether the connected validators (including this node) own at least two thirds of
+    /// the slots and emits an event if that changed.
+    async fn update_quorum(
+        network: &N,
+        state: &Mutex<State<PeerId<N>>>,
+        events: &broadcast::Sender<ValidatorNetworkEvent>,
+    ) {
+        let mut state = state.lock().await;
+        if state.validator_keys.is_empty() {
+            return;
+        }
+
+        let mut total_slots = 0u32;
+        let mut connected_slots = 0u32;
+        for (public_key, &num_slots) in state.validator_keys.iter().zip(&state.validator_slots) {
+            total_slots += u32::from(num_slots);
+
+            let is_connected = state.own_public_key.as_ref() == Some(public_key)
+                || state
+                    .validator_peer_id_cache
+                    .get(public_key)
+                    .map_or(false, |peer_id| network.get_peer(peer_id.clone()).is_some());
+            if is_connected {
+                connected_slots += u32::from(num_slots);
+            }
+        }
+
+        // Same as `policy::TWO_F_PLUS_ONE` for the full set of slots.
+        let has_quorum = connected_slots >= (2 * total_slots + 2) / 3;
+        if state.has_quorum == Some(has_quorum) {
+            return;
+        }
+        state.has_quorum = Some(has_quorum);
+
+        let event = if has_quorum {
+            log::info!(
+                "Connected validators own {} of {} slots, quorum available",
+                connected_slots,
+                total_slots
+            );
+            ValidatorNetworkEvent::QuorumAvailable
+        } else {
+            log::warn!(
+                "Connected validators only own {} of {} slots, quorum lost",
+                connected_slots,
+                total_slots
+            );
+            ValidatorNetworkEvent::QuorumLost
+        };
+        // It's fine if nobody is listening.
+        let _ = events.send(event);
+    }
+
     async fn dial_peer(
         &self,
         peer_id: PeerId<N>,
@@ -74,9 +306,9 @@ where
             }
         };
 
-        tokio::time::timeout(Duration::from_secs(5), future)
+        time::timeout(self.clock.as_ref(), Duration::from_secs(5), future)
             .await
-            .map_err(|_| NetworkError::Unreachable)?
+            .ok_or(NetworkError::Unreachable)?
     }
 
     /// Looks up the peer ID for a validator public key in the DHT.
@@ -88,7 +320,7 @@ where
             .dht_get::<_, SignedValidatorRecord<PeerId<N>>>(&public_key)
             .await?
         {
-            if record.verify(&public_key.uncompress().unwrap()) {
+            if record.verify(&uncompress_validator_key(public_key)?) {
                 Ok(Some(record.record.peer_id))
             } else {
                 Ok(None)
@@ -98,36 +330,90 @@ where
         }
     }
 
-    /// Look up the peer ID for a validator ID.
-    async fn get_validator_peer_id(
+    /// Returns the public key of a validator and its cached peer ID, if there is one. The state
+    /// is only locked for the lookup, so that DHT queries and dials never hold the lock.
+    async fn get_validator_key(
         &self,
         validator_id: usize,
-    ) -> Result<PeerId<N>, NetworkError<N::Error>> {
-        let mut state = self.state.lock().await;
+    ) -> Result<(CompressedPublicKey, Option<PeerId<N>>), NetworkError<N::Error>> {
+        let state = self.state.lock().await;
 
         let public_key = state
             .validator_keys
             .get(validator_id)
             .ok_or(NetworkError::UnknownValidator(validator_id))?
             .clone();
+        let peer_id = state.validator_peer_id_cache.get(&public_key).cloned();
 
-        let entry = state.validator_peer_id_cache.entry(public_key.clone());
-
-        match entry {
-            Entry::Occupied(occupied) => Ok(occupied.get().clone()),
-            Entry::Vacant(vacant) => {
-                if let Some(peer_id) = Self::resolve_peer_id(&self.network, &public_key).await? {
-                    Ok(vacant.insert(peer_id).clone())
-                } else {
-                    log::error!(
-                        "Could not find peer ID for validator in DHT: public_key = {:?}",
-                        public_key
-                    );
-                    Err(NetworkError::UnknownValidator(validator_id))
-                }
+        Ok((public_key, peer_id))
+    }
+
+    /// Looks up the peer ID of a validator in the DHT and caches it, as long as the validator is
+    /// still part of the active set.
+    async fn refresh_validator_peer_id(
+        &self,
+        validator_id: usize,
+        public_key: CompressedPublicKey,
+    ) -> Result<PeerId<N>, NetworkError<N::Error>> {
+        let peer_id = match Self::resolve_peer_id(&self.network, &public_key).await? {
+            Some(peer_id) => peer_id,
+            None => {
+                log::error!(
+                    "Could not find peer ID for validator in DHT: public_key = {:?}",
+                    public_key
+                );
+                return Err(NetworkError::UnknownValidator(validator_id));
+            }
+        };
+
+        self.state
+            .lock()
+            .await
+            .cache_peer_id(public_key, peer_id.clone());
+
+        Ok(peer_id)
+    }
+
+    /// Look up the peer ID for a validator ID.
+    async fn get_validator_peer_id(
+        &self,
+        validator_id: usize,
+    ) -> Result<PeerId<N>, NetworkError<N::Error>> {
+        match self.get_validator_key(validator_id).await? {
+            (_, Some(peer_id)) => Ok(peer_id),
+            (public_key, None) => {
+                self.refresh_validator_peer_id(validator_id, public_key)
+                    .await
             }
         }
     }
+
+    /// Returns the peer of a validator to send a message to. If the peer isn't connected under
+    /// its cached peer ID, the peer ID is looked up again and the peer is dialed if necessary.
+    async fn get_or_dial_validator_peer(
+        &self,
+        validator_id: usize,
+    ) -> Result<Arc<N::PeerType>, NetworkError<N::Error>> {
+        let (public_key, cached_peer_id) = self.get_validator_key(validator_id).await?;
+        if let Some(peer) = cached_peer_id.and_then(|peer_id| self.network.get_peer(peer_id)) {
+            // The peer was cached so the send is fast tracked
+            return Ok(peer);
+        }
+
+        let peer_id = self
+            .refresh_validator_peer_id(validator_id, public_key)
+            .await?;
+        if let Some(peer) = self.network.get_peer(peer_id.clone()) {
+            Ok(peer)
+        } else {
+            log::debug!(
+                "Not connected to validator {} @ {:?}, dialing...",
+                validator_id,
+                peer_id
+            );
+            self.dial_peer(peer_id).await
+        }
+    }
 }
 
 // Proposal - gossip
@@ -145,25 +431,37 @@ where
     type PeerType = N::PeerType;
     type PubsubId = N::PubsubId;
 
-    /// Tells the validator network the validator keys for the current set of active validators. The keys must be
-    /// ordered, such that the k-th entry is the validator with ID k.
-    async fn set_validators(&self, validator_keys: Vec<CompressedPublicKey>) {
+    /// Tells the validator network the validator keys and the number of slots of each validator for the current set
+    /// of active validators. Both must be ordered, such that the k-th entry is the validator with ID k.
+    async fn set_validators(
+        &self,
+        validator_keys: Vec<CompressedPublicKey>,
+        validator_slots: Vec<u16>,
+    ) {
         log::trace!(
             "setting Validators for ValidatorNetwork: {:?}",
             &validator_keys
         );
-        // Create new peer ID cache, but keep validators that are still active.
-        let mut state = self.state.lock().await;
+        debug_assert_eq!(validator_keys.len(), validator_slots.len());
+
+        {
+            // Create new peer ID cache, but keep validators that are still active.
+            let mut state = self.state.lock().await;
 
-        let mut keep_cached = BTreeMap::new();
-        for validator_key in &validator_keys {
-            if let Some(peer_id) = state.validator_peer_id_cache.remove(validator_key) {
-                keep_cached.insert(validator_key.clone(), peer_id);
+            let mut keep_cached = BTreeMap::new();
+            for validator_key in &validator_keys {
+                if let Some(peer_id) = state.validator_peer_id_cache.remove(validator_key) {
+                    keep_cached.insert(validator_key.clone(), peer_id);
+                }
             }
+
+            state.validator_keys = validator_keys;
+            state.validator_slots = validator_slots;
+            state.validator_peer_id_cache = keep_cached;
         }
 
-        state.validator_keys = validator_keys;
-        state.validator_peer_id_cache = keep_cached;
+        Self::resolve_validator_peer_ids(&self.network, &self.state).await;
+        Self::update_quorum(&self.network, &self.state, &self.events).await;
     }
 
     async fn get_validator_peer(
@@ -184,49 +482,19 @@ where
             .copied()
             .map(|validator_id| (validator_id, msg.clone()))
             .map(|(validator_id, msg)| async move {
-                let peer = if let Ok(Some(peer)) = self.get_validator_peer(validator_id).await {
-                    // The peer was cached so the send is fast tracked
-                    peer
-                } else {
-                    // The peer could not be retrieved so we update the cache with a fresh lookup
-                    let mut state = self.state.lock().await;
-
-                    // get the public key for the validator_id, return NetworkError::UnknownValidator if it does not exist
-                    let public_key = state
-                        .validator_keys
-                        .get(validator_id)
-                        .ok_or(NetworkError::UnknownValidator(validator_id))?
-                        .clone();
-
-                    // resolve the public key to the peer_id using the DHT record
-                    if let Some(peer_id) = Self::resolve_peer_id(&self.network, &public_key).await? {
-                        // set the cache with he new peer_id for this public key
-                        state
-                            .validator_peer_id_cache
-                            .entry(public_key.clone())
-                            .and_modify(|id| *id = peer_id.clone())
-                            .or_insert_with(|| peer_id.clone());
-
-                        // try to get the peer for the peer_id. If it does not exist it should be dialed
-                        if let Some(peer) = self.network.get_peer(peer_id.clone()) {
-                            peer
-                        } else {
-                            log::debug!("Not connected to validator {} @ {:?}, dialing...", validator_id, peer_id);
-                            self.dial_peer(peer_id).await?
-                        }
-                    } else {
-                        log::error!(
-                            "send_to failed; Could not find peer ID for validator in DHT: public_key = {:?}",
-                            public_key
+                let peer = self.get_or_dial_validator_peer(validator_id).await?;
+                // Never wait for a slow peer, as that would stall the sends to all other
+                // validators. A peer that can't keep up is disconnected instead.
+                peer.try_send(msg).map_err(|e| {
+                    if let SendError::BufferFull = e {
+                        log::warn!(
+                            "Send buffer of validator {} is full, disconnecting",
+                            validator_id
                         );
-                        return Err(NetworkError::UnknownValidator(validator_id));
+                        peer.close(CloseReason::Other);
                     }
-                };
-                peer
-                    .send(msg.clone())
-                    .await
-                    .map_err(NetworkError::Send)?;
-                Ok(())
+                    NetworkError::Send(e)
+                })
             });
 
         join_all(futures)
@@ -235,6 +503,19 @@ where
             .collect::<Vec<Result<(), Self::Error>>>()
     }
 
+    fn subscribe_events(&self) -> BoxStream<'static, ValidatorNetworkEvent> {
+        stream::unfold(self.events.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => break Some((event, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break None,
+                }
+            }
+        })
+        .boxed()
+    }
+
     fn receive<M: Message>(&self) -> MessageStream<M, PeerId<N>> {
         Box::pin(
             self.network
@@ -271,9 +552,14 @@ where
     ) -> Result<(), Self::Error> {
         let peer_id = self.network.get_local_peer_id();
         let record = ValidatorRecord::new(peer_id);
-        self.network
-            .dht_put(public_key, &record.sign(secret_key))
-            .await?;
+        let signed_record = match &self.network_key {
+            Some(network_key) => record.sign_with_network_key(secret_key, network_key),
+            None => record.sign(secret_key),
+        };
+        self.network.dht_put(public_key, &signed_record).await?;
+
+        self.state.lock().await.own_public_key = Some(public_key.clone());
+        Self::update_quorum(&self.network, &self.state, &self.events).await;
 
         Ok(())
     }
@@ -289,3 +575,31 @@ where
             .map_err(NetworkError::Network)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use nimiq_bls::KeyPair;
+    use nimiq_utils::key_rng::SecureGenerate;
+
+    use super::*;
+
+    #[test]
+    fn it_rejects_malformed_validator_keys() {
+        let key_pair = KeyPair::generate_default_csprng();
+        let public_key = key_pair.public_key.compress();
+        assert_eq!(
+            uncompress_validator_key::<io::Error>(&public_key).unwrap(),
+            key_pair.public_key
+        );
+
+        let malformed_key = CompressedPublicKey {
+            public_key: [0xff; 285],
+        };
+        match uncompress_validator_key::<io::Error>(&malformed_key) {
+            Err(NetworkError::InvalidValidatorKey(key)) => assert_eq!(key, malformed_key),
+            _ => panic!("Malformed key must be rejected"),
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use beserial::{Deserialize, Serialize};
-use nimiq_bls::{PublicKey, SecretKey, Signature};
+use nimiq_bls::{CompressedPublicKey, PublicKey, SecretKey, Signature};
+use nimiq_keys::{
+    KeyPair as NetworkKeyPair, PublicKey as NetworkPublicKey, Signature as NetworkSignature,
+};
 use nimiq_utils::tagged_signing::TaggedSignable;
 
 //struct ValidatorPeerId<TPeerId: Serialize>(TPeerId);
@@ -37,8 +40,60 @@ where
         SignedValidatorRecord {
             record: self,
             signature,
+            network_signature: None,
         }
     }
+
+    /// Signs the record with the validator's BLS key and additionally signs the envelope with the
+    /// node's Ed25519 network key. The BLS signature covers the network public key and the
+    /// Ed25519 signature covers the BLS public key, so both keys are bound to each other.
+    pub fn sign_with_network_key(
+        self,
+        secret_key: &SecretKey,
+        network_key: &NetworkKeyPair,
+    ) -> SignedValidatorRecord<TPeerId> {
+        let validator_key = PublicKey::from_secret(secret_key).compress();
+
+        let signature = secret_key.sign(&Self::validator_signed_data(
+            &self,
+            Some(&network_key.public),
+        ));
+        let network_signature = ValidatorNetworkSignature {
+            public_key: network_key.public,
+            signature: network_key.sign(&Self::network_signed_data(&self, &validator_key)),
+        };
+
+        SignedValidatorRecord {
+            record: self,
+            signature,
+            network_signature: Some(network_signature),
+        }
+    }
+
+    /// The data that is signed by the validator's BLS key.
+    fn validator_signed_data(record: &Self, network_key: Option<&NetworkPublicKey>) -> Vec<u8> {
+        let mut data = record.serialize_to_vec();
+        if let Some(network_key) = network_key {
+            data.extend_from_slice(network_key.as_bytes());
+        }
+        data
+    }
+
+    /// The data that is signed by the node's network key.
+    fn network_signed_data(record: &Self, validator_key: &CompressedPublicKey) -> Vec<u8> {
+        let mut data = record.serialize_to_vec();
+        validator_key
+            .serialize(&mut data)
+            .expect("Serializing into a Vec can't fail");
+        data
+    }
+}
+
+/// The Ed25519 signature of a node's network key over a validator record envelope.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidatorNetworkSignature {
+    pub public_key: NetworkPublicKey,
+    pub signature: NetworkSignature,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -48,13 +103,103 @@ where
 {
     pub record: ValidatorRecord<TPeerId>,
     pub signature: Signature,
+    /// Present if the envelope is also signed by the node's network key.
+    pub network_signature: Option<ValidatorNetworkSignature>,
 }
 
 impl<TPeerId> SignedValidatorRecord<TPeerId>
 where
     TPeerId: Serialize + Deserialize,
 {
+    /// Verifies the BLS signature of the validator and, if present, the signature of the network
+    /// key. Both signatures must bind the record to the respective other key.
     pub fn verify(&self, public_key: &PublicKey) -> bool {
-        public_key.verify(&self.record.serialize_to_vec(), &self.signature)
+        let network_key = self
+            .network_signature
+            .as_ref()
+            .map(|network_signature| &network_signature.public_key);
+
+        let data = ValidatorRecord::validator_signed_data(&self.record, network_key);
+        if !public_key.verify(&data, &self.signature) {
+            return false;
+        }
+
+        match &self.network_signature {
+            Some(network_signature) => {
+                let data =
+                    ValidatorRecord::network_signed_data(&self.record, &public_key.compress());
+                network_signature
+                    .public_key
+                    .verify(&network_signature.signature, &data)
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the network key the record is bound to, if it is signed by one.
+    pub fn network_public_key(&self) -> Option<&NetworkPublicKey> {
+        self.network_signature
+            .as_ref()
+            .map(|network_signature| &network_signature.public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nimiq_bls::KeyPair;
+    use nimiq_utils::key_rng::SecureGenerate;
+
+    use super::*;
+
+    #[test]
+    fn it_verifies_records_bound_to_a_network_key() {
+        let validator_key = KeyPair::generate_default_csprng();
+        let network_key = NetworkKeyPair::generate_default_csprng();
+
+        let signed = ValidatorRecord::new(42u32)
+            .sign_with_network_key(&validator_key.secret_key, &network_key);
+        assert!(signed.verify(&validator_key.public_key));
+        assert_eq!(signed.network_public_key(), Some(&network_key.public));
+
+        // The binding survives serialization.
+        let signed: SignedValidatorRecord<u32> =
+            Deserialize::deserialize_from_vec(&signed.serialize_to_vec()).unwrap();
+        assert!(signed.verify(&validator_key.public_key));
+
+        // Records signed only with the BLS key are still valid.
+        let signed = ValidatorRecord::new(42u32).sign(&validator_key.secret_key);
+        assert!(signed.verify(&validator_key.public_key));
+        assert_eq!(signed.network_public_key(), None);
+    }
+
+    #[test]
+    fn it_rejects_records_with_mismatched_keys() {
+        let validator_key = KeyPair::generate_default_csprng();
+        let other_validator_key = KeyPair::generate_default_csprng();
+        let network_key = NetworkKeyPair::generate_default_csprng();
+        let other_network_key = NetworkKeyPair::generate_default_csprng();
+
+        let signed = ValidatorRecord::new(42u32)
+            .sign_with_network_key(&validator_key.secret_key, &network_key);
+        assert!(!signed.verify(&other_validator_key.public_key));
+
+        // A network signature bound to another validator key.
+        let mut mismatched = signed.clone();
+        mismatched.network_signature = ValidatorRecord::new(42u32)
+            .sign_with_network_key(&other_validator_key.secret_key, &network_key)
+            .network_signature;
+        assert!(!mismatched.verify(&validator_key.public_key));
+
+        // A network signature of a network key the validator didn't sign.
+        let mut mismatched = signed.clone();
+        mismatched.network_signature = ValidatorRecord::new(42u32)
+            .sign_with_network_key(&validator_key.secret_key, &other_network_key)
+            .network_signature;
+        assert!(!mismatched.verify(&validator_key.public_key));
+
+        // The network signature can't be stripped.
+        let mut stripped = signed;
+        stripped.network_signature = None;
+        assert!(!stripped.verify(&validator_key.public_key));
     }
 }
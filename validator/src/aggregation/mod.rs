@@ -1,3 +1,5 @@
+pub use handel::config::Config as AggregationConfig;
+
 pub mod network_sink;
 mod registry;
 pub mod tendermint;
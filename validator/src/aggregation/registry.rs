@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 use bls::PublicKey;
 use collections::BitSet;
@@ -45,8 +45,9 @@ impl IdentityRegistry for ValidatorRegistry {
             // if there is no signers there is no identity.
             Identity::None
         } else {
-            // create a set of validator ids corresponding to the slots
-            let mut ids: HashSet<u16> = HashSet::new();
+            // create a set of validator ids corresponding to the slots. The set is ordered, so the
+            // ids are always visited in ascending order.
+            let mut ids: BTreeSet<u16> = BTreeSet::new();
             for slot in slots.iter() {
                 // insert each validator_address if there is one.
                 let _ = ids.insert(self.validators.get_band_from_slot(slot as u16));
@@ -90,3 +91,50 @@ impl WeightRegistry for ValidatorRegistry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bls::CompressedPublicKey;
+    use keys::{Address, PublicKey};
+    use primitives::slots::Validator;
+
+    use super::*;
+
+    fn registry(slot_ranges: &[(u16, u16)]) -> ValidatorRegistry {
+        let validators = slot_ranges
+            .iter()
+            .enumerate()
+            .map(|(i, slot_range)| {
+                Validator::new(
+                    Address::from([i as u8; Address::SIZE]),
+                    CompressedPublicKey::default(),
+                    PublicKey::default(),
+                    *slot_range,
+                )
+            })
+            .collect();
+        ValidatorRegistry::new(Validators::new(validators))
+    }
+
+    #[test]
+    fn signers_identity_lists_validators_in_ascending_order() {
+        let registry = registry(&[(0, 2), (2, 3), (3, 6), (6, 7), (7, 10)]);
+
+        // Signers of validators 4, 0, 3 and 2.
+        let mut slots = BitSet::new();
+        for slot in [7, 8, 9, 0, 1, 6, 3, 4, 5] {
+            slots.insert(slot);
+        }
+
+        for _ in 0..10 {
+            match registry.signers_identity(&slots) {
+                Identity::Multiple(ids) => assert_eq!(ids, vec![0, 2, 3, 4]),
+                identity => panic!("Unexpected identity: {:?}", identity),
+            }
+        }
+
+        // Partial slot bands are rejected.
+        slots.remove(5);
+        assert!(registry.signers_identity(&slots).is_empty());
+    }
+}
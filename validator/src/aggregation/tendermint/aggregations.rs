@@ -1,3 +1,5 @@
+#[cfg(debug_assertions)]
+use std::sync::RwLock;
 use std::{
     collections::{btree_map::Entry, BTreeMap},
     pin::Pin,
@@ -10,7 +12,7 @@ use std::{
 
 use futures::{
     future,
-    stream::{BoxStream, SelectAll},
+    stream::{self, BoxStream, SelectAll},
     Sink, Stream, StreamExt,
 };
 use tokio::sync::mpsc;
@@ -30,9 +32,11 @@ use crate::aggregation::{
     registry::ValidatorRegistry, tendermint::protocol::TendermintAggregationProtocol,
 };
 
+#[cfg(debug_assertions)]
+use super::utils::UpdateRecorder;
 use super::{
     contribution::TendermintContribution,
-    utils::{AggregationDescriptor, AggregationEvent, TendermintAggregationEvent},
+    utils::{AggregationDescriptor, AggregationEvent, SeenUpdates, TendermintAggregationEvent},
 };
 
 /// Maintains various aggregations for different rounds and steps of Tendermint.
@@ -49,9 +53,14 @@ pub(super) struct TendermintAggregations<N: ValidatorNetwork> {
     future_aggregations: BTreeMap<u32, BitSet>,
     validator_id: u16,
     validator_registry: Arc<ValidatorRegistry>,
+    /// The Handel configuration every aggregation is started with
+    config: Config,
     /// The waker used to wake in case a new Stream is pushed into `self.combined_aggregation_streams`
     /// when there previously was none
     waker: Option<Waker>,
+    /// The last LevelUpdates received for each ongoing aggregation
+    #[cfg(debug_assertions)]
+    update_recorder: Arc<RwLock<UpdateRecorder>>,
 }
 
 impl<N: ValidatorNetwork> TendermintAggregations<N> {
@@ -60,6 +69,7 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
         validator_registry: Arc<ValidatorRegistry>,
         input: BoxStream<'static, LevelUpdateMessage<TendermintContribution, TendermintIdentifier>>,
         event_receiver: mpsc::Receiver<AggregationEvent<N>>,
+        config: Config,
     ) -> Self {
         // Create the instance and return it
         TendermintAggregations {
@@ -70,9 +80,40 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
             validator_id,
             validator_registry,
             event_receiver,
+            config,
             // The waker can be none even though the SelectAll `self.combined_aggregation_streams` is empty
             // because the first poll to it will register the waker if it is still empty at that point.
             waker: None,
+            #[cfg(debug_assertions)]
+            update_recorder: Arc::new(RwLock::new(UpdateRecorder::default())),
+        }
+    }
+
+    /// Returns the recorder of the LevelUpdates received for the ongoing aggregations.
+    #[cfg(debug_assertions)]
+    pub fn update_recorder(&self) -> Arc<RwLock<UpdateRecorder>> {
+        Arc::clone(&self.update_recorder)
+    }
+
+    /// Relays previously recorded LevelUpdates to the aggregations they belong to, as if they were
+    /// received from the network. The aggregations need to have been started already, updates for
+    /// other aggregations are dropped. Used to reproduce a stuck aggregation from captured updates.
+    #[cfg(debug_assertions)]
+    pub fn replay_updates<I>(&mut self, updates: I)
+    where
+        I: IntoIterator<Item = LevelUpdateMessage<TendermintContribution, TendermintIdentifier>>,
+    {
+        for message in updates {
+            match self
+                .aggregation_descriptors
+                .get_mut(&(message.tag.round_number, message.tag.step))
+            {
+                Some(descriptor) => descriptor.relay(message),
+                None => debug!(
+                    "Dropping replayed LevelUpdate for {}-{:?} without an aggregation",
+                    message.tag.round_number, message.tag.step
+                ),
+            }
         }
     }
 
@@ -92,6 +133,9 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
         >,
     ) {
         // TODO: TendermintAggregationEvent
+        // Once an aggregation for a higher round starts, the aggregations of lower rounds are stale.
+        self.cancel_stale_aggregations(id.round_number);
+
         if let Entry::Vacant(entry) = self
             .aggregation_descriptors
             .entry((id.round_number, id.step))
@@ -112,19 +156,37 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
             let aggregation = Aggregation::new(
                 protocol,
                 id.clone(),
-                Config::default(),
+                self.config.clone(),
                 own_contribution,
                 Box::pin(UnboundedReceiverStream::new(receiver)),
                 output_sink,
             );
 
+            // copy round_number for use in drain_filter further down so that id can be moved into closure.
+            let round_number = id.round_number;
+
             // create the stream closer and wrap in Arc so it can be shared borrow
             let stream_closer = Arc::new(AtomicBool::new(true));
 
+            // wrap the aggregation stream
+            let (aggregation, abort_handle) = stream::abortable(
+                aggregation
+                    .map(move |x| ((id.round_number, id.step), x))
+                    .take_while({
+                        let stream_closer = stream_closer.clone();
+                        move |_x| {
+                            future::ready(stream_closer.load(Ordering::Relaxed))
+                            // Todo: Check Ordering
+                        }
+                    }),
+            );
+
             // Create and store AggregationDescriptor
             entry.insert(AggregationDescriptor {
                 input: sender,
-                is_running: stream_closer.clone(),
+                is_running: stream_closer,
+                abort_handle,
+                seen_updates: SeenUpdates::default(),
             });
 
             let tmp_desc: Vec<((&u32, &TendermintStep), bool)> = self
@@ -135,26 +197,14 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
 
             trace!("Aggregation_descriptors: {:?}", &tmp_desc,);
 
-            // copy round_number for use in drain_filter couple of lines down so that id can be moved into closure.
-            let round_number = id.round_number;
-
-            // wrap the aggregation stream
-            let aggregation = Box::pin(
-                aggregation
-                    .map(move |x| ((id.round_number, id.step), x))
-                    .take_while(move |_x| {
-                        future::ready(stream_closer.clone().load(Ordering::Relaxed))
-                        // Todo: Check Ordering
-                    }),
-            );
-
             // Since this instance of Aggregation now becomes the current aggregation all bitsets containing contributors
             // for future aggregations which are older or same age than this one can be discarded.
             self.future_aggregations
                 .drain_filter(|round, _bitset| round <= &round_number);
 
             // Push the aggregation to the select_all streams.
-            self.combined_aggregation_streams.push(aggregation);
+            self.combined_aggregation_streams
+                .push(Box::pin(aggregation));
             // If a waker is registered, wake it up.
             if let Some(waker) = self.waker.take() {
                 waker.wake();
@@ -166,6 +216,28 @@ impl<N: ValidatorNetwork> TendermintAggregations<N> {
         if let Some(descriptor) = self.aggregation_descriptors.get(&(round, step)) {
             trace!("canceling aggregation for {}-{:?}", &round, &step);
             descriptor.is_running.store(false, Ordering::Relaxed);
+            descriptor.abort_handle.abort();
+        }
+    }
+
+    /// Tears down the aggregations of all rounds below `round`. Their descriptors are removed, which
+    /// closes their input, and their streams are aborted, such that they are dropped from
+    /// `combined_aggregation_streams` the next time it is polled instead of lingering until they
+    /// produce another aggregate.
+    fn cancel_stale_aggregations(&mut self, round: u32) {
+        let stale_aggregations = self
+            .aggregation_descriptors
+            .drain_filter(|(aggregation_round, _), _| *aggregation_round < round);
+
+        for ((stale_round, step), descriptor) in stale_aggregations {
+            trace!(
+                "canceling stale aggregation for {}-{:?}, now in round {}",
+                &stale_round,
+                &step,
+                &round
+            );
+            descriptor.is_running.store(false, Ordering::Relaxed);
+            descriptor.abort_handle.abort();
         }
     }
 }
@@ -189,18 +261,22 @@ impl<N: ValidatorNetwork + 'static> Stream for TendermintAggregations<N> {
         // empty out the input stream dispatching messages to the appropriate aggregations
         while let Poll::Ready(message) = self.input.poll_next_unpin(cx) {
             if let Some(message) = message {
+                #[cfg(debug_assertions)]
+                if self
+                    .aggregation_descriptors
+                    .contains_key(&(message.tag.round_number, message.tag.step))
+                {
+                    self.update_recorder
+                        .write()
+                        .expect("update_recorder lock could not be acquired")
+                        .record(&message);
+                }
+
                 if let Some(descriptor) = self
                     .aggregation_descriptors
-                    .get(&(message.tag.round_number, message.tag.step))
+                    .get_mut(&(message.tag.round_number, message.tag.step))
                 {
-                    trace!("New message for ongoing aggregation: {:?}", &message);
-                    let result = descriptor.input.send(message.update);
-                    match result {
-                        Ok(()) => trace!("Sent LevelUpdate message to aggregation"),
-                        Err(e) => {
-                            trace!("Failed to relay LevelUpdate to aggregation, error {} ", e)
-                        }
-                    }
+                    descriptor.relay(message);
                 } else if let Some(((highest_round, _), _)) =
                     self.aggregation_descriptors.last_key_value()
                 {
@@ -254,3 +330,219 @@ impl<N: ValidatorNetwork + 'static> Stream for TendermintAggregations<N> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{ops::Range, time::Duration};
+
+    use futures::FutureExt;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use bls::KeyPair;
+    use keys::{Address, PublicKey};
+    use nimiq_block::TendermintVote;
+    use nimiq_network_mock::{MockHub, MockNetwork};
+    use nimiq_validator_network::network_impl::ValidatorNetworkImpl;
+    use primitives::slots::{Validator, Validators};
+    use utils::key_rng::SecureGenerate;
+
+    use crate::aggregation::network_sink::NetworkSink;
+
+    use super::*;
+
+    type Network = ValidatorNetworkImpl<MockNetwork>;
+
+    fn id(round_number: u32, step: TendermintStep) -> TendermintIdentifier {
+        TendermintIdentifier {
+            block_number: 32,
+            round_number,
+            step,
+        }
+    }
+
+    fn contribution(
+        key: &KeyPair,
+        id: TendermintIdentifier,
+        slots: Range<u16>,
+    ) -> TendermintContribution {
+        let vote = TendermintVote {
+            proposal_hash: None,
+            id,
+        };
+        TendermintContribution::from_vote(vote, &key.secret_key, slots.collect())
+    }
+
+    /// Creates a registry of two validators with half of the slots each, so that an aggregation
+    /// can't complete with the contribution of a single validator.
+    fn registry(key: &KeyPair, other_key: &KeyPair) -> Arc<ValidatorRegistry> {
+        let validators = Validators::new(vec![
+            Validator::new(
+                Address::from([0u8; Address::SIZE]),
+                key.public_key.compress(),
+                PublicKey::default(),
+                (0, policy::SLOTS / 2),
+            ),
+            Validator::new(
+                Address::from([1u8; Address::SIZE]),
+                other_key.public_key.compress(),
+                PublicKey::default(),
+                (policy::SLOTS / 2, policy::SLOTS),
+            ),
+        ]);
+        Arc::new(ValidatorRegistry::new(validators))
+    }
+
+    fn aggregations(
+        registry: Arc<ValidatorRegistry>,
+        input: BoxStream<'static, LevelUpdateMessage<TendermintContribution, TendermintIdentifier>>,
+    ) -> TendermintAggregations<Network> {
+        let (_event_sender, event_receiver) = mpsc::channel(1);
+        TendermintAggregations::new(0, registry, input, event_receiver, Config::default())
+    }
+
+    fn start(
+        aggregations: &mut TendermintAggregations<Network>,
+        network: &Arc<Network>,
+        key: &KeyPair,
+        round_number: u32,
+        step: TendermintStep,
+    ) {
+        let id = id(round_number, step);
+        let contribution = contribution(key, id.clone(), 0..policy::SLOTS / 2);
+
+        aggregations.broadcast_and_aggregate(
+            id,
+            contribution,
+            Box::new(NetworkSink::<
+                LevelUpdateMessage<TendermintContribution, TendermintIdentifier>,
+                Network,
+            >::new(Arc::clone(network))),
+        );
+    }
+
+    /// Polls the aggregations until one of them has all slots signed and returns its vote weight.
+    async fn complete_aggregation(
+        aggregations: &mut TendermintAggregations<Network>,
+        registry: &ValidatorRegistry,
+    ) -> usize {
+        let aggregate = async {
+            loop {
+                if let Some(TendermintAggregationEvent::Aggregation(_, _, contribution)) =
+                    aggregations.next().await
+                {
+                    let weight = registry
+                        .signers_weight(&contribution.contributors())
+                        .unwrap();
+                    if weight == policy::SLOTS as usize {
+                        return weight;
+                    }
+                }
+            }
+        };
+
+        tokio::time::timeout(Duration::from_secs(10), aggregate)
+            .await
+            .expect("Aggregation did not complete")
+    }
+
+    #[tokio::test]
+    async fn stale_round_aggregations_are_torn_down() {
+        let mut hub = MockHub::default();
+        let network = Arc::new(ValidatorNetworkImpl::new(Arc::new(hub.new_network())));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let key = KeyPair::generate(&mut rng);
+        let other_key = KeyPair::generate(&mut rng);
+        let mut aggregations = aggregations(
+            registry(&key, &other_key),
+            Box::pin(futures::stream::pending()),
+        );
+
+        start(
+            &mut aggregations,
+            &network,
+            &key,
+            0,
+            TendermintStep::PreVote,
+        );
+        start(
+            &mut aggregations,
+            &network,
+            &key,
+            0,
+            TendermintStep::PreCommit,
+        );
+        let stale_input = aggregations.aggregation_descriptors[&(0, TendermintStep::PreVote)]
+            .input
+            .clone();
+        assert_eq!(aggregations.combined_aggregation_streams.len(), 2);
+
+        // Moving on to round 1 removes the aggregations of round 0.
+        start(
+            &mut aggregations,
+            &network,
+            &key,
+            1,
+            TendermintStep::PreVote,
+        );
+        assert_eq!(
+            aggregations
+                .aggregation_descriptors
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![&(1, TendermintStep::PreVote)]
+        );
+
+        // Drain the stream until it is pending, so that every aggregation was polled.
+        while aggregations.next().now_or_never().is_some() {}
+
+        // The stale aggregations are dropped rather than left running.
+        assert_eq!(aggregations.combined_aggregation_streams.len(), 1);
+        assert!(stale_input.is_closed());
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn recorded_updates_can_be_replayed() {
+        let mut hub = MockHub::default();
+        let network = Arc::new(ValidatorNetworkImpl::new(Arc::new(hub.new_network())));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let key = KeyPair::generate(&mut rng);
+        let other_key = KeyPair::generate(&mut rng);
+        let registry = registry(&key, &other_key);
+
+        // The contribution of the other validator is received from the network.
+        let (update_sender, update_receiver) = mpsc::unbounded_channel();
+        let mut original = aggregations(
+            Arc::clone(&registry),
+            Box::pin(UnboundedReceiverStream::new(update_receiver)),
+        );
+        start(&mut original, &network, &key, 0, TendermintStep::PreVote);
+
+        let id = id(0, TendermintStep::PreVote);
+        let other_contribution =
+            contribution(&other_key, id.clone(), policy::SLOTS / 2..policy::SLOTS);
+        update_sender
+            .send(
+                LevelUpdate::new(other_contribution.clone(), Some(other_contribution), 1, 1)
+                    .with_tag(id),
+            )
+            .unwrap();
+        let weight = complete_aggregation(&mut original, &registry).await;
+
+        let updates = original
+            .update_recorder()
+            .read()
+            .unwrap()
+            .updates(0, TendermintStep::PreVote);
+        assert_eq!(updates.len(), 1);
+
+        // Replaying the captured updates into a fresh aggregation reaches the same vote weight.
+        let mut replayed =
+            aggregations(Arc::clone(&registry), Box::pin(futures::stream::pending()));
+        start(&mut replayed, &network, &key, 0, TendermintStep::PreVote);
+        replayed.replay_updates(updates);
+        assert_eq!(complete_aggregation(&mut replayed, &registry).await, weight);
+    }
+}
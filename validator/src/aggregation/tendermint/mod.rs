@@ -8,3 +8,4 @@ mod utils;
 mod verifier;
 
 pub use self::tendermint::HandelTendermintAdapter;
+pub use self::utils::AggregationStalled;
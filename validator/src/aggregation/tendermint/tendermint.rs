@@ -1,10 +1,11 @@
 use std::{
     collections::BTreeMap,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use futures::{future, StreamExt};
-use tokio::{sync::mpsc, time};
+use tokio::sync::{broadcast, mpsc};
 
 use bls::SecretKey;
 use hash::Blake2sHash;
@@ -13,17 +14,20 @@ use nimiq_handel::{identity::WeightRegistry, update::LevelUpdateMessage};
 
 use nimiq_primitives::{policy, slots::Validators};
 use nimiq_tendermint::{AggregationResult, TendermintError};
+use nimiq_utils::time::{self, Clock};
 use nimiq_validator_network::ValidatorNetwork;
 
 use crate::aggregation::{
     network_sink::NetworkSink, registry::ValidatorRegistry,
-    tendermint::aggregations::TendermintAggregations,
+    tendermint::aggregations::TendermintAggregations, AggregationConfig,
 };
 
+#[cfg(debug_assertions)]
+use super::utils::UpdateRecorder;
 use super::{
     background_task::BackgroundTask,
     contribution::TendermintContribution,
-    utils::{AggregationEvent, CurrentAggregation},
+    utils::{AggregationEvent, AggregationStalled, CurrentAggregation},
 };
 
 /// Adaption for tendermint not using the handel stream directly. Ideally all of what this Adapter
@@ -39,6 +43,11 @@ pub struct HandelTendermintAdapter<N: ValidatorNetwork> {
     network: Arc<N>,
     event_sender: mpsc::Sender<AggregationEvent<N>>,
     background_task: Option<BackgroundTask<N>>,
+    stall_timeout: Duration,
+    clock: Arc<dyn Clock>,
+    stalled_sender: broadcast::Sender<AggregationStalled>,
+    #[cfg(debug_assertions)]
+    update_recorder: Arc<RwLock<UpdateRecorder>>,
 }
 
 impl<N: ValidatorNetwork + 'static> HandelTendermintAdapter<N>
@@ -51,6 +60,7 @@ where
         block_height: u32,
         network: Arc<N>,
         secret_key: SecretKey,
+        config: AggregationConfig,
     ) -> Self {
         // the input stream is all levelUpdateMessages concerning a TendermintContribution and TendermintIdentifier.
         // We get rid of the sender, but while processing these messages they need to be dispatched to the appropriate Aggregation.
@@ -74,16 +84,22 @@ where
         let validator_registry = Arc::new(ValidatorRegistry::new(active_validators));
 
         let (event_sender, event_receiver) = mpsc::channel::<AggregationEvent<N>>(1);
+        let stall_timeout = config.stall_timeout;
+        let clock = Arc::clone(&config.clock);
 
         let aggregations = TendermintAggregations::new(
             validator_slot_band,
             validator_registry.clone(),
             input,
             event_receiver,
+            config,
         );
+        #[cfg(debug_assertions)]
+        let update_recorder = aggregations.update_recorder();
         let current_bests = Arc::new(RwLock::new(BTreeMap::new()));
         let current_aggregate = Arc::new(RwLock::new(None));
         let pending_new_round = Arc::new(RwLock::new(None));
+        let (stalled_sender, _) = broadcast::channel(16);
 
         let background_task = Some(BackgroundTask::new(
             aggregations,
@@ -104,9 +120,34 @@ where
             network,
             event_sender,
             background_task,
+            stall_timeout,
+            clock,
+            stalled_sender,
+            #[cfg(debug_assertions)]
+            update_recorder,
         }
     }
 
+    /// Subscribes to the events emitted when an aggregation stalls.
+    pub fn subscribe_stalled(&self) -> broadcast::Receiver<AggregationStalled> {
+        self.stalled_sender.subscribe()
+    }
+
+    /// Returns the last LevelUpdateMessages received for the aggregation of `round` and `step`,
+    /// oldest first. Replaying them into a fresh aggregation reproduces it offline. Only available
+    /// in debug builds.
+    #[cfg(debug_assertions)]
+    pub fn recorded_updates(
+        &self,
+        round: u32,
+        step: TendermintStep,
+    ) -> Vec<LevelUpdateMessage<TendermintContribution, TendermintIdentifier>> {
+        self.update_recorder
+            .read()
+            .expect("update_recorder lock could not be aquired.")
+            .updates(round, step)
+    }
+
     /// starts an aggregation for given `round` and `step`.
     /// * `round` is the number indicating in which round Tendermint is
     /// * `step` is either `TendermintStep::PreVote` or `Tendermint::PreCommit`.
@@ -210,20 +251,38 @@ where
 
         // if there is no new Round to return, proceed with waiting for the Event
 
-        // wait for the first result
-        let mut result = match aggregate_receiver.recv().await {
-            Some(event) => event,
-            None => {
-                debug!("The aggregate_receiver could not receive an item");
-                return Err(TendermintError::AggregationError);
+        // wait for the first result. Results are only sent once 2f+1 votes are reached, so if the
+        // best contribution doesn't improve within the stall timeout the aggregation is stalled.
+        let mut best_weight = self.best_weight(round, step);
+        let mut result = loop {
+            match time::timeout(
+                self.clock.as_ref(),
+                self.stall_timeout,
+                aggregate_receiver.recv(),
+            )
+            .await
+            {
+                Some(Some(event)) => break event,
+                Some(None) => {
+                    debug!("The aggregate_receiver could not receive an item");
+                    return Err(TendermintError::AggregationError);
+                }
+                None => {
+                    let weight = self.best_weight(round, step);
+                    if weight > best_weight {
+                        // The vote weight advanced, so wait for another period.
+                        best_weight = weight;
+                        continue;
+                    }
+
+                    return self.on_stalled(round, step, proposal_hash).await;
+                }
             }
         };
 
         // create timeout according to rules. For every consecutive round the timeout must increase by a constant factor.
         // TODO constants
-        let deadline = time::Instant::now()
-            .checked_add(time::Duration::from_millis(300u64 + round as u64 * 100u64))
-            .expect("Cannot create timeout Instant");
+        let deadline = self.clock.now() + 300u64 + round as u64 * 100u64;
 
         loop {
             // check if the current result is final (no improvment possible even though ot all signatories have siged) if so return it immediately
@@ -323,10 +382,11 @@ where
             debug!("Tendermint: {}-{:?}: timeout triggered", &round, &step);
             // if the result is not immediately actionable wait for a new (and better) result to check again.
             // Only wait for a set period of time, if it elapses the current (best) result is returned (likely resulting in a subsequent Nil vote/commit).
-            match time::timeout_at(deadline, aggregate_receiver.recv()).await {
-                Ok(Some(event)) => result = event,
-                Ok(None) => return Err(TendermintError::AggregationError),
-                Err(_) => {
+            let remaining = Duration::from_millis(deadline.saturating_sub(self.clock.now()));
+            match time::timeout(self.clock.as_ref(), remaining, aggregate_receiver.recv()).await {
+                Some(Some(event)) => result = event,
+                Some(None) => return Err(TendermintError::AggregationError),
+                None => {
                     if step == TendermintStep::PreCommit {
                         // PreCommit Aggreations are never requested again, so the aggregation can be canceled.
                         self.event_sender
@@ -343,6 +403,61 @@ where
         }
     }
 
+    /// Returns the vote weight of the best contribution for `round` and `step` received so far.
+    fn best_weight(&self, round: u32, step: TendermintStep) -> usize {
+        self.current_bests
+            .read()
+            .expect("current_bests lock could not be aquired.")
+            .get(&(round, step))
+            .and_then(|contribution| self.validator_registry.signature_weight(contribution))
+            .unwrap_or(0)
+    }
+
+    /// Gives up on the current aggregation, emitting an `AggregationStalled` event. The best
+    /// aggregate so far is returned, which doesn't have 2f+1 votes and thus makes Tendermint
+    /// time out the current step.
+    async fn on_stalled(
+        &mut self,
+        round: u32,
+        step: TendermintStep,
+        proposal_hash: Option<Blake2sHash>,
+    ) -> Result<AggregationResult<Blake2sHash, MultiSignature>, TendermintError> {
+        warn!(
+            "Tendermint: {}-{:?}: aggregation stalled for block {}",
+            &round, &step, self.block_height
+        );
+
+        // Clear the current aggregate such that the next aggregation can be started.
+        self.current_aggregate
+            .write()
+            .expect("current_aggregate lock could not be aquired.")
+            .take();
+
+        if step == TendermintStep::PreCommit {
+            // PreCommit Aggreations are never requested again, so the aggregation can be canceled.
+            self.event_sender
+                .send(AggregationEvent::Cancel(round, step))
+                .await
+                .map_err(|err| {
+                    debug!("event_sender.send failed: {:?}", err);
+                    TendermintError::AggregationError
+                })?;
+        }
+
+        // There might not be any subscribers, which is fine.
+        let _ = self.stalled_sender.send(AggregationStalled {
+            block_number: self.block_height,
+            round,
+            step,
+            proposal_hash,
+        });
+
+        match self.get_aggregate(round, step) {
+            Ok(result) => Ok(result),
+            Err(_) => Ok(AggregationResult::Aggregation(BTreeMap::new())),
+        }
+    }
+
     pub fn get_aggregate(
         &self,
         round: u32,
@@ -1,8 +1,13 @@
+use std::collections::HashSet;
+#[cfg(debug_assertions)]
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
+use beserial::Serialize;
+use futures::stream::AbortHandle;
 use handel::update::LevelUpdateMessage;
-use hash::Blake2sHash;
+use hash::{Blake2bHash, Blake2sHash, Hash};
 use nimiq_validator_network::ValidatorNetwork;
 use tokio::sync::mpsc;
 
@@ -24,6 +29,20 @@ pub(super) struct CurrentAggregation {
     pub(super) step: TendermintStep,
 }
 
+/// Emitted by the HandelTendermintAdapter when an aggregation for a round and step makes no
+/// progress towards 2f+1 votes within the stall timeout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AggregationStalled {
+    /// The block number of the aggregation
+    pub block_number: u32,
+    /// The round of the aggregation
+    pub round: u32,
+    /// The step of the aggregation
+    pub step: TendermintStep,
+    /// The proposal hash this node voted for, `None` for a Nil vote
+    pub proposal_hash: Option<Blake2sHash>,
+}
+
 /// Struct to describe the different ongoing aggregations
 #[derive(std::fmt::Debug)]
 pub(super) struct AggregationDescriptor {
@@ -31,8 +50,102 @@ pub(super) struct AggregationDescriptor {
     /// once set to false the aggregations next poll call will return Poll::Ready(None)
     /// terminating this aggregation.
     pub(super) is_running: Arc<AtomicBool>,
+    /// Handle to abort the aggregation stream, tearing it down immediately.
+    pub(super) abort_handle: AbortHandle,
     /// The sender used for LevelUpdateMessages for this aggregation
     pub(super) input: mpsc::UnboundedSender<LevelUpdate<TendermintContribution>>,
+    /// The LevelUpdates which were already relayed to this aggregation
+    pub(super) seen_updates: SeenUpdates,
+}
+
+impl AggregationDescriptor {
+    /// Relays a LevelUpdate to the aggregation, unless the same update was relayed before.
+    pub(super) fn relay(
+        &mut self,
+        message: LevelUpdateMessage<TendermintContribution, TendermintIdentifier>,
+    ) {
+        // Drop updates which were already relayed to the aggregation.
+        if !self.seen_updates.insert(&message.update) {
+            trace!(
+                "Dropping duplicate LevelUpdate from {} for {}-{:?}",
+                message.update.origin(),
+                message.tag.round_number,
+                message.tag.step
+            );
+            return;
+        }
+
+        trace!("New message for ongoing aggregation: {:?}", &message);
+        let result = self.input.send(message.update);
+        match result {
+            Ok(()) => trace!("Sent LevelUpdate message to aggregation"),
+            Err(e) => {
+                trace!("Failed to relay LevelUpdate to aggregation, error {} ", e)
+            }
+        }
+    }
+}
+
+/// Keeps track of the LevelUpdates relayed to an aggregation, such that updates a peer sends
+/// repeatedly can be dropped before Handel verifies them again.
+///
+/// Updates are identified by their content rather than just by their origin, as the origin is not
+/// authenticated and an update from the same origin may legitimately carry new signers.
+#[derive(Debug, Default)]
+pub(super) struct SeenUpdates {
+    seen: HashSet<Blake2bHash>,
+}
+
+impl SeenUpdates {
+    /// Records the given update. Returns `false` if the same update was already seen.
+    pub(super) fn insert(&mut self, update: &LevelUpdate<TendermintContribution>) -> bool {
+        self.seen
+            .insert(update.serialize_to_vec().hash::<Blake2bHash>())
+    }
+}
+
+/// Records the last LevelUpdateMessages received for each ongoing aggregation, such that a stuck
+/// aggregation can be reproduced offline by replaying them into a fresh one using
+/// `TendermintAggregations::replay_updates`. Only available in debug builds.
+#[cfg(debug_assertions)]
+#[derive(Debug, Default)]
+pub struct UpdateRecorder {
+    updates: BTreeMap<
+        (u32, TendermintStep),
+        VecDeque<LevelUpdateMessage<TendermintContribution, TendermintIdentifier>>,
+    >,
+}
+
+#[cfg(debug_assertions)]
+impl UpdateRecorder {
+    /// The number of updates kept per aggregation. Older updates are discarded.
+    pub const MAX_UPDATES_PER_AGGREGATION: usize = 256;
+
+    pub(super) fn record(
+        &mut self,
+        message: &LevelUpdateMessage<TendermintContribution, TendermintIdentifier>,
+    ) {
+        let updates = self
+            .updates
+            .entry((message.tag.round_number, message.tag.step))
+            .or_default();
+        if updates.len() >= Self::MAX_UPDATES_PER_AGGREGATION {
+            updates.pop_front();
+        }
+        updates.push_back(message.clone());
+    }
+
+    /// Returns the recorded updates of the aggregation for `round` and `step`, oldest first.
+    pub fn updates(
+        &self,
+        round: u32,
+        step: TendermintStep,
+    ) -> Vec<LevelUpdateMessage<TendermintContribution, TendermintIdentifier>> {
+        self.updates
+            .get(&(round, step))
+            .map(|updates| updates.iter().cloned().collect())
+            .unwrap_or_default()
+    }
 }
 
 /// Internal Wrapper for nimiq_tendermint::AggregationResult. Since the usize indicating the vote weight of each individual signature
@@ -63,3 +176,54 @@ impl<N: ValidatorNetwork> std::fmt::Debug for AggregationEvent<N> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use block::TendermintVote;
+    use bls::KeyPair;
+    use handel::contribution::AggregatableContribution;
+    use rand::{rngs::StdRng, SeedableRng};
+    use utils::key_rng::SecureGenerate;
+
+    use super::*;
+
+    fn contribution(key: &KeyPair, slots: Vec<u16>) -> TendermintContribution {
+        let vote = TendermintVote {
+            proposal_hash: None,
+            id: TendermintIdentifier {
+                block_number: 32,
+                round_number: 0,
+                step: TendermintStep::PreVote,
+            },
+        };
+
+        TendermintContribution::from_vote(vote, &key.secret_key, slots)
+    }
+
+    #[test]
+    fn it_drops_duplicate_updates() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let key1 = KeyPair::generate(&mut rng);
+        let key2 = KeyPair::generate(&mut rng);
+
+        let contribution1 = contribution(&key1, vec![0, 1]);
+        let contribution2 = contribution(&key2, vec![2, 3]);
+
+        let mut seen_updates = SeenUpdates::default();
+
+        // The first update of a signer gets through, a repeated one doesn't.
+        let update = LevelUpdate::new(contribution1.clone(), Some(contribution1.clone()), 0, 0);
+        assert!(seen_updates.insert(&update));
+        assert!(!seen_updates.insert(&update.clone()));
+
+        // A new signer gets through.
+        let update = LevelUpdate::new(contribution2.clone(), Some(contribution2.clone()), 0, 2);
+        assert!(seen_updates.insert(&update));
+
+        // An update from a known origin with additional signers gets through as well.
+        let mut aggregate = contribution1.clone();
+        aggregate.combine(&contribution2).unwrap();
+        let update = LevelUpdate::new(aggregate, Some(contribution1), 1, 0);
+        assert!(seen_updates.insert(&update));
+    }
+}
@@ -215,6 +215,7 @@ impl ViewChangeAggregation {
         validator_id: u16,
         active_validators: Validators,
         network: Arc<N>,
+        config: Config,
     ) -> (ViewChange, ViewChangeProof) {
         // TODO expose this somewehere else so we don't need to clone here.
         let weights = Arc::new(ValidatorRegistry::new(active_validators.clone()));
@@ -277,7 +278,7 @@ impl ViewChangeAggregation {
             let aggregation = Aggregation::new(
                 protocol,
                 view_change.clone(),
-                Config::default(),
+                config.clone(),
                 own_contribution,
                 Box::pin(input_switch),
                 Box::new(NetworkSink::<
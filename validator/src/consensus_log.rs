@@ -0,0 +1,77 @@
+//! Structured log events for the phase transitions of the consensus protocols.
+//!
+//! The events are logged as `key=value` pairs on a dedicated target, such that log aggregators can
+//! parse them and they can be enabled independently from the human readable output, e.g. with
+//! `nimiq_validator::consensus=debug`.
+
+use block::TendermintStep;
+use tendermint_protocol::Step;
+
+use crate::aggregation::tendermint::AggregationStalled;
+
+/// The log target all consensus events are logged on.
+pub(crate) const TARGET: &str = "nimiq_validator::consensus";
+
+/// A valid proposal for the given block and round was received from its proposer.
+pub(crate) fn proposal_received(block_number: u32, round: u32, proposer_slot_band: u16) {
+    debug!(
+        target: TARGET,
+        "event=proposal_received block_number={} round={} proposer_slot_band={}",
+        block_number,
+        round,
+        proposer_slot_band
+    );
+}
+
+/// The vote aggregation for the given block, round and step completed. `votes` is the number of
+/// slots which voted for the proposal with the most votes, `total_votes` the number of slots which
+/// voted at all.
+pub(crate) fn aggregation_complete(
+    block_number: u32,
+    round: u32,
+    step: Step,
+    votes: usize,
+    total_votes: usize,
+) {
+    let event = match step {
+        Step::Propose => "propose_complete",
+        Step::Prevote => "prevote_complete",
+        Step::Precommit => "precommit_complete",
+    };
+
+    debug!(
+        target: TARGET,
+        "event={} block_number={} round={} votes={} total_votes={}",
+        event,
+        block_number,
+        round,
+        votes,
+        total_votes
+    );
+}
+
+/// The vote aggregation for the given block, round and step made no progress within the stall
+/// timeout and was given up on.
+pub(crate) fn aggregation_stalled(stalled: &AggregationStalled) {
+    let event = match stalled.step {
+        TendermintStep::Propose => "propose_stalled",
+        TendermintStep::PreVote => "prevote_stalled",
+        TendermintStep::PreCommit => "precommit_stalled",
+    };
+
+    debug!(
+        target: TARGET,
+        "event={} block_number={} round={}", event, stalled.block_number, stalled.round
+    );
+}
+
+/// The view change for the given block completed with `votes` slots signing it.
+pub(crate) fn view_change_complete(block_number: u32, new_view_number: u32, votes: usize) {
+    debug!(
+        target: TARGET,
+        "event=view_change_complete block_number={} view_number={} votes={}",
+        block_number,
+        new_view_number,
+        votes
+    );
+}
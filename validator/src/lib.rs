@@ -29,8 +29,9 @@ extern crate nimiq_validator_network as validator_network;
 extern crate nimiq_vrf as vrf;
 
 pub mod aggregation;
+mod consensus_log;
 mod r#macro;
 mod micro;
-mod slash;
+pub mod slash;
 mod tendermint;
 pub mod validator;
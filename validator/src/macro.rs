@@ -20,6 +20,7 @@ use nimiq_tendermint::{
 use nimiq_validator_network::ValidatorNetwork;
 use nimiq_vrf::VrfSeed;
 
+use crate::aggregation::AggregationConfig;
 use crate::tendermint::TendermintInterface;
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -81,6 +82,7 @@ impl ProduceMacroBlock {
                 <TValidatorNetwork as ValidatorNetwork>::PubsubId,
             ),
         >,
+        aggregation_config: AggregationConfig,
     ) -> Self {
         // create the TendermintOutsideDeps instance
         let deps = TendermintInterface::new(
@@ -93,6 +95,7 @@ impl ProduceMacroBlock {
             block_producer,
             proposal_stream,
             initial_round,
+            aggregation_config,
         );
 
         let state_opt = state.map(|s| TendermintState {
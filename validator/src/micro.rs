@@ -1,12 +1,11 @@
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 use futures::future::BoxFuture;
 use futures::task::{Context, Poll};
 use futures::{ready, FutureExt, Stream};
 use parking_lot::RwLock;
-use tokio::time;
 
 use block::{Block, ForkProof, MicroBlock, ViewChange, ViewChangeProof};
 use block_production::BlockProducer;
@@ -16,10 +15,10 @@ use mempool::mempool::Mempool;
 use nimiq_primitives::slots::Validators;
 
 use nimiq_validator_network::ValidatorNetwork;
-use utils::time::systemtime_to_timestamp;
 use vrf::VrfSeed;
 
-use crate::aggregation::view_change::ViewChangeAggregation;
+use crate::aggregation::{view_change::ViewChangeAggregation, AggregationConfig};
+use crate::consensus_log;
 
 // Ignoring this clippy warning since size difference is not that much (320
 // bytes) and we probably don't want the performance penalty of the allocation.
@@ -43,6 +42,7 @@ struct NextProduceMicroBlockEvent<TValidatorNetwork> {
     view_change_proof: Option<ViewChangeProof>,
     view_change: Option<ViewChange>,
     view_change_delay: Duration,
+    aggregation_config: AggregationConfig,
 }
 
 impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<TValidatorNetwork> {
@@ -62,6 +62,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
         view_change_proof: Option<ViewChangeProof>,
         view_change: Option<ViewChange>,
         view_change_delay: Duration,
+        aggregation_config: AggregationConfig,
     ) -> Self {
         Self {
             blockchain,
@@ -76,6 +77,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
             view_change_proof,
             view_change,
             view_change_delay,
+            aggregation_config,
         }
     }
 
@@ -145,7 +147,9 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
             "[{}] Not our turn at #{}:{}, waiting for micro block",
             self.validator_slot_band, self.block_number, self.view_number
         );
-        time::sleep(self.view_change_delay).await;
+        // The timer is created while holding the lock, but awaited without it.
+        let view_change_timeout = self.blockchain.read().time.sleep(self.view_change_delay);
+        view_change_timeout.await;
         info!(
             "No micro block received within timeout at #{}:{}, starting view change",
             self.block_number, self.view_number
@@ -169,6 +173,11 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
             "View change completed for #{}:{}, new view is {}",
             self.block_number, self.view_number, view_change.new_view_number
         );
+        consensus_log::view_change_complete(
+            view_change.block_number,
+            view_change.new_view_number,
+            view_change_proof.sig.signers.len(),
+        );
         let event = ProduceMicroBlockEvent::ViewChange(view_change, view_change_proof);
         (Some(event), self)
     }
@@ -193,10 +202,9 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
     }
 
     fn produce_micro_block(&self, blockchain: &Blockchain) -> MicroBlock {
-        let timestamp = u64::max(
-            blockchain.timestamp(),
-            systemtime_to_timestamp(SystemTime::now()),
-        );
+        // The timestamp is taken from the blockchain's clock, which is the network adjusted offset
+        // time rather than the local system time.
+        let timestamp = u64::max(blockchain.timestamp(), blockchain.now());
 
         let transactions = self
             .mempool
@@ -240,6 +248,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> NextProduceMicroBlockEvent<T
             self.validator_slot_band,
             active_validators,
             Arc::clone(&self.network),
+            self.aggregation_config.clone(),
         )
         .await;
 
@@ -281,6 +290,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> ProduceMicroBlock<TValidator
         view_change_proof: Option<ViewChangeProof>,
         view_change: Option<ViewChange>,
         view_change_delay: Duration,
+        aggregation_config: AggregationConfig,
     ) -> Self {
         let next_event = NextProduceMicroBlockEvent::new(
             blockchain,
@@ -295,6 +305,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> ProduceMicroBlock<TValidator
             view_change_proof,
             view_change,
             view_change_delay,
+            aggregation_config,
         )
         .next()
         .boxed();
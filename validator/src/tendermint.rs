@@ -9,6 +9,7 @@ use futures::{
     stream::{BoxStream, StreamExt},
 };
 use parking_lot::RwLock;
+use tokio::sync::broadcast;
 
 use block::{
     Block, BlockHeader, MacroBlock, MacroBody, MacroHeader, MultiSignature,
@@ -28,10 +29,10 @@ use tendermint_protocol::{
     AggregationResult, ProposalResult, Step, TendermintError, TendermintOutsideDeps,
     TendermintState,
 };
-use utils::time::OffsetTime;
 use vrf::VrfSeed;
 
-use crate::aggregation::tendermint::HandelTendermintAdapter;
+use crate::aggregation::{tendermint::HandelTendermintAdapter, AggregationConfig};
+use crate::consensus_log;
 use crate::validator::ProposalTopic;
 
 /// The struct that interfaces with the Tendermint crate. It only has to implement the
@@ -39,8 +40,6 @@ use crate::validator::ProposalTopic;
 pub struct TendermintInterface<TValidatorNetwork: ValidatorNetwork> {
     // The network that is going to be used to communicate with the other validators.
     pub network: Arc<TValidatorNetwork>,
-    // This is used to maintain a network-wide time.
-    pub offset_time: OffsetTime,
     // The slot band for our validator.
     pub validator_slot_band: u16,
     // The VRF seed of the parent block.
@@ -121,7 +120,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintOutsideDeps
         // Call the block producer to produce the next macro block (minus the justification, of course).
         let block = self.block_producer.next_macro_block_proposal(
             &blockchain,
-            self.offset_time.now(),
+            blockchain.now(),
             round,
             vec![],
         );
@@ -204,7 +203,8 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintOutsideDeps
     /// Timeout.
     /// Note that it only accepts the first proposal sent by the proposer, valid or invalid. If it is
     /// invalid, then it will immediately return Timeout, even if the timeout duration hasn't elapsed
-    /// yet.
+    /// yet. If the proposer sent several proposals for this round, the preferred one among those
+    /// that already arrived is taken as the first proposal.
     async fn await_proposal(
         &mut self,
         round: u32,
@@ -218,8 +218,18 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintOutsideDeps
                 .expect("Couldn't find slot owner!");
             let proposer_slot_band = proposer_slot.band;
 
-            // Get the validator keys.
-            let proposer_voting_key = *proposer_slot.validator.voting_key.uncompress_unchecked();
+            // Get the validator keys. If the proposer's voting key is malformed, we can't verify
+            // its proposal, so we treat it like a proposal timeout.
+            let proposer_voting_key = match proposer_slot.validator.voting_key.uncompress() {
+                Some(voting_key) => *voting_key,
+                None => {
+                    warn!(
+                        "Tendermint - await_proposal: Invalid voting key for proposer {}: {}",
+                        proposer_slot_band, proposer_slot.validator.voting_key
+                    );
+                    return Ok(ProposalResult::Timeout);
+                }
+            };
             let proposer_signing_key = proposer_slot.validator.signing_key;
 
             // Calculate the timeout duration.
@@ -244,7 +254,9 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintOutsideDeps
         };
 
         // This waits for a proposal from the proposer until it timeouts.
-        let await_res = tokio::time::timeout(
+        let clock = Arc::clone(&self.blockchain.read().time);
+        let await_res = utils::time::timeout(
+            clock.as_ref(),
             timeout,
             self.await_proposal_loop(
                 proposer_slot_band,
@@ -257,9 +269,9 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintOutsideDeps
 
         // Unwrap our await result. If we timed out, we return a proposal timeout right here.
         let (proposal, id) = match await_res {
-            Ok(v) => v,
-            Err(err) => {
-                debug!("Tendermint - await_proposal: Timed out: {:?}", err);
+            Some(v) => v,
+            None => {
+                debug!("Tendermint - await_proposal: Timed out after {:?}", timeout);
                 return Ok(ProposalResult::Timeout);
             }
         };
@@ -355,6 +367,8 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintOutsideDeps
         // If the message was validated successfully, the network may now relay it to other peers.
         // Otherwise, reject or ignore the message.
         if let Some((MsgAcceptance::Accept, header, valid_round)) = acceptance {
+            consensus_log::proposal_received(self.block_height, round, proposer_slot_band);
+
             self.network
                 .validate_message(id, MsgAcceptance::Accept)
                 .await
@@ -379,9 +393,24 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintOutsideDeps
         step: Step,
         proposal_hash: Option<Self::ProposalHashTy>,
     ) -> Result<AggregationResult<Self::ProposalHashTy, Self::ProofTy>, TendermintError> {
-        self.aggregation_adapter
+        let result = self
+            .aggregation_adapter
             .broadcast_and_aggregate(round, step, proposal_hash)
-            .await
+            .await;
+
+        if let Ok(AggregationResult::Aggregation(votes)) = &result {
+            let max_votes = votes.values().map(|(_, weight)| *weight).max();
+            let total_votes = votes.values().map(|(_, weight)| *weight).sum();
+            consensus_log::aggregation_complete(
+                self.block_height,
+                round,
+                step,
+                max_votes.unwrap_or(0),
+                total_votes,
+            );
+        }
+
+        result
     }
 
     /// Returns the vote aggregation for a given round and step. It simply calls the aggregation
@@ -441,7 +470,9 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintInterface<TValidat
                 );
                 if validator_slot_band == msg.signer_idx {
                     if msg.verify(validator_key) {
-                        return (msg.message, id);
+                        return self
+                            .select_proposal((msg.message, id), validator_slot_band, validator_key)
+                            .await;
                     } else {
                         debug!("Tendermint - await_proposal: Invalid signature");
                     }
@@ -459,6 +490,47 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintInterface<TValidat
         unreachable!()
     }
 
+    /// Handles an equivocating proposer, i.e. one that sent different proposals for the same
+    /// round. Besides the given proposal, all proposals of the same round that already arrived are
+    /// considered and the preferred one (see `is_preferred_proposal`) is returned. This way, all
+    /// validators that received the same proposals pick the same one, regardless of the order they
+    /// arrived in. The other proposals are ignored and not relayed.
+    async fn select_proposal(
+        &mut self,
+        mut best: (TendermintProposal, TValidatorNetwork::PubsubId),
+        validator_slot_band: u16,
+        validator_key: &PublicKey,
+    ) -> (TendermintProposal, TValidatorNetwork::PubsubId) {
+        while let Some(Some((msg, id))) = self.proposal_stream.as_mut().next().now_or_never() {
+            if msg.message.value.block_number != best.0.value.block_number
+                || msg.message.round != best.0.round
+                || msg.signer_idx != validator_slot_band
+                || !msg.verify(validator_key)
+            {
+                continue;
+            }
+
+            if msg.message != best.0 {
+                warn!(
+                    "Tendermint - await_proposal: Validator {} sent conflicting proposals for #{}.{}",
+                    validator_slot_band, best.0.value.block_number, best.0.round
+                );
+            }
+
+            let discarded_id = if is_preferred_proposal(&msg.message, &best.0) {
+                std::mem::replace(&mut best, (msg.message, id)).1
+            } else {
+                id
+            };
+            self.network
+                .validate_message(discarded_id, MsgAcceptance::Ignore)
+                .await
+                .unwrap();
+        }
+
+        best
+    }
+
     pub fn new(
         validator_slot_band: u16,
         active_validators: Validators,
@@ -475,6 +547,7 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintInterface<TValidat
             ),
         >,
         initial_round: u32,
+        aggregation_config: AggregationConfig,
     ) -> Self {
         // Create the aggregation object.
         let aggregation_adapter = HandelTendermintAdapter::new(
@@ -483,12 +556,24 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintInterface<TValidat
             block_height,
             network.clone(),
             block_producer.voting_key.secret_key,
+            aggregation_config,
         );
 
+        // Log the aggregations that stall. The task ends once the aggregation adapter is dropped.
+        let mut stalled = aggregation_adapter.subscribe_stalled();
+        tokio::spawn(async move {
+            loop {
+                match stalled.recv().await {
+                    Ok(event) => consensus_log::aggregation_stalled(&event),
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
         // Create the instance and return it.
         Self {
             network,
-            offset_time: OffsetTime::default(),
             validator_slot_band,
             prev_seed,
             block_height,
@@ -502,3 +587,150 @@ impl<TValidatorNetwork: ValidatorNetwork + 'static> TendermintInterface<TValidat
         }
     }
 }
+
+/// The deterministic tiebreak between two proposals for the same round: The proposal whose header
+/// has the lowest hash is preferred.
+fn is_preferred_proposal(proposal: &TendermintProposal, other: &TendermintProposal) -> bool {
+    proposal.value.hash::<Blake2bHash>() < other.value.hash::<Blake2bHash>()
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+
+    use database::volatile::VolatileEnvironment;
+    use nimiq_network_mock::{MockHub, MockId, MockNetwork, MockPeerId};
+    use nimiq_test_utils::blockchain::{fill_micro_blocks, signing_key, voting_key};
+    use primitives::{networks::NetworkId, policy};
+    use utils::time::OffsetTime;
+    use validator_network::network_impl::ValidatorNetworkImpl;
+
+    use super::*;
+
+    type ProposalMessage = (SignedTendermintProposal, MockId<MockPeerId>);
+
+    fn sign(proposal: &TendermintProposal, signer_idx: u16, peer_id: u64) -> ProposalMessage {
+        (
+            SignedTendermintProposal::from_message(
+                proposal.clone(),
+                &voting_key().secret_key,
+                signer_idx,
+            ),
+            MockId::new(MockPeerId::from(peer_id)),
+        )
+    }
+
+    /// Lets the validator await the proposal of round 0 for the next block, while the given
+    /// messages are delivered. Returns the header that was picked together with the messages that
+    /// were validated.
+    async fn await_proposal(
+        blockchain: &Arc<RwLock<Blockchain>>,
+        messages: Vec<ProposalMessage>,
+    ) -> (MacroHeader, Vec<(MockPeerId, MsgAcceptance)>) {
+        let (active_validators, prev_seed, block_height) = {
+            let blockchain = blockchain.read();
+            (
+                blockchain.current_validators().unwrap(),
+                blockchain.head().seed().clone(),
+                blockchain.block_number() + 1,
+            )
+        };
+
+        // The stream stays open like the real proposal stream, so only the proposals that
+        // already arrived are considered.
+        let proposal_stream = stream::iter(messages).chain(stream::pending()).boxed();
+
+        let network = Arc::new(MockHub::default().new_network());
+        let validator_network: Arc<ValidatorNetworkImpl<MockNetwork>> =
+            Arc::new(ValidatorNetworkImpl::new(Arc::clone(&network)));
+
+        let mut interface = TendermintInterface::new(
+            0,
+            active_validators,
+            prev_seed,
+            block_height,
+            validator_network,
+            Arc::clone(blockchain),
+            BlockProducer::new(signing_key(), voting_key()),
+            proposal_stream,
+            0,
+            AggregationConfig::default(),
+        );
+
+        let header = match interface.await_proposal(0).await {
+            Ok(ProposalResult::Proposal(header, None)) => header,
+            result => panic!("Unexpected proposal result: {:?}", result),
+        };
+
+        (header, network.validated_messages())
+    }
+
+    #[tokio::test]
+    async fn colliding_proposals_are_resolved_independent_of_order() {
+        let time = Arc::new(OffsetTime::new());
+        let env = VolatileEnvironment::new(10).unwrap();
+        let blockchain = Arc::new(RwLock::new(
+            Blockchain::new(env, NetworkId::UnitAlbatross, time).unwrap(),
+        ));
+        let producer = BlockProducer::new(signing_key(), voting_key());
+        fill_micro_blocks(&producer, &blockchain);
+
+        // The proposer sends two conflicting proposals for round 0 of the macro block.
+        let proposals: Vec<TendermintProposal> = {
+            let blockchain = blockchain.read();
+            let timestamp = blockchain.time.now() + policy::BATCH_LENGTH as u64 * 1000;
+            vec![vec![1], vec![2]]
+                .into_iter()
+                .map(|extra_data| TendermintProposal {
+                    value: producer
+                        .next_macro_block_proposal(&blockchain, timestamp, 0, extra_data)
+                        .header,
+                    valid_round: None,
+                    round: 0,
+                })
+                .collect()
+        };
+        let first = sign(&proposals[0], 0, 1);
+        let second = sign(&proposals[1], 0, 2);
+
+        // Between the conflicting proposals, a proposal for another round and one from another
+        // signer arrive. Both have to be skipped without being validated.
+        let mut other_round = proposals[1].clone();
+        other_round.round = 1;
+        let other_round = sign(&other_round, 0, 3);
+        let other_signer = sign(&proposals[1], 1, 4);
+
+        let (header1, validated1) = await_proposal(
+            &blockchain,
+            vec![
+                first.clone(),
+                other_round.clone(),
+                other_signer.clone(),
+                second.clone(),
+            ],
+        )
+        .await;
+        let (header2, validated2) =
+            await_proposal(&blockchain, vec![second, other_round, other_signer, first]).await;
+
+        // Both orders pick the proposal with the lowest header hash.
+        let (winner, loser) = if proposals[0].value.hash::<Blake2bHash>()
+            < proposals[1].value.hash::<Blake2bHash>()
+        {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        assert_eq!(header1, proposals[winner].value);
+        assert_eq!(header2, proposals[winner].value);
+
+        // The picked proposal is relayed, the other one is ignored.
+        for validated in [validated1, validated2] {
+            assert_eq!(validated.len(), 2);
+            assert_eq!(validated[0].0, MockPeerId::from(loser as u64 + 1));
+            assert!(matches!(validated[0].1, MsgAcceptance::Ignore));
+            assert_eq!(validated[1].0, MockPeerId::from(winner as u64 + 1));
+            assert!(matches!(validated[1].1, MsgAcceptance::Accept));
+        }
+    }
+}
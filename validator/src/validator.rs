@@ -8,14 +8,16 @@ use futures::{
 };
 use linked_hash_map::LinkedHashMap;
 use parking_lot::RwLock;
-use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 
 use account::StakingContract;
 use block::{Block, BlockType, SignedTendermintProposal, ViewChange, ViewChangeProof};
 use block_production::BlockProducer;
 use blockchain::{AbstractBlockchain, Blockchain, BlockchainEvent, ForkEvent, PushResult};
 use bls::{CompressedPublicKey, KeyPair as BlsKeyPair};
-use consensus::{sync::block_queue::BlockTopic, Consensus, ConsensusEvent, ConsensusProxy};
+use consensus::{
+    sync::block_queue::BlockTopic, Consensus, ConsensusEvent, ConsensusEventOf, ConsensusProxy,
+};
 use database::{Database, Environment, ReadTransaction, WriteTransaction};
 use hash::{Blake2bHash, Hash};
 use keys::{Address, KeyPair as SchnorrKeyPair};
@@ -29,8 +31,9 @@ use primitives::policy;
 use tendermint_protocol::TendermintReturn;
 use transaction_builder::TransactionBuilder;
 use utils::observer::NotifierStream;
-use validator_network::ValidatorNetwork;
+use validator_network::{ValidatorNetwork, ValidatorNetworkEvent};
 
+use crate::aggregation::AggregationConfig;
 use crate::micro::{ProduceMicroBlock, ProduceMicroBlockEvent};
 use crate::r#macro::{PersistedMacroState, ProduceMacroBlock};
 use crate::slash::ForkProofPool;
@@ -57,8 +60,20 @@ struct ActiveEpochState {
     validator_slot_band: u16,
 }
 
+/// Information about a validator that is active in the current epoch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidatorInfo {
+    /// The address of the validator.
+    pub validator_address: Address,
+    /// The slot band of the validator in the current epoch.
+    pub validator_slot_band: u16,
+    /// The address the rewards of the validator are paid out to, as currently registered in the
+    /// staking contract.
+    pub reward_address: Address,
+}
+
 struct BlockchainState {
-    fork_proofs: ForkProofPool,
+    fork_proofs: Arc<RwLock<ForkProofPool>>,
 }
 
 struct ProduceMicroBlockState {
@@ -83,6 +98,12 @@ pub struct ValidatorProxy {
     pub signing_key: Arc<RwLock<SchnorrKeyPair>>,
     pub voting_key: Arc<RwLock<BlsKeyPair>>,
     pub fee_key: Arc<RwLock<SchnorrKeyPair>>,
+    pub fork_proofs: Arc<RwLock<ForkProofPool>>,
+    /// The slot band of this validator if it is active in the current epoch, `None` otherwise.
+    pub active_slot_band: Arc<RwLock<Option<u16>>>,
+    /// Whether the validators we are connected to (including ourselves) own at least two thirds
+    /// of the slots. `None` until the validator network determined it.
+    pub has_quorum: Arc<RwLock<Option<bool>>>,
 }
 
 impl Clone for ValidatorProxy {
@@ -92,6 +113,9 @@ impl Clone for ValidatorProxy {
             signing_key: Arc::clone(&self.signing_key),
             voting_key: Arc::clone(&self.voting_key),
             fee_key: Arc::clone(&self.fee_key),
+            fork_proofs: Arc::clone(&self.fork_proofs),
+            active_slot_band: Arc::clone(&self.active_slot_band),
+            has_quorum: Arc::clone(&self.has_quorum),
         }
     }
 }
@@ -110,11 +134,13 @@ pub struct Validator<TNetwork: Network, TValidatorNetwork: ValidatorNetwork + 's
 
     proposal_receiver: ProposalReceiver<TValidatorNetwork>,
 
-    consensus_event_rx: BroadcastStream<ConsensusEvent>,
+    consensus_event_rx: BroadcastStream<ConsensusEventOf<TNetwork>>,
     blockchain_event_rx: NotifierStream<BlockchainEvent>,
     fork_event_rx: NotifierStream<ForkEvent>,
 
     epoch_state: Option<ActiveEpochState>,
+    active_slot_band: Arc<RwLock<Option<u16>>>,
+    has_quorum: Arc<RwLock<Option<bool>>>,
     blockchain_state: BlockchainState,
     parking_state: Option<ParkingState>,
 
@@ -126,6 +152,8 @@ pub struct Validator<TNetwork: Network, TValidatorNetwork: ValidatorNetwork + 's
 
     pub mempool: Arc<Mempool>,
     mempool_state: MempoolState,
+
+    aggregation_config: AggregationConfig,
 }
 
 impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
@@ -133,7 +161,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
 {
     const MACRO_STATE_DB_NAME: &'static str = "ValidatorState";
     const MACRO_STATE_KEY: &'static str = "validatorState";
-    const VIEW_CHANGE_DELAY: Duration = Duration::from_secs(10);
+    pub const VIEW_CHANGE_DELAY: Duration = Duration::from_secs(10);
     const FORK_PROOFS_MAX_SIZE: usize = 1_000; // bytes
 
     pub fn new(
@@ -144,10 +172,13 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
         voting_key: BlsKeyPair,
         fee_key: SchnorrKeyPair,
         mempool_config: MempoolConfig,
+        mut aggregation_config: AggregationConfig,
     ) -> Self {
         let consensus_event_rx = consensus.subscribe_events();
 
         let mut blockchain = consensus.blockchain.write();
+        // The aggregation timers run on the same clock as the rest of the validator.
+        aggregation_config.clock = Arc::clone(&blockchain.time);
         let blockchain_event_rx = blockchain.notifier.as_stream();
         let fork_event_rx = blockchain.fork_notifier.as_stream();
 
@@ -159,7 +190,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
         drop(blockchain);
 
         let blockchain_state = BlockchainState {
-            fork_proofs: ForkProofPool::new(),
+            fork_proofs: Arc::new(RwLock::new(ForkProofPool::new())),
         };
 
         let env = consensus.env.clone();
@@ -173,7 +204,11 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
         let network1 = Arc::clone(&network);
         let (proposal_sender, proposal_receiver) = ProposalBuffer::new();
 
-        let mempool = Arc::new(Mempool::new(consensus.blockchain.clone(), mempool_config));
+        let mempool = Arc::new(Mempool::with_env(
+            consensus.blockchain.clone(),
+            mempool_config,
+            env.clone(),
+        ));
         let mempool_state = MempoolState::Inactive;
 
         let mut this = Self {
@@ -195,6 +230,8 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
             fork_event_rx,
 
             epoch_state: None,
+            active_slot_band: Arc::new(RwLock::new(None)),
+            has_quorum: Arc::new(RwLock::new(None)),
             blockchain_state,
             parking_state: None,
 
@@ -206,9 +243,22 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
 
             mempool: Arc::clone(&mempool),
             mempool_state,
+
+            aggregation_config,
         };
         this.init();
 
+        let has_quorum = Arc::clone(&this.has_quorum);
+        let network_events = this.network.subscribe_events();
+        tokio::spawn(async move {
+            network_events
+                .for_each(|event| {
+                    *has_quorum.write() = Some(event == ValidatorNetworkEvent::QuorumAvailable);
+                    async {}
+                })
+                .await
+        });
+
         tokio::spawn(async move {
             network1
                 .subscribe::<ProposalTopic>()
@@ -255,6 +305,8 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
             }
         }
 
+        // The epoch state is not persisted, as everything it contains is derived from the current
+        // validators. A restarted validator recomputes it here and resumes its duties mid-epoch.
         let validators = blockchain.current_validators().unwrap();
 
         self.epoch_state = None;
@@ -275,12 +327,25 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                 break;
             }
         }
+        *self.active_slot_band.write() = self
+            .epoch_state
+            .as_ref()
+            .map(|epoch_state| epoch_state.validator_slot_band);
 
         let voting_keys: Vec<CompressedPublicKey> = validators
             .iter()
             .map(|validator| validator.voting_key.compressed().clone())
             .collect();
         let key = self.voting_key();
+        let validator_slots: Vec<u16> = blockchain
+            .current_validators()
+            .map(|validators| {
+                validators
+                    .iter()
+                    .map(|validator| validator.num_slots())
+                    .collect()
+            })
+            .unwrap_or_default();
         let network = Arc::clone(&self.network);
 
         // TODO might better be done without the task.
@@ -293,7 +358,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
             {
                 error!("could not set up DHT record: {:?}", err);
             }
-            network.set_validators(voting_keys).await;
+            network.set_validators(voting_keys, validator_slots).await;
         });
     }
 
@@ -345,6 +410,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                     next_view_number,
                     state,
                     proposal_stream,
+                    self.aggregation_config.clone(),
                 ));
             }
             BlockType::Micro => {
@@ -357,6 +423,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                 let fork_proofs = self
                     .blockchain_state
                     .fork_proofs
+                    .read()
                     .get_fork_proofs_for_block(Self::FORK_PROOFS_MAX_SIZE);
                 let prev_seed = head.seed().clone();
 
@@ -375,6 +442,7 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                     self.micro_state.view_change_proof.clone(),
                     self.micro_state.view_change.clone(),
                     Self::VIEW_CHANGE_DELAY,
+                    self.aggregation_config.clone(),
                 ));
             }
         }
@@ -403,7 +471,10 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
             .expect("Head block not found");
 
         // Update mempool and blockchain state
-        self.blockchain_state.fork_proofs.apply_block(&block);
+        self.blockchain_state
+            .fork_proofs
+            .write()
+            .apply_block(&block);
         self.mempool
             .mempool_update(&vec![(hash.clone(), block)], &[].to_vec());
     }
@@ -414,18 +485,22 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
         new_chain: &[(Blake2bHash, Block)],
     ) {
         // Update mempool and blockchain state
+        let mut fork_proofs = self.blockchain_state.fork_proofs.write();
         for (_hash, block) in old_chain.iter() {
-            self.blockchain_state.fork_proofs.revert_block(block);
+            fork_proofs.revert_block(block);
         }
         for (_hash, block) in new_chain.iter() {
-            self.blockchain_state.fork_proofs.apply_block(block);
+            fork_proofs.apply_block(block);
         }
+        drop(fork_proofs);
         self.mempool.mempool_update(new_chain, old_chain);
     }
 
     fn on_fork_event(&mut self, event: ForkEvent) {
         match event {
-            ForkEvent::Detected(fork_proof) => self.blockchain_state.fork_proofs.insert(fork_proof),
+            ForkEvent::Detected(fork_proof) => {
+                self.blockchain_state.fork_proofs.write().insert(fork_proof)
+            }
         };
     }
 
@@ -508,7 +583,9 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
                         &beserial::Serialize::serialize_to_vec(&persistable_state),
                     );
 
-                    write_transaction.commit();
+                    if let Err(e) = write_transaction.try_commit() {
+                        warn!("Failed to persist macro state: {}", e);
+                    }
 
                     self.macro_state = Some(persistable_state);
                 }
@@ -618,6 +695,27 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
         self.validator_address.read().clone()
     }
 
+    /// Returns information about this validator, or `None` if it isn't active in the current
+    /// epoch. The slot band identifies the validator in the active set, while the reward address
+    /// is needed to attribute the rewards it earns.
+    pub fn validator_info(&self) -> Option<ValidatorInfo> {
+        let validator_slot_band = self.epoch_state.as_ref()?.validator_slot_band;
+        let validator_address = self.validator_address();
+
+        let blockchain = self.consensus.blockchain.read();
+        let accounts_tree = &blockchain.state().accounts.tree;
+        let db_txn = blockchain.read_transaction();
+        let reward_address =
+            StakingContract::get_validator(accounts_tree, &db_txn, &validator_address)?
+                .reward_address;
+
+        Some(ValidatorInfo {
+            validator_address,
+            validator_slot_band,
+            reward_address,
+        })
+    }
+
     pub fn voting_key(&self) -> BlsKeyPair {
         self.voting_key.read().clone()
     }
@@ -630,12 +728,39 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork>
         self.fee_key.read().clone()
     }
 
+    fn on_consensus_established(&mut self) {
+        self.init();
+        if let MempoolState::Inactive = self.mempool_state {
+            let mempool = Arc::clone(&self.mempool);
+            let network = Arc::clone(&self.consensus.network);
+            tokio::spawn(async move {
+                mempool.restore().await;
+                mempool.start_executor(network).await;
+            });
+            self.mempool_state = MempoolState::Active;
+        }
+    }
+
+    fn on_consensus_lost(&mut self) {
+        if let MempoolState::Active = self.mempool_state {
+            let mempool = Arc::clone(&self.mempool);
+            let network = Arc::clone(&self.consensus.network);
+            tokio::spawn(async move {
+                mempool.stop_executor(network).await;
+            });
+            self.mempool_state = MempoolState::Inactive;
+        }
+    }
+
     pub fn proxy(&self) -> ValidatorProxy {
         ValidatorProxy {
             validator_address: Arc::clone(&self.validator_address),
             signing_key: Arc::clone(&self.signing_key),
             voting_key: Arc::clone(&self.voting_key),
             fee_key: Arc::clone(&self.fee_key),
+            fork_proofs: Arc::clone(&self.blockchain_state.fork_proofs),
+            active_slot_band: Arc::clone(&self.active_slot_band),
+            has_quorum: Arc::clone(&self.has_quorum),
         }
     }
 }
@@ -649,28 +774,19 @@ impl<TNetwork: Network, TValidatorNetwork: ValidatorNetwork> Future
         // Process consensus updates.
         while let Poll::Ready(Some(event)) = self.consensus_event_rx.poll_next_unpin(cx) {
             match event {
-                Ok(ConsensusEvent::Established) => {
-                    self.init();
-                    if let MempoolState::Inactive = self.mempool_state {
-                        let mempool = Arc::clone(&self.mempool);
-                        let network = Arc::clone(&self.consensus.network);
-                        tokio::spawn(async move {
-                            mempool.start_executor(network).await;
-                        });
-                        self.mempool_state = MempoolState::Active;
-                    }
-                }
-                Ok(ConsensusEvent::Lost) => {
-                    if let MempoolState::Active = self.mempool_state {
-                        let mempool = Arc::clone(&self.mempool);
-                        let network = Arc::clone(&self.consensus.network);
-                        tokio::spawn(async move {
-                            mempool.stop_executor(network).await;
-                        });
-                        self.mempool_state = MempoolState::Inactive;
+                Ok(ConsensusEvent::Established) => self.on_consensus_established(),
+                Ok(ConsensusEvent::Lost) => self.on_consensus_lost(),
+                Ok(ConsensusEvent::SyncProgress(_)) | Ok(ConsensusEvent::PeerSynced(_)) => {}
+                Err(BroadcastStreamRecvError::Lagged(num_events)) => {
+                    // We might have missed an `Established` or `Lost` event, so catch up with the
+                    // current consensus state.
+                    warn!("Validator lagged behind by {} consensus events", num_events);
+                    match (self.consensus.is_established(), &self.mempool_state) {
+                        (true, MempoolState::Inactive) => self.on_consensus_established(),
+                        (false, MempoolState::Active) => self.on_consensus_lost(),
+                        _ => {}
                     }
                 }
-                Err(_) => return Poll::Ready(()),
             }
         }
 
@@ -1,19 +1,32 @@
 use futures::{future, StreamExt};
 use tokio::time;
 
-use nimiq_block::{MultiSignature, SignedViewChange, ViewChange};
+use nimiq_block::{MultiSignature, SignedViewChange, TendermintStep, ViewChange};
 use nimiq_blockchain::{AbstractBlockchain, BlockchainEvent};
 use nimiq_bls::{AggregateSignature, KeyPair as BlsKeyPair};
 use nimiq_build_tools::genesis::GenesisBuilder;
 use nimiq_collections::BitSet;
 use nimiq_handel::update::{LevelUpdate, LevelUpdateMessage};
+use nimiq_hash::{Blake2sHash, Blake2sHasher, Hasher};
 use nimiq_keys::{Address, KeyPair, SecureGenerate};
-use nimiq_network_interface::network::Network;
+use nimiq_mempool::config::MempoolConfig;
+use nimiq_network_interface::{network::Network, peer::SendError};
 use nimiq_network_mock::{MockHub, MockNetwork};
+use nimiq_primitives::{policy, slots::ValidatorsBuilder};
+use nimiq_tendermint::AggregationResult;
 use nimiq_test_utils::validator::{
-    build_validator, build_validators, seeded_rng, validator_for_slot,
+    build_validator, build_validators, build_validators_with_clock, seeded_rng, validator_for_slot,
 };
+use nimiq_test_utils::validator_network::{
+    build_validator_networks, build_validator_networks_on, equal_slot_validators,
+};
+use nimiq_utils::time::{Clock, MockClock};
+use nimiq_validator::aggregation::tendermint::{AggregationStalled, HandelTendermintAdapter};
 use nimiq_validator::aggregation::view_change::SignedViewChangeMessage;
+use nimiq_validator::aggregation::AggregationConfig;
+use nimiq_validator::validator::{ProposalTopic, Validator, ValidatorInfo};
+use nimiq_validator_network::network_impl::ValidatorNetworkImpl;
+use nimiq_validator_network::{NetworkError, ValidatorNetwork, ValidatorNetworkEvent};
 use nimiq_vrf::VrfSeed;
 use std::sync::Arc;
 use std::time::Duration;
@@ -60,6 +73,117 @@ async fn one_validator_can_create_micro_blocks() {
     assert!(consensus1.blockchain.read().block_number() >= 10);
 }
 
+#[tokio::test]
+async fn active_validator_has_reward_address() {
+    let hub = MockHub::default();
+
+    let voting_key = BlsKeyPair::generate(&mut seeded_rng(0));
+    let validator_key = KeyPair::generate(&mut seeded_rng(0));
+    let fee_key = KeyPair::generate(&mut seeded_rng(0));
+    let signing_key = KeyPair::generate(&mut seeded_rng(0));
+    let reward_address = Address::from([3u8; 20]);
+    let genesis = GenesisBuilder::default()
+        .with_genesis_validator(
+            Address::from(&validator_key),
+            signing_key.public,
+            voting_key.public_key,
+            reward_address.clone(),
+        )
+        .generate()
+        .unwrap();
+
+    let (validator, _consensus) = build_validator::<MockNetwork>(
+        1,
+        Address::from(&validator_key),
+        signing_key,
+        voting_key,
+        fee_key,
+        genesis,
+        &mut Some(hub),
+    )
+    .await;
+
+    assert_eq!(
+        validator.validator_info(),
+        Some(ValidatorInfo {
+            validator_address: Address::from(&validator_key),
+            validator_slot_band: 0,
+            reward_address,
+        })
+    );
+}
+
+#[tokio::test]
+async fn restarted_validator_resumes_mid_epoch() {
+    let hub = MockHub::default();
+
+    let voting_key = BlsKeyPair::generate(&mut seeded_rng(0));
+    let validator_key = KeyPair::generate(&mut seeded_rng(0));
+    let fee_key = KeyPair::generate(&mut seeded_rng(0));
+    let signing_key = KeyPair::generate(&mut seeded_rng(0));
+    let genesis = GenesisBuilder::default()
+        .with_genesis_validator(
+            Address::from(&validator_key),
+            signing_key.public,
+            voting_key.public_key,
+            Address::default(),
+        )
+        .generate()
+        .unwrap();
+
+    let (validator, mut consensus1) = build_validator::<MockNetwork>(
+        1,
+        Address::from(&validator_key),
+        signing_key.clone(),
+        voting_key.clone(),
+        fee_key.clone(),
+        genesis,
+        &mut Some(hub),
+    )
+    .await;
+
+    consensus1.force_established();
+    let validator = tokio::spawn(validator);
+
+    let events1 = consensus1.blockchain.write().notifier.as_stream();
+    events1.take(5).for_each(|_| future::ready(())).await;
+
+    // Stop the validator in the middle of the epoch and start a new one on the same chain.
+    validator.abort();
+    assert!(validator.await.unwrap_err().is_cancelled());
+    let block_number = consensus1.blockchain.read().block_number();
+    assert!(!policy::is_macro_block_at(block_number));
+
+    // The new validator subscribes to the proposals again.
+    consensus1
+        .network
+        .unsubscribe::<ProposalTopic>()
+        .await
+        .unwrap();
+
+    let validator = Validator::new(
+        &consensus1,
+        Arc::new(ValidatorNetworkImpl::new(Arc::clone(&consensus1.network))),
+        Address::from(&validator_key),
+        signing_key,
+        voting_key,
+        fee_key,
+        MempoolConfig::default(),
+        AggregationConfig::default(),
+    );
+
+    // The restarted validator knows its slot band without waiting for the next election block,
+    // and continues to produce blocks.
+    assert_eq!(*validator.proxy().active_slot_band.read(), Some(0));
+
+    tokio::spawn(validator);
+
+    let events1 = consensus1.blockchain.write().notifier.as_stream();
+    events1.take(5).for_each(|_| future::ready(())).await;
+
+    assert!(consensus1.blockchain.read().block_number() >= block_number + 5);
+}
+
 #[tokio::test]
 async fn four_validators_can_create_micro_blocks() {
     let hub = MockHub::default();
@@ -108,6 +232,54 @@ async fn four_validators_can_view_change() {
     assert!(blockchain.read().view_number() >= 1);
 }
 
+#[tokio::test]
+async fn mock_clock_triggers_view_change() {
+    let hub = MockHub::default();
+    let clock = Arc::new(MockClock::from_system_time());
+
+    let validators =
+        build_validators_with_clock::<MockNetwork>(4, &mut Some(hub), clock.clone()).await;
+
+    // Disconnect the next block producer.
+    let validator = validator_for_slot(&validators, 1, 0);
+    validator.consensus.network.shutdown();
+
+    // Listen for blockchain events from the new block producer (after view change).
+    let validator = validator_for_slot(&validators, 1, 1);
+    let blockchain = Arc::clone(&validator.consensus.blockchain);
+    let mut events = blockchain.write().notifier.as_stream();
+
+    tokio::spawn(future::join_all(validators));
+
+    // Nothing happens as long as the clock stands still.
+    assert!(time::timeout(Duration::from_secs(1), events.next())
+        .await
+        .is_err());
+    assert_eq!(blockchain.read().block_number(), 0);
+
+    // Advance the clock in small steps, so that the view change timeout and the timers of the
+    // view change aggregation expire.
+    let start = clock.now();
+    time::timeout(Duration::from_secs(30), async {
+        while time::timeout(Duration::from_millis(10), events.next())
+            .await
+            .is_err()
+        {
+            clock.advance(Duration::from_millis(100));
+        }
+    })
+    .await
+    .expect("No block was produced after the view change");
+
+    // The view change only started once the view change delay passed on the clock.
+    let view_change_delay =
+        Validator::<MockNetwork, ValidatorNetworkImpl<MockNetwork>>::VIEW_CHANGE_DELAY;
+    assert!(clock.now() - start >= view_change_delay.as_millis() as u64);
+
+    let block = blockchain.read().get_block_at(1, false, None).unwrap();
+    assert_eq!(block.view_number(), 1);
+}
+
 fn create_view_change_update(
     block_number: u32,
     new_view_number: u32,
@@ -252,3 +424,262 @@ async fn validator_can_catch_up() {
 
     assert!(false);
 }
+
+#[tokio::test]
+async fn tendermint_aggregation_stalls_without_threshold() {
+    let mut hub = MockHub::default();
+    let mut rng = seeded_rng(0);
+
+    // Four validators with equal slots, but only the first one is running. Its own vote can
+    // never reach 2f+1.
+    let voting_keys: Vec<BlsKeyPair> = (0..4).map(|_| BlsKeyPair::generate(&mut rng)).collect();
+    let mut validators = ValidatorsBuilder::new();
+    for (i, voting_key) in voting_keys.iter().enumerate() {
+        let signing_key = KeyPair::generate(&mut rng);
+        for _ in 0..policy::SLOTS / 4 {
+            validators.push(
+                Address::from([i as u8; 20]),
+                voting_key.public_key,
+                signing_key.public,
+            );
+        }
+    }
+
+    let network = Arc::new(ValidatorNetworkImpl::new(Arc::new(hub.new_network())));
+    let mut adapter = HandelTendermintAdapter::new(
+        0,
+        validators.build(),
+        policy::BATCH_LENGTH,
+        network,
+        voting_keys[0].secret_key,
+        AggregationConfig {
+            stall_timeout: Duration::from_millis(500),
+            ..Default::default()
+        },
+    );
+    let mut stalled = adapter.subscribe_stalled();
+    tokio::spawn(adapter.create_background_task());
+
+    let result = time::timeout(
+        Duration::from_secs(10),
+        adapter.broadcast_and_aggregate(0, TendermintStep::PreVote, None),
+    )
+    .await
+    .expect("Stalled aggregation should not block");
+
+    // The returned aggregation doesn't have 2f+1 votes.
+    match result {
+        Ok(AggregationResult::Aggregation(map)) => {
+            assert!(map
+                .values()
+                .all(|(_, weight)| *weight < policy::TWO_F_PLUS_ONE as usize));
+        }
+        _ => panic!("Expected an aggregation result"),
+    }
+
+    assert_eq!(
+        stalled.recv().await.unwrap(),
+        AggregationStalled {
+            block_number: policy::BATCH_LENGTH,
+            round: 0,
+            step: TendermintStep::PreVote,
+            proposal_hash: None,
+        }
+    );
+
+    // A new aggregation can be started after the stalled one.
+    assert!(time::timeout(
+        Duration::from_secs(10),
+        adapter.broadcast_and_aggregate(1, TendermintStep::PreVote, None),
+    )
+    .await
+    .unwrap()
+    .is_ok());
+}
+
+#[tokio::test]
+async fn three_validators_aggregate_tendermint_votes() {
+    let mut hub = MockHub::default();
+    let mut rng = seeded_rng(0);
+
+    let voting_keys: Vec<BlsKeyPair> = (0..3).map(|_| BlsKeyPair::generate(&mut rng)).collect();
+    let validators = equal_slot_validators(&voting_keys);
+    let networks = build_validator_networks(&voting_keys, &mut hub).await;
+
+    // All validators vote for the same proposal in the first round.
+    let proposal_hash: Blake2sHash = Blake2sHasher::default().digest(b"proposal");
+    let rounds = networks.into_iter().zip(&voting_keys).enumerate().map(
+        |(validator_id, (network, voting_key))| {
+            let mut adapter = HandelTendermintAdapter::new(
+                validator_id as u16,
+                validators.clone(),
+                policy::BATCH_LENGTH,
+                network,
+                voting_key.secret_key,
+                AggregationConfig::default(),
+            );
+            tokio::spawn(adapter.create_background_task());
+
+            let proposal_hash = proposal_hash.clone();
+            async move {
+                adapter
+                    .broadcast_and_aggregate(0, TendermintStep::PreVote, Some(proposal_hash))
+                    .await
+            }
+        },
+    );
+
+    let results = time::timeout(Duration::from_secs(10), future::join_all(rounds))
+        .await
+        .expect("The validators should complete the aggregation");
+
+    // Every validator ends up with 2f+1 votes for the proposal.
+    for result in results {
+        match result {
+            Ok(AggregationResult::Aggregation(map)) => {
+                let (_, weight) = map.get(&Some(proposal_hash.clone())).unwrap();
+                assert!(*weight >= policy::TWO_F_PLUS_ONE as usize);
+            }
+            _ => panic!("Expected an aggregation result"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn unreachable_validator_does_not_stall_send_to_others() {
+    let mut hub = MockHub::default();
+    let mut rng = seeded_rng(0);
+
+    let voting_keys: Vec<BlsKeyPair> = (0..4).map(|_| BlsKeyPair::generate(&mut rng)).collect();
+    let networks = build_validator_networks(&voting_keys[..3], &mut hub).await;
+
+    // The fourth validator is part of the active set, but never published a DHT record.
+    networks[0]
+        .set_validators(
+            voting_keys
+                .iter()
+                .map(|key| key.public_key.compress())
+                .collect(),
+            equal_slot_validators(&voting_keys)
+                .iter()
+                .map(|validator| validator.num_slots())
+                .collect(),
+        )
+        .await;
+
+    let mut receivers: Vec<_> = networks[1..]
+        .iter()
+        .map(|network| network.receive::<LevelUpdateMessage<SignedViewChangeMessage, ViewChange>>())
+        .collect();
+
+    let update = create_view_change_update(
+        1,
+        1,
+        VrfSeed::default(),
+        voting_keys[0].clone(),
+        0,
+        &vec![0],
+    );
+    let results = time::timeout(
+        Duration::from_secs(1),
+        networks[0].send_to(&[1, 2, 3], update),
+    )
+    .await
+    .expect("Sending to the reachable validators should not stall");
+
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    assert!(matches!(results[2], Err(NetworkError::UnknownValidator(3))));
+
+    for receiver in receivers.iter_mut() {
+        let (update, _) = time::timeout(Duration::from_secs(1), receiver.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(update.tag.new_view_number, 1);
+    }
+}
+
+#[tokio::test]
+async fn slow_validator_does_not_stall_send_to_others() {
+    let mut hub = MockHub::default();
+    let mut rng = seeded_rng(0);
+
+    let voting_keys: Vec<BlsKeyPair> = (0..4).map(|_| BlsKeyPair::generate(&mut rng)).collect();
+    let networks = build_validator_networks(&voting_keys, &mut hub).await;
+
+    let mut receivers: Vec<_> = networks[1..3]
+        .iter()
+        .map(|network| network.receive::<LevelUpdateMessage<SignedViewChangeMessage, ViewChange>>())
+        .collect();
+    // The fourth validator subscribes to the messages, but never reads them.
+    let _slow_receiver =
+        networks[3].receive::<LevelUpdateMessage<SignedViewChangeMessage, ViewChange>>();
+
+    let mut slow_validator_failed = false;
+    for view_number in 1..=32 {
+        let update = create_view_change_update(
+            1,
+            view_number,
+            VrfSeed::default(),
+            voting_keys[0].clone(),
+            0,
+            &vec![0],
+        );
+        let results = time::timeout(
+            Duration::from_secs(1),
+            networks[0].send_to(&[1, 2, 3], update),
+        )
+        .await
+        .expect("A slow validator should not stall sending to the others");
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_ok());
+        if let Err(NetworkError::Send(SendError::BufferFull)) = results[2] {
+            slow_validator_failed = true;
+        }
+
+        for receiver in receivers.iter_mut() {
+            let (update, _) = time::timeout(Duration::from_secs(1), receiver.next())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(update.tag.new_view_number, view_number);
+        }
+    }
+
+    assert!(slow_validator_failed);
+}
+
+#[tokio::test]
+async fn validators_leaving_cause_quorum_loss() {
+    let mut hub = MockHub::default();
+    let mut rng = seeded_rng(0);
+
+    let voting_keys: Vec<BlsKeyPair> = (0..4).map(|_| BlsKeyPair::generate(&mut rng)).collect();
+    let networks: Vec<Arc<MockNetwork>> = voting_keys
+        .iter()
+        .map(|_| Arc::new(hub.new_network()))
+        .collect();
+    MockNetwork::connect_network(&networks).await;
+    let validator_networks = build_validator_networks_on(networks.clone(), &voting_keys).await;
+
+    let mut events = validator_networks[0].subscribe_events();
+    let timeout = Duration::from_secs(1);
+
+    // Three of four validators still own more than two thirds of the slots.
+    networks[3].disconnect();
+    assert!(time::timeout(timeout, events.next()).await.is_err());
+
+    networks[2].disconnect();
+    assert_eq!(
+        time::timeout(timeout, events.next()).await.unwrap(),
+        Some(ValidatorNetworkEvent::QuorumLost)
+    );
+
+    networks[2].dial_mock(&networks[0]);
+    assert_eq!(
+        time::timeout(timeout, events.next()).await.unwrap(),
+        Some(ValidatorNetworkEvent::QuorumAvailable)
+    );
+}